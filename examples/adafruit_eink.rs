@@ -1,4 +1,5 @@
 extern crate embedded_graphics;
+#[macro_use]
 extern crate il0373;
 extern crate linux_embedded_hal;
 
@@ -15,7 +16,11 @@ use linux_embedded_hal::{
     Pin, Spidev,
 };
 
-fn main() -> Result<(), std::convert::Infallible> {
+// the 2.13" tri-color panel
+const ROWS: u16 = 212;
+const COLS: u8 = 104;
+
+fn main() -> Result<(), il0373::Error<std::convert::Infallible>> {
     // Configure SPI
     let mut spi = Spidev::open("/dev/spidev0.0").expect("SPI device");
     let options = SpidevOptions::new()
@@ -55,13 +60,13 @@ fn main() -> Result<(), std::convert::Infallible> {
     let pins = (cs, busy, dc, reset);
 
     // need some buffers
-    let mut black = [0u8; 212 * 104 / 8];
-    let mut red = [0u8; 212 * 104 / 8];
+    let mut black = [0u8; buffer_size!(ROWS, COLS)];
+    let mut red = [0u8; buffer_size!(ROWS, COLS)];
 
     let config = Builder::new()
         .dimensions(Dimensions {
-            rows: 212,
-            cols: 104,
+            rows: ROWS,
+            cols: COLS,
         })
         .rotation(Rotation::Rotate270)
         .build()
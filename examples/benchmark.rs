@@ -0,0 +1,64 @@
+//! Times drawing a screen of text at each rotation, without hardware.
+//!
+//! Run with `cargo run --release --example benchmark --features test`, add `sram` to compare the
+//! SRAM mode.
+#[macro_use]
+extern crate il0373;
+
+use il0373::bench::{run, BenchInterface};
+use il0373::{Builder, Dimensions, Display, GraphicDisplay, Rotation};
+
+const ROWS: u16 = 296;
+const COLS: u8 = 128;
+const TEXT: &str = "The quick brown fox jumps over the lazy dog";
+
+fn display(rotation: Rotation) -> Display<BenchInterface> {
+    let config = Builder::new()
+        .dimensions(Dimensions {
+            rows: ROWS,
+            cols: COLS,
+        })
+        .rotation(rotation)
+        .build()
+        .expect("invalid config");
+    Display::new(BenchInterface::new(64 * 1024), config)
+}
+
+fn main() {
+    let rotations = [
+        Rotation::Rotate0,
+        Rotation::Rotate90,
+        Rotation::Rotate180,
+        Rotation::Rotate270,
+    ];
+    let mut black = [0u8; buffer_size!(ROWS, COLS)];
+    let mut red = [0u8; buffer_size!(ROWS, COLS)];
+
+    println!("mode  rotation   clear      draw       update     bus bytes");
+    for rotation in rotations.iter() {
+        let mut display = GraphicDisplay::new(display(*rotation), &mut black, &mut red);
+        let timings = run(&mut display, TEXT);
+        println!(
+            "ram   {:<10} {:<10?} {:<10?} {:<10?} {}",
+            format!("{:?}", rotation),
+            timings.clear,
+            timings.draw,
+            timings.update,
+            display.interface().bus_bytes()
+        );
+    }
+
+    #[cfg(feature = "sram")]
+    for rotation in rotations.iter() {
+        let mut display = il0373::SramGraphicDisplay::new_sram(display(*rotation));
+        let timings = run(&mut display, TEXT);
+        println!(
+            "sram  {:<10} {:<10?} {:<10?} {:<10?} {}",
+            format!("{:?}", rotation),
+            timings.clear,
+            timings.draw,
+            timings.update,
+            display.interface().bus_bytes()
+        );
+    }
+}
@@ -0,0 +1,31 @@
+extern crate embedded_graphics;
+extern crate il0373;
+
+use embedded_graphics::{
+    mono_font::{ascii::FONT_6X10, MonoTextStyle},
+    prelude::*,
+    primitives::{PrimitiveStyle, Rectangle},
+    text::Text,
+};
+use il0373::inky::{InkyPhat, GPIO_CHIP};
+use il0373::Color;
+
+fn main() -> std::io::Result<()> {
+    // InkyPhat::new uses sysfs pins, on kernels that still have it
+    let mut inky = InkyPhat::new_cdev("/dev/spidev0.0", GPIO_CHIP)?;
+
+    let size = inky.size();
+    Rectangle::new(Point::zero(), size)
+        .into_styled(PrimitiveStyle::with_stroke(Color::Red, 3))
+        .draw(&mut inky)
+        .unwrap();
+    Text::new(
+        "Hello from Rust!",
+        Point::new(10, size.height as i32 / 2),
+        MonoTextStyle::new(&FONT_6X10, Color::Black),
+    )
+    .draw(&mut inky)
+    .unwrap();
+
+    inky.show()
+}
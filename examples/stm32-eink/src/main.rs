@@ -9,8 +9,8 @@ use stm32f1xx_hal as board;
 use {defmt_rtt as _, panic_probe as _};
 
 use il0373::{
-    Builder, Color, Dimensions, Display, Rotation, SpiSramBus, SramDisplayInterface,
-    SramGraphicDisplay,
+    config::Config, Builder, Color, Dimensions, Display, Rotation, SpiSramBus,
+    SramDisplayInterface, SramGraphicDisplay,
 };
 
 // Graphics
@@ -29,6 +29,15 @@ use embedded_graphics::{
 const ROWS: u16 = 212;
 const COLS: u8 = 104;
 
+// display configuration, checked at compile time
+const CONFIG: Config = Builder::new()
+    .dimensions(Dimensions {
+        rows: ROWS,
+        cols: COLS,
+    })
+    .rotation(Rotation::Rotate270)
+    .build_const();
+
 #[entry]
 fn main() -> ! {
     // Get access to the core peripherals from the cortex-m crate
@@ -77,8 +86,8 @@ fn main() -> ! {
 
     let epd_cs = gpiob.pb6.into_push_pull_output(&mut gpiob.crl);
     let sram_cs = gpiob.pb10.into_push_pull_output(&mut gpiob.crh);
-    let mut sdmmc_cs = gpiob.pb5.into_push_pull_output(&mut gpiob.crl);
-    sdmmc_cs.set_high();
+    // the SD card chip select must be held high, or it will corrupt the spi bus
+    let sdmmc_cs = gpiob.pb5.into_push_pull_output(&mut gpiob.crl);
     let cs_pins = (epd_cs, sram_cs);
 
     // configure spi1
@@ -93,7 +102,7 @@ fn main() -> ! {
         4.MHz(),
         clocks,
     );
-    let spi_bus = SpiSramBus::new(spi, cs_pins);
+    let spi_bus = SpiSramBus::new(spi, cs_pins).hold_high(sdmmc_cs);
 
     // Initialize display controller
     let mut delay = cp.SYST.delay(&clocks);
@@ -102,19 +111,9 @@ fn main() -> ! {
 
     delay.delay_ms(800u32);
 
-    let config = Builder::new()
-        .dimensions(Dimensions {
-            rows: ROWS,
-            cols: COLS,
-        })
-        .rotation(Rotation::Rotate270)
-        .build()
-        .ok()
-        .unwrap();
-
-    let display = Display::new(controller, config);
+    let display = Display::new(controller, CONFIG);
 
-    let mut display = SramGraphicDisplay::new(display);
+    let mut display = SramGraphicDisplay::new_sram(display);
 
     let text_style_black = MonoTextStyle::new(&FONT_6X9, Color::Black);
     let text_style_red = MonoTextStyle::new(&FONT_10X20, Color::Red);
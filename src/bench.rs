@@ -0,0 +1,174 @@
+//! Timing clear, draw and update, to compare configurations and catch regressions.
+//!
+//! [run] times the three phases of a typical frame on any [GraphicDisplay], so it can be used
+//! with real hardware. [BenchInterface] is an interface that sends nothing, counting the bytes
+//! that would have crossed the bus, so the packing and rotation code can be timed on the host.
+//! With the `sram` feature it also keeps the buffers in an emulated SRAM, to compare RAM and SRAM
+//! modes.
+//!
+//! Only available with the `test` feature, which needs `std`. See `examples/benchmark.rs`.
+
+use crate::error::Error;
+use crate::graphics::{FrameBufferStore, GraphicDisplay};
+use crate::interface::DisplayInterface;
+#[cfg(feature = "sram")]
+use crate::interface::{SramAddr, SramBacked};
+use crate::Color;
+use embedded_graphics::{
+    mono_font::{ascii::FONT_6X10, MonoTextStyle},
+    prelude::*,
+    text::Text,
+};
+use std::fmt::Debug;
+use std::time::{Duration, Instant};
+#[cfg(feature = "sram")]
+use std::vec::Vec;
+
+/// Time taken by each phase of a frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Timings {
+    /// clearing the buffers
+    pub clear: Duration,
+    /// drawing the text
+    pub draw: Duration,
+    /// sending the buffers and starting the refresh, not waiting for it
+    pub update: Duration,
+}
+
+/// Clear the display, fill it with lines of `text` and update it, timing each step.
+pub fn run<I, S>(display: &mut GraphicDisplay<I, S>, text: &str) -> Timings
+where
+    I: DisplayInterface,
+    I::Error: Debug,
+    S: FrameBufferStore<I>,
+    S::Error: Debug,
+{
+    let start = Instant::now();
+    display.clear(Color::White).unwrap();
+    let clear = start.elapsed();
+
+    let start = Instant::now();
+    let style = MonoTextStyle::new(&FONT_6X10, Color::Black);
+    let height = display.size().height as i32;
+    let mut y = 10;
+    while y < height {
+        Text::new(text, Point::new(0, y), style)
+            .draw(display)
+            .unwrap();
+        y += 10;
+    }
+    let draw = start.elapsed();
+
+    let start = Instant::now();
+    display.update().unwrap();
+    let update = start.elapsed();
+
+    Timings {
+        clear,
+        draw,
+        update,
+    }
+}
+
+/// An interface that discards everything sent, counting the bytes.
+#[derive(Debug, Default)]
+pub struct BenchInterface {
+    bus_bytes: usize,
+    #[cfg(feature = "sram")]
+    sram: Vec<u8>,
+}
+
+impl BenchInterface {
+    /// Create an interface, with `sram_bytes` of emulated SRAM when using the `sram` feature.
+    pub fn new(sram_bytes: usize) -> Self {
+        let _ = sram_bytes;
+        BenchInterface {
+            bus_bytes: 0,
+            #[cfg(feature = "sram")]
+            sram: vec![0; sram_bytes],
+        }
+    }
+
+    /// Returns the number of bytes that would have been sent over the bus.
+    pub fn bus_bytes(&self) -> usize {
+        self.bus_bytes
+    }
+}
+
+impl DisplayInterface for BenchInterface {
+    type Error = ();
+
+    fn send_command(&mut self, _command: u8) -> Result<(), Self::Error> {
+        self.bus_bytes += 1;
+        Ok(())
+    }
+
+    fn send_data(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        self.bus_bytes += data.len();
+        Ok(())
+    }
+
+    fn reset<D: hal::blocking::delay::DelayMs<u8>>(&mut self, _delay: &mut D) {}
+
+    fn busy_wait(&mut self) {}
+
+    fn epd_update_data(
+        &mut self,
+        _layer: u8,
+        _nbytes: u16,
+        buf: &[u8],
+    ) -> Result<(), Error<Self::Error>> {
+        self.bus_bytes += 1 + buf.len();
+        Ok(())
+    }
+}
+
+// an SRAM read or write is an instruction and a 16-bit address before the data
+#[cfg(feature = "sram")]
+const SRAM_HEADER_BYTES: usize = 3;
+
+#[cfg(feature = "sram")]
+impl SramBacked for BenchInterface {
+    fn sram_epd_update_data(
+        &mut self,
+        _layer: u8,
+        nbytes: u32,
+        _start_address: SramAddr,
+    ) -> Result<(), Error<Self::Error>> {
+        // the data is clocked out of the sram straight into the controller
+        self.bus_bytes += 1 + SRAM_HEADER_BYTES + nbytes as usize;
+        Ok(())
+    }
+
+    fn sram_read(&mut self, address: SramAddr, data: &mut [u8]) -> Result<(), Error<Self::Error>> {
+        let start = address.get() as usize;
+        data.copy_from_slice(&self.sram[start..start + data.len()]);
+        self.bus_bytes += SRAM_HEADER_BYTES + data.len();
+        Ok(())
+    }
+
+    fn sram_write(&mut self, address: SramAddr, data: &[u8]) -> Result<(), Error<Self::Error>> {
+        let start = address.get() as usize;
+        self.sram[start..start + data.len()].copy_from_slice(data);
+        self.bus_bytes += SRAM_HEADER_BYTES + data.len();
+        Ok(())
+    }
+
+    fn sram_clear(
+        &mut self,
+        address: SramAddr,
+        nbytes: u32,
+        val: u8,
+    ) -> Result<(), Error<Self::Error>> {
+        let start = address.get() as usize;
+        for byte in &mut self.sram[start..start + nbytes as usize] {
+            *byte = val;
+        }
+        self.bus_bytes += SRAM_HEADER_BYTES + nbytes as usize;
+        Ok(())
+    }
+
+    fn sram_capacity(&self) -> u32 {
+        self.sram.len() as u32
+    }
+}
@@ -0,0 +1,246 @@
+//! Run length encoding of packed planes, for sending frames over slow links.
+//!
+//! A gateway encodes each plane of a frame with [encode], sends it to a node over a radio, and
+//! the node writes it straight into its buffers with
+//! [GraphicDisplay::apply_rle](../graphics/struct.GraphicDisplay.html#method.apply_rle),
+//! without holding the whole frame twice. Mostly white frames shrink to a few bytes.
+//!
+//! The encoded data is a sequence of runs, each starting with a header byte:
+//!
+//! * `0x00..=0x7F` - the next `header + 1` bytes are copied as they are
+//! * `0x80..=0xFF` - the next byte is repeated `(header & 0x7F) + 2` times
+//!
+//! ### Example
+//!
+//! ```
+//! use il0373::codec;
+//!
+//! let plane = [0xFFu8; 200];
+//! let mut encoded = [0u8; codec::max_encoded_len(200)];
+//! let len = codec::encode(&plane, &mut encoded).unwrap();
+//! assert_eq!(len, 4);
+//!
+//! let mut decoded = [0u8; 200];
+//! codec::decode(&encoded[..len], &mut decoded).unwrap();
+//! assert_eq!(decoded[..], plane[..]);
+//! ```
+
+use crate::error::Error;
+use core::convert::Infallible;
+
+const REPEAT: u8 = 0x80;
+// the longest run of each kind
+const MAX_LITERAL: usize = 128;
+const MAX_REPEAT: usize = 129;
+
+/// A run of decoded bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Run<'a> {
+    /// Bytes copied as they are.
+    Literal(&'a [u8]),
+    /// A byte repeated a number of times.
+    Repeat {
+        /// The repeated byte.
+        byte: u8,
+        /// The number of times it is repeated.
+        count: u32,
+    },
+}
+
+impl<'a> Run<'a> {
+    /// Returns the number of bytes the run decodes to.
+    pub fn len(&self) -> u32 {
+        match *self {
+            Run::Literal(bytes) => bytes.len() as u32,
+            Run::Repeat { count, .. } => count,
+        }
+    }
+
+    /// Returns true if the run decodes to no bytes, which a valid run never does.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Iterator over the runs of encoded data, created by [runs].
+pub struct Runs<'a> {
+    rest: &'a [u8],
+}
+
+impl<'a> Iterator for Runs<'a> {
+    type Item = Result<Run<'a>, Error<Infallible>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (&header, tail) = self.rest.split_first()?;
+        let run = if header & REPEAT != 0 {
+            tail.first().map(|&byte| {
+                self.rest = &tail[1..];
+                Run::Repeat {
+                    byte,
+                    count: u32::from(header & !REPEAT) + 2,
+                }
+            })
+        } else {
+            let len = usize::from(header) + 1;
+            tail.get(..len).map(|bytes| {
+                self.rest = &tail[len..];
+                Run::Literal(bytes)
+            })
+        };
+        if run.is_none() {
+            // stop after a truncated run
+            self.rest = &[];
+        }
+        Some(run.ok_or(Error::InvalidStream))
+    }
+}
+
+/// Returns the runs of encoded data.
+///
+/// A truncated run is returned as `Error::InvalidStream`.
+pub fn runs(encoded: &[u8]) -> Runs<'_> {
+    Runs { rest: encoded }
+}
+
+/// Returns the longest encoding of `len` bytes, for sizing the output of [encode].
+pub const fn max_encoded_len(len: usize) -> usize {
+    len + len.div_ceil(MAX_LITERAL)
+}
+
+/// Returns the number of bytes encoded data decodes to.
+///
+/// Returns `Error::InvalidStream` if the data is truncated.
+pub fn decoded_len(encoded: &[u8]) -> Result<usize, Error<Infallible>> {
+    runs(encoded).try_fold(0, |len, run| Ok(len + run?.len() as usize))
+}
+
+/// Encode `input` into `output`, returning the length of the encoding.
+///
+/// Returns `Error::BufferSize` if `output` is too short, `expected` is then the length that is
+/// always long enough, see [max_encoded_len].
+pub fn encode(input: &[u8], output: &mut [u8]) -> Result<usize, Error<Infallible>> {
+    let too_short = Error::BufferSize {
+        expected: max_encoded_len(input.len()),
+        actual: output.len(),
+    };
+    let (mut i, mut len) = (0, 0);
+    while i < input.len() {
+        let repeats = input[i..]
+            .iter()
+            .take(MAX_REPEAT)
+            .take_while(|&&b| b == input[i])
+            .count();
+        if repeats >= 2 {
+            output
+                .get_mut(len..len + 2)
+                .ok_or(too_short)?
+                .copy_from_slice(&[REPEAT | (repeats - 2) as u8, input[i]]);
+            len += 2;
+            i += repeats;
+            continue;
+        }
+        // copy up to a run of three bytes, which is shorter as a repeat
+        let start = i;
+        while i < input.len() && i - start < MAX_LITERAL {
+            if input[i..].len() >= 3 && input[i] == input[i + 1] && input[i] == input[i + 2] {
+                break;
+            }
+            i += 1;
+        }
+        let literal = &input[start..i];
+        let run = output
+            .get_mut(len..len + 1 + literal.len())
+            .ok_or(too_short)?;
+        run[0] = (literal.len() - 1) as u8;
+        run[1..].copy_from_slice(literal);
+        len += run.len();
+    }
+    Ok(len)
+}
+
+/// Decode `encoded` into `output`, returning the decoded length.
+///
+/// Returns `Error::InvalidStream` if the data is truncated, and `Error::BufferSize` if `output`
+/// is too short. Nothing is written in either case.
+pub fn decode(encoded: &[u8], output: &mut [u8]) -> Result<usize, Error<Infallible>> {
+    let len = decoded_len(encoded)?;
+    if len > output.len() {
+        return Err(Error::BufferSize {
+            expected: len,
+            actual: output.len(),
+        });
+    }
+    let mut at = 0;
+    for run in runs(encoded) {
+        let run = run?;
+        let out = &mut output[at..at + run.len() as usize];
+        match run {
+            Run::Literal(bytes) => out.copy_from_slice(bytes),
+            Run::Repeat { byte, .. } => {
+                for b in out.iter_mut() {
+                    *b = byte;
+                }
+            }
+        }
+        at += out.len();
+    }
+    Ok(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    fn round_trip(input: &[u8]) -> Vec<u8> {
+        let mut encoded = vec![0u8; max_encoded_len(input.len())];
+        let len = encode(input, &mut encoded).unwrap();
+        encoded.truncate(len);
+        let mut decoded = vec![0u8; input.len()];
+        assert_eq!(decode(&encoded, &mut decoded), Ok(input.len()));
+        assert_eq!(decoded, input);
+        encoded
+    }
+
+    #[test]
+    fn encoding() {
+        assert_eq!(round_trip(&[]), []);
+        assert_eq!(round_trip(&[1]), [0x00, 1]);
+        assert_eq!(round_trip(&[7, 7, 7, 7]), [0x82, 7]);
+        assert_eq!(round_trip(&[1, 2, 2, 3, 3, 3]), [0x02, 1, 2, 2, 0x81, 3]);
+        assert_eq!(round_trip(&[1, 2, 3, 4, 4, 4]), [0x02, 1, 2, 3, 0x81, 4]);
+        assert_eq!(
+            round_trip(&[0xFF; 300]),
+            [0xFF, 0xFF, 0xFF, 0xFF, 0xA8, 0xFF]
+        );
+        assert_eq!(round_trip(&[5, 5, 6]), [0x80, 5, 0x00, 6]);
+
+        // incompressible data is at its longest
+        let noise: Vec<u8> = (0..300u32).map(|i| (i * 7 % 256) as u8).collect();
+        assert_eq!(round_trip(&noise).len(), max_encoded_len(noise.len()));
+    }
+
+    #[test]
+    fn errors() {
+        let mut output = [0u8; 3];
+        assert_eq!(
+            encode(&[1, 2, 3, 4], &mut output),
+            Err(Error::BufferSize {
+                expected: 5,
+                actual: 3
+            })
+        );
+        assert_eq!(
+            decode(&[0x02, 1, 2], &mut output),
+            Err(Error::InvalidStream)
+        );
+        assert_eq!(decode(&[0x85], &mut output), Err(Error::InvalidStream));
+        assert_eq!(
+            decode(&[0x82, 1], &mut output),
+            Err(Error::BufferSize {
+                expected: 4,
+                actual: 3
+            })
+        );
+    }
+}
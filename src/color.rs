@@ -1,13 +1,100 @@
 use embedded_graphics_core::pixelcolor::PixelColor;
 
 /// Represents the state of a pixel in the display
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Color {
     Black,
     White,
     Red,
 }
 
+impl Color {
+    /// Returns the color the panel shows for a pixel's B/W and Red plane bits.
+    ///
+    /// A cleared bit is ink. Red ink is shown over black ink, as the controller does.
+    pub fn from_planes(black: bool, red: bool) -> Color {
+        match (black, red) {
+            (_, false) => Color::Red,
+            (false, true) => Color::Black,
+            (true, true) => Color::White,
+        }
+    }
+}
+
 impl PixelColor for Color {
     type Raw = ();
 }
+
+/// A color that can be drawn into the B/W and Red planes of a `GraphicDisplay`.
+///
+/// Each color maps to one bit in each plane, so the two planes hold up to four colors. Implement
+/// this to give the planes other meanings, like the yellow plane of a 4-color panel.
+pub trait PanelColor: PixelColor {
+    /// Returns the B/W and Red plane bits for the color, a cleared bit is ink.
+    fn planes(self) -> (bool, bool);
+}
+
+impl PanelColor for Color {
+    fn planes(self) -> (bool, bool) {
+        match self {
+            Color::Black => (false, true),
+            Color::White => (true, true),
+            Color::Red => (true, false),
+        }
+    }
+}
+
+/// How RGB colors are mapped to the nearest panel color, by the `ColorAdapter` of a
+/// `GraphicDisplay`.
+///
+/// Set per panel with `Builder::color_thresholds`, as panels from different batches show mid
+/// grays and dark reds differently. A color is red if its red channel is at least `red_chroma`
+/// above both the green and blue channels and its hue is within `red_hue` degrees of red,
+/// otherwise it is black if its luma is below `luminance` and white if not.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ColorThresholds {
+    /// the luma below which a color is black, 128 by default
+    ///
+    /// Raise it to show mid grays as black, lower it to show them as white.
+    pub luminance: u8,
+    /// how far the red channel must be above the green and blue channels, 128 by default
+    pub red_chroma: u8,
+    /// how far the hue may be from red, in degrees towards yellow or magenta, 60 by default
+    ///
+    /// 60 or more allows every hue with red as the largest channel, lower it to keep orange and
+    /// pink out of the red plane.
+    pub red_hue: u8,
+}
+
+impl ColorThresholds {
+    /// The default thresholds.
+    pub const DEFAULT: ColorThresholds = ColorThresholds {
+        luminance: 128,
+        red_chroma: 128,
+        red_hue: 60,
+    };
+
+    /// Returns the panel color nearest to an RGB color.
+    pub fn nearest(&self, r: u8, g: u8, b: u8) -> Color {
+        let (r, g, b) = (u32::from(r), u32::from(g), u32::from(b));
+        let (max, min) = (g.max(b), g.min(b));
+        // the hue is 0 for pure red and 60 degrees for yellow or magenta
+        if r > max
+            && r - max >= u32::from(self.red_chroma)
+            && 60 * (max - min) / (r - min) <= u32::from(self.red_hue)
+        {
+            Color::Red
+        } else if (299 * r + 587 * g + 114 * b) / 1000 < u32::from(self.luminance) {
+            Color::Black
+        } else {
+            Color::White
+        }
+    }
+}
+
+impl Default for ColorThresholds {
+    /// Default is [ColorThresholds::DEFAULT].
+    fn default() -> Self {
+        ColorThresholds::DEFAULT
+    }
+}
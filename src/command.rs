@@ -1,15 +1,68 @@
-use core::ops;
-use interface::DisplayInterface;
-
-trait Contains<C>
-where
-    C: Copy + PartialOrd,
-{
-    fn contains(&self, item: C) -> bool;
+use crate::display::PartialWindow;
+use crate::error::Error;
+use crate::interface::DisplayInterface;
+use crate::regs::{self, cdi, psr, ptl, pwr};
+use crate::wait::Operation;
+
+/// Defines a newtype wrapping a register value that must not exceed `$max`.
+macro_rules! bounded_u8 {
+    ($(#[$meta:meta])* $name:ident, $max:expr) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct $name(u8);
+
+        impl $name {
+            /// The largest value accepted by the controller.
+            pub const MAX: u8 = $max;
+
+            /// Create a new value, returns `None` if `value` is out of range.
+            pub const fn new(value: u8) -> Option<Self> {
+                if value <= Self::MAX {
+                    Some($name(value))
+                } else {
+                    None
+                }
+            }
+
+            /// Create a new value without checking the range.
+            ///
+            /// The caller must ensure `value` is not greater than `MAX`, out of range values
+            /// can damage the panel.
+            pub const fn new_unchecked(value: u8) -> Self {
+                $name(value)
+            }
+
+            /// Returns the raw register value.
+            pub const fn value(self) -> u8 {
+                self.0
+            }
+        }
+    };
 }
 
+bounded_u8!(
+    /// Internal VDH power selection for the black/white pixel (PWR byte 3)
+    Vdh,
+    0b11_1111
+);
+bounded_u8!(
+    /// Internal VDL power selection for the black/white pixel (PWR byte 4)
+    Vdl,
+    0b11_1111
+);
+bounded_u8!(
+    /// Internal VDHR power selection for the red pixel (PWR byte 5)
+    Vdhr,
+    0b11_1111
+);
+bounded_u8!(
+    /// VCOM DC level (VDCS)
+    VcomDc,
+    0b11_1010
+);
+
 /// Display Resolution
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum DisplayResolution {
     R96x230,
     R96x252,
@@ -17,16 +70,101 @@ pub enum DisplayResolution {
     R160x296,
 }
 
+impl DisplayResolution {
+    /// Returns the number of columns (sources).
+    pub const fn cols(self) -> u8 {
+        match self {
+            DisplayResolution::R96x230 | DisplayResolution::R96x252 => 96,
+            DisplayResolution::R128x296 => 128,
+            DisplayResolution::R160x296 => 160,
+        }
+    }
+
+    /// Returns the number of rows (gates).
+    pub const fn rows(self) -> u16 {
+        match self {
+            DisplayResolution::R96x230 => 230,
+            DisplayResolution::R96x252 => 252,
+            DisplayResolution::R128x296 | DisplayResolution::R160x296 => 296,
+        }
+    }
+
+    /// Returns the smallest resolution with at least `rows` and `cols`.
+    pub const fn containing(rows: u16, cols: u8) -> Option<DisplayResolution> {
+        let all = [
+            DisplayResolution::R96x230,
+            DisplayResolution::R96x252,
+            DisplayResolution::R128x296,
+            DisplayResolution::R160x296,
+        ];
+        let mut i = 0;
+        while i < all.len() {
+            if all[i].rows() >= rows && all[i].cols() >= cols {
+                return Some(all[i]);
+            }
+            i += 1;
+        }
+        None
+    }
+}
+
+/// Resolution set by TRES, in sources (horizontal) and gates (vertical)
+///
+/// The register holds bits 7 to 3 of the horizontal resolution and bits 8 to 0 of the vertical
+/// resolution, so larger values, or horizontal values that aren't a multiple of 8, can't be
+/// encoded. Use [Resolution::new] to check them rather than have them truncated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Resolution {
+    horizontal: u8,
+    vertical: u16,
+}
+
+impl Resolution {
+    /// The largest horizontal resolution the register can hold.
+    pub const MAX_HORIZONTAL: u16 = 0xF8;
+    /// The largest vertical resolution the register can hold.
+    pub const MAX_VERTICAL: u16 = 0x1FF;
+
+    /// Create a new resolution, returns `None` if either value is out of range, or the horizontal
+    /// resolution isn't a multiple of 8.
+    pub const fn new(horizontal: u16, vertical: u16) -> Option<Self> {
+        if horizontal <= Self::MAX_HORIZONTAL
+            && horizontal.is_multiple_of(8)
+            && vertical <= Self::MAX_VERTICAL
+        {
+            Some(Resolution {
+                horizontal: horizontal as u8,
+                vertical,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the horizontal resolution (sources).
+    pub const fn horizontal(self) -> u16 {
+        self.horizontal as u16
+    }
+
+    /// Returns the vertical resolution (gates).
+    pub const fn vertical(self) -> u16 {
+        self.vertical
+    }
+}
+
 /// Data Polarity
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum DataPolarity {
     BWOnly,
     RedOnly,
     Both,
 }
 
-/// Data Interval
-#[derive(Clone, Copy)]
+/// Data Interval, the number of frames (hsync periods) between the VCOM and data output
+///
+/// `V10` is 10 frames, and so on. The register value counts down, 2 frames is `0b1111` and 17
+/// frames is `0b0000`, use [DataInterval::from_frames] to avoid mapping by hand.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum DataInterval {
     V2,
     V3,
@@ -46,13 +184,98 @@ pub enum DataInterval {
     V17,
 }
 
+impl DataInterval {
+    /// Returns the interval for a number of frames, `None` unless `frames` is from 2 to 17.
+    pub const fn from_frames(frames: u8) -> Option<DataInterval> {
+        use self::DataInterval::*;
+
+        let interval = match frames {
+            2 => V2,
+            3 => V3,
+            4 => V4,
+            5 => V5,
+            6 => V6,
+            7 => V7,
+            8 => V8,
+            9 => V9,
+            10 => V10,
+            11 => V11,
+            12 => V12,
+            13 => V13,
+            14 => V14,
+            15 => V15,
+            16 => V16,
+            17 => V17,
+            _ => return None,
+        };
+        Some(interval)
+    }
+
+    /// Returns the number of frames.
+    pub const fn frames(self) -> u8 {
+        self as u8 + 2
+    }
+
+    /// Returns the CDI register value.
+    pub const fn value(self) -> u8 {
+        17 - self.frames()
+    }
+}
+
+impl Default for DataInterval {
+    /// Default is the controller's power on value of 10 frames (`V10`).
+    fn default() -> Self {
+        DataInterval::V10
+    }
+}
+
+/// How the VCOM and data interval setting (CDI) is sent, see [Command::with_cdi_encoding].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CdiEncoding {
+    /// One byte, the border data, data polarity and interval packed together.
+    Single,
+    /// Two bytes, the border data and data polarity in the first and the interval in the
+    /// second, for variants that read them separately.
+    Extended,
+}
+
+impl Default for CdiEncoding {
+    /// Default is `Single`.
+    fn default() -> Self {
+        CdiEncoding::Single
+    }
+}
+
+/// Number of data lines used to send the frame data, see [Command::DualSpi].
+#[cfg(feature = "dual-spi")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BusWidth {
+    /// One data line, the controller's power on setting.
+    Single,
+    /// Two data lines, the MM pin carrying the second bit of each pair.
+    Dual,
+}
+
+#[cfg(feature = "dual-spi")]
+impl Default for BusWidth {
+    /// Default is `Single`.
+    fn default() -> Self {
+        BusWidth::Single
+    }
+}
+
 /// A command that can be issued to the controller.
 #[derive(Clone, Copy)]
 pub enum Command {
     /// Set the panel (PSR), overwritten by ResolutionSetting (TRES)
     PanelSetting(DisplayResolution),
+    /// Set the panel (PSR) to use the waveforms in the LUT registers instead of OTP, in black and
+    /// white mode if the bool is true
+    PanelSettingLut(DisplayResolution, bool),
+    /// Soft reset, PSR with RST_N low. Registers are set to defaults and the booster is turned off
+    SoftReset,
     /// Gate scanning sequence and direction (PWR)
-    PowerSetting(u8, u8, u8),
+    PowerSetting(Vdh, Vdl, Vdhr),
     /// Power OFF (POF)
     PowerOff,
     /// Power OFF Sequence
@@ -69,6 +292,9 @@ pub enum Command {
     /// Display Refresh (DRF)
     DisplayRefresh,
     /// Data Start Transmission 2 (DTM2)
+    /// Dual SPI (DUSPI), the MM pin is used as a second data line when the width is `Dual`
+    #[cfg(feature = "dual-spi")]
+    DualSpi(BusWidth),
     /// VCOM LUT (LUTC)
     /// W2W LUT (LUTWW)
     /// B2W LUT (LUTBW/LUTR)
@@ -81,19 +307,25 @@ pub enum Command {
     /// Temperature Sensor Read
     /// VCOM and Data Interval Setting (CDI)
     VCOMDataIntervalSetting(u8, DataPolarity, DataInterval),
+    /// VCOM and Data Interval Setting (CDI) in the two byte form, the border data and data
+    /// polarity (VBD and DDX) then the interval (CDI), see [CdiEncoding::Extended]
+    VCOMDataIntervalSettingExt(u8, u8),
     /// Low Power Detection
     /// TCON Setting
     /// ResolutionSetting (TRES). Has higher priority than (PSR)
-    ResolutionSetting(u8, u16),
+    ResolutionSetting(Resolution),
     /// Revision
     /// Get Status
     /// Auto Measure VCOM
     /// VCOM Value
     /// VCM DC Setting (VDCS)
-    VCMDCSetting(u8),
-    // Partial Window
-    // Partial In
-    // Partial Out
+    VCMDCSetting(VcomDc),
+    /// Partial Window (PTL), the area sent and refreshed while in partial mode
+    PartialWindow(PartialWindow),
+    /// Partial In (PTIN)
+    PartialIn,
+    /// Partial Out (PTOUT)
+    PartialOut,
     // Program Mode
     // Active Program
     // Read OTP Data
@@ -119,7 +351,7 @@ pub enum BufCommand<'buf> {
 /// appropriately sized slice into populated buffer.
 /// E.g.
 ///
-/// let mut buf = [0u8; 5];
+/// let mut buf = [0u8; 7];
 /// let (command, data) = pack!(buf, 0x3C, [0x12, 0x34]);
 macro_rules! pack {
     ($buf:ident, $cmd:expr,[]) => {
@@ -155,95 +387,209 @@ macro_rules! pack {
         $buf[4] = $arg4;
         ($cmd, &$buf[..5])
     }};
+    ($buf:ident, $cmd:expr,[$arg0:expr, $arg1:expr, $arg2:expr, $arg3:expr, $arg4:expr, $arg5:expr, $arg6:expr]) => {{
+        $buf[0] = $arg0;
+        $buf[1] = $arg1;
+        $buf[2] = $arg2;
+        $buf[3] = $arg3;
+        $buf[4] = $arg4;
+        $buf[5] = $arg5;
+        $buf[6] = $arg6;
+        ($cmd, &$buf[..7])
+    }};
+}
+
+// the RES bits of the panel setting
+pub(crate) fn resolution_bits(resolution: DisplayResolution) -> u8 {
+    match resolution {
+        DisplayResolution::R96x230 => psr::RES_96X230,
+        DisplayResolution::R96x252 => psr::RES_96X252,
+        DisplayResolution::R128x296 => psr::RES_128X296,
+        DisplayResolution::R160x296 => psr::RES_160X296,
+    }
+}
+
+// the DDX bits of the VCOM and data interval setting
+pub(crate) fn ddx_bits(polarity: DataPolarity) -> u8 {
+    match polarity {
+        DataPolarity::BWOnly => cdi::DDX_BW,
+        DataPolarity::RedOnly => cdi::DDX_RED,
+        DataPolarity::Both => cdi::DDX_BOTH,
+    }
 }
 
 impl Command {
+    /// Returns a VCOM and data interval setting (CDI) command sent in `encoding`.
+    ///
+    /// The same border data, polarity and interval are sent in either form, other commands are
+    /// returned unchanged.
+    pub fn with_cdi_encoding(self, encoding: CdiEncoding) -> Command {
+        match (self, encoding) {
+            (
+                Command::VCOMDataIntervalSetting(border, polarity, interval),
+                CdiEncoding::Extended,
+            ) => {
+                let border_polarity = regs::cdi_value(border, ddx_bits(polarity), 0);
+                Command::VCOMDataIntervalSettingExt(border_polarity, interval.value())
+            }
+            (
+                Command::VCOMDataIntervalSettingExt(border_polarity, interval),
+                CdiEncoding::Single,
+            ) => {
+                let border = border_polarity >> cdi::VBD_SHIFT;
+                let polarity = match border_polarity & cdi::DDX_BOTH {
+                    cdi::DDX_BW => DataPolarity::BWOnly,
+                    cdi::DDX_RED => DataPolarity::RedOnly,
+                    _ => DataPolarity::Both,
+                };
+                let interval = DataInterval::from_frames(17 - (interval & cdi::INTERVAL_MASK))
+                    .unwrap_or_default();
+                Command::VCOMDataIntervalSetting(border, polarity, interval)
+            }
+            (command, _) => command,
+        }
+    }
+
     /// Execute the command, transmitting any associated data as well.
+    ///
+    /// Commands the controller signals the end of with the busy pin, PON, DRF and POF, are
+    /// followed by a busy wait, so the next command can't be sent too early. See
+    /// [Command::execute_no_wait] to wait separately.
     pub fn execute<I: DisplayInterface>(&self, interface: &mut I) -> Result<(), I::Error> {
+        self.execute_no_wait(interface)?;
+        if let Some(operation) = self.busy_operation() {
+            interface.busy_wait_for(operation);
+        }
+        Ok(())
+    }
+
+    /// Returns the operation the controller is busy with after this command, if any.
+    pub fn busy_operation(&self) -> Option<Operation> {
+        match *self {
+            Command::PowerOn => Some(Operation::PowerOn),
+            Command::DisplayRefresh => Some(Operation::Refresh),
+            Command::PowerOff => Some(Operation::PowerOff),
+            _ => None,
+        }
+    }
+
+    /// Execute the command without waiting for the controller afterwards.
+    ///
+    /// For callers that do other work while the controller is busy, they must wait for the
+    /// [Command::busy_operation] themselves before sending another command.
+    pub fn execute_no_wait<I: DisplayInterface>(&self, interface: &mut I) -> Result<(), I::Error> {
+        let mut buf = [0u8; 7];
+        let (command, data) = self.encode(&mut buf);
+        interface.send_command(command)?;
+        if data.is_empty() {
+            Ok(())
+        } else {
+            interface.send_data(data)
+        }
+    }
+
+    // the command byte and its data, packed into buf
+    fn encode<'b>(&self, buf: &'b mut [u8; 7]) -> (u8, &'b [u8]) {
         use self::Command::*;
 
-        let mut buf = [0u8; 5];
-        let (command, data) = match *self {
+        match *self {
             PanelSetting(resolution) => {
-                let res = match resolution {
-                    self::DisplayResolution::R96x230 => 0b0000_0000,
-                    self::DisplayResolution::R96x252 => 0b0100_0000,
-                    self::DisplayResolution::R128x296 => 0b1000_0000,
-                    self::DisplayResolution::R160x296 => 0b1100_0000,
-                };
-                pack!(buf, 0x0, [res | 0b001111])
+                pack!(buf, regs::PSR, [resolution_bits(resolution) | psr::DEFAULT])
+            }
+            PanelSettingLut(resolution, black_white) => {
+                let kw = if black_white { psr::BWR } else { 0 };
+                pack!(
+                    buf,
+                    regs::PSR,
+                    [resolution_bits(resolution) | psr::REG_EN | kw | psr::DEFAULT]
+                )
+            }
+            SoftReset => {
+                pack!(buf, regs::PSR, [psr::DEFAULT & !psr::RST_N])
             }
             PowerSetting(vdh, vdl, vdhr) => {
-                debug_assert!(vdh < 64);
-                debug_assert!(vdl < 64);
-                debug_assert!(vdhr < 64);
-                pack!(buf, 0x1, [0x3, 0x0, vdh, vdl, vdhr])
+                pack!(
+                    buf,
+                    regs::PWR,
+                    [
+                        pwr::VDS_EN | pwr::VDG_EN,
+                        0x0,
+                        vdh.value(),
+                        vdl.value(),
+                        vdhr.value()
+                    ]
+                )
             }
             PowerOff => {
-                pack!(buf, 0x3, [])
+                pack!(buf, regs::POF, [])
             }
             PowerOn => {
-                pack!(buf, 0x4, [])
+                pack!(buf, regs::PON, [])
             }
             BoosterSoftStart(phase_a, phase_b, phase_c) => {
-                pack!(buf, 0x6, [phase_a, phase_b, phase_c])
+                pack!(buf, regs::BTST, [phase_a, phase_b, phase_c])
             }
             DeepSleep => {
-                pack!(buf, 0x8, [0xa5])
+                pack!(buf, regs::DSLP, [regs::DSLP_CHECK])
             }
             DataStop => {
-                pack!(buf, 0x11, [])
+                pack!(buf, regs::DSP, [])
             }
             DisplayRefresh => {
-                pack!(buf, 0x12, [])
+                pack!(buf, regs::DRF, [])
+            }
+            #[cfg(feature = "dual-spi")]
+            DualSpi(width) => {
+                // MM_EN and DUSPI_EN
+                let duspi = match width {
+                    BusWidth::Single => 0,
+                    BusWidth::Dual => regs::duspi::MM_EN | regs::duspi::DUSPI_EN,
+                };
+                pack!(buf, regs::DUSPI, [duspi])
             }
             PLLControl(clock) => {
-                pack!(buf, 0x30, [clock])
+                pack!(buf, regs::PLL, [clock])
             }
             VCOMDataIntervalSetting(border_data, data_polarity, interval) => {
                 debug_assert!(border_data < 4);
-                let vbd = border_data << 6;
-                let ddx = match data_polarity {
-                    DataPolarity::BWOnly => 0b01_0000,
-                    DataPolarity::RedOnly => 0b10_0000,
-                    DataPolarity::Both => 0b11_0000,
-                };
-                let cdi = match interval {
-                    DataInterval::V2 => 0b1111,
-                    DataInterval::V3 => 0b1110,
-                    DataInterval::V4 => 0b1101,
-                    DataInterval::V5 => 0b1100,
-                    DataInterval::V6 => 0b1011,
-                    DataInterval::V7 => 0b1010,
-                    DataInterval::V8 => 0b1001,
-                    DataInterval::V9 => 0b1000,
-                    DataInterval::V10 => 0b0111,
-                    DataInterval::V11 => 0b0110,
-                    DataInterval::V12 => 0b0101,
-                    DataInterval::V13 => 0b0100,
-                    DataInterval::V14 => 0b0011,
-                    DataInterval::V15 => 0b0010,
-                    DataInterval::V16 => 0b0001,
-                    DataInterval::V17 => 0b0000,
-                };
-                pack!(buf, 0x50, [vbd | ddx | cdi])
+                let cdi = regs::cdi_value(border_data, ddx_bits(data_polarity), interval.value());
+                pack!(buf, regs::CDI, [cdi])
+            }
+            VCOMDataIntervalSettingExt(border_polarity, interval) => {
+                pack!(buf, regs::CDI, [border_polarity, interval])
             }
-            ResolutionSetting(horiz, vertical) => {
-                let vres_hi = ((vertical & 0x100) >> 8) as u8;
-                let vres_lo = (vertical & 0xFF) as u8;
-                pack!(buf, 0x61, [horiz, vres_hi, vres_lo])
+            ResolutionSetting(resolution) => {
+                let vres_hi = (resolution.vertical >> 8) as u8;
+                let vres_lo = (resolution.vertical & 0xFF) as u8;
+                pack!(buf, regs::TRES, [resolution.horizontal, vres_hi, vres_lo])
             }
             VCMDCSetting(vcom_dc) => {
-                debug_assert!(vcom_dc <= 0b11_1010);
-                pack!(buf, 0x82, [vcom_dc])
+                pack!(buf, regs::VDCS, [vcom_dc.value()])
+            }
+            PartialWindow(window) => {
+                let [gate_start_hi, gate_start_lo] = window.gate_start.to_be_bytes();
+                let [gate_end_hi, gate_end_lo] = window.gate_end.to_be_bytes();
+                // with PT_SCAN set, as in the Good Display sample code
+                pack!(
+                    buf,
+                    regs::PTL,
+                    [
+                        window.source_start & !7,
+                        window.source_end | 7,
+                        gate_start_hi,
+                        gate_start_lo,
+                        gate_end_hi,
+                        gate_end_lo,
+                        ptl::PT_SCAN
+                    ]
+                )
+            }
+            PartialIn => {
+                pack!(buf, regs::PTIN, [])
+            }
+            PartialOut => {
+                pack!(buf, regs::PTOUT, [])
             }
-        };
-
-        interface.send_command(command)?;
-        if data.is_empty() {
-            Ok(())
-        } else {
-            interface.send_data(data)
         }
     }
 }
@@ -254,8 +600,8 @@ impl<'buf> BufCommand<'buf> {
         use self::BufCommand::*;
 
         let (command, data) = match self {
-            WriteBlackData(buffer) => (0x10, buffer),
-            WriteRedData(buffer) => (0x13, buffer),
+            WriteBlackData(buffer) => (regs::DTM1, buffer),
+            WriteRedData(buffer) => (regs::DTM2, buffer),
         };
 
         interface.send_command(command)?;
@@ -267,117 +613,257 @@ impl<'buf> BufCommand<'buf> {
     }
 }
 
-impl<C> Contains<C> for ops::Range<C>
-where
-    C: Copy + PartialOrd,
-{
-    fn contains(&self, item: C) -> bool {
-        item >= self.start && item < self.end
-    }
+/// Commands gathered to be sent together, in a buffer of `N` bytes.
+///
+/// Sending a command toggles chip select and data/command for the command byte and again for its
+/// data, which is slow when the pins are driven through sysfs, as on a Raspberry Pi. A batch is
+/// sent with [DisplayInterface::send_batch], which [Interface] does with chip select held low
+/// throughout, only toggling data/command between the command bytes and their data.
+///
+/// Each command takes 3 bytes of the buffer plus its data. Commands that don't fit are dropped,
+/// and [CommandBatch::send] returns `Error::BufferSize` rather than sending part of the batch.
+/// Commands that leave the controller busy, such as `PowerOn`, should end a batch, the caller
+/// then waits for them.
+///
+/// ### Example
+///
+/// ```ignore
+/// let mut batch = CommandBatch::<16>::new();
+/// batch.push(&Command::PanelSetting(DisplayResolution::R96x230));
+/// batch.push(&Command::VCMDCSetting(VcomDc::new(0x0A).unwrap()));
+/// batch.send(&mut interface)?;
+/// ```
+///
+/// [DisplayInterface::send_batch]: ../interface/trait.DisplayInterface.html#method.send_batch
+/// [Interface]: ../interface/struct.Interface.html
+pub struct CommandBatch<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+    // the bytes needed for everything pushed, more than N if some were dropped
+    needed: usize,
 }
 
-impl<C> Contains<C> for ops::RangeInclusive<C>
-where
-    C: Copy + PartialOrd,
-{
-    fn contains(&self, item: C) -> bool {
-        item >= *self.start() && item <= *self.end()
+impl<const N: usize> Default for CommandBatch<N> {
+    fn default() -> Self {
+        CommandBatch::new()
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+impl<const N: usize> CommandBatch<N> {
+    /// Create an empty batch.
+    pub const fn new() -> Self {
+        CommandBatch {
+            buf: [0; N],
+            len: 0,
+            needed: 0,
+        }
+    }
 
-    struct MockInterface {
-        data: [u8; 256],
-        offset: usize,
+    /// Add a command.
+    pub fn push(&mut self, command: &Command) -> &mut Self {
+        let mut buf = [0u8; 7];
+        let (command, data) = command.encode(&mut buf);
+        self.push_raw(command, data)
     }
 
-    impl MockInterface {
-        fn new() -> Self {
-            MockInterface {
-                data: [0; 256],
-                offset: 0,
-            }
+    /// Add a command byte and its data, for commands without a [Command].
+    pub fn push_raw(&mut self, command: u8, data: &[u8]) -> &mut Self {
+        let size = 3 + data.len();
+        self.needed += size;
+        if self.needed > N || data.len() > usize::from(u16::MAX) {
+            return self;
         }
+        let entry = &mut self.buf[self.len..self.len + size];
+        entry[0] = command;
+        entry[1..3].copy_from_slice(&(data.len() as u16).to_le_bytes());
+        entry[3..].copy_from_slice(data);
+        self.len += size;
+        self
+    }
 
-        fn write(&mut self, byte: u8) {
-            self.data[self.offset] = byte;
-            self.offset += 1;
-        }
+    /// Remove every command.
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.needed = 0;
+    }
 
-        fn data(&self) -> &[u8] {
-            &self.data[0..self.offset]
-        }
+    /// Returns true if no commands have been pushed.
+    pub fn is_empty(&self) -> bool {
+        self.needed == 0
     }
 
-    impl DisplayInterface for MockInterface {
-        type Error = ();
+    /// Returns true if every command pushed fit in the buffer.
+    pub fn fits(&self) -> bool {
+        self.needed <= N
+    }
 
-        fn send_command(&mut self, command: u8) -> Result<(), Self::Error> {
-            self.write(command);
-            Ok(())
-        }
+    /// Returns the command bytes and their data, in the order they were pushed.
+    pub fn iter(&self) -> impl Iterator<Item = (u8, &[u8])> {
+        let mut rest = &self.buf[..self.len];
+        core::iter::from_fn(move || {
+            let (&[command, lo, hi], tail) = rest.split_first_chunk::<3>()?;
+            let (data, tail) = tail.split_at(usize::from(u16::from_le_bytes([lo, hi])));
+            rest = tail;
+            Some((command, data))
+        })
+    }
 
-        fn send_data(&mut self, data: &[u8]) -> Result<(), Self::Error> {
-            for byte in data {
-                self.write(*byte)
-            }
-            Ok(())
+    /// Send the batch, with [DisplayInterface::send_batch].
+    ///
+    /// Returns `Error::BufferSize` without sending anything if some commands didn't fit.
+    ///
+    /// [DisplayInterface::send_batch]: ../interface/trait.DisplayInterface.html#method.send_batch
+    pub fn send<I: DisplayInterface>(&self, interface: &mut I) -> Result<(), Error<I::Error>> {
+        if !self.fits() {
+            return Err(Error::BufferSize {
+                expected: self.needed,
+                actual: N,
+            });
         }
+        interface.send_batch(self).map_err(Error::Interface)
+    }
+}
 
-        fn reset<D: hal::blocking::delay::DelayMs<u8>>(&mut self, _delay: &mut D) {
-            self.data = [0; 256];
-            self.offset = 0;
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::MockInterface;
 
-        fn busy_wait(&self) {
-            // nop
-        }
+    #[test]
+    fn test_command_execute() {
+        let mut interface = MockInterface::new();
+        let b = 0xCF;
+        let command = Command::PanelSetting(DisplayResolution::R160x296);
 
-        fn epd_update_data(
-            &mut self,
-            _layer: u8,
-            _nbytes: u16,
-            _buf: &[u8],
-        ) -> Result<(), Self::Error> {
-            Ok(())
-        }
+        command.execute(&mut interface).unwrap();
+        assert_eq!(&interface.data[..], &[0x00, b]);
+    }
 
-        #[cfg(feature = "sram")]
-        fn sram_read(&mut self, _address: u16, _data: &mut [u8]) -> Result<(), Self::Error> {
-            Ok(())
-        }
+    #[test]
+    fn test_execute_busy_waits() {
+        let mut interface = MockInterface::new();
+        Command::PowerOn.execute(&mut interface).unwrap();
+        Command::DisplayRefresh.execute(&mut interface).unwrap();
+        Command::PowerOff.execute(&mut interface).unwrap();
+        assert_eq!(interface.busy_waits, 3);
+
+        Command::PowerOn.execute_no_wait(&mut interface).unwrap();
+        Command::PanelSetting(DisplayResolution::R160x296)
+            .execute(&mut interface)
+            .unwrap();
+        assert_eq!(interface.busy_waits, 3);
+        assert_eq!(&interface.data[..], &[0x04, 0x12, 0x02, 0x04, 0x00, 0xCF]);
+    }
 
-        #[cfg(feature = "sram")]
-        fn sram_write(&mut self, _address: u16, _data: &[u8]) -> Result<(), Self::Error> {
-            Ok(())
-        }
+    #[test]
+    fn test_cdi_encoding() {
+        let mut interface = MockInterface::new();
+        let command =
+            Command::VCOMDataIntervalSetting(0b10, DataPolarity::BWOnly, DataInterval::V12);
+        let extended = command.with_cdi_encoding(CdiEncoding::Extended);
+        extended.execute(&mut interface).unwrap();
+        assert_eq!(&interface.data[..], &[0x50, 0x90, 0x05]);
+        interface.data.clear();
+        extended
+            .with_cdi_encoding(CdiEncoding::Single)
+            .execute(&mut interface)
+            .unwrap();
+        assert_eq!(&interface.data[..], &[0x50, 0x95]);
+        // other commands are unchanged
+        assert!(matches!(
+            Command::PowerOn.with_cdi_encoding(CdiEncoding::Extended),
+            Command::PowerOn
+        ));
+    }
 
-        #[cfg(feature = "sram")]
-        fn sram_clear(&mut self, _address: u16, _nbytes: u16, _val: u8) -> Result<(), Self::Error> {
-            Ok(())
-        }
+    #[test]
+    fn test_resolution_setting() {
+        let mut interface = MockInterface::new();
+        let resolution = Resolution::new(160, 296).unwrap();
+        assert_eq!((resolution.horizontal(), resolution.vertical()), (160, 296));
+        Command::ResolutionSetting(resolution)
+            .execute(&mut interface)
+            .unwrap();
+        assert_eq!(&interface.data[..], &[0x61, 0xA0, 0x01, 0x28]);
+
+        assert!(Resolution::new(Resolution::MAX_HORIZONTAL, Resolution::MAX_VERTICAL).is_some());
+        assert_eq!(Resolution::new(256, 296), None);
+        assert_eq!(Resolution::new(160, 512), None);
+        assert_eq!(Resolution::new(156, 296), None);
+    }
 
-        #[cfg(feature = "sram")]
-        fn sram_epd_update_data(
-            &mut self,
-            _layer: u8,
-            _nbytes: u16,
-            _start_address: u16,
-        ) -> Result<(), Self::Error> {
-            Ok(())
+    #[test]
+    fn test_bounded_values() {
+        assert_eq!(Vdh::new(0x3F), Some(Vdh::new_unchecked(0x3F)));
+        assert_eq!(Vdh::new(0x40), None);
+        assert_eq!(VcomDc::new(0b11_1010).map(VcomDc::value), Some(0b11_1010));
+        assert_eq!(VcomDc::new(0b11_1011), None);
+    }
+
+    #[test]
+    fn test_data_interval_frames() {
+        for frames in 2..=17 {
+            let interval = DataInterval::from_frames(frames).unwrap();
+            assert_eq!(interval.frames(), frames);
+            assert_eq!(interval.value(), 17 - frames);
         }
+        assert_eq!(DataInterval::from_frames(1), None);
+        assert_eq!(DataInterval::from_frames(18), None);
+        assert_eq!(DataInterval::V2.value(), 0b1111);
+        assert_eq!(DataInterval::default().value(), 0b0111);
     }
 
     #[test]
-    fn test_command_execute() {
+    fn test_power_setting_execute() {
         let mut interface = MockInterface::new();
-        let b = 0xCF;
-        let command = Command::PanelSetting(DisplayResolution::R160x296);
+        let command = Command::PowerSetting(
+            Vdh::new(0x2b).unwrap(),
+            Vdl::new(0x2b).unwrap(),
+            Vdhr::new(0x9).unwrap(),
+        );
 
         command.execute(&mut interface).unwrap();
-        assert_eq!(interface.data(), &[0x00, b]);
+        assert_eq!(&interface.data[..], &[0x01, 0x03, 0x00, 0x2b, 0x2b, 0x09]);
+    }
+
+    #[test]
+    fn command_batch() {
+        let mut batch = CommandBatch::<16>::new();
+        assert!(batch.is_empty());
+        batch
+            .push(&Command::PanelSetting(DisplayResolution::R160x296))
+            .push(&Command::PowerOn)
+            .push_raw(0x61, &[0xA0, 0x01, 0x28]);
+        let entries: std::vec::Vec<_> = batch.iter().collect();
+        assert_eq!(
+            entries,
+            [
+                (0x00, &[0xCF][..]),
+                (0x04, &[][..]),
+                (0x61, &[0xA0, 0x01, 0x28][..])
+            ]
+        );
+
+        let mut interface = MockInterface::new();
+        batch.send(&mut interface).unwrap();
+        assert_eq!(
+            &interface.data[..],
+            &[0x00, 0xCF, 0x04, 0x61, 0xA0, 0x01, 0x28]
+        );
+
+        // one byte short, nothing is sent
+        batch.push(&Command::VCMDCSetting(VcomDc::new_unchecked(0x0A)));
+        assert!(!batch.fits());
+        let mut interface = MockInterface::new();
+        assert_eq!(
+            batch.send(&mut interface),
+            Err(Error::BufferSize {
+                expected: 17,
+                actual: 16
+            })
+        );
+        assert!(interface.data.is_empty());
+        batch.clear();
+        assert!(batch.is_empty() && batch.iter().next().is_none());
     }
 }
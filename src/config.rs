@@ -1,5 +1,12 @@
-use command::{Command, DisplayResolution};
-use display::{self, Dimensions, Rotation};
+use crate::color::ColorThresholds;
+use crate::command::{
+    CdiEncoding, Command, DataInterval, DataPolarity, DisplayResolution, VcomDc, Vdh, Vdhr, Vdl,
+};
+use crate::display::{self, Dimensions, Rotation};
+use crate::graphics::{BitOrder, BufferLayout, OutOfBounds, UpdateStrategy};
+use crate::interface::PostInit;
+use crate::luts::LutProfile;
+use crate::profile::PanelProfile;
 
 /// Builder for constructing a display Config.
 ///
@@ -17,61 +24,418 @@ use display::{self, Dimensions, Rotation};
 ///     })
 ///     .rotation(Rotation::Rotate270)
 ///     .build()
-///     .expect("columns must be evenly divisible by 4");
+///     .expect("columns must be evenly divisible by 8");
 /// ```
 pub struct Builder {
     power_setting: Command,
     booster_soft_start: Command,
-    panel_setting: Command,
+    panel_setting: Option<DisplayResolution>,
     pll: Command,
+    vcom_data_interval: Command,
+    cdi_encoding: CdiEncoding,
+    vcom_dc: Command,
+    soft_reset: bool,
+    invert_black_plane: bool,
+    invert_red_plane: bool,
+    power_on_delay_ms: u16,
+    post_vcm_delay_ms: u16,
+    lut_profile: LutProfile,
+    tricolor: bool,
+    buffer_layout: BufferLayout,
+    out_of_bounds: OutOfBounds,
+    update_strategy: UpdateStrategy,
+    color_thresholds: ColorThresholds,
+    bit_orders: [BitOrder; 4],
+    post_init: Option<PostInit>,
     dimensions: Option<Dimensions>,
     rotation: Rotation,
 }
 
-/// Error returned if Builder configuration is invalid.
+/// Power setting, booster soft start and clock presets.
 ///
-/// Currently only returned if a configuration is built without dimensions.
-#[derive(Debug)]
-pub struct BuilderError {}
+/// Trades refresh speed for peak current draw, which matters when running from a coin cell
+/// through a boost converter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PowerProfile {
+    /// The default values used by the Adafruit and Good Display sample code.
+    Standard,
+    /// Longer booster soft start, weaker drive and a slower frame rate for the lowest peak
+    /// current. Refreshes take longer.
+    LowRipple,
+    /// Stronger booster drive and a faster frame rate for the quickest refresh, at the cost of
+    /// higher peak current.
+    FastCharge,
+}
+
+impl PowerProfile {
+    // returns the PWR, BTST and PLL commands for the profile
+    const fn commands(self) -> (Command, Command, Command) {
+        let power = Command::PowerSetting(
+            Vdh::new_unchecked(0x2b),
+            Vdl::new_unchecked(0x2b),
+            Vdhr::new_unchecked(0x9),
+        );
+        match self {
+            PowerProfile::Standard => (
+                power,
+                Command::BoosterSoftStart(0x17, 0x17, 0x17),
+                Command::PLLControl(0x29),
+            ),
+            // 40ms soft start, strength 1
+            PowerProfile::LowRipple => (
+                power,
+                Command::BoosterSoftStart(0xC7, 0xC7, 0x07),
+                Command::PLLControl(0x3A),
+            ),
+            // 10ms soft start, strength 5
+            PowerProfile::FastCharge => (
+                power,
+                Command::BoosterSoftStart(0x27, 0x27, 0x27),
+                Command::PLLControl(0x39),
+            ),
+        }
+    }
+}
+
+/// Error returned if Builder configuration is invalid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BuilderError {
+    /// The configuration was built without dimensions.
+    MissingDimensions,
+    /// The dimensions are too large for the controller.
+    InvalidDimensions {
+        /// The dimensions set with [Builder::dimensions].
+        dimensions: Dimensions,
+    },
+    /// The columns aren't divisible by 8, so a row doesn't fill whole bytes of the buffers and of
+    /// the resolution setting.
+    UnalignedColumns {
+        /// The dimensions set with [Builder::dimensions].
+        dimensions: Dimensions,
+    },
+    /// The dimensions are invalid, but would be valid with rows and columns swapped. They were
+    /// probably given after rotation, rather than in the controller's native orientation.
+    DimensionsLookSwapped {
+        /// The dimensions with rows and columns swapped.
+        suggested: Dimensions,
+    },
+    /// The panel setting resolution is smaller than the dimensions.
+    ResolutionMismatch {
+        /// The resolution set with [Builder::panel_setting].
+        resolution: DisplayResolution,
+        /// The dimensions set with [Builder::dimensions].
+        dimensions: Dimensions,
+    },
+    /// A LUT profile other than `Otp` was set for a tri-color panel, see [Builder::lut_profile].
+    MonoLutOnTricolor,
+}
 
 /// Display configuration.
 ///
 /// Passed to Display::new. Use `Builder` to construct a `Config`.
+#[derive(Clone, Copy)]
 pub struct Config {
     pub(crate) power_setting: Command,
     pub(crate) booster_soft_start: Command,
     pub(crate) panel_setting: Command,
     pub(crate) pll: Command,
+    pub(crate) vcom_data_interval: Command,
+    pub(crate) cdi_encoding: CdiEncoding,
+    pub(crate) vcom_dc: Command,
+    pub(crate) soft_reset: bool,
+    pub(crate) invert_black_plane: bool,
+    pub(crate) invert_red_plane: bool,
+    pub(crate) power_on_delay_ms: u16,
+    pub(crate) post_vcm_delay_ms: u16,
+    pub(crate) lut_profile: LutProfile,
+    pub(crate) tricolor: bool,
+    pub(crate) buffer_layout: BufferLayout,
+    pub(crate) out_of_bounds: OutOfBounds,
+    pub(crate) update_strategy: UpdateStrategy,
+    pub(crate) color_thresholds: ColorThresholds,
+    pub(crate) bit_orders: [BitOrder; 4],
+    pub(crate) post_init: Option<PostInit>,
     pub(crate) dimensions: Dimensions,
     pub(crate) rotation: Rotation,
 }
 
+impl Config {
+    /// Returns the power setting (PWR) command.
+    pub const fn power_setting(&self) -> Command {
+        self.power_setting
+    }
+
+    /// Returns the booster soft start (BTST) command.
+    pub const fn booster_soft_start(&self) -> Command {
+        self.booster_soft_start
+    }
+
+    /// Returns the panel setting (PSR) command.
+    pub const fn panel_setting(&self) -> Command {
+        self.panel_setting
+    }
+
+    /// Returns the PLL control (PLL) command.
+    pub const fn pll(&self) -> Command {
+        self.pll
+    }
+
+    /// Returns the VCOM and data interval setting (CDI) command.
+    pub const fn vcom_data_interval(&self) -> Command {
+        self.vcom_data_interval
+    }
+
+    /// Returns how the VCOM and data interval setting (CDI) is sent.
+    pub const fn cdi_encoding(&self) -> CdiEncoding {
+        self.cdi_encoding
+    }
+
+    /// Returns the VCOM DC setting (VDCS) command.
+    pub const fn vcom_dc(&self) -> Command {
+        self.vcom_dc
+    }
+
+    /// Returns true if the controller is reset with a soft reset.
+    pub const fn soft_reset(&self) -> bool {
+        self.soft_reset
+    }
+
+    /// Returns true if the bits of the B/W plane are inverted.
+    pub const fn invert_black_plane(&self) -> bool {
+        self.invert_black_plane
+    }
+
+    /// Returns true if the bits of the Red plane are inverted.
+    pub const fn invert_red_plane(&self) -> bool {
+        self.invert_red_plane
+    }
+
+    /// Returns the delay after Power ON during initialization, in milliseconds.
+    pub const fn power_on_delay_ms(&self) -> u16 {
+        self.power_on_delay_ms
+    }
+
+    /// Returns the delay after the VCOM DC setting during initialization, in milliseconds.
+    pub const fn post_vcm_delay_ms(&self) -> u16 {
+        self.post_vcm_delay_ms
+    }
+
+    /// Returns the waveforms used to refresh.
+    pub const fn lut_profile(&self) -> LutProfile {
+        self.lut_profile
+    }
+
+    /// Returns the layout of the frame buffers.
+    pub const fn buffer_layout(&self) -> BufferLayout {
+        self.buffer_layout
+    }
+
+    /// Returns what drawing does with pixels outside the display.
+    pub const fn out_of_bounds(&self) -> OutOfBounds {
+        self.out_of_bounds
+    }
+
+    /// Returns how an update sends the buffers.
+    pub const fn update_strategy(&self) -> UpdateStrategy {
+        self.update_strategy
+    }
+
+    /// Returns true if the panel is tri-color.
+    pub const fn tricolor(&self) -> bool {
+        self.tricolor
+    }
+
+    /// Returns how colors are mapped to the panel colors when converted.
+    pub const fn color_thresholds(&self) -> ColorThresholds {
+        self.color_thresholds
+    }
+
+    /// Returns the order of the pixels in each byte of the planes, in the display rotation.
+    pub const fn bit_order(&self) -> BitOrder {
+        self.bit_order_for(self.rotation)
+    }
+
+    /// Returns the order of the pixels in each byte of the planes, in a rotation.
+    pub const fn bit_order_for(&self, rotation: Rotation) -> BitOrder {
+        self.bit_orders[rotation_index(rotation)]
+    }
+
+    /// Returns the hook run at the end of initialization, if there is one.
+    pub const fn post_init(&self) -> Option<PostInit> {
+        self.post_init
+    }
+
+    /// Returns the display dimensions.
+    pub const fn dimensions(&self) -> Dimensions {
+        self.dimensions
+    }
+
+    /// Returns the display rotation.
+    pub const fn rotation(&self) -> Rotation {
+        self.rotation
+    }
+}
+
+// index of a rotation in the settings kept for each one
+const fn rotation_index(rotation: Rotation) -> usize {
+    match rotation {
+        Rotation::Rotate0 => 0,
+        Rotation::Rotate90 => 1,
+        Rotation::Rotate180 => 2,
+        Rotation::Rotate270 => 3,
+    }
+}
+
 impl Default for Builder {
     fn default() -> Self {
-        Builder {
-            power_setting: Command::PowerSetting(0x2b, 0x2b, 0x9),
-            booster_soft_start: Command::BoosterSoftStart(0x17, 0x17, 0x17),
-            panel_setting: Command::PanelSetting(DisplayResolution::R160x296), // 0xCF
-            pll: Command::PLLControl(0x29),                                    // 0x29
-            dimensions: None,
-            rotation: Rotation::default(),
-        }
+        Self::new()
     }
 }
 
 impl Builder {
     /// Create a new Builder.
-    pub fn new() -> Self {
-        Self::default()
+    pub const fn new() -> Self {
+        let (power_setting, booster_soft_start, pll) = PowerProfile::Standard.commands();
+        Builder {
+            power_setting,
+            booster_soft_start,
+            panel_setting: None,
+            pll,
+            vcom_data_interval: Command::VCOMDataIntervalSetting(
+                0x0,
+                DataPolarity::Both,
+                DataInterval::V10,
+            ), // 0x37
+            cdi_encoding: CdiEncoding::Single,
+            vcom_dc: Command::VCMDCSetting(VcomDc::new_unchecked(0xA)),
+            soft_reset: false,
+            invert_black_plane: false,
+            invert_red_plane: false,
+            power_on_delay_ms: 200,
+            post_vcm_delay_ms: 20,
+            lut_profile: LutProfile::Otp,
+            tricolor: false,
+            buffer_layout: BufferLayout::Planar,
+            out_of_bounds: OutOfBounds::Clip,
+            update_strategy: UpdateStrategy::Full,
+            color_thresholds: ColorThresholds::DEFAULT,
+            bit_orders: [BitOrder::MsbFirst; 4],
+            post_init: None,
+            dimensions: None,
+            rotation: Rotation::Rotate0,
+        }
+    }
+
+    /// Create a Builder preset for 2.9" 128x296 tri-color panels.
+    ///
+    /// This matches the Adafruit 2.9" Tri-Color eInk module. The panel setting and
+    /// dimensions agree on 128 columns by 296 rows, so the resolution sent in
+    /// ResolutionSetting (TRES) is the same as the one selected by PanelSetting (PSR).
+    pub const fn tricolor_2in9() -> Self {
+        Self::new()
+            .tricolor(true)
+            .panel_setting(DisplayResolution::R128x296)
+            .power_profile(PowerProfile::Standard)
+            .vcom_data_interval(0x0, DataPolarity::Both, DataInterval::V10)
+            .vcom_dc(VcomDc::new_unchecked(0xA))
+            .dimensions(Dimensions {
+                rows: 296,
+                cols: 128,
+            })
+    }
+
+    /// Create a Builder preset for the Pimoroni Inky pHAT, a 2.13" 212x104 tri-color panel.
+    ///
+    /// The border is driven rather than left floating, and VCOM DC is set to -1.0V. Rotated so
+    /// the pHAT reads in landscape with the header at the top. See the
+    /// [inky](../inky/index.html) module for a ready made display on a Raspberry Pi.
+    pub const fn inky_phat() -> Self {
+        Self::new()
+            .tricolor(true)
+            .power_profile(PowerProfile::Standard)
+            .vcom_data_interval(0b10, DataPolarity::Both, DataInterval::V10)
+            .vcom_dc(VcomDc::new_unchecked(0x12))
+            .dimensions(Dimensions {
+                rows: 212,
+                cols: 104,
+            })
+            .rotation(Rotation::Rotate270)
+    }
+
+    /// Create a Builder from a panel profile, such as one read from EEPROM.
+    ///
+    /// Sets the dimensions, panel setting, power setting, booster soft start, clock, VCOM and
+    /// data interval, VCOM DC level and LUT profile, see the [profile](../profile/index.html)
+    /// module. Other settings, like the rotation, can be set after.
+    pub const fn from_profile(profile: &PanelProfile) -> Self {
+        let (vdh, vdl, vdhr) = profile.power;
+        let [vhh, vhl, vhgl] = profile.booster_soft_start;
+        Self::new()
+            .dimensions(profile.dimensions)
+            .panel_setting(profile.resolution)
+            .power_setting(vdh, vdl, vdhr)
+            .booster_soft_start(vhh, vhl, vhgl)
+            .pll(profile.pll)
+            .vcom_data_interval(
+                profile.border_data,
+                profile.data_polarity,
+                profile.data_interval,
+            )
+            .vcom_dc(profile.vcom_dc)
+            .lut_profile(profile.lut_profile)
+    }
+
+    // the panel settings as a profile for a panel of `dimensions`, the inverse of from_profile
+    pub(crate) const fn to_profile(&self, dimensions: Dimensions) -> PanelProfile {
+        let resolution = match self.panel_setting {
+            Some(resolution) => resolution,
+            None => match DisplayResolution::containing(dimensions.rows, dimensions.cols) {
+                Some(resolution) => resolution,
+                None => DisplayResolution::R160x296,
+            },
+        };
+        let (power, booster_soft_start, pll) =
+            match (self.power_setting, self.booster_soft_start, self.pll) {
+                (
+                    Command::PowerSetting(vdh, vdl, vdhr),
+                    Command::BoosterSoftStart(vhh, vhl, vhgl),
+                    Command::PLLControl(pll),
+                ) => ((vdh, vdl, vdhr), [vhh, vhl, vhgl], pll),
+                _ => panic!("builder holds the wrong power commands"),
+            };
+        let (border_data, data_polarity, data_interval) = match self.vcom_data_interval {
+            Command::VCOMDataIntervalSetting(border, polarity, interval) => {
+                (border, polarity, interval)
+            }
+            _ => panic!("builder holds the wrong vcom data interval command"),
+        };
+        let vcom_dc = match self.vcom_dc {
+            Command::VCMDCSetting(vcom_dc) => vcom_dc,
+            _ => panic!("builder holds the wrong vcom dc command"),
+        };
+        PanelProfile {
+            dimensions,
+            resolution,
+            pll,
+            power,
+            booster_soft_start,
+            border_data,
+            data_polarity,
+            data_interval,
+            vcom_dc,
+            lut_profile: self.lut_profile,
+        }
     }
 
     /// Set the panel
     ///
-    /// Defaults to 160x296. Corresponds to command 0x0.
-    /// Is overwritten by the row and column values in [Builder::dimensions]
-    pub fn panel_setting(self, res: DisplayResolution) -> Self {
+    /// Defaults to the smallest resolution containing the [Builder::dimensions]. Corresponds to
+    /// command 0x0. The row and column values in [Builder::dimensions] are sent in the
+    /// resolution setting (TRES) and take priority, but must fit in this resolution, otherwise
+    /// [Builder::build] returns `BuilderError::ResolutionMismatch`.
+    pub const fn panel_setting(self, res: DisplayResolution) -> Self {
         Self {
-            panel_setting: Command::PanelSetting(res),
+            panel_setting: Some(res),
             ..self
         }
     }
@@ -79,7 +443,7 @@ impl Builder {
     /// Set the power
     ///
     /// Defaults to 0x2b, 0x2b, 0x9. Corresponds to command 0x1.
-    pub fn power_setting(self, vdh: u8, vdl: u8, vdhr: u8) -> Self {
+    pub const fn power_setting(self, vdh: Vdh, vdl: Vdl, vdhr: Vdhr) -> Self {
         Self {
             power_setting: Command::PowerSetting(vdh, vdl, vdhr),
             ..self
@@ -89,7 +453,7 @@ impl Builder {
     /// Set the booster power settings
     ///
     /// Defaults to 0x17, 0x17, 0x17. Corresponds to command 0x6.
-    pub fn booster_soft_start(self, vhh: u8, vhl: u8, vhgl: u8) -> Self {
+    pub const fn booster_soft_start(self, vhh: u8, vhl: u8, vhgl: u8) -> Self {
         Self {
             booster_soft_start: Command::BoosterSoftStart(vhh, vhl, vhgl),
             ..self
@@ -99,33 +463,259 @@ impl Builder {
     /// Set the Clock
     ///
     /// Defaults to 0x29. Corresponds to command 0x30.
-    pub fn pll(self, value: u8) -> Self {
+    pub const fn pll(self, value: u8) -> Self {
         Self {
             pll: Command::PLLControl(value),
             ..self
         }
     }
 
+    /// Set the power setting, booster soft start and clock from a preset.
+    ///
+    /// Defaults to `PowerProfile::Standard`. Overwrites the values set by
+    /// [Builder::power_setting], [Builder::booster_soft_start] and [Builder::pll].
+    pub const fn power_profile(self, profile: PowerProfile) -> Self {
+        let (power_setting, booster_soft_start, pll) = profile.commands();
+        Self {
+            power_setting,
+            booster_soft_start,
+            pll,
+            ..self
+        }
+    }
+
+    /// Set the VCOM and data interval
+    ///
+    /// Defaults to 0x0, Both, V10. Corresponds to command 0x50.
+    pub const fn vcom_data_interval(
+        self,
+        border_data: u8,
+        data_polarity: DataPolarity,
+        interval: DataInterval,
+    ) -> Self {
+        Self {
+            vcom_data_interval: Command::VCOMDataIntervalSetting(
+                border_data,
+                data_polarity,
+                interval,
+            ),
+            ..self
+        }
+    }
+
+    /// Set how the VCOM and data interval setting is sent. Defaults to `Single`.
+    ///
+    /// Some variants of the controller read a second byte, with the border data and data
+    /// polarity in the first and the interval in the second, use `Extended` for those panels.
+    pub const fn cdi_encoding(self, cdi_encoding: CdiEncoding) -> Self {
+        Self {
+            cdi_encoding,
+            ..self
+        }
+    }
+
+    /// Set the VCOM DC level
+    ///
+    /// Defaults to 0xA. Corresponds to command 0x82.
+    pub const fn vcom_dc(self, value: VcomDc) -> Self {
+        Self {
+            vcom_dc: Command::VCMDCSetting(value),
+            ..self
+        }
+    }
+
+    /// Reset the controller with a soft reset as well as the reset pin.
+    ///
+    /// Defaults to false. Enable this when the reset pin isn't connected, and use
+    /// [NoPin](../interface/struct.NoPin.html) for it. A soft reset can't wake the controller
+    /// from deep sleep, so [Display::deep_sleep](../display/struct.Display.html#method.deep_sleep)
+    /// must not be used, the controller will not respond until it is power cycled.
+    pub const fn soft_reset(self, soft_reset: bool) -> Self {
+        Self { soft_reset, ..self }
+    }
+
+    /// Invert the bits of the B/W plane.
+    ///
+    /// Some batches of panels show a negative image, treating a 0 bit as white in the B/W plane.
+    /// When set, [GraphicDisplay](../graphics/struct.GraphicDisplay.html) packs the plane
+    /// inverted. Defaults to false.
+    pub const fn invert_black_plane(self, invert_black_plane: bool) -> Self {
+        Self {
+            invert_black_plane,
+            ..self
+        }
+    }
+
+    /// Invert the bits of the Red plane, as [Builder::invert_black_plane]. Defaults to false.
+    pub const fn invert_red_plane(self, invert_red_plane: bool) -> Self {
+        Self {
+            invert_red_plane,
+            ..self
+        }
+    }
+
+    /// Set the delay after Power ON (PON) during initialization.
+    ///
+    /// Boards with a slow boost converter may need longer for the supplies to settle. Defaults to
+    /// 200 ms.
+    pub const fn power_on_delay_ms(self, power_on_delay_ms: u16) -> Self {
+        Self {
+            power_on_delay_ms,
+            ..self
+        }
+    }
+
+    /// Set the delay after the VCOM DC setting (VDCS) during initialization. Defaults to 20 ms.
+    pub const fn post_vcm_delay_ms(self, post_vcm_delay_ms: u16) -> Self {
+        Self {
+            post_vcm_delay_ms,
+            ..self
+        }
+    }
+
+    /// Set the waveforms used to refresh, see the [luts](../luts/index.html) module.
+    ///
+    /// Profiles other than `Otp` load their tables into the LUT registers during initialization
+    /// and put the controller in black and white (KW) mode, where an update sends the old frame
+    /// and the new B/W plane rather than the two planes. They are only for black and white
+    /// panels, [Builder::build] returns `BuilderError::MonoLutOnTricolor` for a tri-color one.
+    /// Defaults to `Otp`.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// use il0373::luts::LutProfile;
+    /// use il0373::{Builder, Dimensions};
+    ///
+    /// let config = Builder::new()
+    ///     .dimensions(Dimensions {
+    ///         rows: 212,
+    ///         cols: 104,
+    ///     })
+    ///     .lut_profile(LutProfile::FastMono)
+    ///     .build()
+    ///     .expect("invalid config");
+    /// ```
+    pub const fn lut_profile(self, lut_profile: LutProfile) -> Self {
+        Self {
+            lut_profile,
+            ..self
+        }
+    }
+
+    /// Set whether the panel is tri-color, so must be refreshed with the waveforms programmed
+    /// into it. Defaults to false, the tri-color presets set it.
+    pub const fn tricolor(self, tricolor: bool) -> Self {
+        Self { tricolor, ..self }
+    }
+
+    /// Set the layout of the frame buffers of a `GraphicDisplay`. Defaults to `Planar`.
+    ///
+    /// `Interleaved` makes drawing a pixel a single byte read and write, at the cost of an unpack
+    /// pass when updating, see [BufferLayout].
+    ///
+    /// [BufferLayout]: ../graphics/enum.BufferLayout.html
+    pub const fn buffer_layout(self, buffer_layout: BufferLayout) -> Self {
+        Self {
+            buffer_layout,
+            ..self
+        }
+    }
+
+    /// Set what drawing does with pixels outside the display. Defaults to `Clip`.
+    ///
+    /// `Error` and `Panic` help find layout bugs during development, see [OutOfBounds].
+    ///
+    /// [OutOfBounds]: ../graphics/enum.OutOfBounds.html
+    pub const fn out_of_bounds(self, out_of_bounds: OutOfBounds) -> Self {
+        Self {
+            out_of_bounds,
+            ..self
+        }
+    }
+
+    /// Set how an update sends the buffers. Defaults to `Full`.
+    ///
+    /// `DiffUpload` only sends the rows that changed since the last update, when the buffers keep
+    /// the previous frame, and `WhiteFlashFirst` refreshes the panel to white before each update
+    /// to remove ghosting, see [UpdateStrategy].
+    ///
+    /// [UpdateStrategy]: ../graphics/enum.UpdateStrategy.html
+    pub const fn update_strategy(self, update_strategy: UpdateStrategy) -> Self {
+        Self {
+            update_strategy,
+            ..self
+        }
+    }
+
+    /// Set how colors drawn through
+    /// [GraphicDisplay::color_converted](../graphics/struct.GraphicDisplay.html#method.color_converted)
+    /// are mapped to black, white and red. Defaults to `ColorThresholds::DEFAULT`.
+    ///
+    /// Tune it for a batch of panels showing converted photos, see [ColorThresholds].
+    ///
+    /// [ColorThresholds]: ../struct.ColorThresholds.html
+    pub const fn color_thresholds(self, color_thresholds: ColorThresholds) -> Self {
+        Self {
+            color_thresholds,
+            ..self
+        }
+    }
+
+    /// Set the order of the pixels in each byte of the planes, when drawing in a rotation.
+    ///
+    /// Defaults to `BitOrder::MsbFirst` in every rotation, the order the controller expects. Use
+    /// `BitOrder::LsbFirst` for a rotation that shows every 8 pixels mirrored, see [BitOrder].
+    ///
+    /// [BitOrder]: ../graphics/enum.BitOrder.html
+    pub const fn bit_order(self, rotation: Rotation, order: BitOrder) -> Self {
+        let mut bit_orders = self.bit_orders;
+        bit_orders[rotation_index(rotation)] = order;
+        Self { bit_orders, ..self }
+    }
+
+    /// Run a hook at the end of initialization, to send commands the driver doesn't.
+    ///
+    /// The hook is called by `Display::reset` after the resolution setting, with the interface
+    /// as a [CommandSink], for panels that need extra registers set. An interface error in the
+    /// hook is returned from the reset. Defaults to no hook.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// use il0373::{Builder, CommandSink, Dimensions, SinkError};
+    ///
+    /// // TCON setting (TCON)
+    /// fn tcon(sink: &mut dyn CommandSink) -> Result<(), SinkError> {
+    ///     sink.command(0x60, &[0x22])
+    /// }
+    ///
+    /// let config = Builder::new()
+    ///     .dimensions(Dimensions {
+    ///         rows: 212,
+    ///         cols: 104,
+    ///     })
+    ///     .with_post_init(tcon)
+    ///     .build()
+    ///     .expect("invalid config");
+    /// ```
+    ///
+    /// [CommandSink]: ../interface/trait.CommandSink.html
+    pub const fn with_post_init(self, post_init: PostInit) -> Self {
+        Self {
+            post_init: Some(post_init),
+            ..self
+        }
+    }
+
     /// Set the display dimensions.
     ///
     /// There is no default for this setting. The dimensions must be set for the builder to
     /// successfully build a Config.
     ///
-    /// Has higher priority in than the [Builder::panel_setting] value.
-    pub fn dimensions(self, dimensions: Dimensions) -> Self {
-        assert!(
-            dimensions.cols % 4 == 0,
-            "cols must be evenly divisible by 4"
-        );
-        assert!(
-            dimensions.rows <= display::MAX_GATE_OUTPUTS, // GATE is rows
-            "rows must be less than MAX_GATE_OUTPUTS"
-        );
-        assert!(
-            dimensions.cols <= display::MAX_SOURCE_OUTPUTS, // SOURCE is columns
-            "columns must be less than MAX_SOURCE_OUTPUTS"
-        );
-
+    /// Has higher priority in than the [Builder::panel_setting] value. The dimensions are in the
+    /// controller's native orientation, before rotation, and are checked by [Builder::build].
+    pub const fn dimensions(self, dimensions: Dimensions) -> Self {
         Self {
             dimensions: Some(dimensions),
             ..self
@@ -136,21 +726,128 @@ impl Builder {
     ///
     /// Defaults to no rotation (`Rotation::Rotate0`). Use this to translate between the physical
     /// rotation of the display and how the data is displayed on the display.
-    pub fn rotation(self, rotation: Rotation) -> Self {
+    pub const fn rotation(self, rotation: Rotation) -> Self {
         Self { rotation, ..self }
     }
 
     /// Build the display Config.
     ///
-    /// Will fail if dimensions are not set.
-    pub fn build(self) -> Result<Config, BuilderError> {
+    /// Will fail if dimensions are not set, or don't fit in the panel setting resolution.
+    pub const fn build(self) -> Result<Config, BuilderError> {
+        let dimensions = match self.dimensions {
+            Some(dimensions) => dimensions,
+            None => return Err(BuilderError::MissingDimensions),
+        };
+        if !dimensions_fit(dimensions.rows, dimensions.cols as u16) {
+            // a common mistake is passing the dimensions after rotation
+            if dimensions.rows <= u8::MAX as u16
+                && dimensions_valid(dimensions.cols as u16, dimensions.rows)
+            {
+                return Err(BuilderError::DimensionsLookSwapped {
+                    suggested: Dimensions {
+                        rows: dimensions.cols as u16,
+                        cols: dimensions.rows as u8,
+                    },
+                });
+            }
+            return Err(BuilderError::InvalidDimensions { dimensions });
+        }
+        if !dimensions.cols.is_multiple_of(8) {
+            return Err(BuilderError::UnalignedColumns { dimensions });
+        }
+        if self.tricolor && !matches!(self.lut_profile, LutProfile::Otp) {
+            return Err(BuilderError::MonoLutOnTricolor);
+        }
+        let resolution = match self.panel_setting {
+            Some(resolution) => {
+                if resolution.rows() < dimensions.rows || resolution.cols() < dimensions.cols {
+                    return Err(BuilderError::ResolutionMismatch {
+                        resolution,
+                        dimensions,
+                    });
+                }
+                resolution
+            }
+            // dimensions() asserts they fit the largest resolution
+            None => match DisplayResolution::containing(dimensions.rows, dimensions.cols) {
+                Some(resolution) => resolution,
+                None => DisplayResolution::R160x296,
+            },
+        };
         Ok(Config {
             power_setting: self.power_setting,
             booster_soft_start: self.booster_soft_start,
-            panel_setting: self.panel_setting,
+            panel_setting: match self.lut_profile {
+                LutProfile::Otp => Command::PanelSetting(resolution),
+                _ => Command::PanelSettingLut(resolution, true),
+            },
             pll: self.pll,
-            dimensions: self.dimensions.ok_or(BuilderError {})?,
+            vcom_data_interval: self.vcom_data_interval,
+            cdi_encoding: self.cdi_encoding,
+            vcom_dc: self.vcom_dc,
+            soft_reset: self.soft_reset,
+            invert_black_plane: self.invert_black_plane,
+            invert_red_plane: self.invert_red_plane,
+            power_on_delay_ms: self.power_on_delay_ms,
+            post_vcm_delay_ms: self.post_vcm_delay_ms,
+            lut_profile: self.lut_profile,
+            tricolor: self.tricolor,
+            buffer_layout: self.buffer_layout,
+            out_of_bounds: self.out_of_bounds,
+            update_strategy: self.update_strategy,
+            color_thresholds: self.color_thresholds,
+            bit_orders: self.bit_orders,
+            post_init: self.post_init,
+            dimensions,
             rotation: self.rotation,
         })
     }
+
+    /// Build the display Config, panicking if it is invalid.
+    ///
+    /// This allows a Config to be built at compile time, where an invalid configuration is a
+    /// compile error.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// use il0373::config::Config;
+    /// use il0373::{Builder, Rotation};
+    ///
+    /// static CONFIG: Config = Builder::tricolor_2in9()
+    ///     .rotation(Rotation::Rotate90)
+    ///     .build_const();
+    /// ```
+    pub const fn build_const(self) -> Config {
+        match self.build() {
+            Ok(config) => config,
+            Err(BuilderError::MissingDimensions) => panic!("dimensions must be set"),
+            Err(BuilderError::InvalidDimensions { .. }) => panic!(
+                "rows must be at most MAX_GATE_OUTPUTS, and columns at most MAX_SOURCE_OUTPUTS"
+            ),
+            Err(BuilderError::UnalignedColumns { .. }) => {
+                panic!("columns must be evenly divisible by 8")
+            }
+            Err(BuilderError::DimensionsLookSwapped { .. }) => {
+                panic!("dimensions look swapped, rows and columns are before rotation")
+            }
+            Err(BuilderError::ResolutionMismatch { .. }) => {
+                panic!("dimensions must fit in the panel setting resolution")
+            }
+            Err(BuilderError::MonoLutOnTricolor) => {
+                panic!("tri-color panels must use LutProfile::Otp")
+            }
+        }
+    }
+}
+
+// rows fit the gates, and cols fit the sources
+const fn dimensions_fit(rows: u16, cols: u16) -> bool {
+    rows <= display::MAX_GATE_OUTPUTS // GATE is rows
+        && cols <= display::MAX_SOURCE_OUTPUTS as u16 // SOURCE is columns
+}
+
+// the dimensions fit, and each row is whole bytes
+pub(crate) const fn dimensions_valid(rows: u16, cols: u16) -> bool {
+    dimensions_fit(rows, cols) && cols.is_multiple_of(8)
 }
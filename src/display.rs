@@ -1,7 +1,20 @@
-use command::{Command, DataInterval, DataPolarity};
-use config::Config;
-use hal;
-use interface::DisplayInterface;
+use core::cmp::{max, min};
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+use embedded_graphics_core::primitives::Rectangle;
+
+#[cfg(feature = "dual-spi")]
+use crate::command::BusWidth;
+use crate::command::{Command, CommandBatch, DataInterval, DataPolarity, Resolution, VcomDc};
+use crate::config::{self, Config};
+use crate::error::Error;
+use crate::interface::{self, DisplayInterface};
+use crate::luts::LutProfile;
+use crate::odometer::{Odometer, RefreshKind};
+use crate::regs;
+use crate::thermal::{DefaultThermalPolicy, ThermalAdjustment, ThermalPolicy};
+use crate::timing::Clock;
+use crate::wait::{self, Operation};
 
 // Max display resolution is 160x296
 /// The maximum number of rows supported by the controller
@@ -9,7 +22,53 @@ pub const MAX_GATE_OUTPUTS: u16 = 296;
 /// The maximum number of cols supported by the controller
 pub const MAX_SOURCE_OUTPUTS: u8 = 160;
 
+// the batches sent during init hold at most four commands, each encoded with up to 7 bytes of
+// data, so they always fit
+const INIT_BATCH_SIZE: usize = 4 * (3 + 7);
+
+/// The size in bytes of each of the black and red frame buffers for a display.
+///
+/// Panics if the dimensions are not valid for the controller, which is a compile error when
+/// evaluated in a const context such as an array length. See also [buffer_size!].
+///
+/// ### Example
+///
+/// ```ignore
+/// let mut black = [0u8; buffer_size(212, 104)];
+/// ```
+///
+/// [buffer_size!]: ../macro.buffer_size.html
+pub const fn buffer_size(rows: u16, cols: u8) -> usize {
+    assert!(
+        config::dimensions_valid(rows, cols as u16),
+        "invalid display dimensions"
+    );
+    rows as usize * cols as usize / 8
+}
+
+/// The size in bytes of each frame buffer, checked when compiling.
+///
+/// Like [buffer_size](display/fn.buffer_size.html), but always evaluated at compile time, so
+/// invalid dimensions fail the build even outside an array length.
+///
+/// ### Example
+///
+/// ```ignore
+/// const ROWS: u16 = 212;
+/// const COLS: u8 = 104;
+/// let mut black = [0u8; buffer_size!(ROWS, COLS)];
+/// let mut red = [0u8; buffer_size!(ROWS, COLS)];
+/// ```
+#[macro_export]
+macro_rules! buffer_size {
+    ($rows:expr, $cols:expr) => {{
+        const SIZE: usize = $crate::display::buffer_size($rows, $cols);
+        SIZE
+    }};
+}
+
 /// Represents the dimensions of the display.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Dimensions {
     /// The number of rows the display has.
     ///
@@ -26,7 +85,7 @@ pub struct Dimensions {
 /// For example the native orientation of the Inky pHAT display is a tall (portrait) 104x212
 /// display. `Rotate270` can be used to make it the right way up when attached to a Raspberry Pi
 /// Zero with the ports on the top.
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Rotation {
     Rotate0,
     Rotate90,
@@ -41,6 +100,65 @@ impl Default for Rotation {
     }
 }
 
+/// A window of the controller's native gate/source grid, for partial updates.
+///
+/// Sources are byte aligned: `source_start` is a multiple of 8 and `source_end + 1` is a
+/// multiple of 8. Both ranges are inclusive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PartialWindow {
+    /// first source (column)
+    pub source_start: u8,
+    /// last source (column)
+    pub source_end: u8,
+    /// first gate (row)
+    pub gate_start: u16,
+    /// last gate (row)
+    pub gate_end: u16,
+}
+
+/// The power state of the panel, as last set by the driver.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PanelState {
+    /// The controller hasn't been reset since the display was created, the last change of power
+    /// state failed part way, or an interface error interrupted a transfer, see
+    /// [Display::recover].
+    Unknown,
+    /// The controller is initialized and powered on.
+    Awake,
+    /// The panel drivers are powered off, see [Display::standby].
+    Standby,
+    /// The controller is in deep sleep, see [Display::deep_sleep].
+    DeepSleep,
+}
+
+/// A snapshot of what the driver knows about the controller, see [Display::state].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DriverState {
+    /// the power state of the panel
+    pub panel: PanelState,
+    /// whether the last refresh was started, `None` if there hasn't been one
+    ///
+    /// A refresh fails if sending the buffers or the refresh command fails, or if it is skipped
+    /// because the supply is low.
+    pub last_refresh_ok: Option<bool>,
+    /// the number of refreshes started since the display was created
+    pub refreshes: u32,
+    /// whether the B/W and Red planes have been uploaded since the last refresh
+    pub planes_uploaded: [bool; 2],
+}
+
+impl DriverState {
+    /// Returns true if the panel drivers are powered on.
+    pub fn is_powered(&self) -> bool {
+        self.panel == PanelState::Awake
+    }
+
+    /// Returns true if the controller is in deep sleep and needs a reset to wake.
+    pub fn is_asleep(&self) -> bool {
+        self.panel == PanelState::DeepSleep
+    }
+}
+
 /// A configured display with a hardware interface.
 pub struct Display<I>
 where
@@ -48,28 +166,150 @@ where
 {
     interface: I,
     config: Config,
+    state: PanelState,
+    last_refresh_ok: Option<bool>,
+    refreshes: u32,
+    // a refresh was signalled and hasn't been waited for
+    refreshing: bool,
+    uploaded: [bool; 2],
+    // the controller RAM holds the frame kept as the previous one by the buffers
+    frame_in_ram: bool,
+    thermal: ThermalAdjustment,
+    odometer: Odometer,
 }
 
 impl<I> Display<I>
 where
     I: DisplayInterface,
 {
+    /// Time between polls of the busy pin in [Display::wait_for_refresh], in milliseconds.
+    pub const REFRESH_POLL_MS: u8 = 10;
+
     /// Create a new display instance from a DisplayInterface and Config.
     ///
     /// The `Config` is typically created with `config::Builder`.
     pub fn new(interface: I, config: Config) -> Self {
-        Self { interface, config }
+        Self::resume(interface, config, PanelState::Unknown)
+    }
+
+    /// Re-create a display from the parts returned by [Display::release].
+    ///
+    /// No commands are sent, the controller is assumed to still be in `state`. A display in
+    /// standby can be woken with [Display::wake_from_standby] rather than a full reset.
+    pub fn resume(interface: I, config: Config, state: PanelState) -> Self {
+        Self {
+            interface,
+            config,
+            state,
+            last_refresh_ok: None,
+            refreshes: 0,
+            refreshing: false,
+            uploaded: [false; 2],
+            frame_in_ram: false,
+            thermal: ThermalAdjustment::default(),
+            odometer: Odometer::default(),
+        }
+    }
+
+    /// Release the interface, for example to share the SPI bus with another device.
+    ///
+    /// The config and panel state are returned so the display can be re-created with
+    /// [Display::resume].
+    pub fn release(self) -> (I, Config, PanelState) {
+        (self.interface, self.config, self.state)
     }
 
     /// Perform a hardware reset
     ///
-    /// This will wake a controller that has previously entered deep sleep.
+    /// This will wake a controller that has previously entered deep sleep. If the `Config`
+    /// enables a soft reset, it is sent after the hardware reset.
+    ///
+    /// The delay is also passed to [DisplayInterface::reset]. An embedded-hal 1.0 `DelayNs` can
+    /// be borrowed for the reset with the `eh1` feature, as `&mut Eh1::new(&mut delay)`, see the
+    /// [compat](../interface/compat/index.html) module.
     pub fn reset<D: hal::blocking::delay::DelayMs<u8>>(
         &mut self,
         delay: &mut D,
     ) -> Result<(), I::Error> {
         self.interface.reset(delay);
-        self.init(delay)
+        self.set_state(PanelState::Unknown);
+        self.uploaded = [false; 2];
+        self.refreshing = false;
+        if self.config.soft_reset {
+            Command::SoftReset.execute(&mut self.interface)?;
+            delay.delay_ms(10);
+        }
+        self.init(delay)?;
+        self.set_state(PanelState::Awake);
+        Ok(())
+    }
+
+    /// Perform a hardware reset, returning a guard that puts the display into deep sleep when
+    /// dropped.
+    ///
+    /// Leaving the panel powered degrades it, so the guard makes sure an early return, such as
+    /// `?` on an error between `reset` and `deep_sleep`, still powers it down. If the reset fails,
+    /// deep sleep is attempted before returning the error.
+    ///
+    /// A `GraphicDisplay` has its own `reset_guarded`, whose guard can update it.
+    ///
+    /// ### Example
+    ///
+    /// ```ignore
+    /// let mut display = display.reset_guarded(&mut delay)?;
+    /// display.show_const_image(&BLACK, &RED)?;
+    /// display.sleep()?;
+    /// ```
+    pub fn reset_guarded<D: hal::blocking::delay::DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<WakeGuard<'_, I>, I::Error> {
+        WakeGuard::reset(self, delay)
+    }
+
+    /// Perform a hardware reset, then initialize the controller from a table of commands.
+    ///
+    /// Each entry is a command and its data, as found in vendor sample code. The table replaces
+    /// the built-in initialization, which uses the values in the `Config`. The busy pin is waited
+    /// on after Power ON (0x04), Display Refresh (0x12) and Power OFF (0x02) commands.
+    ///
+    /// ### Example
+    ///
+    /// ```ignore
+    /// display.init_from_table(
+    ///     &[
+    ///         (0x01, &[0x03, 0x00, 0x2b, 0x2b, 0x09]),
+    ///         (0x06, &[0x17, 0x17, 0x17]),
+    ///         (0x04, &[]),
+    ///         (0x00, &[0xCF]),
+    ///         (0x50, &[0x37]),
+    ///         (0x30, &[0x29]),
+    ///         (0x61, &[0x68, 0x00, 0xD4]),
+    ///         (0x82, &[0x0A]),
+    ///     ],
+    ///     &mut delay,
+    /// )?;
+    /// ```
+    pub fn init_from_table<D: hal::blocking::delay::DelayMs<u8>>(
+        &mut self,
+        table: &[(u8, &[u8])],
+        delay: &mut D,
+    ) -> Result<(), I::Error> {
+        self.interface.reset(delay);
+        self.set_state(PanelState::Unknown);
+        self.uploaded = [false; 2];
+        self.refreshing = false;
+        for (command, data) in table {
+            self.interface.send_command(*command)?;
+            if !data.is_empty() {
+                self.interface.send_data(data)?;
+            }
+            if let Some(operation) = Operation::for_command(*command) {
+                self.interface.busy_wait_for(operation);
+            }
+        }
+        self.set_state(PanelState::Awake);
+        Ok(())
     }
 
     /// Initialize the controller
@@ -77,44 +317,524 @@ where
         &mut self,
         delay: &mut D,
     ) -> Result<(), I::Error> {
-        self.config.power_setting.execute(&mut self.interface)?;
-        self.config
-            .booster_soft_start
-            .execute(&mut self.interface)?;
-        Command::PowerOn.execute(&mut self.interface)?;
-        delay.delay_ms(200);
-        self.config.panel_setting.execute(&mut self.interface)?;
-        Command::VCOMDataIntervalSetting(0x0, DataPolarity::Both, DataInterval::V10)
-            .execute(&mut self.interface)?;
-        self.config.pll.execute(&mut self.interface)?;
-        Command::VCMDCSetting(0xA).execute(&mut self.interface)?;
-        delay.delay_ms(20);
-        Command::ResolutionSetting(self.config.dimensions.cols, self.config.dimensions.rows)
-            .execute(&mut self.interface)?;
+        // the commands between waits are sent together, see CommandBatch
+        let mut batch = CommandBatch::<INIT_BATCH_SIZE>::new();
+        #[cfg(feature = "dual-spi")]
+        if self.interface.bus_width() == BusWidth::Dual {
+            batch.push(&Command::DualSpi(BusWidth::Dual));
+        }
+        batch
+            .push(&self.config.power_setting)
+            .push(&self.config.booster_soft_start)
+            .push(&Command::PowerOn);
+        self.interface.send_batch(&batch)?;
+        wait::delay_ms(delay, self.config.power_on_delay_ms.into());
+        self.interface.busy_wait_for(Operation::PowerOn);
+        batch.clear();
+        batch.push(&self.config.panel_setting);
+        if let Some(lut) = self.config.lut_profile.lut() {
+            // the tables are too large to batch, so are sent on their own
+            self.interface.send_batch(&batch)?;
+            lut.execute(&mut self.interface)?;
+            batch.clear();
+        }
+        batch
+            .push(&self.data_interval_setting())
+            .push(&self.pll_control())
+            .push(&self.config.vcom_dc);
+        self.interface.send_batch(&batch)?;
+        wait::delay_ms(delay, self.config.post_vcm_delay_ms.into());
+        self.resolution_setting().execute(&mut self.interface)?;
+        if let Some(post_init) = self.config.post_init {
+            interface::run_post_init(&mut self.interface, post_init)?;
+        }
         Ok(())
     }
 
+    // change the panel state
+    fn set_state(&mut self, state: PanelState) {
+        log_debug!("panel state {:?} -> {:?}", self.state, state);
+        if let PanelState::Unknown | PanelState::DeepSleep = state {
+            self.frame_in_ram = false;
+        }
+        self.state = state;
+    }
+
     /// Tell the hardware to update the display
+    ///
+    /// Returns as soon as the refresh is started, so the MCU can do other work. Anything that
+    /// powers off the panel waits for the refresh to finish first.
     pub fn signal_update(&mut self) -> Result<(), I::Error> {
+        self.signal_refresh(RefreshKind::for_profile(self.config.lut_profile))
+    }
+
+    // start a refresh, counting it in the odometer as `kind`
+    pub(crate) fn signal_refresh(&mut self, kind: RefreshKind) -> Result<(), I::Error> {
         // Kick off the display update
-        Command::DisplayRefresh.execute(&mut self.interface)
+        let result = Command::DisplayRefresh.execute_no_wait(&mut self.interface);
+        if result.is_ok() {
+            self.refreshes = self.refreshes.wrapping_add(1);
+            self.refreshing = true;
+            self.odometer.record(kind);
+            self.uploaded = [false; 2];
+        } else {
+            self.record_bus_error();
+        }
+        self.record_refresh(result.is_ok());
+        result
+    }
+
+    // remember whether the last refresh was started
+    pub(crate) fn record_refresh(&mut self, ok: bool) {
+        self.last_refresh_ok = Some(ok);
+    }
+
+    // the controller may have missed part of a transfer, so can't be trusted until it is reset
+    pub(crate) fn record_bus_error(&mut self) {
+        self.set_state(PanelState::Unknown);
+    }
+
+    /// Bring the controller back to a known state after an error.
+    ///
+    /// An interface error part way through an upload or refresh leaves the controller in an
+    /// unknown state, and [Display::refresh] and
+    /// [GraphicDisplay::upload_plane](../graphics/struct.GraphicDisplay.html#method.upload_plane)
+    /// return `Error::InvalidState` until it is reset. This does a hardware reset and
+    /// initialization. With `deghost` the panel is also blanked, as [Display::clear_screen], to
+    /// remove anything left by an interrupted refresh. The display buffers are untouched, so the
+    /// update can then be retried. Use [Error::recoverable](../enum.Error.html#method.recoverable)
+    /// to decide whether retrying is worthwhile.
+    ///
+    /// ### Example
+    ///
+    /// ```ignore
+    /// if let Err(e) = display.update() {
+    ///     if e.recoverable() {
+    ///         display.recover(&mut delay, false)?;
+    ///         display.update()?;
+    ///     }
+    /// }
+    /// ```
+    pub fn recover<D: hal::blocking::delay::DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+        deghost: bool,
+    ) -> Result<(), I::Error> {
+        log_debug!("recovering from {:?}", self.state);
+        if deghost {
+            self.clear_screen(delay)
+        } else {
+            self.reset(delay)
+        }
+    }
+
+    // remember that a layer has been sent to the controller
+    pub(crate) fn record_upload(&mut self, layer: u8) {
+        self.uploaded[usize::from(layer != 0)] = true;
+        self.frame_in_ram = false;
+    }
+
+    // remember whether the controller RAM matches the previous frame kept by the buffers, which
+    // it does once both planes have been sent
+    pub(crate) fn record_frame(&mut self, in_ram: bool) {
+        if in_ram {
+            self.uploaded = [true; 2];
+        }
+        self.frame_in_ram = in_ram;
+    }
+
+    // the controller RAM matches the previous frame kept by the buffers
+    pub(crate) fn frame_in_ram(&self) -> bool {
+        self.frame_in_ram
+    }
+
+    /// Refresh the display from planes already uploaded to the controller.
+    ///
+    /// The controller keeps the planes in its RAM, so they can be uploaded separately, for
+    /// example with [GraphicDisplay::upload_plane] between MCU sleeps, and refreshed once both
+    /// are there. Returns `Error::InvalidState` without refreshing unless the panel is awake and
+    /// both planes have been uploaded since the last refresh, reset or deep sleep.
+    ///
+    /// [GraphicDisplay::upload_plane]: ../graphics/struct.GraphicDisplay.html#method.upload_plane
+    pub fn refresh(&mut self) -> Result<(), Error<I::Error>> {
+        if self.state != PanelState::Awake || self.uploaded != [true; 2] {
+            return Err(Error::InvalidState);
+        }
+        self.signal_update().map_err(Error::Interface)
+    }
+
+    /// Reset the controller and blank the display, without any buffers.
+    ///
+    /// White is streamed directly into both the B/W and Red layers, then the display is refreshed
+    /// and this waits for the refresh to finish. The controller is left powered, call
+    /// [Display::deep_sleep] afterwards if the display will not be updated again.
+    pub fn clear_screen<D: hal::blocking::delay::DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<(), I::Error> {
+        self.reset(delay)?;
+        let (black_mask, red_mask) = self.plane_masks();
+        self.fill_plane(0, 0xFF ^ black_mask)?;
+        self.fill_plane(1, 0xFF ^ red_mask)?;
+        self.signal_update()?;
+        self.finish_refresh();
+        Ok(())
+    }
+
+    /// Send packed B/W and Red images straight to the controller and refresh.
+    ///
+    /// The images are streamed from where they are, usually flash, so no buffers are needed in
+    /// RAM or SRAM. Each must be `rows` * `cols` / `8` bytes, otherwise `Error::BufferSize` is
+    /// returned before anything is sent. The images are in the format of the buffers of a
+    /// [GraphicDisplay], and are inverted as they are sent for a `Config` that inverts a plane.
+    ///
+    /// [GraphicDisplay]: ../graphics/struct.GraphicDisplay.html
+    ///
+    /// ### Example
+    ///
+    /// ```ignore
+    /// static BLACK: [u8; 4736] = *include_bytes!("logo_black.bin");
+    /// static RED: [u8; 4736] = *include_bytes!("logo_red.bin");
+    /// display.reset(&mut delay)?;
+    /// display.show_const_image(&BLACK, &RED)?;
+    /// ```
+    pub fn show_const_image(
+        &mut self,
+        black: &'static [u8],
+        red: &'static [u8],
+    ) -> Result<(), Error<I::Error>> {
+        let nbytes = self.rows() as usize * self.cols() as usize / 8;
+        for image in [black, red].iter() {
+            if image.len() != nbytes {
+                return Err(Error::BufferSize {
+                    expected: nbytes,
+                    actual: image.len(),
+                });
+            }
+        }
+        self.frame_in_ram = false;
+        let (black_mask, red_mask) = self.plane_masks();
+        let uploaded = self
+            .send_image(0, black, black_mask)
+            .and_then(|_| self.send_image(1, red, red_mask));
+        if let Err(e) = uploaded {
+            if let Error::Interface(_) = e {
+                self.record_bus_error();
+            }
+            self.record_refresh(false);
+            return Err(e);
+        }
+        self.signal_update().map_err(Error::Interface)
+    }
+
+    // send an image to a layer, flipping the bits in mask
+    fn send_image(&mut self, layer: u8, image: &[u8], mask: u8) -> Result<(), Error<I::Error>> {
+        if mask == 0 {
+            return self
+                .interface
+                .epd_update_data(layer, image.len() as u16, image);
+        }
+        self.interface
+            .send_command(regs::data_transmission(layer))
+            .map_err(Error::Interface)?;
+        let mut chunk = [0u8; 32];
+        for part in image.chunks(chunk.len()) {
+            for (inverted, byte) in chunk.iter_mut().zip(part) {
+                *inverted = byte ^ mask;
+            }
+            self.interface
+                .send_data(&chunk[..part.len()])
+                .map_err(Error::Interface)?;
+        }
+        Ok(())
+    }
+
+    // bits to flip in each plane for panels with inverted polarity
+    pub(crate) fn plane_masks(&self) -> (u8, u8) {
+        let mask = |invert| if invert { 0xFF } else { 0x00 };
+        (
+            mask(self.config.invert_black_plane),
+            mask(self.config.invert_red_plane),
+        )
+    }
+
+    // send a layer to the controller with every byte set to a value, without a buffer
+    pub(crate) fn fill_plane(&mut self, layer: u8, byte: u8) -> Result<(), I::Error> {
+        let nbytes = self.rows() as usize * self.cols() as usize / 8;
+        self.frame_in_ram = false;
+        self.interface
+            .send_command(regs::data_transmission(layer))?;
+        self.stream(&[byte; 32], nbytes)
+    }
+
+    // send nbytes of data by repeating chunk
+    fn stream(&mut self, chunk: &[u8], mut nbytes: usize) -> Result<(), I::Error> {
+        while nbytes > 0 {
+            let sz = nbytes.min(chunk.len());
+            self.interface.send_data(&chunk[..sz])?;
+            nbytes -= sz;
+        }
+        Ok(())
+    }
+
+    /// Change the data polarity of the VCOM and data interval setting (CDI).
+    ///
+    /// Sends CDI with the border and interval from the `Config`, without a full initialization.
+    /// For example, use `BWOnly` for monochrome updates and switch to `Both` for screens that use
+    /// red. The new polarity is kept in the `Config`, so it is also used after a reset.
+    pub fn set_data_polarity(&mut self, polarity: DataPolarity) -> Result<(), I::Error> {
+        if let Command::VCOMDataIntervalSetting(border, _, interval) =
+            self.config.vcom_data_interval
+        {
+            self.config.vcom_data_interval =
+                Command::VCOMDataIntervalSetting(border, polarity, interval);
+        }
+        self.data_interval_setting().execute(&mut self.interface)
+    }
+
+    /// Change the VCOM DC level (VDCS) between updates.
+    ///
+    /// Sends VDCS without a full initialization, for example to correct contrast drift on a
+    /// panel that has been refreshed many times, see the [vcom](../vcom/index.html) module. The
+    /// new level is kept in the `Config`, so it is also used after a reset.
+    pub fn set_vcom_dc(&mut self, value: VcomDc) -> Result<(), I::Error> {
+        self.config.vcom_dc = Command::VCMDCSetting(value);
+        self.config.vcom_dc.execute(&mut self.interface)
+    }
+
+    /// Change the waveforms used by the following refreshes.
+    ///
+    /// Sends the panel setting and the tables of `profile` without a full initialization, for
+    /// example to follow a few fast refreshes with a full one, see the
+    /// [scheduler](../scheduler/index.html) module. The profile is kept in the `Config`, so it is
+    /// also used after a reset. Tri-color panels must stay on `LutProfile::Otp`, other profiles
+    /// return `Error::Unsupported` without sending anything, see [Config::tricolor].
+    pub fn set_lut_profile(&mut self, profile: LutProfile) -> Result<(), Error<I::Error>> {
+        if self.config.tricolor && profile != LutProfile::Otp {
+            return Err(Error::Unsupported);
+        }
+        if let Command::PanelSetting(resolution) | Command::PanelSettingLut(resolution, _) =
+            self.config.panel_setting
+        {
+            self.config.panel_setting = match profile {
+                LutProfile::Otp => Command::PanelSetting(resolution),
+                _ => Command::PanelSettingLut(resolution, true),
+            };
+        }
+        self.config.lut_profile = profile;
+        self.config
+            .panel_setting
+            .execute(&mut self.interface)
+            .map_err(Error::Interface)?;
+        if let Some(lut) = profile.lut() {
+            lut.execute(&mut self.interface).map_err(Error::Interface)?;
+        }
+        Ok(())
+    }
+
+    /// Compensate for the panel temperature with the [DefaultThermalPolicy].
+    ///
+    /// See [Display::apply_thermal_with].
+    ///
+    /// [DefaultThermalPolicy]: ../thermal/struct.DefaultThermalPolicy.html
+    pub fn apply_thermal(&mut self, temp_c: i8) -> Result<(), I::Error> {
+        self.apply_thermal_with(&mut DefaultThermalPolicy, temp_c)
+    }
+
+    /// Compensate for the panel temperature, measured by the application in °C.
+    ///
+    /// The PLL control (PLL) and VCOM and data interval setting (CDI) adjusted by `policy` are
+    /// sent now, and again whenever the controller is initialized, until the next call. The
+    /// `Config` isn't changed, so returning to a normal temperature restores its values. Call
+    /// before each update with a fresh reading.
+    pub fn apply_thermal_with<P: ThermalPolicy>(
+        &mut self,
+        policy: &mut P,
+        temp_c: i8,
+    ) -> Result<(), I::Error> {
+        self.thermal = policy.adjust(temp_c);
+        self.pll_control().execute(&mut self.interface)?;
+        self.data_interval_setting().execute(&mut self.interface)
+    }
+
+    // the PLL command, with any thermal adjustment
+    fn pll_control(&self) -> Command {
+        match self.thermal.pll {
+            Some(clock) => Command::PLLControl(clock),
+            None => self.config.pll,
+        }
+    }
+
+    // the TRES command for the configured dimensions
+    fn resolution_setting(&self) -> Command {
+        let dimensions = self.config.dimensions;
+        let resolution = Resolution::new(dimensions.cols.into(), dimensions.rows)
+            .expect("dimensions are checked by the Builder");
+        Command::ResolutionSetting(resolution)
+    }
+
+    // the CDI command, with any thermal adjustment
+    fn data_interval_setting(&self) -> Command {
+        let command = match (self.config.vcom_data_interval, self.thermal.data_interval) {
+            (Command::VCOMDataIntervalSetting(border, polarity, _), Some(interval)) => {
+                Command::VCOMDataIntervalSetting(border, polarity, interval)
+            }
+            (command, _) => command,
+        };
+        command.with_cdi_encoding(self.config.cdi_encoding)
+    }
+
+    /// Wait for the controller to finish, returning how long it was busy in milliseconds.
+    ///
+    /// Call after [Display::signal_update] to time a refresh, see the [timing](../timing/index.html)
+    /// module.
+    pub fn busy_wait_timed<C: Clock>(&mut self, clock: &mut C) -> u32 {
+        let start = clock.now_ms();
+        self.interface.busy_wait_for(Operation::Refresh);
+        self.refreshing = false;
+        let elapsed = clock.now_ms().wrapping_sub(start);
+        log_debug!("busy for {} ms", elapsed);
+        elapsed
+    }
+
+    /// Wait for a refresh to finish, giving up after `timeout_ms` milliseconds.
+    ///
+    /// Call after [Display::signal_update]. The busy pin is polled every
+    /// [REFRESH_POLL_MS](Display::REFRESH_POLL_MS), and if the controller is still busy after the
+    /// timeout `Error::RefreshFailed` is returned and the panel state becomes `Unknown`, so the
+    /// failure can be logged and the display brought back with [Display::recover]. The status
+    /// register can only be read over a bidirectional data line, which the interfaces don't
+    /// support, so the busy pin is the only check. An interface that can't report the busy pin,
+    /// see [DisplayInterface::is_busy], is waited on without a timeout.
+    pub fn wait_for_refresh<D: hal::blocking::delay::DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+        timeout_ms: u32,
+    ) -> Result<(), Error<I::Error>> {
+        let mut waited = 0;
+        loop {
+            match self.interface.is_busy() {
+                Some(true) => (),
+                Some(false) => {
+                    self.refreshing = false;
+                    return Ok(());
+                }
+                // waits the fixed time for interfaces without a busy pin
+                None => {
+                    self.finish_refresh();
+                    return Ok(());
+                }
+            }
+            if waited >= timeout_ms {
+                log_debug!("refresh still busy after {} ms", waited);
+                self.record_bus_error();
+                self.record_refresh(false);
+                return Err(Error::RefreshFailed);
+            }
+            let step = (timeout_ms - waited).min(Self::REFRESH_POLL_MS.into());
+            delay.delay_ms(step as u8);
+            waited += step;
+        }
+    }
+
+    // wait for a signalled refresh to finish, if it hasn't been waited for already
+    pub(crate) fn finish_refresh(&mut self) {
+        if self.refreshing {
+            self.interface.busy_wait_for(Operation::Refresh);
+            self.refreshing = false;
+        }
     }
 
     fn power_down(&mut self) -> Result<(), I::Error> {
-        self.interface.busy_wait();
+        self.finish_refresh();
         Command::VCOMDataIntervalSetting(0x0, DataPolarity::BWOnly, DataInterval::V10)
+            .with_cdi_encoding(self.config.cdi_encoding)
             .execute(&mut self.interface)?;
-        Command::VCMDCSetting(0).execute(&mut self.interface)?;
+        Command::VCMDCSetting(VcomDc::new_unchecked(0)).execute(&mut self.interface)?;
         Command::PowerOff.execute(&mut self.interface)
     }
 
+    /// Enter standby by powering off the panel drivers (POF).
+    ///
+    /// The image on the panel is kept, as it is in deep sleep, and the controller keeps its
+    /// registers, so [Display::wake_from_standby] can skip the reset and initialization. Standby
+    /// draws more current than deep sleep, so it suits displays that are updated often.
+    pub fn standby(&mut self) -> Result<(), I::Error> {
+        self.set_state(PanelState::Unknown);
+        self.power_down()?;
+        self.set_state(PanelState::Standby);
+        Ok(())
+    }
+
+    /// Wake the controller after [Display::standby].
+    ///
+    /// Restores the VCOM settings changed when powering off and powers on the panel drivers,
+    /// without a reset or the full initialization. Use `reset` to wake from deep sleep.
+    pub fn wake_from_standby<D: hal::blocking::delay::DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<(), I::Error> {
+        self.set_state(PanelState::Unknown);
+        self.data_interval_setting().execute(&mut self.interface)?;
+        self.config.vcom_dc.execute(&mut self.interface)?;
+        Command::PowerOn.execute_no_wait(&mut self.interface)?;
+        wait::delay_ms(delay, self.config.power_on_delay_ms.into());
+        self.interface.busy_wait_for(Operation::PowerOn);
+        self.set_state(PanelState::Awake);
+        Ok(())
+    }
+
     /// Enter deep sleep mode.
     ///
-    /// This puts the display controller into a low power mode. `reset` must be called to wake it
-    /// from sleep.
+    /// This puts the display controller into a low power mode. The image on the panel is kept,
+    /// but the controller loses its settings, so `reset` must be called to wake it from sleep.
     pub fn deep_sleep(&mut self) -> Result<(), I::Error> {
+        self.set_state(PanelState::Unknown);
+        self.uploaded = [false; 2];
         self.power_down()?;
-        Command::DeepSleep.execute(&mut self.interface)
+        Command::DeepSleep.execute(&mut self.interface)?;
+        self.set_state(PanelState::DeepSleep);
+        Ok(())
+    }
+
+    /// Convert a rectangle in logical (rotated) coordinates to the controller's window.
+    ///
+    /// The window is clamped to the display and widened to byte boundaries. Returns `None` if the
+    /// rectangle doesn't overlap the display.
+    pub fn partial_window_for(&self, area: Rectangle) -> Option<PartialWindow> {
+        let (s0, s1, g0, g1) = self.native_rect(area)?;
+        Some(PartialWindow {
+            source_start: (s0 & !7) as u8,
+            source_end: min(s1 | 7, self.cols() as u32 - 1) as u8,
+            gate_start: g0 as u16,
+            gate_end: g1 as u16,
+        })
+    }
+
+    // clip a rectangle in logical coordinates to the display, and return its corners as
+    // (first source, last source, first gate, last gate), the same mapping as the graphics buffers
+    pub(crate) fn native_rect(&self, area: Rectangle) -> Option<(u32, u32, u32, u32)> {
+        let cols = i32::from(self.cols());
+        let rows = i32::from(self.rows());
+        let (width, height) = match self.rotation() {
+            Rotation::Rotate0 | Rotation::Rotate180 => (cols, rows),
+            Rotation::Rotate90 | Rotation::Rotate270 => (rows, cols),
+        };
+        let bottom_right = area.bottom_right()?;
+        let x0 = max(area.top_left.x, 0);
+        let y0 = max(area.top_left.y, 0);
+        let x1 = min(bottom_right.x, width - 1);
+        let y1 = min(bottom_right.y, height - 1);
+        if x0 > x1 || y0 > y1 {
+            return None;
+        }
+        let (s0, s1, g0, g1) = match self.rotation() {
+            Rotation::Rotate0 => (x0, x1, y0, y1),
+            Rotation::Rotate90 => (cols - 1 - y1, cols - 1 - y0, x0, x1),
+            Rotation::Rotate180 => (cols - 1 - x1, cols - 1 - x0, rows - 1 - y1, rows - 1 - y0),
+            Rotation::Rotate270 => (y0, y1, rows - 1 - x1, rows - 1 - x0),
+        };
+        Some((s0 as u32, s1 as u32, g0 as u32, g1 as u32))
     }
 
     /// Returns the number of rows the display has.
@@ -132,8 +852,278 @@ where
         self.config.rotation
     }
 
+    /// Returns the power state of the panel.
+    pub fn panel_state(&self) -> PanelState {
+        self.state
+    }
+
+    /// Returns a snapshot of the driver state, for retry logic or bug reports.
+    pub fn state(&self) -> DriverState {
+        DriverState {
+            panel: self.state,
+            last_refresh_ok: self.last_refresh_ok,
+            refreshes: self.refreshes,
+            planes_uploaded: self.uploaded,
+        }
+    }
+
+    /// Returns the refreshes counted by kind, see the [odometer](../odometer/index.html)
+    /// module.
+    pub fn odometer(&self) -> Odometer {
+        self.odometer
+    }
+
+    /// Restore an odometer, usually one persisted before the last power cycle.
+    pub fn set_odometer(&mut self, odometer: Odometer) {
+        self.odometer = odometer;
+    }
+
+    /// Returns the configuration the display was created with.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
     /// returns the interface
     pub fn interface(&mut self) -> &mut I {
         &mut self.interface
     }
 }
+
+impl<I> AsMut<Display<I>> for Display<I>
+where
+    I: DisplayInterface,
+{
+    fn as_mut(&mut self) -> &mut Display<I> {
+        self
+    }
+}
+
+/// A display that was reset with [Display::reset_guarded], or a `GraphicDisplay` reset with its
+/// `reset_guarded`, which enters deep sleep when dropped.
+///
+/// Errors entering deep sleep on drop are ignored, use [WakeGuard::sleep] to see them.
+pub struct WakeGuard<'a, I, T = Display<I>>
+where
+    I: DisplayInterface,
+    T: AsMut<Display<I>>,
+{
+    display: &'a mut T,
+    interface: PhantomData<I>,
+}
+
+impl<'a, I, T> WakeGuard<'a, I, T>
+where
+    I: DisplayInterface,
+    T: AsMut<Display<I>>,
+{
+    // reset the display and guard it, or attempt deep sleep if the reset fails
+    pub(crate) fn reset<D: hal::blocking::delay::DelayMs<u8>>(
+        display: &'a mut T,
+        delay: &mut D,
+    ) -> Result<Self, I::Error> {
+        if let Err(e) = display.as_mut().reset(delay) {
+            display.as_mut().deep_sleep().ok();
+            return Err(e);
+        }
+        Ok(WakeGuard {
+            display,
+            interface: PhantomData,
+        })
+    }
+
+    /// Enter deep sleep now, returning any error.
+    pub fn sleep(self) -> Result<(), I::Error> {
+        self.display.as_mut().deep_sleep()
+    }
+}
+
+impl<'a, I, T> Deref for WakeGuard<'a, I, T>
+where
+    I: DisplayInterface,
+    T: AsMut<Display<I>>,
+{
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.display
+    }
+}
+
+impl<'a, I, T> DerefMut for WakeGuard<'a, I, T>
+where
+    I: DisplayInterface,
+    T: AsMut<Display<I>>,
+{
+    fn deref_mut(&mut self) -> &mut T {
+        self.display
+    }
+}
+
+impl<'a, I, T> Drop for WakeGuard<'a, I, T>
+where
+    I: DisplayInterface,
+    T: AsMut<Display<I>>,
+{
+    fn drop(&mut self) {
+        let display = self.display.as_mut();
+        // already asleep after sleep(), or deep_sleep() through the guard
+        if display.state != PanelState::DeepSleep {
+            display.deep_sleep().ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Builder;
+    use crate::interface::{Interface, NoPin};
+    use crate::test_support::{MockInterface, MockPin, MockSpi, RecordingDelay};
+    use crate::wait::{BusyDelays, TimedWait};
+    use embedded_graphics_core::geometry::{Point, Size};
+    use std::vec::Vec;
+
+    // 104 sources x 212 gates
+    fn display(rotation: Rotation) -> Display<MockInterface> {
+        let config = Builder::new()
+            .dimensions(Dimensions {
+                rows: 212,
+                cols: 104,
+            })
+            .rotation(rotation)
+            .build()
+            .unwrap();
+        Display::new(MockInterface::new(), config)
+    }
+
+    #[test]
+    fn buffer_sizes() {
+        assert_eq!(buffer_size(212, 104), 2756);
+        assert_eq!(buffer_size!(296, 128), 4736);
+        let buffer = [0u8; buffer_size!(212, 104)];
+        let display = display(Rotation::Rotate0);
+        assert_eq!(buffer.len(), buffer_size(display.rows(), display.cols()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn buffer_size_invalid() {
+        buffer_size(300, 104);
+    }
+
+    fn window(source_start: u8, source_end: u8, gate_start: u16, gate_end: u16) -> PartialWindow {
+        PartialWindow {
+            source_start,
+            source_end,
+            gate_start,
+            gate_end,
+        }
+    }
+
+    fn rect(x: i32, y: i32, w: u32, h: u32) -> Rectangle {
+        Rectangle::new(Point::new(x, y), Size::new(w, h))
+    }
+
+    #[test]
+    fn partial_window_rotate0() {
+        let display = display(Rotation::Rotate0);
+        assert_eq!(
+            display.partial_window_for(rect(0, 0, 8, 1)),
+            Some(window(0, 7, 0, 0))
+        );
+        assert_eq!(
+            display.partial_window_for(rect(3, 10, 10, 5)),
+            Some(window(0, 15, 10, 14))
+        );
+        assert_eq!(
+            display.partial_window_for(rect(100, 200, 50, 50)),
+            Some(window(96, 103, 200, 211))
+        );
+    }
+
+    #[test]
+    fn partial_window_rotate90() {
+        let display = display(Rotation::Rotate90);
+        // logical y 0 is the last source
+        assert_eq!(
+            display.partial_window_for(rect(0, 0, 1, 1)),
+            Some(window(96, 103, 0, 0))
+        );
+        assert_eq!(
+            display.partial_window_for(rect(20, 8, 10, 8)),
+            Some(window(88, 95, 20, 29))
+        );
+        assert_eq!(
+            display.partial_window_for(rect(211, 103, 1, 1)),
+            Some(window(0, 7, 211, 211))
+        );
+    }
+
+    #[test]
+    fn partial_window_rotate180() {
+        let display = display(Rotation::Rotate180);
+        assert_eq!(
+            display.partial_window_for(rect(0, 0, 1, 1)),
+            Some(window(96, 103, 211, 211))
+        );
+        assert_eq!(
+            display.partial_window_for(rect(4, 0, 8, 2)),
+            Some(window(88, 103, 210, 211))
+        );
+        assert_eq!(
+            display.partial_window_for(rect(0, 0, 104, 212)),
+            Some(window(0, 103, 0, 211))
+        );
+    }
+
+    #[test]
+    fn partial_window_rotate270() {
+        let display = display(Rotation::Rotate270);
+        // logical x 0 is the last gate
+        assert_eq!(
+            display.partial_window_for(rect(0, 0, 1, 1)),
+            Some(window(0, 7, 211, 211))
+        );
+        assert_eq!(
+            display.partial_window_for(rect(200, 15, 12, 2)),
+            Some(window(8, 23, 0, 11))
+        );
+    }
+
+    #[test]
+    fn partial_window_outside() {
+        let display = display(Rotation::Rotate0);
+        assert_eq!(display.partial_window_for(rect(104, 0, 8, 8)), None);
+        assert_eq!(display.partial_window_for(rect(-10, -10, 5, 5)), None);
+        assert_eq!(display.partial_window_for(rect(0, 0, 0, 0)), None);
+        assert_eq!(
+            display.partial_window_for(rect(-4, -4, 8, 8)),
+            Some(window(0, 7, 0, 3))
+        );
+    }
+
+    #[test]
+    fn timed_wait_without_busy_pin() {
+        let mut waits = Vec::new();
+        let mut resets = Vec::new();
+        let wait = TimedWait::new(RecordingDelay(&mut waits), BusyDelays::default());
+        let interface = Interface::new(MockSpi::default(), (MockPin, NoPin, MockPin, MockPin))
+            .wait_strategy(wait);
+        let config = Builder::new()
+            .dimensions(Dimensions { rows: 8, cols: 8 })
+            .build()
+            .unwrap();
+        let mut display = Display::new(interface, config);
+        assert_eq!(display.interface.is_busy(), None);
+        display.reset(&mut RecordingDelay(&mut resets)).unwrap();
+        display.signal_update().unwrap();
+        display
+            .wait_for_refresh(&mut RecordingDelay(&mut resets), 30_000)
+            .unwrap();
+        display.deep_sleep().unwrap();
+        drop(display);
+        // power on, the whole refresh, then power off
+        let waited: u32 = waits.iter().map(|ms| u32::from(*ms)).sum();
+        assert_eq!(waited, 100 + 20_000 + 100);
+    }
+}
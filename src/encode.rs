@@ -0,0 +1,444 @@
+//! Encode the byte stream the driver sends to the controller.
+//!
+//! An [EncodeInterface] is a [DisplayInterface] that writes records to a [Sink] instead of to a
+//! controller. A host can wrap one in a `Display` or `GraphicDisplay`, draw and update as usual,
+//! and store the stream. An MCU can later send the stored stream to a real controller with
+//! [replay].
+//!
+//! The stream is a sequence of records, each starting with a tag byte:
+//!
+//! * `0x00 command` - a command byte
+//! * `0x01 len_hi len_lo data...` - up to 65535 data bytes
+//! * `0x02` - a hardware reset
+//! * `0x03` - wait for the controller to not be busy
+//! * `0x04 command` - wait for the operation started by a command, PON, DRF or POF, to finish
+//! * `0x05 ms...` - a delay, in big endian u32 milliseconds
+//!
+//! Delays the driver makes during initialization, such as the power on delay, are recorded by
+//! passing an [EncodeDelay] on the same sink where the driver takes a delay.
+//!
+//! ### Example
+//!
+//! ```
+//! use il0373::{encode, Builder, Dimensions};
+//!
+//! let config = Builder::new()
+//!     .dimensions(Dimensions { rows: 8, cols: 8 })
+//!     .build()
+//!     .unwrap();
+//! let (black, red) = ([0xFFu8; 8], [0xFFu8; 8]);
+//! let mut stream = [0u8; 128];
+//! let mut len = 0;
+//! encode::encode_frame(config, &black, &red, &mut |bytes: &[u8]| {
+//!     stream[len..len + bytes.len()].copy_from_slice(bytes);
+//!     len += bytes.len();
+//! })
+//! .unwrap();
+//! ```
+
+use crate::config::Config;
+use crate::display::Display;
+use crate::error::Error;
+use crate::interface::DisplayInterface;
+use crate::regs;
+use crate::wait::{self, Operation};
+use core::cell::RefCell;
+use core::convert::Infallible;
+
+const TAG_COMMAND: u8 = 0x00;
+const TAG_DATA: u8 = 0x01;
+const TAG_RESET: u8 = 0x02;
+const TAG_BUSY_WAIT: u8 = 0x03;
+const TAG_BUSY_WAIT_FOR: u8 = 0x04;
+const TAG_DELAY: u8 = 0x05;
+
+/// Destination for encoded bytes.
+pub trait Sink {
+    /// Append bytes to the stream.
+    fn write(&mut self, bytes: &[u8]);
+}
+
+impl<F> Sink for F
+where
+    F: FnMut(&[u8]),
+{
+    fn write(&mut self, bytes: &[u8]) {
+        self(bytes)
+    }
+}
+
+/// A sink in a `RefCell`, shared by an [EncodeInterface] and an [EncodeDelay].
+impl<S> Sink for &RefCell<S>
+where
+    S: Sink,
+{
+    fn write(&mut self, bytes: &[u8]) {
+        self.borrow_mut().write(bytes)
+    }
+}
+
+/// A delay that encodes each delay into a stream, for the delays the driver makes between
+/// commands.
+///
+/// ### Example
+///
+/// ```ignore
+/// let sink = RefCell::new(sink);
+/// let mut display = Display::new(EncodeInterface::new(&mut &sink), config);
+/// display.reset(&mut EncodeDelay::new(&sink))?;
+/// ```
+pub struct EncodeDelay<'a, S> {
+    sink: &'a RefCell<S>,
+}
+
+impl<'a, S> EncodeDelay<'a, S>
+where
+    S: Sink,
+{
+    /// Create a new delay, writing to the sink in `sink`.
+    pub fn new(sink: &'a RefCell<S>) -> Self {
+        EncodeDelay { sink }
+    }
+}
+
+impl<'a, S> hal::blocking::delay::DelayMs<u8> for EncodeDelay<'a, S>
+where
+    S: Sink,
+{
+    fn delay_ms(&mut self, ms: u8) {
+        let ms = u32::from(ms).to_be_bytes();
+        self.sink
+            .borrow_mut()
+            .write(&[TAG_DELAY, ms[0], ms[1], ms[2], ms[3]]);
+    }
+}
+
+/// A display interface that encodes everything sent to it.
+pub struct EncodeInterface<'a, S>
+where
+    S: Sink,
+{
+    sink: &'a mut S,
+}
+
+impl<'a, S> EncodeInterface<'a, S>
+where
+    S: Sink,
+{
+    /// Create a new interface, writing the stream to `sink`.
+    pub fn new(sink: &'a mut S) -> Self {
+        EncodeInterface { sink }
+    }
+
+    // send a plane with its bits flipped by invert, in the same records as send_data
+    fn send_plane(&mut self, layer: u8, plane: &[u8], invert: u8) {
+        self.sink
+            .write(&[TAG_COMMAND, regs::data_transmission(layer)]);
+        for chunk in plane.chunks(u16::MAX as usize) {
+            let len = chunk.len() as u16;
+            self.sink
+                .write(&[TAG_DATA, (len >> 8) as u8, (len & 0xFF) as u8]);
+            let mut inverted = [0u8; 32];
+            for part in chunk.chunks(inverted.len()) {
+                for (byte, b) in inverted.iter_mut().zip(part) {
+                    *byte = b ^ invert;
+                }
+                self.sink.write(&inverted[..part.len()]);
+            }
+        }
+    }
+}
+
+impl<'a, S> DisplayInterface for EncodeInterface<'a, S>
+where
+    S: Sink,
+{
+    type Error = Infallible;
+
+    fn send_command(&mut self, command: u8) -> Result<(), Self::Error> {
+        self.sink.write(&[TAG_COMMAND, command]);
+        Ok(())
+    }
+
+    fn send_data(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        for chunk in data.chunks(u16::MAX as usize) {
+            let len = chunk.len() as u16;
+            self.sink
+                .write(&[TAG_DATA, (len >> 8) as u8, (len & 0xFF) as u8]);
+            self.sink.write(chunk);
+        }
+        Ok(())
+    }
+
+    fn reset<D: hal::blocking::delay::DelayMs<u8>>(&mut self, _delay: &mut D) {
+        self.sink.write(&[TAG_RESET]);
+    }
+
+    fn busy_wait(&mut self) {
+        self.sink.write(&[TAG_BUSY_WAIT]);
+    }
+
+    fn busy_wait_for(&mut self, operation: Operation) {
+        self.sink.write(&[TAG_BUSY_WAIT_FOR, operation.command()]);
+    }
+
+    fn epd_update_data(
+        &mut self,
+        layer: u8,
+        nbytes: u16,
+        buf: &[u8],
+    ) -> Result<(), Error<Self::Error>> {
+        if nbytes as usize != buf.len() {
+            return Err(Error::BufferSize {
+                expected: nbytes as usize,
+                actual: buf.len(),
+            });
+        }
+        self.send_command(regs::data_transmission(layer))
+            .map_err(Error::Interface)?;
+        self.send_data(buf).map_err(Error::Interface)
+    }
+}
+
+/// Encode a complete update from packed B/W and Red buffers.
+///
+/// The stream resets and initializes the controller from `config`, sends both buffers, refreshes
+/// the display and puts the controller into deep sleep, exactly as the driver would. The buffers
+/// are in the controller's usual polarity, a plane inverted by `config` is inverted as it is sent.
+/// Returns `Error::BufferSize` if either buffer is not `rows` * `cols` / `8` bytes.
+pub fn encode_frame<S: Sink>(
+    config: Config,
+    black: &[u8],
+    red: &[u8],
+    sink: &mut S,
+) -> Result<(), Error<Infallible>> {
+    let nbytes = (config.dimensions().rows as usize * config.dimensions().cols as usize) / 8;
+    if let Some(actual) = [black.len(), red.len()].into_iter().find(|&l| l != nbytes) {
+        return Err(Error::BufferSize {
+            expected: nbytes,
+            actual,
+        });
+    }
+    let mask = |invert| if invert { 0xFF } else { 0x00 };
+    // shared with the delay, so the delays land between the commands
+    let sink = RefCell::new(|bytes: &[u8]| sink.write(bytes));
+    let mut shared = &sink;
+    let mut display = Display::new(EncodeInterface::new(&mut shared), config);
+    display
+        .reset(&mut EncodeDelay::new(&sink))
+        .map_err(Error::Interface)?;
+    display
+        .interface()
+        .send_plane(0, black, mask(config.invert_black_plane()));
+    display
+        .interface()
+        .send_plane(1, red, mask(config.invert_red_plane()));
+    display.signal_update().map_err(Error::Interface)?;
+    display.deep_sleep().map_err(Error::Interface)
+}
+
+/// Send an encoded stream to a controller.
+///
+/// Returns `Error::InvalidStream` if the stream is malformed. Records before the malformed one
+/// will already have been sent.
+pub fn replay<I, D>(interface: &mut I, delay: &mut D, stream: &[u8]) -> Result<(), Error<I::Error>>
+where
+    I: DisplayInterface,
+    D: hal::blocking::delay::DelayMs<u8>,
+{
+    let mut rest = stream;
+    while let Some((&tag, tail)) = rest.split_first() {
+        rest = match tag {
+            TAG_COMMAND => {
+                let (&command, tail) = tail.split_first().ok_or(Error::InvalidStream)?;
+                interface.send_command(command).map_err(Error::Interface)?;
+                tail
+            }
+            TAG_DATA => {
+                if tail.len() < 2 {
+                    return Err(Error::InvalidStream);
+                }
+                let len = ((tail[0] as usize) << 8) | tail[1] as usize;
+                let data = tail.get(2..2 + len).ok_or(Error::InvalidStream)?;
+                interface.send_data(data).map_err(Error::Interface)?;
+                &tail[2 + len..]
+            }
+            TAG_RESET => {
+                interface.reset(delay);
+                tail
+            }
+            TAG_BUSY_WAIT => {
+                interface.busy_wait();
+                tail
+            }
+            TAG_BUSY_WAIT_FOR => {
+                let (&command, tail) = tail.split_first().ok_or(Error::InvalidStream)?;
+                let operation = Operation::for_command(command).ok_or(Error::InvalidStream)?;
+                interface.busy_wait_for(operation);
+                tail
+            }
+            TAG_DELAY => {
+                let ms = tail.get(..4).ok_or(Error::InvalidStream)?;
+                wait::delay_ms(delay, u32::from_be_bytes([ms[0], ms[1], ms[2], ms[3]]));
+                &tail[4..]
+            }
+            _ => return Err(Error::InvalidStream),
+        };
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Builder;
+    use crate::display::Dimensions;
+    use crate::test_support::{MockInterface, RecordingDelay};
+    use std::vec::Vec;
+
+    struct NoDelay;
+
+    impl hal::blocking::delay::DelayMs<u8> for NoDelay {
+        fn delay_ms(&mut self, _ms: u8) {}
+    }
+
+    #[test]
+    fn encode_and_replay() {
+        let config = Builder::new()
+            .dimensions(Dimensions { rows: 2, cols: 8 })
+            .build()
+            .unwrap();
+        let mut stream = Vec::new();
+        encode_frame(
+            config,
+            &[0x12, 0x34],
+            &[0x56, 0x78],
+            &mut |bytes: &[u8]| stream.extend_from_slice(bytes),
+        )
+        .unwrap();
+
+        let mut interface = MockInterface::default();
+        replay(&mut interface, &mut NoDelay, &stream).unwrap();
+        assert_eq!(interface.resets, 1);
+        assert_eq!(interface.busy_waits, 3);
+        #[rustfmt::skip]
+        assert_eq!(
+            interface.data[23..],
+            [
+                0x10, 0x12, 0x34,
+                0x13, 0x56, 0x78,
+                0x12,
+                0x50, 0x17,
+                0x82, 0x00,
+                0x02,
+                0x07, 0xA5,
+            ]
+        );
+    }
+
+    #[test]
+    fn replay_keeps_delays() {
+        let config = Builder::new()
+            .dimensions(Dimensions { rows: 2, cols: 8 })
+            .power_on_delay_ms(300)
+            .post_vcm_delay_ms(5)
+            .soft_reset(true)
+            .build()
+            .unwrap();
+        let mut stream = Vec::new();
+        encode_frame(
+            config,
+            &[0x12, 0x34],
+            &[0x56, 0x78],
+            &mut |bytes: &[u8]| stream.extend_from_slice(bytes),
+        )
+        .unwrap();
+
+        let mut delays = Vec::new();
+        let mut interface = MockInterface::default();
+        replay(&mut interface, &mut RecordingDelay(&mut delays), &stream).unwrap();
+        // the soft reset, power on and post VCM delays, as a live reset makes them
+        assert_eq!(delays, [10, 255, 45, 5]);
+    }
+
+    #[test]
+    fn encode_inverted_plane() {
+        let config = Builder::new()
+            .dimensions(Dimensions { rows: 2, cols: 8 })
+            .invert_red_plane(true)
+            .build()
+            .unwrap();
+        let mut stream = Vec::new();
+        encode_frame(
+            config,
+            &[0x12, 0x34],
+            &[0x56, 0x78],
+            &mut |bytes: &[u8]| stream.extend_from_slice(bytes),
+        )
+        .unwrap();
+
+        let mut interface = MockInterface::default();
+        replay(&mut interface, &mut NoDelay, &stream).unwrap();
+        assert_eq!(interface.data[23..29], [0x10, 0x12, 0x34, 0x13, 0xA9, 0x87]);
+    }
+
+    #[test]
+    fn encode_wrong_size() {
+        let config = Builder::new()
+            .dimensions(Dimensions { rows: 2, cols: 8 })
+            .build()
+            .unwrap();
+        let mut sink = |_: &[u8]| {};
+        assert_eq!(
+            encode_frame(config, &[0x12, 0x34], &[0x56], &mut sink),
+            Err(Error::BufferSize {
+                expected: 2,
+                actual: 1
+            })
+        );
+    }
+
+    #[test]
+    fn sleep_without_refresh() {
+        let config = Builder::new()
+            .dimensions(Dimensions { rows: 2, cols: 8 })
+            .build()
+            .unwrap();
+        let mut stream = Vec::new();
+        {
+            let mut sink = |bytes: &[u8]| stream.extend_from_slice(bytes);
+            let mut display = Display::new(EncodeInterface::new(&mut sink), config);
+            display.reset(&mut NoDelay).unwrap();
+            display.deep_sleep().unwrap();
+        }
+        // only power on and off are waited for
+        let mut interface = MockInterface::default();
+        replay(&mut interface, &mut NoDelay, &stream).unwrap();
+        assert_eq!(interface.busy_waits, 2);
+    }
+
+    #[test]
+    fn replay_malformed() {
+        let mut interface = MockInterface::default();
+        assert_eq!(
+            replay(&mut interface, &mut NoDelay, &[0x01, 0x00, 0x03, 0xFF]),
+            Err(Error::InvalidStream)
+        );
+        assert_eq!(
+            replay(&mut interface, &mut NoDelay, &[0x04]),
+            Err(Error::InvalidStream)
+        );
+        assert_eq!(
+            replay(&mut interface, &mut NoDelay, &[0x04, 0x10]),
+            Err(Error::InvalidStream)
+        );
+        assert_eq!(
+            replay(&mut interface, &mut NoDelay, &[0x05, 0x00, 0x00, 0x01]),
+            Err(Error::InvalidStream)
+        );
+        assert_eq!(
+            replay(&mut interface, &mut NoDelay, &[0x06]),
+            Err(Error::InvalidStream)
+        );
+    }
+}
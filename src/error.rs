@@ -0,0 +1,53 @@
+/// Errors returned by display operations that do more than pass data to the interface.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error<E> {
+    /// An error from the interface, usually from the SPI bus.
+    Interface(E),
+    /// A buffer or transfer length didn't match the expected length.
+    BufferSize {
+        /// The expected length in bytes.
+        expected: usize,
+        /// The supplied length in bytes.
+        actual: usize,
+    },
+    /// An encoded stream was malformed.
+    InvalidStream,
+    /// The supply check failed, so the refresh wasn't started.
+    SupplyLow,
+    /// The panel isn't in a state for the operation, for example a refresh before both planes
+    /// were uploaded, or an upload while in deep sleep.
+    InvalidState,
+    /// The refresh didn't finish within the timeout, see
+    /// [Display::wait_for_refresh](display/struct.Display.html#method.wait_for_refresh).
+    RefreshFailed,
+    /// A pixel was drawn outside the display, with `OutOfBounds::Error`.
+    OutOfBounds {
+        /// The x coordinate of the pixel, in drawing coordinates.
+        x: i32,
+        /// The y coordinate of the pixel, in drawing coordinates.
+        y: i32,
+    },
+    /// The operation isn't supported, for example an interleaved update from a
+    /// `FrameBufferStore` that only holds planar buffers.
+    Unsupported,
+}
+
+impl<E> Error<E> {
+    /// Returns true if the operation may succeed if retried after
+    /// [Display::recover](display/struct.Display.html#method.recover).
+    ///
+    /// Interface errors are usually transient bus glitches, a low supply may recover, and a
+    /// recovery resets the panel state. Wrong buffer sizes, malformed streams, pixels out of
+    /// bounds and unsupported operations fail again however often they are retried.
+    pub fn recoverable(&self) -> bool {
+        match self {
+            Error::Interface(_) | Error::SupplyLow | Error::InvalidState | Error::RefreshFailed => {
+                true
+            }
+            Error::BufferSize { .. }
+            | Error::InvalidStream
+            | Error::OutOfBounds { .. }
+            | Error::Unsupported => false,
+        }
+    }
+}
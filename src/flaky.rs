@@ -0,0 +1,267 @@
+//! Injecting failures into an interface, to test error handling.
+//!
+//! A [FlakyInterface] wraps any [DisplayInterface] and fails on cue: a chosen write returns an
+//! error, the busy pin sticks, or resets are ignored. Application code and the driver's own tests
+//! use it to check that errors are returned, retries give up and
+//! [Display::recover](../display/struct.Display.html#method.recover) brings the panel back, the
+//! same way on every run rather than on flaky hardware.
+//!
+//! Only available with the `test` feature.
+//!
+//! ### Example
+//!
+//! ```ignore
+//! let mut display = GraphicDisplay::new(
+//!     Display::new(FlakyInterface::new(interface), config),
+//!     &mut black,
+//!     &mut red,
+//! );
+//! display.reset(&mut delay)?;
+//! // the third write of the update fails
+//! display.interface().fail_write(3);
+//! assert_eq!(display.update(), Err(Error::Interface(FlakyError::Injected)));
+//! display.recover(&mut delay, false)?;
+//! display.update()?;
+//! ```
+
+#[cfg(feature = "dual-spi")]
+use crate::command::BusWidth;
+use crate::error::Error;
+use crate::interface::DisplayInterface;
+use crate::wait::Operation;
+
+/// Error returned by a [FlakyInterface].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FlakyError<E> {
+    /// A failure set up with [FlakyInterface::fail_write].
+    Injected,
+    /// An error from the wrapped interface.
+    Inner(E),
+}
+
+/// A [DisplayInterface] that injects failures into another, see the [module
+/// documentation](index.html).
+///
+/// Commands, data and buffer updates each count as a write. A batch counts a write for each
+/// command and its data, as it is sent a command at a time.
+pub struct FlakyInterface<I> {
+    inner: I,
+    // writes until the failing one, 1 for the next
+    fail_in: Option<u32>,
+    busy_stuck: bool,
+    ignore_reset: bool,
+    writes: u32,
+    resets: u32,
+}
+
+impl<I> FlakyInterface<I>
+where
+    I: DisplayInterface,
+{
+    /// Wrap an interface, without any failures set up.
+    pub fn new(inner: I) -> Self {
+        FlakyInterface {
+            inner,
+            fail_in: None,
+            busy_stuck: false,
+            ignore_reset: false,
+            writes: 0,
+            resets: 0,
+        }
+    }
+
+    /// Fail the nth write from now, 1 being the next, once. 0 cancels a failure not yet
+    /// injected.
+    ///
+    /// The failing write isn't passed to the wrapped interface.
+    pub fn fail_write(&mut self, n: u32) {
+        self.fail_in = (n > 0).then_some(n);
+    }
+
+    /// Stick the busy pin, so the controller never finishes.
+    ///
+    /// [DisplayInterface::is_busy] returns true and the waits return at once, as a stuck pin
+    /// would otherwise hang the test. Waits with a timeout, like
+    /// [Display::wait_for_refresh](../display/struct.Display.html#method.wait_for_refresh), see
+    /// the controller still busy.
+    pub fn set_busy_stuck(&mut self, busy_stuck: bool) {
+        self.busy_stuck = busy_stuck;
+    }
+
+    /// Ignore resets, as a controller with its reset line broken would.
+    ///
+    /// The resets are still counted, but not passed to the wrapped interface.
+    pub fn set_ignore_reset(&mut self, ignore_reset: bool) {
+        self.ignore_reset = ignore_reset;
+    }
+
+    /// Returns the number of writes so far, including failed ones.
+    pub fn writes(&self) -> u32 {
+        self.writes
+    }
+
+    /// Returns the number of resets so far, including ignored ones.
+    pub fn resets(&self) -> u32 {
+        self.resets
+    }
+
+    /// Returns the wrapped interface.
+    pub fn inner(&mut self) -> &mut I {
+        &mut self.inner
+    }
+
+    /// Release the wrapped interface.
+    pub fn release(self) -> I {
+        self.inner
+    }
+
+    // count a write, failing it if it's the one set up
+    fn write(&mut self) -> Result<(), FlakyError<I::Error>> {
+        self.writes = self.writes.saturating_add(1);
+        match self.fail_in {
+            Some(1) => {
+                self.fail_in = None;
+                Err(FlakyError::Injected)
+            }
+            Some(n) => {
+                self.fail_in = Some(n - 1);
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl<I> DisplayInterface for FlakyInterface<I>
+where
+    I: DisplayInterface,
+{
+    type Error = FlakyError<I::Error>;
+
+    fn send_command(&mut self, command: u8) -> Result<(), Self::Error> {
+        self.write()?;
+        self.inner.send_command(command).map_err(FlakyError::Inner)
+    }
+
+    fn send_data(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        self.write()?;
+        self.inner.send_data(data).map_err(FlakyError::Inner)
+    }
+
+    fn reset<D: hal::blocking::delay::DelayMs<u8>>(&mut self, delay: &mut D) {
+        self.resets = self.resets.saturating_add(1);
+        if !self.ignore_reset {
+            self.inner.reset(delay);
+        }
+    }
+
+    fn busy_wait(&mut self) {
+        if !self.busy_stuck {
+            self.inner.busy_wait();
+        }
+    }
+
+    fn busy_wait_for(&mut self, operation: Operation) {
+        if !self.busy_stuck {
+            self.inner.busy_wait_for(operation);
+        }
+    }
+
+    fn is_busy(&mut self) -> Option<bool> {
+        if self.busy_stuck {
+            Some(true)
+        } else {
+            self.inner.is_busy()
+        }
+    }
+
+    #[cfg(feature = "dual-spi")]
+    fn bus_width(&self) -> BusWidth {
+        self.inner.bus_width()
+    }
+
+    fn epd_update_data(
+        &mut self,
+        layer: u8,
+        nbytes: u16,
+        buf: &[u8],
+    ) -> Result<(), Error<Self::Error>> {
+        self.write().map_err(Error::Interface)?;
+        self.inner
+            .epd_update_data(layer, nbytes, buf)
+            .map_err(|e| match e {
+                Error::Interface(e) => Error::Interface(FlakyError::Inner(e)),
+                Error::BufferSize { expected, actual } => Error::BufferSize { expected, actual },
+                Error::InvalidStream => Error::InvalidStream,
+                Error::SupplyLow => Error::SupplyLow,
+                Error::InvalidState => Error::InvalidState,
+                Error::RefreshFailed => Error::RefreshFailed,
+                Error::OutOfBounds { x, y } => Error::OutOfBounds { x, y },
+                Error::Unsupported => Error::Unsupported,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::PanelState;
+    use crate::test_support::{MockInterface, RecordingDelay};
+    use crate::{Builder, Dimensions, Display, GraphicDisplay};
+    use std::vec::Vec;
+
+    fn build_display() -> Display<FlakyInterface<MockInterface>> {
+        let config = Builder::new()
+            .dimensions(Dimensions { rows: 3, cols: 8 })
+            .build()
+            .unwrap();
+        Display::new(FlakyInterface::new(MockInterface::new()), config)
+    }
+
+    #[test]
+    fn injected_write_recovers() {
+        let mut delays = Vec::new();
+        let mut delay = RecordingDelay(&mut delays);
+        let (mut black, mut red) = ([0u8; 3], [0u8; 3]);
+        let mut display = GraphicDisplay::new(build_display(), &mut black, &mut red);
+        display.reset(&mut delay).unwrap();
+        // the Red plane
+        display.interface().fail_write(2);
+        assert_eq!(
+            display.update(),
+            Err(Error::Interface(FlakyError::Injected))
+        );
+        assert_eq!(display.state().panel, PanelState::Unknown);
+        assert_eq!(display.refresh(), Err(Error::InvalidState));
+        display.recover(&mut delay, false).unwrap();
+        display.update().unwrap();
+        assert_eq!(display.interface().resets(), 2);
+    }
+
+    #[test]
+    fn busy_stuck() {
+        let mut delays = Vec::new();
+        let mut display = build_display();
+        display.reset(&mut RecordingDelay(&mut delays)).unwrap();
+        display.interface().set_busy_stuck(true);
+        display.signal_update().unwrap();
+        delays.clear();
+        assert_eq!(
+            display.wait_for_refresh(&mut RecordingDelay(&mut delays), 100),
+            Err(Error::RefreshFailed)
+        );
+        assert_eq!(delays.iter().map(|&ms| u32::from(ms)).sum::<u32>(), 100);
+        assert_eq!(display.state().panel, PanelState::Unknown);
+    }
+
+    #[test]
+    fn reset_ignored() {
+        let mut delays = Vec::new();
+        let mut display = build_display();
+        display.interface().set_ignore_reset(true);
+        display.reset(&mut RecordingDelay(&mut delays)).unwrap();
+        assert_eq!(display.interface().resets(), 1);
+        assert_eq!(display.interface().inner().resets, 0);
+        assert!(display.interface().writes() > 0);
+    }
+}
@@ -0,0 +1,114 @@
+//! Bitmap fonts of 8 pixel wide cells, drawn a cell at a time.
+//!
+//! Text drawn through `DrawTarget` sets one pixel at a time, and on a display rotated by 90 or
+//! 270 degrees the pixels of a row of text are in a different byte of the buffers for every
+//! column, so each pixel costs a read and write of both planes. A [CellFont] holds glyphs as
+//! 8 pixel wide cells, like the classic 8x8 and 8x16 console fonts, which
+//! [GraphicDisplay::draw_glyph_cell](../graphics/struct.GraphicDisplay.html#method.draw_glyph_cell)
+//! turns on their side in registers and writes a whole byte of each column at once.
+//!
+//! ### Example
+//!
+//! ```ignore
+//! // an 8x16 font of the printable ASCII characters, 16 bytes per glyph
+//! const FONT: CellFont = CellFont::new(&FONT_8X16, 16, ' ');
+//! display.draw_cell_text("21.5C", &FONT, Point::new(4, 40), Color::Black, Some(Color::White))?;
+//! ```
+
+/// A font of 8 pixel wide glyphs, one byte per row.
+///
+/// Each glyph is `height` bytes, the top row first, with the leftmost pixel in the most
+/// significant bit and a set bit for the foreground. The glyphs are for consecutive characters
+/// from `first`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CellFont<'a> {
+    glyphs: &'a [u8],
+    height: u32,
+    first: char,
+}
+
+impl<'a> CellFont<'a> {
+    /// The width of every glyph.
+    pub const WIDTH: u32 = 8;
+
+    /// Create a font with glyphs `height` rows tall, at most 32, for the characters from
+    /// `first`.
+    pub const fn new(glyphs: &'a [u8], height: u32, first: char) -> Self {
+        assert!(
+            height > 0 && height <= 32,
+            "glyphs must be 1 to 32 rows tall"
+        );
+        CellFont {
+            glyphs,
+            height,
+            first,
+        }
+    }
+
+    /// Returns the height of the glyphs.
+    pub const fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the glyph of a character, `None` if the font doesn't have it.
+    pub fn glyph(&self, c: char) -> Option<&'a [u8]> {
+        let index = (c as u32).checked_sub(self.first as u32)? as usize;
+        let height = self.height as usize;
+        self.glyphs.get(index * height..(index + 1) * height)
+    }
+}
+
+// the columns of a glyph, each with the top row in the most significant bit
+pub(crate) fn columns(glyph: &[u8]) -> [u32; 8] {
+    let mut columns = [0u32; 8];
+    for (block, rows) in glyph.chunks(8).enumerate() {
+        let mut cell = [0u8; 8];
+        cell[..rows.len()].copy_from_slice(rows);
+        for (column, bits) in columns.iter_mut().zip(transpose(cell).iter()) {
+            *column |= u32::from(*bits) << (24 - 8 * block);
+        }
+    }
+    columns
+}
+
+// transpose an 8x8 cell of bits, row i bit 7 - j becomes row j bit 7 - i
+fn transpose(cell: [u8; 8]) -> [u8; 8] {
+    let mut x = u64::from_be_bytes(cell);
+    let t = (x ^ (x >> 7)) & 0x00AA_00AA_00AA_00AA;
+    x ^= t ^ (t << 7);
+    let t = (x ^ (x >> 14)) & 0x0000_CCCC_0000_CCCC;
+    x ^= t ^ (t << 14);
+    let t = (x ^ (x >> 28)) & 0x0000_0000_F0F0_F0F0;
+    x ^= t ^ (t << 28);
+    x.to_be_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transpose_cell() {
+        let cell = [0x81, 0x42, 0x24, 0x18, 0xF0, 0x0F, 0x01, 0x80];
+        let columns = transpose(cell);
+        for (i, row) in cell.iter().enumerate() {
+            for (j, column) in columns.iter().enumerate() {
+                assert_eq!(row >> (7 - j) & 1, column >> (7 - i) & 1);
+            }
+        }
+    }
+
+    #[test]
+    fn glyphs() {
+        let mut glyphs = [0u8; 10];
+        glyphs[5] = 0x80;
+        glyphs[9] = 0x01;
+        let font = CellFont::new(&glyphs, 5, 'A');
+        assert_eq!(font.glyph('B'), Some(&glyphs[5..10]));
+        assert_eq!(font.glyph('C'), None);
+        assert_eq!(font.glyph(' '), None);
+        let columns = columns(font.glyph('B').unwrap());
+        assert_eq!(columns[0], 0x8000_0000);
+        assert_eq!(columns[7], 0x0800_0000);
+    }
+}
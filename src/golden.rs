@@ -0,0 +1,339 @@
+//! Golden image regression tests, run on the host.
+//!
+//! [render] draws a scene on a [GraphicDisplay] and captures the planes sent to the controller
+//! as a [Frame], whatever the rotation, bit order and buffer layout. [check] compares a frame
+//! with a golden file checked into the repository. On a mismatch it writes the frame next to the
+//! golden file, with the extension `actual`, and a PBM image of the differing pixels, with the
+//! extension `diff.pbm`, which most image viewers open.
+//!
+//! A missing golden file is a failure, so a file left out of a commit isn't silently recreated in
+//! CI. Set the `IL0373_BLESS` environment variable to write new golden files, or overwrite them
+//! after an intended change.
+//!
+//! Only available with the `golden-tests` feature, which needs `std`. See `tests/golden.rs`.
+//!
+//! ### Example
+//!
+//! ```ignore
+//! let frame = golden::render(config, |display| golden::example_scene(display).unwrap());
+//! golden::check(&frame, "tests/golden/scene.bin").unwrap();
+//! ```
+
+use crate::color::Color;
+use crate::config::Config;
+use crate::display::{self, Display};
+use crate::error::Error;
+use crate::graphics::{GraphicDisplay, RamBuffers};
+use crate::interface::DisplayInterface;
+use crate::regs;
+use embedded_graphics::{
+    mono_font::{ascii::FONT_6X10, MonoTextStyle},
+    prelude::*,
+    primitives::{Circle, Line, PrimitiveStyle, Rectangle},
+    text::{Baseline, Text},
+};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::string::String;
+use std::vec::Vec;
+
+/// Set to overwrite golden files with the rendered frames.
+pub const BLESS_VAR: &str = "IL0373_BLESS";
+
+/// An interface that keeps the last B/W and Red planes sent to the controller.
+#[derive(Debug, Default)]
+pub struct GoldenInterface {
+    planes: [Vec<u8>; 2],
+    // the plane the data being sent belongs to
+    writing: Option<usize>,
+}
+
+impl GoldenInterface {
+    /// Create an interface that hasn't been sent any planes.
+    pub fn new() -> Self {
+        GoldenInterface::default()
+    }
+}
+
+impl DisplayInterface for GoldenInterface {
+    type Error = ();
+
+    fn send_command(&mut self, command: u8) -> Result<(), Self::Error> {
+        self.writing = match command {
+            0x10 => Some(0),
+            0x13 => Some(1),
+            _ => None,
+        };
+        if let Some(plane) = self.writing {
+            self.planes[plane].clear();
+        }
+        Ok(())
+    }
+
+    fn send_data(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        if let Some(plane) = self.writing {
+            self.planes[plane].extend_from_slice(data);
+        }
+        Ok(())
+    }
+
+    fn reset<D: hal::blocking::delay::DelayMs<u8>>(&mut self, _delay: &mut D) {}
+
+    fn busy_wait(&mut self) {}
+
+    fn epd_update_data(
+        &mut self,
+        layer: u8,
+        nbytes: u16,
+        buf: &[u8],
+    ) -> Result<(), Error<Self::Error>> {
+        if usize::from(nbytes) != buf.len() {
+            return Err(Error::BufferSize {
+                expected: nbytes.into(),
+                actual: buf.len(),
+            });
+        }
+        self.send_command(regs::data_transmission(layer))
+            .and_then(|_| self.send_data(buf))
+            .map_err(Error::Interface)
+    }
+}
+
+/// The planes of a frame, as sent to the controller.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    /// the number of sources (columns) in each row
+    pub cols: u8,
+    /// the number of gates (rows)
+    pub rows: u16,
+    /// the B/W plane, a set bit is white
+    pub black: Vec<u8>,
+    /// the Red plane, as configured with `invert_red_plane`
+    pub red: Vec<u8>,
+}
+
+impl Frame {
+    /// Returns the frame as stored in a golden file, the B/W plane followed by the Red plane.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.black.clone();
+        bytes.extend_from_slice(&self.red);
+        bytes
+    }
+
+    /// Returns a bitmap with a set bit for each pixel that differs from `golden`, in either plane.
+    ///
+    /// `golden` is the contents of a golden file. Pixels missing from a short file differ.
+    pub fn diff(&self, golden: &[u8]) -> Vec<u8> {
+        let len = self.black.len();
+        let plane = |bytes: &[u8], i: usize| bytes.get(i).copied();
+        (0..len)
+            .map(|i| {
+                let black = match plane(golden, i) {
+                    Some(byte) => byte ^ self.black[i],
+                    None => 0xFF,
+                };
+                let red = match (plane(golden, len + i), self.red.get(i)) {
+                    (Some(golden), Some(&byte)) => golden ^ byte,
+                    (None, None) => 0,
+                    _ => 0xFF,
+                };
+                black | red
+            })
+            .collect()
+    }
+
+    /// Returns the B/W plane as a PBM image.
+    pub fn black_pbm(&self) -> Vec<u8> {
+        let inverted: Vec<u8> = self.black.iter().map(|b| !b).collect();
+        to_pbm(&inverted, self.cols, self.rows)
+    }
+}
+
+/// Encode a plane as a binary PBM (P4) image, a set bit is drawn black.
+///
+/// Each row of the image is a gate, as the controller scans the panel, so it is unrotated.
+pub fn to_pbm(plane: &[u8], cols: u8, rows: u16) -> Vec<u8> {
+    let mut pbm = format!("P4\n{} {}\n", cols, rows).into_bytes();
+    pbm.extend_from_slice(plane);
+    pbm
+}
+
+/// Draw a scene on a display with `config`, and return the frame sent to the controller.
+///
+/// The buffers start white, as after `clear`.
+pub fn render<F>(config: Config, draw: F) -> Frame
+where
+    F: FnOnce(&mut GraphicDisplay<GoldenInterface, RamBuffers<'_>>),
+{
+    let dimensions = config.dimensions();
+    let size = display::buffer_size(dimensions.rows, dimensions.cols);
+    let (mut black, mut red) = (vec![0u8; size], vec![0u8; size]);
+    let display = Display::new(GoldenInterface::new(), config);
+    let mut display = GraphicDisplay::new(display, &mut black, &mut red);
+    display.clear(Color::White).unwrap();
+    draw(&mut display);
+    display.update().unwrap();
+    let [black, red] = core::mem::take(&mut display.interface().planes);
+    Frame {
+        cols: dimensions.cols,
+        rows: dimensions.rows,
+        black,
+        red,
+    }
+}
+
+/// Draw a scene exercising text, lines, outlines and filled shapes in each color.
+///
+/// The scene is scaled to the target, so it can be drawn in any rotation.
+pub fn example_scene<D>(target: &mut D) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = Color>,
+{
+    let area = target.bounding_box();
+    let Size { width, height } = area.size;
+    area.into_styled(PrimitiveStyle::with_stroke(Color::Black, 1))
+        .draw(target)?;
+    Line::new(
+        Point::zero(),
+        Point::new(width as i32 - 1, height as i32 - 1),
+    )
+    .into_styled(PrimitiveStyle::with_stroke(Color::Red, 1))
+    .draw(target)?;
+    let diameter = width.min(height) / 2;
+    Circle::new(Point::new(2, 2), diameter)
+        .into_styled(PrimitiveStyle::with_fill(Color::Red))
+        .draw(target)?;
+    Rectangle::new(
+        Point::new(width as i32 / 2, height as i32 / 2),
+        Size::new(width / 3, height / 4),
+    )
+    .into_styled(PrimitiveStyle::with_fill(Color::Black))
+    .draw(target)?;
+    Text::with_baseline(
+        "IL0373",
+        Point::new(2, height as i32 - 12),
+        MonoTextStyle::new(&FONT_6X10, Color::Black),
+        Baseline::Top,
+    )
+    .draw(target)?;
+    Ok(())
+}
+
+/// Why a frame didn't match its golden file.
+#[derive(Debug)]
+pub enum Mismatch {
+    /// The golden file couldn't be read, or the outputs written.
+    Io(io::Error),
+    /// The golden file doesn't exist, the frame was written next to where it should be.
+    Missing {
+        /// the golden file
+        golden: PathBuf,
+        /// the frame, in the golden file format
+        actual: PathBuf,
+    },
+    /// Pixels differ, the frame and the diff were written next to the golden file.
+    Pixels {
+        /// the number of differing pixels
+        count: u32,
+        /// the frame, in the golden file format
+        actual: PathBuf,
+        /// a PBM image of the differing pixels
+        diff: PathBuf,
+    },
+}
+
+impl From<io::Error> for Mismatch {
+    fn from(e: io::Error) -> Self {
+        Mismatch::Io(e)
+    }
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mismatch::Io(e) => write!(f, "golden file error: {}", e),
+            Mismatch::Missing { golden, actual } => write!(
+                f,
+                "golden file {} is missing, see {} or set {} to write it",
+                golden.display(),
+                actual.display(),
+                BLESS_VAR
+            ),
+            Mismatch::Pixels {
+                count,
+                actual,
+                diff,
+            } => write!(
+                f,
+                "{} pixels differ from the golden file, see {} and {}",
+                count,
+                actual.display(),
+                diff.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Mismatch {}
+
+/// Compare a frame with a golden file.
+///
+/// The golden file is written if [BLESS_VAR] is set. Otherwise this is [compare], which fails
+/// with `Mismatch::Missing` if the golden file doesn't exist.
+pub fn check<P: AsRef<Path>>(frame: &Frame, golden: P) -> Result<(), Mismatch> {
+    let golden = golden.as_ref();
+    if std::env::var_os(BLESS_VAR).is_some() {
+        if let Some(dir) = golden.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(golden, frame.to_bytes())?;
+        return Ok(());
+    }
+    compare(frame, golden)
+}
+
+/// Compare a frame with a golden file, writing the frame and a diff next to it if they differ.
+///
+/// Returns `Mismatch::Missing`, writing the frame, if the golden file doesn't exist.
+pub fn compare<P: AsRef<Path>>(frame: &Frame, golden: P) -> Result<(), Mismatch> {
+    let golden = golden.as_ref();
+    let bytes = frame.to_bytes();
+    if !golden.exists() {
+        let actual = with_suffix(golden, "actual");
+        if let Some(dir) = golden.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&actual, &bytes)?;
+        return Err(Mismatch::Missing {
+            golden: golden.to_path_buf(),
+            actual,
+        });
+    }
+    let expected = fs::read(golden)?;
+    if expected == bytes {
+        return Ok(());
+    }
+    let diff = frame.diff(&expected);
+    let count = diff.iter().map(|b| b.count_ones()).sum();
+    let actual = with_suffix(golden, "actual");
+    let diff_path = with_suffix(golden, "diff.pbm");
+    fs::write(&actual, &bytes)?;
+    fs::write(&diff_path, to_pbm(&diff, frame.cols, frame.rows))?;
+    Err(Mismatch::Pixels {
+        count,
+        actual,
+        diff: diff_path,
+    })
+}
+
+// the path with an extension appended
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path
+        .file_name()
+        .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+    name.push('.');
+    name.push_str(suffix);
+    path.with_file_name(name)
+}
@@ -1,23 +1,873 @@
-use color::Color;
+use crate::codec::{self, Run};
+use crate::color::{Color, PanelColor};
+use crate::command::Command;
+use crate::config::Config;
+use crate::display::{Display, PanelState, PartialWindow, Rotation, WakeGuard};
+use crate::error::Error;
+use crate::glyph::{self, CellFont};
+use crate::interface::DisplayInterface;
+#[cfg(feature = "sram")]
+use crate::interface::{SramAddr, SramBacked, SramHandle, SramRegion};
+use crate::luts::LutProfile;
+use crate::odometer::RefreshKind;
+use crate::regs;
+use crate::sprite::Sprite;
+use core::marker::PhantomData;
 use core::ops::{Deref, DerefMut};
-use display::{Display, Rotation};
-use interface::DisplayInterface;
+use embedded_graphics_core::prelude::*;
+use embedded_graphics_core::primitives::Rectangle;
+
+/// Storage for the B/W and Red buffers of a [GraphicDisplay].
+///
+/// Layer 0 is the B/W buffer and layer 1 is the Red buffer, as in
+/// [DisplayInterface::epd_update_data]. The interface is passed to every method so that
+/// backends that live on the display bus, like SRAM, can use it.
+pub trait FrameBufferStore<I>
+where
+    I: DisplayInterface,
+{
+    /// Error returned when accessing the buffers.
+    type Error;
+
+    /// read the byte at index in a layer
+    fn read_byte(&mut self, interface: &mut I, layer: u8, index: u32) -> Result<u8, Self::Error>;
+
+    /// write the byte at index in a layer
+    fn write_byte(
+        &mut self,
+        interface: &mut I,
+        layer: u8,
+        index: u32,
+        byte: u8,
+    ) -> Result<(), Self::Error>;
+
+    /// set every byte in a layer to a value
+    fn fill(&mut self, interface: &mut I, layer: u8, byte: u8) -> Result<(), Self::Error>;
+
+    /// send nbytes of a layer to the controller
+    fn update_epd(
+        &mut self,
+        interface: &mut I,
+        layer: u8,
+        nbytes: u32,
+    ) -> Result<(), Error<I::Error>>;
+    /// set nbytes of a layer, starting at index, to a value
+    ///
+    /// Defaults to writing each byte, backends override this to write a block at once.
+    fn fill_range(
+        &mut self,
+        interface: &mut I,
+        layer: u8,
+        index: u32,
+        nbytes: u32,
+        byte: u8,
+    ) -> Result<(), Self::Error> {
+        for i in index..index + nbytes {
+            self.write_byte(interface, layer, i, byte)?;
+        }
+        Ok(())
+    }
+
+    /// send nbytes of a layer to the controller, unpacked from the interleaved layout
+    ///
+    /// The packed pixels continue from layer 0 into layer 1, see [BufferLayout::Interleaved], and
+    /// each unpacked byte is xored with `invert`. Returns `Error::Unsupported` unless the store
+    /// overrides this.
+    fn update_epd_interleaved(
+        &mut self,
+        _interface: &mut I,
+        _layer: u8,
+        _nbytes: u32,
+        _invert: u8,
+    ) -> Result<(), Error<I::Error>> {
+        Err(Error::Unsupported)
+    }
+
+    /// send nbytes of a layer, starting at index, as data for a command already sent
+    ///
+    /// Sends the rows of a partial window, see [GraphicDisplay::update_window]. Returns
+    /// `Error::Unsupported` unless the store overrides this.
+    fn update_epd_range(
+        &mut self,
+        _interface: &mut I,
+        _layer: u8,
+        _index: u32,
+        _nbytes: u32,
+    ) -> Result<(), Error<I::Error>> {
+        Err(Error::Unsupported)
+    }
+
+    /// send nbytes of a layer, starting at index, unpacked from the interleaved layout, as data
+    /// for a command already sent
+    ///
+    /// Each unpacked byte is xored with `invert`, as in
+    /// [FrameBufferStore::update_epd_interleaved]. Returns `Error::Unsupported` unless the store
+    /// overrides this.
+    fn update_epd_range_interleaved(
+        &mut self,
+        _interface: &mut I,
+        _layer: u8,
+        _index: u32,
+        _nbytes: u32,
+        _invert: u8,
+    ) -> Result<(), Error<I::Error>> {
+        Err(Error::Unsupported)
+    }
+
+    /// check that the bytes of both layers before `end` can be sent with
+    /// [FrameBufferStore::update_epd_range], or [FrameBufferStore::update_epd_range_interleaved]
+    /// if `interleaved`
+    ///
+    /// Called before the controller is put in partial mode, so a window that can't be sent
+    /// leaves it untouched. Returns `Error::Unsupported` unless the store overrides this.
+    fn check_range(&self, _interleaved: bool, _end: u32) -> Result<(), Error<I::Error>> {
+        Err(Error::Unsupported)
+    }
+
+    /// returns true if the store keeps the previous frame, for [UpdateStrategy::DiffUpload]
+    ///
+    /// Defaults to false.
+    fn keeps_previous(&self) -> bool {
+        false
+    }
+
+    /// send nbytes of the previous frame of a layer as data for a command already sent
+    ///
+    /// Sends the old frame of a refresh with a LUT profile other than `Otp`. Returns
+    /// `Error::Unsupported` unless the store overrides this.
+    fn update_epd_previous(
+        &mut self,
+        _interface: &mut I,
+        _layer: u8,
+        _nbytes: u32,
+    ) -> Result<(), Error<I::Error>> {
+        Err(Error::Unsupported)
+    }
+
+    /// compare nbytes of a layer, starting at index, with the previous frame, then keep them as
+    /// the previous frame
+    ///
+    /// Returns true if any of the bytes changed. Defaults to true, for stores that don't keep the
+    /// previous frame.
+    fn retain_range(
+        &mut self,
+        _interface: &mut I,
+        _layer: u8,
+        _index: u32,
+        _nbytes: u32,
+    ) -> Result<bool, Error<I::Error>> {
+        Ok(true)
+    }
+}
+
+/// How the pixels of a [GraphicDisplay] are laid out in its [FrameBufferStore].
+///
+/// Set with [Builder::buffer_layout](../config/struct.Builder.html#method.buffer_layout).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BufferLayout {
+    /// Layer 0 is the B/W plane and layer 1 the Red plane, as sent to the controller. Drawing a
+    /// pixel reads and writes a byte in each layer.
+    Planar,
+    /// The B/W and Red bits of each pixel are adjacent, two bits per pixel with the B/W bit
+    /// first, and the pixels continue from layer 0 into layer 1. Drawing a pixel reads and writes
+    /// a single byte, which saves SRAM transactions, but the planes are unpacked while updating.
+    Interleaved,
+}
+
+impl Default for BufferLayout {
+    /// Default is `Planar`.
+    fn default() -> Self {
+        BufferLayout::Planar
+    }
+}
+
+/// The order of the pixels in each byte of the planes.
+///
+/// The controller takes the lowest source in the most significant bit, and the drawing functions
+/// pack the planes that way in every rotation. Some panels are wired, or some firmware expects
+/// them, the other way around, which shows as every 8 pixels mirrored along the source axis,
+/// usually in only one of the rotations. Set per rotation with
+/// [Builder::bit_order](../config/struct.Builder.html#method.bit_order) to correct it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BitOrder {
+    /// The lowest source is in the most significant bit.
+    MsbFirst,
+    /// The lowest source is in the least significant bit.
+    LsbFirst,
+}
+
+impl Default for BitOrder {
+    /// Default is `MsbFirst`.
+    fn default() -> Self {
+        BitOrder::MsbFirst
+    }
+}
+
+impl BitOrder {
+    // convert a mask of bits in the controller's order to this order, or back
+    pub(crate) const fn mask(self, mask: u8) -> u8 {
+        match self {
+            BitOrder::MsbFirst => mask,
+            BitOrder::LsbFirst => mask.reverse_bits(),
+        }
+    }
+}
+
+/// What drawing does with a pixel outside the display.
+///
+/// Set with [Builder::out_of_bounds](../config/struct.Builder.html#method.out_of_bounds). Shapes
+/// and text that overlap the edge are common, so the default is to clip them. Rectangle fills
+/// and sprites are always clipped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutOfBounds {
+    /// The pixel is skipped.
+    Clip,
+    /// Drawing stops at the pixel and returns `Error::OutOfBounds`, the pixels before it are
+    /// drawn.
+    Error,
+    /// Drawing panics at the pixel.
+    Panic,
+}
+
+impl Default for OutOfBounds {
+    /// Default is `Clip`.
+    fn default() -> Self {
+        OutOfBounds::Clip
+    }
+}
+
+/// How [GraphicDisplay::update] sends the buffers to the controller.
+///
+/// Set with [Builder::update_strategy](../config/struct.Builder.html#method.update_strategy).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UpdateStrategy {
+    /// Both planes are sent in full.
+    Full,
+    /// Only the rows that changed since the previous update are sent, in partial windows, then
+    /// the whole panel is refreshed, so it still flashes. The store must keep the previous
+    /// frame, such as [RamBuffers::with_previous], and the buffers must be
+    /// [BufferLayout::Planar], otherwise the planes are sent in full. They are also sent in full
+    /// the first time, and after the controller is reset, put in deep sleep or sent anything else.
+    DiffUpload,
+    /// The panel is refreshed to white before the planes are sent and refreshed, removing any
+    /// ghost of the previous image. Takes two refreshes, so suits infrequent changes of the whole
+    /// image. The white planes are generated while sending, without a second buffer.
+    WhiteFlashFirst,
+}
+
+impl Default for UpdateStrategy {
+    /// Default is `Full`.
+    fn default() -> Self {
+        UpdateStrategy::Full
+    }
+}
+
+/// One of the two planes held by a [GraphicDisplay].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Plane {
+    /// The B/W plane, layer 0
+    Black,
+    /// The Red plane, layer 1
+    Red,
+}
+
+/// Buffers held in RAM.
+pub struct RamBuffers<'a> {
+    black: &'a mut [u8],
+    red: &'a mut [u8],
+    previous: Option<(&'a mut [u8], &'a mut [u8])>,
+}
+
+impl<'a> RamBuffers<'a> {
+    /// Use the slices as B/W and Red buffers.
+    pub fn new(black: &'a mut [u8], red: &'a mut [u8]) -> Self {
+        RamBuffers {
+            black,
+            red,
+            previous: None,
+        }
+    }
+
+    /// Keep the previous frame in a second pair of slices, the same size as the buffers, for
+    /// [UpdateStrategy::DiffUpload].
+    ///
+    /// ### Example
+    ///
+    /// ```ignore
+    /// let buffers = RamBuffers::new(&mut black, &mut red).with_previous(&mut prev_black, &mut prev_red);
+    /// ```
+    pub fn with_previous(self, black: &'a mut [u8], red: &'a mut [u8]) -> Self {
+        Self {
+            previous: Some((black, red)),
+            ..self
+        }
+    }
+
+    /// Returns the buffer of a plane.
+    ///
+    /// With [BufferLayout::Interleaved] the buffers hold packed pixels rather than planes.
+    pub fn plane(&self, plane: Plane) -> &[u8] {
+        match plane {
+            Plane::Black => self.black,
+            Plane::Red => self.red,
+        }
+    }
+
+    fn layer(&mut self, layer: u8) -> &mut [u8] {
+        if layer == 0 {
+            self.black
+        } else {
+            self.red
+        }
+    }
+}
+
+impl<'a, I> FrameBufferStore<I> for RamBuffers<'a>
+where
+    I: DisplayInterface,
+{
+    type Error = core::convert::Infallible;
+
+    fn read_byte(&mut self, _interface: &mut I, layer: u8, index: u32) -> Result<u8, Self::Error> {
+        Ok(self.layer(layer)[index as usize])
+    }
+
+    fn write_byte(
+        &mut self,
+        _interface: &mut I,
+        layer: u8,
+        index: u32,
+        byte: u8,
+    ) -> Result<(), Self::Error> {
+        self.layer(layer)[index as usize] = byte;
+        Ok(())
+    }
+
+    fn fill(&mut self, _interface: &mut I, layer: u8, byte: u8) -> Result<(), Self::Error> {
+        for b in self.layer(layer).iter_mut() {
+            *b = byte;
+        }
+        Ok(())
+    }
+
+    fn fill_range(
+        &mut self,
+        _interface: &mut I,
+        layer: u8,
+        index: u32,
+        nbytes: u32,
+        byte: u8,
+    ) -> Result<(), Self::Error> {
+        let start = index as usize;
+        for b in self.layer(layer)[start..start + nbytes as usize].iter_mut() {
+            *b = byte;
+        }
+        Ok(())
+    }
+
+    fn update_epd(
+        &mut self,
+        interface: &mut I,
+        layer: u8,
+        nbytes: u32,
+    ) -> Result<(), Error<I::Error>> {
+        interface.epd_update_data(layer, nbytes as u16, self.layer(layer))
+    }
+
+    fn update_epd_interleaved(
+        &mut self,
+        interface: &mut I,
+        layer: u8,
+        nbytes: u32,
+        invert: u8,
+    ) -> Result<(), Error<I::Error>> {
+        let n = nbytes as usize;
+        for buffer in [&*self.black, &*self.red].iter() {
+            if buffer.len() != n {
+                return Err(Error::BufferSize {
+                    expected: n,
+                    actual: buffer.len(),
+                });
+            }
+        }
+        let (black, red) = (&*self.black, &*self.red);
+        send_unpacked(interface, layer, nbytes, invert, |_, index, packed| {
+            for (i, byte) in packed.iter_mut().enumerate() {
+                let j = index as usize + i;
+                *byte = if j < n { black[j] } else { red[j - n] };
+            }
+            Ok(())
+        })
+    }
+
+    fn update_epd_range(
+        &mut self,
+        interface: &mut I,
+        layer: u8,
+        index: u32,
+        nbytes: u32,
+    ) -> Result<(), Error<I::Error>> {
+        let buffer = self.layer(layer);
+        let (start, end) = (index as usize, (index + nbytes) as usize);
+        let data = buffer.get(start..end).ok_or(Error::BufferSize {
+            expected: end,
+            actual: buffer.len(),
+        })?;
+        interface.send_data(data).map_err(Error::Interface)
+    }
+
+    fn update_epd_range_interleaved(
+        &mut self,
+        interface: &mut I,
+        layer: u8,
+        index: u32,
+        nbytes: u32,
+        invert: u8,
+    ) -> Result<(), Error<I::Error>> {
+        let (black, red) = (&*self.black, &*self.red);
+        let (n, end) = (black.len(), 2 * (index + nbytes) as usize);
+        if end > n + red.len() {
+            return Err(Error::BufferSize {
+                expected: end,
+                actual: n + red.len(),
+            });
+        }
+        send_unpacked_range(
+            interface,
+            layer,
+            index,
+            nbytes,
+            invert,
+            |_, index, packed| {
+                for (i, byte) in packed.iter_mut().enumerate() {
+                    let j = index as usize + i;
+                    *byte = if j < n { black[j] } else { red[j - n] };
+                }
+                Ok(())
+            },
+        )
+    }
+
+    fn check_range(&self, interleaved: bool, end: u32) -> Result<(), Error<I::Error>> {
+        let (end, len) = if interleaved {
+            (2 * end as usize, self.black.len() + self.red.len())
+        } else {
+            (end as usize, self.black.len().min(self.red.len()))
+        };
+        if end > len {
+            return Err(Error::BufferSize {
+                expected: end,
+                actual: len,
+            });
+        }
+        Ok(())
+    }
+
+    fn keeps_previous(&self) -> bool {
+        self.previous.is_some()
+    }
+
+    fn update_epd_previous(
+        &mut self,
+        interface: &mut I,
+        layer: u8,
+        nbytes: u32,
+    ) -> Result<(), Error<I::Error>> {
+        let previous = match &self.previous {
+            Some((black, _)) if layer == 0 => &**black,
+            Some((_, red)) => &**red,
+            None => return Err(Error::Unsupported),
+        };
+        let data = previous.get(..nbytes as usize).ok_or(Error::BufferSize {
+            expected: nbytes as usize,
+            actual: previous.len(),
+        })?;
+        interface.send_data(data).map_err(Error::Interface)
+    }
+
+    fn retain_range(
+        &mut self,
+        _interface: &mut I,
+        layer: u8,
+        index: u32,
+        nbytes: u32,
+    ) -> Result<bool, Error<I::Error>> {
+        let (current, previous) = match &mut self.previous {
+            Some((black, _)) if layer == 0 => (&*self.black, black),
+            Some((_, red)) => (&*self.red, red),
+            None => return Ok(true),
+        };
+        let (start, end) = (index as usize, (index + nbytes) as usize);
+        let len = current.len().min(previous.len());
+        if end > len {
+            return Err(Error::BufferSize {
+                expected: end,
+                actual: len,
+            });
+        }
+        let changed = current[start..end] != previous[start..end];
+        if changed {
+            previous[start..end].copy_from_slice(&current[start..end]);
+        }
+        Ok(changed)
+    }
+}
+
+/// Buffers held in the SRAM of the display interface.
+///
+/// The B/W buffer is at address 0, followed by the Red buffer.
+#[cfg(feature = "sram")]
+pub struct SramBuffers {
+    black: SramRegion,
+    red: SramRegion,
+}
+
+#[cfg(feature = "sram")]
+impl SramBuffers {
+    /// Allocate buffers of `buffer_size` bytes at the start of the sram.
+    pub fn new(buffer_size: u32) -> Self {
+        let black = SramRegion::new(SramAddr::ZERO, buffer_size);
+        SramBuffers {
+            black,
+            red: SramRegion::new(black.end(), buffer_size),
+        }
+    }
+
+    /// Returns the first sram address after the buffers.
+    pub fn end_address(&self) -> SramAddr {
+        self.red.end()
+    }
+
+    /// Returns the region of the sram holding a plane.
+    pub fn region(&self, plane: Plane) -> SramRegion {
+        match plane {
+            Plane::Black => self.black,
+            Plane::Red => self.red,
+        }
+    }
+
+    fn layer(&self, layer: u8) -> SramRegion {
+        if layer == 0 {
+            self.black
+        } else {
+            self.red
+        }
+    }
+
+    // the address of nbytes at index in a layer, they must be inside the plane
+    fn address<E>(&self, layer: u8, index: u32, nbytes: u32) -> Result<SramAddr, Error<E>> {
+        let plane = self.layer(layer);
+        plane.address(index, nbytes).ok_or(Error::BufferSize {
+            expected: (index + nbytes) as usize,
+            actual: plane.len() as usize,
+        })
+    }
+}
+
+#[cfg(feature = "sram")]
+impl<I> FrameBufferStore<I> for SramBuffers
+where
+    I: SramBacked,
+{
+    type Error = Error<I::Error>;
+
+    fn read_byte(&mut self, interface: &mut I, layer: u8, index: u32) -> Result<u8, Self::Error> {
+        let mut byte: [u8; 1] = [0];
+        interface.sram_read(self.address(layer, index, 1)?, &mut byte)?;
+        Ok(byte[0])
+    }
+
+    fn write_byte(
+        &mut self,
+        interface: &mut I,
+        layer: u8,
+        index: u32,
+        byte: u8,
+    ) -> Result<(), Self::Error> {
+        interface.sram_write(self.address(layer, index, 1)?, &[byte])
+    }
+
+    fn fill(&mut self, interface: &mut I, layer: u8, byte: u8) -> Result<(), Self::Error> {
+        let plane = self.layer(layer);
+        interface.sram_clear(plane.start(), plane.len(), byte)
+    }
+
+    fn fill_range(
+        &mut self,
+        interface: &mut I,
+        layer: u8,
+        index: u32,
+        nbytes: u32,
+        byte: u8,
+    ) -> Result<(), Self::Error> {
+        let chunk = [byte; 32];
+        let mut offset = 0;
+        while offset < nbytes {
+            let sz = (nbytes - offset).min(chunk.len() as u32);
+            let address = self.address(layer, index + offset, sz)?;
+            interface.sram_write(address, &chunk[..sz as usize])?;
+            offset += sz;
+        }
+        Ok(())
+    }
+
+    fn update_epd(
+        &mut self,
+        interface: &mut I,
+        layer: u8,
+        nbytes: u32,
+    ) -> Result<(), Error<I::Error>> {
+        // the move must stay inside the plane, not just the device
+        let start = self.address(layer, 0, nbytes)?;
+        interface.sram_epd_update_data(layer, nbytes, start)
+    }
+
+    fn update_epd_interleaved(
+        &mut self,
+        interface: &mut I,
+        layer: u8,
+        nbytes: u32,
+        invert: u8,
+    ) -> Result<(), Error<I::Error>> {
+        // the Red buffer follows the B/W buffer, so the packed pixels are contiguous
+        let packed = SramRegion::new(self.black.start(), self.black.len() + self.red.len());
+        send_unpacked(interface, layer, nbytes, invert, |interface, index, buf| {
+            let address = packed
+                .address(index, buf.len() as u32)
+                .ok_or(Error::BufferSize {
+                    expected: 2 * nbytes as usize,
+                    actual: packed.len() as usize,
+                })?;
+            interface.sram_read(address, buf)
+        })
+    }
+
+    fn update_epd_range(
+        &mut self,
+        interface: &mut I,
+        layer: u8,
+        index: u32,
+        nbytes: u32,
+    ) -> Result<(), Error<I::Error>> {
+        let plane = self.layer(layer);
+        let mut buf = [0u8; 32];
+        let mut offset = 0;
+        while offset < nbytes {
+            let sz = (nbytes - offset).min(buf.len() as u32);
+            let address = plane.address(index + offset, sz).ok_or(Error::BufferSize {
+                expected: (index + nbytes) as usize,
+                actual: plane.len() as usize,
+            })?;
+            let buf = &mut buf[..sz as usize];
+            interface.sram_read(address, buf)?;
+            interface.send_data(buf).map_err(Error::Interface)?;
+            offset += sz;
+        }
+        Ok(())
+    }
+
+    fn check_range(&self, interleaved: bool, end: u32) -> Result<(), Error<I::Error>> {
+        let (end, len) = if interleaved {
+            (2 * end, self.black.len() + self.red.len())
+        } else {
+            (end, self.black.len().min(self.red.len()))
+        };
+        if end > len {
+            return Err(Error::BufferSize {
+                expected: end as usize,
+                actual: len as usize,
+            });
+        }
+        Ok(())
+    }
+
+    fn update_epd_range_interleaved(
+        &mut self,
+        interface: &mut I,
+        layer: u8,
+        index: u32,
+        nbytes: u32,
+        invert: u8,
+    ) -> Result<(), Error<I::Error>> {
+        let packed = SramRegion::new(self.black.start(), self.black.len() + self.red.len());
+        send_unpacked_range(
+            interface,
+            layer,
+            index,
+            nbytes,
+            invert,
+            |interface, index, buf| {
+                let address = packed
+                    .address(index, buf.len() as u32)
+                    .ok_or(Error::BufferSize {
+                        expected: (index as usize) + buf.len(),
+                        actual: packed.len() as usize,
+                    })?;
+                interface.sram_read(address, buf)
+            },
+        )
+    }
+}
+
+// stream a plane unpacked from interleaved buffers, read_packed fills a slice with the packed
+// bytes starting at an index
+fn send_unpacked<I, F>(
+    interface: &mut I,
+    layer: u8,
+    nbytes: u32,
+    invert: u8,
+    read_packed: F,
+) -> Result<(), Error<I::Error>>
+where
+    I: DisplayInterface,
+    F: FnMut(&mut I, u32, &mut [u8]) -> Result<(), Error<I::Error>>,
+{
+    interface
+        .send_command(regs::data_transmission(layer))
+        .map_err(Error::Interface)?;
+    send_unpacked_range(interface, layer, 0, nbytes, invert, read_packed)
+}
+
+// stream nbytes of a plane from index, unpacked from interleaved buffers, without the command
+fn send_unpacked_range<I, F>(
+    interface: &mut I,
+    layer: u8,
+    start: u32,
+    nbytes: u32,
+    invert: u8,
+    mut read_packed: F,
+) -> Result<(), Error<I::Error>>
+where
+    I: DisplayInterface,
+    F: FnMut(&mut I, u32, &mut [u8]) -> Result<(), Error<I::Error>>,
+{
+    let mut packed = [0u8; 64];
+    let mut plane = [0u8; 32];
+    let end = start + nbytes;
+    let mut index = start;
+    while index < end {
+        let len = (end - index).min(plane.len() as u32) as usize;
+        read_packed(interface, 2 * index, &mut packed[..2 * len])?;
+        for (byte, pair) in plane.iter_mut().zip(packed.chunks(2)).take(len) {
+            *byte = unpack(pair[0], pair[1], layer) ^ invert;
+        }
+        interface
+            .send_data(&plane[..len])
+            .map_err(Error::Interface)?;
+        index += len as u32;
+    }
+    Ok(())
+}
+
+// the plane byte of eight pixels from their two interleaved bytes
+fn unpack(first: u8, second: u8, layer: u8) -> u8 {
+    // the B/W bit is the high bit of each pair
+    let shift = if layer == 0 { 1 } else { 0 };
+    let mut byte = 0;
+    for (i, packed) in [first, second].iter().enumerate() {
+        for pixel in 0..4 {
+            let bit = (packed >> (6 - 2 * pixel + shift)) & 1;
+            byte |= bit << (7 - 4 * i - pixel);
+        }
+    }
+    byte
+}
+
+// the two interleaved bytes of eight pixels from their plane bytes
+fn pack(black: u8, red: u8) -> [u8; 2] {
+    let mut packed = [0u8; 2];
+    for pixel in 0..8 {
+        let black = (black >> (7 - pixel)) & 1;
+        let red = (red >> (7 - pixel)) & 1;
+        packed[pixel / 4] |= ((black << 1) | red) << (6 - 2 * (pixel % 4));
+    }
+    packed
+}
+
+// the interleaved byte index and shift of the bit pair of a pixel, from its planar index and bit
+fn packed_pixel(index: u32, bit: u8) -> (u32, u32) {
+    let pixel = bit.leading_zeros();
+    (2 * index + pixel / 4, 6 - 2 * (pixel % 4))
+}
 
 /// A display that holds buffers for drawing into and updating the display from.
 ///
+/// The buffers are kept in a [FrameBufferStore], [RamBuffers] when created with
+/// [GraphicDisplay::new] or [SramBuffers] when created with `GraphicDisplay::new_sram`.
+///
 /// When the `graphics` feature is enabled `GraphicDisplay` implements the `DrawTarget` trait from
 /// [embedded-graphics](https://crates.io/crates/embedded-graphics). This allows basic shapes and
 /// text to be drawn on the display.
-pub struct GraphicDisplay<'a, I>
+///
+/// Pixels are drawn in [Color] unless another [PanelColor] is chosen with
+/// [GraphicDisplay::with_panel_color].
+pub struct GraphicDisplay<I, S, C = Color>
 where
     I: DisplayInterface,
+    S: FrameBufferStore<I>,
+    C: PanelColor,
 {
     display: Display<I>,
-    black_buffer: &'a mut [u8],
-    red_buffer: &'a mut [u8],
+    store: S,
+    stats: DrawStats,
+    compositing: Compositing,
+    color: PhantomData<C>,
+}
+
+/// How drawing a color combines with what is already in the buffers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compositing {
+    /// Each pixel drawn replaces both planes, red ink clears the B/W plane to white.
+    Replace,
+    /// Red ink is only drawn into the Red plane, leaving the B/W plane as it is. Red shows over
+    /// black on the panel, so this stamps red over existing content, like a "LOW BATTERY"
+    /// overlay, and the black shows again when the red is cleared.
+    RedOverlay,
+}
+
+impl Default for Compositing {
+    /// Default is `Replace`.
+    fn default() -> Self {
+        Compositing::Replace
+    }
 }
 
-impl<'a, I> GraphicDisplay<'a, I>
+/// Number of pixels drawn in each color since the buffers were last cleared.
+///
+/// A pixel drawn twice is counted twice. Red takes the longest to drive and stresses the panel
+/// most, so tooling can use the red count to judge panel wear.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DrawStats {
+    /// pixels drawn black
+    pub black: u32,
+    /// pixels drawn white
+    pub white: u32,
+    /// pixels drawn red
+    pub red: u32,
+    /// the color the buffers were last cleared to, if they have been
+    pub cleared_to: Option<Color>,
+}
+
+impl DrawStats {
+    fn count(&mut self, color: Color) {
+        self.add(color, 1)
+    }
+
+    fn add(&mut self, color: Color, pixels: u32) {
+        let counter = match color {
+            Color::Black => &mut self.black,
+            Color::White => &mut self.white,
+            Color::Red => &mut self.red,
+        };
+        *counter = counter.saturating_add(pixels);
+    }
+}
+
+/// A display that uses SRAM for backing buffers for drawing into and updating the display from.
+#[cfg(feature = "sram")]
+pub type SramGraphicDisplay<I> = GraphicDisplay<I, SramBuffers>;
+
+impl<'a, I> GraphicDisplay<I, RamBuffers<'a>>
 where
     I: DisplayInterface,
 {
@@ -26,50 +876,1341 @@ where
     /// B/W and Red buffers for drawing into must be supplied. These should be `rows` * `cols` / `8` in
     /// length.
     pub fn new(display: Display<I>, black_buffer: &'a mut [u8], red_buffer: &'a mut [u8]) -> Self {
+        Self::with_store(display, RamBuffers::new(black_buffer, red_buffer))
+    }
+
+    /// Iterate over the packed rows of a plane.
+    ///
+    /// Rows are in the controller's native orientation, each `cols` / `8` bytes, as sent by
+    /// [GraphicDisplay::update]. Useful for compressing a frame to send to another display.
+    /// With [BufferLayout::Interleaved] the rows are of packed pixels rather than a plane.
+    pub fn iter_rows(&self, plane: Plane) -> core::slice::Chunks<'_, u8> {
+        let stride = self.cols() as usize / 8;
+        self.store.plane(plane).chunks(stride)
+    }
+
+    /// Iterate over the pixels in the buffers.
+    ///
+    /// Pixels are in drawing coordinates, after rotation, row by row from the top left, so they
+    /// can be drawn on another display as they are. The colors are as the panel would show them.
+    ///
+    /// ### Example
+    ///
+    /// ```ignore
+    /// // mirror the frame on a second display
+    /// mirror.draw_iter(display.pixels())?;
+    /// ```
+    pub fn pixels(&self) -> Pixels<'_> {
+        Pixels {
+            black: self.store.plane(Plane::Black),
+            red: self.store.plane(Plane::Red),
+            config: self.config(),
+            size: self.size(),
+            masks: self.plane_masks(),
+            next: 0,
+        }
+    }
+}
+
+/// Iterator over the pixels in the buffers of a [GraphicDisplay], created by
+/// [GraphicDisplay::pixels].
+pub struct Pixels<'d> {
+    black: &'d [u8],
+    red: &'d [u8],
+    config: &'d Config,
+    size: Size,
+    masks: (u8, u8),
+    // index of the next pixel, counting row by row
+    next: u32,
+}
+
+impl<'d> Pixels<'d> {
+    // the B/W and Red plane bits of a pixel
+    fn planes(&self, x: u32, y: u32) -> (bool, bool) {
+        let (index, bit) = plane_index(x, y, self.config);
+        if self.config.buffer_layout == BufferLayout::Interleaved {
+            let (index, shift) = packed_pixel(index as u32, bit);
+            let index = index as usize;
+            let n = self.black.len();
+            let byte = if index < n {
+                self.black[index]
+            } else {
+                self.red[index - n]
+            };
+            return ((byte >> (shift + 1)) & 1 != 0, (byte >> shift) & 1 != 0);
+        }
+        (
+            (self.black[index] ^ self.masks.0) & bit != 0,
+            (self.red[index] ^ self.masks.1) & bit != 0,
+        )
+    }
+}
+
+impl<'d> Iterator for Pixels<'d> {
+    type Item = Pixel<Color>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let width = self.size.width;
+        if self.next >= width * self.size.height {
+            return None;
+        }
+        let (x, y) = (self.next % width, self.next / width);
+        self.next += 1;
+        let (black, red) = self.planes(x, y);
+        Some(Pixel(
+            Point::new(x as i32, y as i32),
+            Color::from_planes(black, red),
+        ))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = (self.size.width * self.size.height - self.next) as usize;
+        (left, Some(left))
+    }
+}
+
+#[cfg(feature = "sram")]
+impl<I> GraphicDisplay<I, SramBuffers>
+where
+    I: SramBacked,
+{
+    /// Promote a `Display` to a `SramGraphicDisplay`.
+    pub fn new_sram(display: Display<I>) -> Self {
+        let sz = (display.rows() as u32 * display.cols() as u32) / 8;
+        Self::with_store(display, SramBuffers::new(sz))
+    }
+
+    /// Borrow the sram after the display buffers as general storage.
+    ///
+    /// Anything stored there is kept across updates.
+    pub fn storage(&mut self) -> SramHandle<'_, I> {
+        let start = self.store.end_address();
+        let len = self
+            .display
+            .interface()
+            .sram_capacity()
+            .saturating_sub(start.get());
+        SramHandle::new(self.display.interface(), start, len)
+    }
+}
+
+#[cfg(feature = "sram")]
+impl<I, C> GraphicDisplay<I, SramBuffers, C>
+where
+    I: SramBacked,
+    C: PanelColor,
+{
+    /// Move the buffers from the sram into RAM, for faster drawing.
+    ///
+    /// The contents of the buffers are copied, and the panel isn't reset, so drawing continues
+    /// where it left off. `black_buffer` and `red_buffer` must be the length of a plane. On an
+    /// error the display is returned with the buffers still in the sram.
+    ///
+    /// ### Example
+    ///
+    /// ```ignore
+    /// // plenty of memory while idle
+    /// let mut display = display.into_ram(&mut black, &mut red).map_err(|(_, e)| e)?;
+    /// draw_dashboard(&mut display)?;
+    /// // give the memory back for the TLS handshake
+    /// let display = display.into_sram().map_err(|(_, e)| e)?;
+    /// ```
+    #[allow(clippy::type_complexity, clippy::result_large_err)]
+    pub fn into_ram<'a>(
+        mut self,
+        black_buffer: &'a mut [u8],
+        red_buffer: &'a mut [u8],
+    ) -> Result<GraphicDisplay<I, RamBuffers<'a>, C>, (Self, Error<I::Error>)> {
+        let copied = [
+            (Plane::Black, &mut *black_buffer),
+            (Plane::Red, &mut *red_buffer),
+        ]
+        .iter_mut()
+        .try_for_each(|(plane, buffer)| {
+            let region = self.store.region(*plane);
+            if buffer.len() != region.len() as usize {
+                return Err(Error::BufferSize {
+                    expected: region.len() as usize,
+                    actual: buffer.len(),
+                });
+            }
+            self.display.interface().sram_read(region.start(), buffer)
+        });
+        match copied {
+            Ok(()) => Ok(self.replace_store(RamBuffers::new(black_buffer, red_buffer))),
+            Err(e) => Err((self, e)),
+        }
+    }
+}
+
+#[cfg(feature = "sram")]
+impl<'a, I, C> GraphicDisplay<I, RamBuffers<'a>, C>
+where
+    I: SramBacked,
+    C: PanelColor,
+{
+    /// Move the buffers from RAM into the sram, freeing the RAM buffers.
+    ///
+    /// The inverse of [GraphicDisplay::into_ram], the buffers are placed in the sram as by
+    /// `GraphicDisplay::new_sram`. Returns `Error::BufferSize` if the buffers aren't the length
+    /// of a plane or don't fit in the sram. On an error the display is returned with the buffers
+    /// still in RAM.
+    #[allow(clippy::type_complexity, clippy::result_large_err)]
+    pub fn into_sram(
+        mut self,
+    ) -> Result<GraphicDisplay<I, SramBuffers, C>, (Self, Error<I::Error>)> {
+        let store = SramBuffers::new(self.plane_bytes());
+        let capacity = self.display.interface().sram_capacity();
+        let copied = [Plane::Black, Plane::Red].iter().try_for_each(|plane| {
+            let region = store.region(*plane);
+            let buffer = self.store.plane(*plane);
+            if buffer.len() != region.len() as usize {
+                return Err(Error::BufferSize {
+                    expected: region.len() as usize,
+                    actual: buffer.len(),
+                });
+            }
+            if !region.start().fits(region.len(), capacity) {
+                return Err(Error::BufferSize {
+                    expected: store.end_address().get() as usize,
+                    actual: capacity as usize,
+                });
+            }
+            self.display.interface().sram_write(region.start(), buffer)
+        });
+        match copied {
+            Ok(()) => Ok(self.replace_store(store)),
+            Err(e) => Err((self, e)),
+        }
+    }
+}
+
+impl<I, S> GraphicDisplay<I, S>
+where
+    I: DisplayInterface,
+    S: FrameBufferStore<I>,
+{
+    /// Promote a `Display` to a `GraphicDisplay` with buffers in `store`.
+    pub fn with_store(display: Display<I>, store: S) -> Self {
         GraphicDisplay {
             display,
-            black_buffer,
-            red_buffer,
+            store,
+            stats: DrawStats::default(),
+            compositing: Compositing::default(),
+            color: PhantomData,
+        }
+    }
+}
+
+impl<I, S, C> GraphicDisplay<I, S, C>
+where
+    I: DisplayInterface,
+    S: FrameBufferStore<I>,
+    C: PanelColor,
+{
+    /// Draw in another color type, keeping the buffers as they are.
+    ///
+    /// ### Example
+    ///
+    /// ```ignore
+    /// let mut display = GraphicDisplay::new(display, &mut black, &mut red)
+    ///     .with_panel_color::<Bwry>();
+    /// ```
+    pub fn with_panel_color<P: PanelColor>(self) -> GraphicDisplay<I, S, P> {
+        GraphicDisplay {
+            display: self.display,
+            store: self.store,
+            stats: self.stats,
+            compositing: self.compositing,
+            color: PhantomData,
+        }
+    }
+
+    // swap the store, keeping everything else
+    #[cfg(feature = "sram")]
+    fn replace_store<T: FrameBufferStore<I>>(self, store: T) -> GraphicDisplay<I, T, C> {
+        GraphicDisplay {
+            display: self.display,
+            store,
+            stats: self.stats,
+            compositing: self.compositing,
+            color: PhantomData,
+        }
+    }
+
+    /// Returns the pixels drawn in each color since the last clear.
+    ///
+    /// Pixels drawn through [GraphicDisplay::black_layer] or [GraphicDisplay::red_layer] count as
+    /// black or red when `On`, and are not counted when `Off`.
+    pub fn stats(&self) -> DrawStats {
+        self.stats
+    }
+
+    /// Returns how drawing combines with the buffers.
+    pub fn compositing(&self) -> Compositing {
+        self.compositing
+    }
+
+    /// Set how drawing combines with the buffers, see [Compositing]. Defaults to `Replace`.
+    ///
+    /// Applies to drawing pixels and [GraphicDisplay::fill_rect], clearing always replaces.
+    ///
+    /// ### Example
+    ///
+    /// ```ignore
+    /// display.set_compositing(Compositing::RedOverlay);
+    /// Text::new("LOW BATTERY", Point::new(4, 20), style_red).draw(&mut display)?;
+    /// display.set_compositing(Compositing::Replace);
+    /// ```
+    pub fn set_compositing(&mut self, compositing: Compositing) {
+        self.compositing = compositing;
+    }
+
+    /// update the display
+    ///
+    /// With [UpdateStrategy::DiffUpload] only the rows that changed since the previous update
+    /// are sent, and with [UpdateStrategy::WhiteFlashFirst] the panel is refreshed to white
+    /// first, waiting for that refresh to finish. Returns `Error::BufferSize` if the buffers are not `rows` * `cols` / `8` in
+    /// length.
+    ///
+    /// With a LUT profile other than `LutProfile::Otp` the controller is in black and white mode,
+    /// and the previous frame is sent as the old frame (DTM1) and the B/W plane as the new frame
+    /// (DTM2). `LutProfile::FastMono` only drives the pixels that differ, so needs a store that
+    /// keeps the previous frame, such as [RamBuffers::with_previous].
+    pub fn update(&mut self) -> Result<(), Error<I::Error>> {
+        self.update_guarded(|| true)
+    }
+
+    /// Perform a hardware reset, returning a guard that puts the display into deep sleep when
+    /// dropped, see [Display::reset_guarded].
+    ///
+    /// ### Example
+    ///
+    /// ```ignore
+    /// let mut display = display.reset_guarded(&mut delay)?;
+    /// display.update()?;
+    /// display.sleep()?;
+    /// ```
+    pub fn reset_guarded<D: hal::blocking::delay::DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<WakeGuard<'_, I, Self>, I::Error> {
+        WakeGuard::reset(self, delay)
+    }
+
+    /// update the display, then wait for the refresh to finish
+    ///
+    /// Returns `Error::RefreshFailed` if the controller is still busy after `timeout_ms`
+    /// milliseconds, see [Display::wait_for_refresh]. Unattended devices can log the failure and
+    /// call [Display::recover] rather than assume the refresh succeeded.
+    ///
+    /// ### Example
+    ///
+    /// ```ignore
+    /// if let Err(Error::RefreshFailed) = display.update_checked(&mut delay, 30_000) {
+    ///     log_failure();
+    ///     display.recover(&mut delay, true)?;
+    /// }
+    /// ```
+    pub fn update_checked<D: hal::blocking::delay::DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+        timeout_ms: u32,
+    ) -> Result<(), Error<I::Error>> {
+        self.update()?;
+        self.display.wait_for_refresh(delay, timeout_ms)
+    }
+
+    /// update the display, if the supply is good enough to finish a refresh
+    ///
+    /// A refresh interrupted by a brown-out can leave a permanent ghost image on the panel.
+    /// `supply_ok` is called after the buffers are sent, just before the refresh is started, and
+    /// should return false if the supply is too low, for example if the battery voltage is below
+    /// the panel minimum. Returns `Error::SupplyLow` without starting the refresh in that case.
+    /// With [UpdateStrategy::WhiteFlashFirst] it is called before the white refresh, once for
+    /// both refreshes.
+    ///
+    /// ### Example
+    ///
+    /// ```ignore
+    /// display.update_guarded(|| battery_mv(&mut adc) > 3300)?;
+    /// ```
+    pub fn update_guarded<F>(&mut self, supply_ok: F) -> Result<(), Error<I::Error>>
+    where
+        F: FnOnce() -> bool,
+    {
+        let white_first = self.config().update_strategy == UpdateStrategy::WhiteFlashFirst;
+        // update black, then red
+        // the partial windows of a diff upload are still shown by a full refresh
+        let full = RefreshKind::for_profile(self.config().lut_profile());
+        let uploaded = if white_first {
+            self.upload_white()
+        } else if self.diff_upload() {
+            self.upload_diff()
+        } else {
+            self.upload_frame()
+        };
+        if let Err(e) = uploaded {
+            return Err(self.upload_failed(e));
+        }
+        if !supply_ok() {
+            self.display.record_refresh(false);
+            return Err(Error::SupplyLow);
+        }
+        self.display
+            .signal_refresh(full)
+            .map_err(Error::Interface)?;
+        if white_first {
+            self.display.finish_refresh();
+            if let Err(e) = self.upload_frame() {
+                return Err(self.upload_failed(e));
+            }
+            self.display
+                .signal_refresh(full)
+                .map_err(Error::Interface)?;
+        }
+        Ok(())
+    }
+
+    // record an upload that didn't finish, returning its error
+    fn upload_failed(&mut self, e: Error<I::Error>) -> Error<I::Error> {
+        if let Error::Interface(_) = e {
+            self.display.record_bus_error();
+        }
+        self.display.record_refresh(false);
+        e
+    }
+
+    // send both planes, or the old and new frame in black and white mode
+    fn upload_frame(&mut self) -> Result<(), Error<I::Error>> {
+        if self.config().lut_profile() == LutProfile::Otp {
+            self.upload_layer(0).and_then(|_| self.upload_layer(1))
+        } else {
+            self.upload_mono()
+        }
+    }
+
+    // send the old frame to DTM1 and the B/W plane to DTM2, for the black and white (KW) mode of
+    // LUT profiles other than Otp, keeping the B/W plane as the old frame
+    //
+    // Without the previous frame the B/W plane is sent as the old frame too, so every pixel
+    // counts as unchanged and only the full waveforms drive it.
+    fn upload_mono(&mut self) -> Result<(), Error<I::Error>> {
+        let nbytes = self.plane_bytes();
+        let interleaved = self.interleaved();
+        if self.store.keeps_previous() && !interleaved {
+            let interface = self.display.interface();
+            interface
+                .send_command(regs::data_transmission(0))
+                .map_err(Error::Interface)?;
+            self.store.update_epd_previous(interface, 0, nbytes)?;
+            self.display.record_upload(0);
+        } else {
+            self.upload_layer(0)?;
+        }
+        let (black_mask, _) = self.plane_masks();
+        let interface = self.display.interface();
+        interface
+            .send_command(regs::data_transmission(1))
+            .map_err(Error::Interface)?;
+        if interleaved {
+            self.store
+                .update_epd_range_interleaved(interface, 0, 0, nbytes, black_mask)?;
+        } else {
+            self.store.update_epd_range(interface, 0, 0, nbytes)?;
+            self.store.retain_range(interface, 0, 0, nbytes)?;
+        }
+        self.display.record_upload(1);
+        Ok(())
+    }
+
+    // send white to both layers, for UpdateStrategy::WhiteFlashFirst
+    fn upload_white(&mut self) -> Result<(), Error<I::Error>> {
+        let (black, red) = color_bytes(Color::White);
+        let (black_mask, red_mask) = self.plane_masks();
+        for (layer, byte) in [(0, black ^ black_mask), (1, red ^ red_mask)]
+            .iter()
+            .copied()
+        {
+            self.display
+                .fill_plane(layer, byte)
+                .map_err(Error::Interface)?;
+            self.display.record_upload(layer);
+        }
+        Ok(())
+    }
+
+    /// Send the pixels in an area and refresh only that part of the panel, then wait for the
+    /// refresh to finish.
+    ///
+    /// The area is clipped to the display and widened to byte boundaries in the controller's
+    /// sources, see [Display::partial_window_for]. The controller is put in partial mode, sent
+    /// the window of each plane, refreshed and taken out of partial mode again, so a small area
+    /// such as a clock or a [Ticker](../ticker/struct.Ticker.html) changes without the rest of the
+    /// panel flashing. Nothing is sent if the area is outside the display. Returns
+    /// `Error::Unsupported` if the store can't send part of a plane.
+    ///
+    /// ### Example
+    ///
+    /// ```ignore
+    /// let clock = Rectangle::new(Point::new(150, 0), Size::new(62, 16));
+    /// Text::with_baseline(&time, clock.top_left, style, Baseline::Top).draw(&mut display)?;
+    /// display.update_window(clock)?;
+    /// ```
+    pub fn update_window(&mut self, area: Rectangle) -> Result<(), Error<I::Error>> {
+        let window = match self.display.partial_window_for(area) {
+            Some(window) => window,
+            None => return Ok(()),
+        };
+        // nothing is sent if the store can't send the window
+        let stride = self.cols() as u32 / 8;
+        let end = u32::from(window.gate_end) * stride + (u32::from(window.source_end) + 1) / 8;
+        self.store.check_range(self.interleaved(), end)?;
+        let sent = self.upload_window(window).and_then(|_| {
+            self.display
+                .signal_refresh(RefreshKind::Partial)
+                .map_err(Error::Interface)
+        });
+        if let Err(e) = sent {
+            // don't leave the controller in partial mode, or trust it if that fails too
+            if Command::PartialOut
+                .execute(self.display.interface())
+                .is_err()
+            {
+                self.display.record_bus_error();
+            }
+            return Err(self.upload_failed(e));
+        }
+        self.display.finish_refresh();
+        Command::PartialOut
+            .execute(self.display.interface())
+            .map_err(|e| {
+                self.display.record_bus_error();
+                Error::Interface(e)
+            })
+    }
+
+    // enter partial mode and send the window of both planes
+    fn upload_window(&mut self, window: PartialWindow) -> Result<(), Error<I::Error>> {
+        self.display.record_frame(false);
+        Command::PartialIn
+            .execute(self.display.interface())
+            .map_err(Error::Interface)?;
+        self.send_window(window)
+    }
+
+    // set the window, in partial mode, and send it from both planes
+    fn send_window(&mut self, window: PartialWindow) -> Result<(), Error<I::Error>> {
+        let interleaved = self.interleaved();
+        let (black_mask, red_mask) = self.plane_masks();
+        let stride = self.cols() as u32 / 8;
+        let first = u32::from(window.source_start) / 8;
+        let nbytes = (u32::from(window.source_end) + 1) / 8 - first;
+        Command::PartialWindow(window)
+            .execute(self.display.interface())
+            .map_err(Error::Interface)?;
+        for (layer, invert) in [(0, black_mask), (1, red_mask)].iter().copied() {
+            let interface = self.display.interface();
+            interface
+                .send_command(regs::data_transmission(layer))
+                .map_err(Error::Interface)?;
+            for gate in window.gate_start..=window.gate_end {
+                let index = u32::from(gate) * stride + first;
+                if interleaved {
+                    self.store
+                        .update_epd_range_interleaved(interface, layer, index, nbytes, invert)?;
+                } else {
+                    self.store
+                        .update_epd_range(interface, layer, index, nbytes)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // update with UpdateStrategy::DiffUpload
+    fn diff_upload(&self) -> bool {
+        self.config().update_strategy == UpdateStrategy::DiffUpload
+            && self.config().lut_profile() == LutProfile::Otp
+            && !self.interleaved()
+            && self.store.keeps_previous()
+    }
+
+    // send the runs of rows that changed since the previous frame, or both planes if the
+    // controller RAM doesn't hold it, keeping the frame as the previous one
+    fn upload_diff(&mut self) -> Result<(), Error<I::Error>> {
+        let stride = self.cols() as u32 / 8;
+        let rows = self.rows();
+        if !self.display.frame_in_ram() {
+            self.upload_layer(0)?;
+            self.upload_layer(1)?;
+            for layer in 0..2 {
+                self.store.retain_range(
+                    self.display.interface(),
+                    layer,
+                    0,
+                    rows as u32 * stride,
+                )?;
+            }
+            self.display.record_frame(true);
+            return Ok(());
+        }
+        // the previous frame is updated as the rows are compared, so the controller RAM can't be
+        // trusted to match it until every changed row has been sent
+        self.display.record_frame(false);
+        let mut partial = false;
+        let mut run = None;
+        for gate in 0..=rows {
+            let changed = gate < rows && {
+                let interface = self.display.interface();
+                let index = u32::from(gate) * stride;
+                let black = self.store.retain_range(interface, 0, index, stride)?;
+                let red = self.store.retain_range(interface, 1, index, stride)?;
+                black || red
+            };
+            match (run, changed) {
+                (None, true) => run = Some(gate),
+                (Some(gate_start), false) => {
+                    if !partial {
+                        Command::PartialIn
+                            .execute(self.display.interface())
+                            .map_err(Error::Interface)?;
+                        partial = true;
+                    }
+                    self.send_window(PartialWindow {
+                        source_start: 0,
+                        source_end: self.cols() - 1,
+                        gate_start,
+                        gate_end: gate - 1,
+                    })?;
+                    run = None;
+                }
+                _ => (),
+            }
+        }
+        if partial {
+            Command::PartialOut
+                .execute(self.display.interface())
+                .map_err(Error::Interface)?;
+        }
+        self.display.record_frame(true);
+        Ok(())
+    }
+
+    /// Send one plane to the controller, without refreshing.
+    ///
+    /// The planes can be sent at different times, sleeping the MCU in between, then shown with
+    /// [Display::refresh]. The controller must not be reset or put in deep sleep in between, as
+    /// that loses the planes. Returns `Error::InvalidState` if the panel is in deep sleep or
+    /// hasn't been reset.
+    ///
+    /// ### Example
+    ///
+    /// ```ignore
+    /// display.upload_plane(Plane::Black)?;
+    /// // sleep the MCU, leaving the controller in standby
+    /// display.upload_plane(Plane::Red)?;
+    /// display.refresh()?;
+    /// ```
+    pub fn upload_plane(&mut self, plane: Plane) -> Result<(), Error<I::Error>> {
+        match self.panel_state() {
+            PanelState::Awake | PanelState::Standby => (),
+            PanelState::Unknown | PanelState::DeepSleep => return Err(Error::InvalidState),
+        }
+        self.upload_layer(match plane {
+            Plane::Black => 0,
+            Plane::Red => 1,
+        })
+    }
+
+    // send a layer to the controller
+    fn upload_layer(&mut self, layer: u8) -> Result<(), Error<I::Error>> {
+        let nbytes = self.plane_bytes();
+        if self.interleaved() {
+            let (black_mask, red_mask) = self.plane_masks();
+            let invert = if layer == 0 { black_mask } else { red_mask };
+            self.store
+                .update_epd_interleaved(self.display.interface(), layer, nbytes, invert)?;
+        } else {
+            self.store
+                .update_epd(self.display.interface(), layer, nbytes)?;
+        }
+        self.display.record_upload(layer);
+        Ok(())
+    }
+
+    /// Clear the buffers, filling them a single color.
+    fn clear(&mut self, color: C) -> Result<(), S::Error> {
+        let (black, red) = color_bytes(color);
+        let (black, red) = if self.interleaved() {
+            let byte = pack(black, red)[0];
+            (byte, byte)
+        } else {
+            let (black_mask, red_mask) = self.plane_masks();
+            (black ^ black_mask, red ^ red_mask)
+        };
+        self.store.fill(self.display.interface(), 0, black)?;
+        self.store.fill(self.display.interface(), 1, red)?;
+        let (black_bit, red_bit) = color.planes();
+        self.stats = DrawStats {
+            cleared_to: Some(Color::from_planes(black_bit, red_bit)),
+            ..DrawStats::default()
+        };
+        Ok(())
+    }
+
+    /// Fill a rectangle with a color.
+    ///
+    /// The rectangle is in the same coordinates as drawing, and is clipped to the display. Whole
+    /// bytes are written without reading them first, in blocks where the store supports it, so
+    /// this is much faster than drawing a filled rectangle pixel by pixel.
+    pub fn fill_rect(&mut self, area: Rectangle, color: C) -> Result<(), S::Error> {
+        let (s0, s1, g0, g1) = match self.display.native_rect(area) {
+            Some(rect) => rect,
+            None => return Ok(()),
+        };
+        let (black_bit, red_bit) = color.planes();
+        self.stats.add(
+            Color::from_planes(black_bit, red_bit),
+            (s1 - s0 + 1) * (g1 - g0 + 1),
+        );
+        let (black, red) = color_bytes(color);
+        // the bits of the B/W plane that are written
+        let black_bits = if self.keeps_black(red_bit) {
+            0x00
+        } else {
+            0xFF
+        };
+        let (black_mask, red_mask) = self.plane_masks();
+        let stride = self.cols() as u32 / 8;
+        let (first, last) = (s0 / 8, s1 / 8);
+        let order = self.config().bit_order();
+        let head = order.mask(0xFFu8 >> (s0 % 8));
+        let tail = order.mask(0xFFu8 << (7 - s1 % 8));
+        for gate in g0..=g1 {
+            let row = gate * stride;
+            // partial bytes at either end are read, modified and written
+            let (mut lo, mut hi) = (first, last + 1);
+            if first == last && head & tail != 0xFF {
+                self.mask_byte(row + first, head & tail, black_bits, black, red)?;
+                continue;
+            }
+            if head != 0xFF {
+                self.mask_byte(row + first, head, black_bits, black, red)?;
+                lo += 1;
+            }
+            if tail != 0xFF {
+                self.mask_byte(row + last, tail, black_bits, black, red)?;
+                hi -= 1;
+            }
+            if lo < hi && self.interleaved() && black_bits != 0xFF {
+                // whole bytes hold both planes, so keeping the B/W plane needs a read
+                for index in lo..hi {
+                    self.mask_byte(row + index, 0xFF, black_bits, black, red)?;
+                }
+            } else if lo < hi && self.interleaved() {
+                let byte = pack(black, red)[0];
+                self.fill_packed(2 * (row + lo), 2 * (hi - lo), byte)?;
+            } else if lo < hi {
+                let interface = self.display.interface();
+                if black_bits == 0xFF {
+                    self.store
+                        .fill_range(interface, 0, row + lo, hi - lo, black ^ black_mask)?;
+                }
+                self.store
+                    .fill_range(interface, 1, row + lo, hi - lo, red ^ red_mask)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Replace a plane with run length encoded data, see the [codec](../codec/index.html) module.
+    ///
+    /// The data is the plane as sent to the controller, such as a plane of another display
+    /// encoded with [codec::encode]. It is decoded straight into the buffers, a run at a time.
+    /// Returns `Error::InvalidStream` if the data is truncated and `Error::BufferSize` if it
+    /// doesn't decode to `rows` * `cols` / `8` bytes, without changing the plane in either case.
+    ///
+    /// ### Example
+    ///
+    /// ```ignore
+    /// let len = radio.receive(&mut packet)?;
+    /// display.apply_rle(Plane::Black, &packet[..len])?;
+    /// ```
+    pub fn apply_rle(&mut self, plane: Plane, rle: &[u8]) -> Result<(), Error<S::Error>> {
+        let nbytes = self.plane_bytes();
+        let len = codec::decoded_len(rle).map_err(|_| Error::InvalidStream)?;
+        if len != nbytes as usize {
+            return Err(Error::BufferSize {
+                expected: nbytes as usize,
+                actual: len,
+            });
+        }
+        let layer = match plane {
+            Plane::Black => 0,
+            Plane::Red => 1,
+        };
+        let mut index = 0;
+        for run in codec::runs(rle) {
+            let run = run.map_err(|_| Error::InvalidStream)?;
+            match run {
+                Run::Literal(bytes) => {
+                    for (i, byte) in bytes.iter().enumerate() {
+                        self.write_plane_byte(layer, index + i as u32, *byte)?;
+                    }
+                }
+                Run::Repeat { byte, count } if !self.interleaved() => {
+                    self.store
+                        .fill_range(self.display.interface(), layer, index, count, byte)
+                        .map_err(Error::Interface)?;
+                }
+                Run::Repeat { byte, count } => {
+                    for i in 0..count {
+                        self.write_plane_byte(layer, index + i, byte)?;
+                    }
+                }
+            }
+            index += run.len();
+        }
+        Ok(())
+    }
+
+    // write a byte of a plane as sent to the controller
+    fn write_plane_byte(&mut self, layer: u8, index: u32, byte: u8) -> Result<(), Error<S::Error>> {
+        if !self.interleaved() {
+            return self
+                .store
+                .write_byte(self.display.interface(), layer, index, byte)
+                .map_err(Error::Interface);
+        }
+        let (black_mask, red_mask) = self.plane_masks();
+        // interleaved bytes hold the planes before inversion
+        let (byte, bits) = if layer == 0 {
+            (pack(byte ^ black_mask, 0x00), pack(0xFF, 0x00))
+        } else {
+            (pack(0x00, byte ^ red_mask), pack(0x00, 0xFF))
+        };
+        for i in 0..2 {
+            let old = self.read_packed(2 * index + i).map_err(Error::Interface)?;
+            let new = (old & !bits[i as usize]) | byte[i as usize];
+            self.write_packed(2 * index + i, new)
+                .map_err(Error::Interface)?;
+        }
+        Ok(())
+    }
+
+    /// Replace the planes with buffers in the layout of Adafruit's CircuitPython IL0373 driver.
+    ///
+    /// Those buffers are `rows` * `cols` / `8` bytes of rows in the controller's native
+    /// orientation, the lowest source in the most significant bit and a cleared bit for ink, so
+    /// frames drawn or captured with the Python driver show the same here whatever the rotation,
+    /// [BitOrder], plane inversion and [BufferLayout] of the config. Returns
+    /// `Error::BufferSize` if either buffer is the wrong size, without changing the planes.
+    ///
+    /// ### Example
+    ///
+    /// ```ignore
+    /// display.load_adafruit_buf(include_bytes!("black.bin"), include_bytes!("red.bin"))?;
+    /// display.update()?;
+    /// ```
+    pub fn load_adafruit_buf(&mut self, black: &[u8], red: &[u8]) -> Result<(), Error<S::Error>> {
+        self.check_adafruit_buf(black.len(), red.len())?;
+        let (black_mask, red_mask) = self.plane_masks();
+        let order = self.config().bit_order();
+        for (layer, buf, mask) in [(0, black, black_mask), (1, red, red_mask)] {
+            for (index, byte) in buf.iter().enumerate() {
+                self.write_plane_byte(layer, index as u32, order.mask(*byte) ^ mask)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Copy the planes into buffers in the layout of Adafruit's CircuitPython IL0373 driver, the
+    /// inverse of [GraphicDisplay::load_adafruit_buf].
+    ///
+    /// Useful for screenshots, the buffers can be written to a file and shown with the Python
+    /// driver or converted to an image on a PC. Returns `Error::BufferSize` if either buffer is
+    /// the wrong size.
+    pub fn export_adafruit_buf(
+        &mut self,
+        black: &mut [u8],
+        red: &mut [u8],
+    ) -> Result<(), Error<S::Error>> {
+        self.check_adafruit_buf(black.len(), red.len())?;
+        let (black_mask, red_mask) = self.plane_masks();
+        let order = self.config().bit_order();
+        for (layer, buf, mask) in [(0, black, black_mask), (1, red, red_mask)] {
+            for (index, byte) in buf.iter_mut().enumerate() {
+                let plane = self
+                    .read_plane_byte(layer, index as u32)
+                    .map_err(Error::Interface)?;
+                *byte = order.mask(plane ^ mask);
+            }
+        }
+        Ok(())
+    }
+
+    // check the sizes of a pair of Adafruit buffers
+    fn check_adafruit_buf<E>(&self, black: usize, red: usize) -> Result<(), Error<E>> {
+        let expected = self.plane_bytes() as usize;
+        match [black, red].into_iter().find(|&len| len != expected) {
+            Some(actual) => Err(Error::BufferSize { expected, actual }),
+            None => Ok(()),
+        }
+    }
+
+    // read a byte of a plane as sent to the controller
+    fn read_plane_byte(&mut self, layer: u8, index: u32) -> Result<u8, S::Error> {
+        if !self.interleaved() {
+            return self.store.read_byte(self.display.interface(), layer, index);
+        }
+        let (black_mask, red_mask) = self.plane_masks();
+        let mask = if layer == 0 { black_mask } else { red_mask };
+        let first = self.read_packed(2 * index)?;
+        let second = self.read_packed(2 * index + 1)?;
+        Ok(unpack(first, second, layer) ^ mask)
+    }
+
+    /// Invert the B/W plane in a rectangle, swapping black and white.
+    ///
+    /// The rectangle is in the same coordinates as drawing, and is clipped to the display.
+    /// Inverting the same rectangle again restores it, so this suits toggling a selection
+    /// highlight in a menu, shown with a partial refresh. The Red plane is not changed.
+    pub fn invert_rect(&mut self, area: Rectangle) -> Result<(), S::Error> {
+        let (s0, s1, g0, g1) = match self.display.native_rect(area) {
+            Some(rect) => rect,
+            None => return Ok(()),
+        };
+        let stride = self.cols() as u32 / 8;
+        let order = self.config().bit_order();
+        for gate in g0..=g1 {
+            for index in s0 / 8..=s1 / 8 {
+                // the bits of this byte inside the rectangle
+                let lo = if index == s0 / 8 { s0 % 8 } else { 0 };
+                let hi = if index == s1 / 8 { s1 % 8 } else { 7 };
+                let mask = order.mask((0xFFu8 >> lo) & (0xFFu8 << (7 - hi)));
+                self.invert_byte(gate * stride + index, mask)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Draw a sprite with its top left corner at `top_left`, skipping pixels of the
+    /// `transparent` color.
+    ///
+    /// The sprite is clipped to the display and drawn with the [Compositing] of the display. In
+    /// `Rotate0` with planar buffers the planes are copied a byte at a time, with the transparent
+    /// pixels masked out, in other rotations and layouts the sprite is drawn pixel by pixel.
+    ///
+    /// ### Example
+    ///
+    /// ```ignore
+    /// // a battery icon over a patterned background, without a white box around it
+    /// display.draw_sprite(&BATTERY, Point::new(200, 2), Some(Color::White))?;
+    /// ```
+    pub fn draw_sprite(
+        &mut self,
+        sprite: &Sprite<'_>,
+        top_left: Point,
+        transparent: Option<C>,
+    ) -> Result<(), S::Error> {
+        let area = Rectangle::new(top_left, Size::new(sprite.width(), sprite.height()));
+        let (s0, s1, g0, g1) = match self.display.native_rect(area) {
+            Some(rect) => rect,
+            None => return Ok(()),
+        };
+        let key = transparent.map(PanelColor::planes);
+        if self.rotation() != Rotation::Rotate0 || self.interleaved() {
+            // native_rect is in controller coordinates, so clip the sprite in drawing ones
+            let (cols, rows) = (u32::from(self.cols()), u32::from(self.rows()));
+            let bounds = match self.rotation() {
+                Rotation::Rotate0 | Rotation::Rotate180 => Size::new(cols, rows),
+                Rotation::Rotate90 | Rotation::Rotate270 => Size::new(rows, cols),
+            };
+            let bounds = Rectangle::new(Point::zero(), bounds);
+            let pixels = area.intersection(&bounds).points().filter_map(|point| {
+                let offset = point - top_left;
+                let color = sprite.pixel(offset.x as u32, offset.y as u32)?;
+                (Some(color.planes()) != key).then_some((point.x as u32, point.y as u32, color))
+            });
+            return self.set_pixels(pixels);
+        }
+        let stride = self.cols() as u32 / 8;
+        let order = self.config().bit_order();
+        let overlay = self.compositing == Compositing::RedOverlay;
+        for gate in g0..=g1 {
+            let y = (gate as i32 - top_left.y) as u32;
+            for index in s0 / 8..=s1 / 8 {
+                // the bits of this byte inside the sprite and the display
+                let lo = if index == s0 / 8 { s0 % 8 } else { 0 };
+                let hi = if index == s1 / 8 { s1 % 8 } else { 7 };
+                let mut mask = (0xFFu8 >> lo) & (0xFFu8 << (7 - hi));
+                let (black, red) = sprite.bits(y, (index * 8) as i32 - top_left.x);
+                if let Some((key_black, key_red)) = key {
+                    let same = |bits: u8, key: bool| if key { bits } else { !bits };
+                    mask &= !(same(black, key_black) & same(red, key_red));
+                }
+                if mask == 0 {
+                    continue;
+                }
+                self.stats
+                    .add(Color::Black, (mask & !black & red).count_ones());
+                self.stats
+                    .add(Color::White, (mask & black & red).count_ones());
+                self.stats.add(Color::Red, (mask & !red).count_ones());
+                // red pixels leave the B/W plane alone when overlaid
+                let black_bits = if overlay { red } else { 0xFF };
+                self.mask_byte(
+                    gate * stride + index,
+                    order.mask(mask),
+                    order.mask(black_bits),
+                    order.mask(black),
+                    order.mask(red),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Draw an 8 pixel wide glyph with its top left corner at `top_left`, see the
+    /// [glyph](../glyph/index.html) module.
+    ///
+    /// `glyph` is a byte per row, as in a [CellFont], of at most 32 rows. Set bits are drawn in
+    /// `foreground` and cleared bits in `background`, or skipped if it is `None`. In `Rotate90`
+    /// and `Rotate270` the cell is turned on its side in registers and each of its columns is
+    /// written as whole bytes of a gate, rather than a byte for every pixel. Cells in other
+    /// rotations, where the rows are already bytes, or crossing the edge of the display, which
+    /// are clipped, are drawn pixel by pixel.
+    pub fn draw_glyph_cell(
+        &mut self,
+        glyph: &[u8],
+        top_left: Point,
+        foreground: C,
+        background: Option<C>,
+    ) -> Result<(), S::Error> {
+        let glyph = &glyph[..glyph.len().min(32)];
+        if glyph.is_empty() {
+            return Ok(());
+        }
+        let height = glyph.len() as u32;
+        let area = Rectangle::new(top_left, Size::new(CellFont::WIDTH, height));
+        let bounds = Rectangle::new(Point::zero(), self.size());
+        let rotated = matches!(self.rotation(), Rotation::Rotate90 | Rotation::Rotate270);
+        if !rotated || area.intersection(&bounds) != area {
+            let pixels = area.intersection(&bounds).points().filter_map(|point| {
+                let offset = point - top_left;
+                let set = glyph[offset.y as usize] & (0x80 >> offset.x) != 0;
+                let color = if set { Some(foreground) } else { background }?;
+                Some((point.x as u32, point.y as u32, color))
+            });
+            return self.set_pixels(pixels);
+        }
+        let (cols, rows) = (u32::from(self.cols()), u32::from(self.rows()));
+        let stride = cols / 8;
+        let order = self.config().bit_order();
+        let overlay = self.compositing == Compositing::RedOverlay;
+        let fill = |bit| if bit { 0xFFu8 } else { 0x00 };
+        let (fg_black, fg_red) = foreground.planes();
+        let (bg_black, bg_red) = background.map_or((true, true), PanelColor::planes);
+        let (x0, y0) = (top_left.x as u32, top_left.y as u32);
+        // the cell's rows in the top bits, in the order of the sources
+        let cell = u32::MAX << (32 - height);
+        for (x, column) in (x0..).zip(glyph::columns(glyph)) {
+            let (gate, source, ink) = match self.rotation() {
+                Rotation::Rotate90 => (
+                    x,
+                    cols - y0 - height,
+                    column.reverse_bits() << (32 - height),
+                ),
+                _ => (rows - 1 - x, y0, column),
+            };
+            // the bytes of the gate the column covers, from the first
+            let shift = source % 8;
+            let ink = (u64::from(ink) << 32) >> shift;
+            let cell = (u64::from(cell) << 32) >> shift;
+            for b in 0..(shift + height).div_ceil(8) {
+                let ink = (ink >> (56 - 8 * b)) as u8;
+                let cell = (cell >> (56 - 8 * b)) as u8;
+                let mask = if background.is_some() { cell } else { ink };
+                if mask == 0 {
+                    continue;
+                }
+                let black = (ink & fill(fg_black)) | (!ink & fill(bg_black));
+                let red = (ink & fill(fg_red)) | (!ink & fill(bg_red));
+                self.stats.add(
+                    Color::from_planes(fg_black, fg_red),
+                    (mask & ink).count_ones(),
+                );
+                self.stats.add(
+                    Color::from_planes(bg_black, bg_red),
+                    (mask & !ink).count_ones(),
+                );
+                // red pixels leave the B/W plane alone when overlaid
+                let black_bits = if overlay { red } else { 0xFF };
+                self.mask_byte(
+                    gate * stride + source / 8 + b,
+                    order.mask(mask),
+                    order.mask(black_bits),
+                    order.mask(black),
+                    order.mask(red),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Draw a line of text in a [CellFont], the first cell with its top left corner at
+    /// `top_left`, with [GraphicDisplay::draw_glyph_cell].
+    ///
+    /// Returns the top left corner of the cell after the text. Characters the font doesn't have
+    /// are skipped, leaving their cell unchanged.
+    pub fn draw_cell_text(
+        &mut self,
+        text: &str,
+        font: &CellFont<'_>,
+        top_left: Point,
+        foreground: C,
+        background: Option<C>,
+    ) -> Result<Point, S::Error> {
+        let mut position = top_left;
+        for c in text.chars() {
+            if let Some(glyph) = font.glyph(c) {
+                self.draw_glyph_cell(glyph, position, foreground, background)?;
+            }
+            position.x += CellFont::WIDTH as i32;
+        }
+        Ok(position)
+    }
+
+    // invert the masked bits of a byte in the B/W plane
+    fn invert_byte(&mut self, index: u32, mask: u8) -> Result<(), S::Error> {
+        if self.interleaved() {
+            let masks = pack(mask, 0x00);
+            for i in 0..2 {
+                let byte = self.read_packed(2 * index + i)?;
+                self.write_packed(2 * index + i, byte ^ masks[i as usize])?;
+            }
+            return Ok(());
         }
+        let interface = self.display.interface();
+        let byte = self.store.read_byte(interface, 0, index)?;
+        self.store.write_byte(interface, 0, index, byte ^ mask)
     }
 
-    /// update the display
-    pub fn update(&mut self) -> Result<(), I::Error> {
-        let buf_limit = ((self.rows() * self.cols() as u16) as u32 / 8) as u16;
-        // update black
-        self.display
-            .interface()
-            .epd_update_data(0, buf_limit, self.black_buffer)
-            .ok();
-        // update red
-        self.display
-            .interface()
-            .epd_update_data(1, buf_limit, self.red_buffer)
-            .ok();
-        self.display.signal_update()
+    // set the masked bits of a byte in both planes, only the black_bits of the B/W plane
+    fn mask_byte(
+        &mut self,
+        index: u32,
+        mask: u8,
+        black_bits: u8,
+        black: u8,
+        red: u8,
+    ) -> Result<(), S::Error> {
+        if self.interleaved() {
+            let (packed, masks) = (pack(black, red), pack(mask & black_bits, mask));
+            for i in 0..2 {
+                let old = self.read_packed(2 * index + i)?;
+                let new = (old & !masks[i as usize]) | (packed[i as usize] & masks[i as usize]);
+                self.write_packed(2 * index + i, new)?;
+            }
+            return Ok(());
+        }
+        let (black_mask, red_mask) = self.plane_masks();
+        let interface = self.display.interface();
+        let planes = [
+            (0, black, black_mask, mask & black_bits),
+            (1, red, red_mask, mask),
+        ];
+        for &(layer, value, invert, mask) in planes.iter().filter(|plane| plane.3 != 0) {
+            let old = self.store.read_byte(interface, layer, index)? ^ invert;
+            let new = (old & !mask) | (value & mask);
+            self.store
+                .write_byte(interface, layer, index, new ^ invert)?;
+        }
+        Ok(())
     }
 
-    /// Clear the buffers, filling them a single color.
-    fn clear(&mut self, color: Color) -> Result<(), core::convert::Infallible> {
-        let (black, red) = match color {
-            Color::White => (0xFF, 0xFF),
-            Color::Black => (0x00, 0xFF),
-            Color::Red => (0xFF, 0x00),
-        };
+    // whether drawing a color with the red plane bit leaves the B/W plane alone
+    fn keeps_black(&self, red_bit: bool) -> bool {
+        !red_bit && self.compositing == Compositing::RedOverlay
+    }
 
-        for byte in &mut self.black_buffer.iter_mut() {
-            *byte = black; // background_color.get_byte_value();
+    // whether the store holds interleaved pixels
+    fn interleaved(&self) -> bool {
+        self.config().buffer_layout == BufferLayout::Interleaved
+    }
+
+    // the size of each plane in bytes
+    fn plane_bytes(&self) -> u32 {
+        (self.rows() as u32 * self.cols() as u32) / 8
+    }
+
+    // the layer and index in the layer of an interleaved byte
+    fn packed_location(&self, index: u32) -> (u8, u32) {
+        let n = self.plane_bytes();
+        if index < n {
+            (0, index)
+        } else {
+            (1, index - n)
         }
+    }
+
+    fn read_packed(&mut self, index: u32) -> Result<u8, S::Error> {
+        let (layer, index) = self.packed_location(index);
+        self.store.read_byte(self.display.interface(), layer, index)
+    }
+
+    fn write_packed(&mut self, index: u32, byte: u8) -> Result<(), S::Error> {
+        let (layer, index) = self.packed_location(index);
+        self.store
+            .write_byte(self.display.interface(), layer, index, byte)
+    }
 
-        // TODO: Combine loops
-        for byte in &mut self.red_buffer.iter_mut() {
-            *byte = red; // background_color.get_byte_value();
+    // set nbytes interleaved bytes to a value, which may span both layers
+    fn fill_packed(&mut self, index: u32, nbytes: u32, byte: u8) -> Result<(), S::Error> {
+        let n = self.plane_bytes();
+        let end = index + nbytes;
+        let interface = self.display.interface();
+        if index < n {
+            self.store
+                .fill_range(interface, 0, index, end.min(n) - index, byte)?;
+        }
+        if end > n {
+            let start = index.max(n);
+            self.store
+                .fill_range(interface, 1, start - n, end - start, byte)?;
         }
         Ok(())
     }
 
-    /// set a pixel to a color
-    fn set_pixel(&mut self, x: u32, y: u32, color: Color) -> Result<(), core::convert::Infallible> {
+    /// set pixels to colors
+    ///
+    /// Consecutive pixels in the same byte are combined, so each byte is read and written once.
+    fn set_pixels<P, T>(&mut self, pixels: P) -> Result<(), S::Error>
+    where
+        P: IntoIterator<Item = (u32, u32, T)>,
+        T: PanelColor,
+    {
+        let (cols, rows, rot) = (self.cols() as u32, self.rows() as u32, self.rotation());
+        self.set_pixels_located(pixels, move |x, y| rotation(x, y, cols, rows, rot))
+    }
+
+    // set_pixels, with `locate` returning the index and bit of a pixel in the buffers, before
+    // the bit order is applied
+    fn set_pixels_located<P, T, L>(&mut self, pixels: P, locate: L) -> Result<(), S::Error>
+    where
+        P: IntoIterator<Item = (u32, u32, T)>,
+        T: PanelColor,
+        L: Fn(u32, u32) -> (u32, u8),
+    {
+        if self.interleaved() {
+            return self.set_packed_pixels(pixels, locate);
+        }
+        let order = self.config().bit_order();
+        let (black_mask, red_mask) = self.plane_masks();
+        // the byte being changed, as (index, black, red)
+        let mut pending: Option<(u32, u8, u8)> = None;
+        for (x, y, color) in pixels {
+            let (index, bit) = locate(x, y);
+            let bit = order.mask(bit);
+            let (black_bit, red_bit) = color.planes();
+            self.stats.count(Color::from_planes(black_bit, red_bit));
+            let (_, mut black, mut red) = match pending {
+                Some(byte) if byte.0 == index => byte,
+                _ => {
+                    if let Some(byte) = pending {
+                        self.write_bytes(byte, black_mask, red_mask)?;
+                    }
+                    // get the existing buffer bytes
+                    let interface = self.display.interface();
+                    let black = self.store.read_byte(interface, 0, index)? ^ black_mask;
+                    let red = self.store.read_byte(interface, 1, index)? ^ red_mask;
+                    (index, black, red)
+                }
+            };
+            if self.keeps_black(red_bit) {
+                // leave the B/W plane
+            } else if black_bit {
+                black |= bit;
+            } else {
+                black &= !bit;
+            }
+            if red_bit {
+                red |= bit;
+            } else {
+                red &= !bit;
+            }
+            pending = Some((index, black, red));
+        }
+        match pending {
+            Some(byte) => self.write_bytes(byte, black_mask, red_mask),
+            None => Ok(()),
+        }
+    }
+
+    // set_pixels for the interleaved layout, each pixel is in a single byte
+    fn set_packed_pixels<P, T, L>(&mut self, pixels: P, locate: L) -> Result<(), S::Error>
+    where
+        P: IntoIterator<Item = (u32, u32, T)>,
+        T: PanelColor,
+        L: Fn(u32, u32) -> (u32, u8),
+    {
+        let order = self.config().bit_order();
+        // the byte being changed, as (index, byte)
+        let mut pending: Option<(u32, u8)> = None;
+        for (x, y, color) in pixels {
+            let (index, bit) = locate(x, y);
+            let bit = order.mask(bit);
+            let (index, shift) = packed_pixel(index, bit);
+            let (black_bit, red_bit) = color.planes();
+            self.stats.count(Color::from_planes(black_bit, red_bit));
+            let byte = match pending {
+                Some((i, byte)) if i == index => byte,
+                _ => {
+                    if let Some((i, byte)) = pending {
+                        self.write_packed(i, byte)?;
+                    }
+                    self.read_packed(index)?
+                }
+            };
+            // the B/W bit then the Red bit, cleared for ink
+            let bits = (u8::from(black_bit) << 1) | u8::from(red_bit);
+            let written = if self.keeps_black(red_bit) {
+                0b01
+            } else {
+                0b11
+            };
+            pending = Some((
+                index,
+                (byte & !(written << shift)) | ((bits & written) << shift),
+            ));
+        }
+        match pending {
+            Some((index, byte)) => self.write_packed(index, byte),
+            None => Ok(()),
+        }
+    }
+
+    // write the new buffer bytes
+    fn write_bytes(
+        &mut self,
+        (index, black, red): (u32, u8, u8),
+        black_mask: u8,
+        red_mask: u8,
+    ) -> Result<(), S::Error> {
+        let interface = self.display.interface();
+        self.store
+            .write_byte(interface, 0, index, black ^ black_mask)?;
+        self.store.write_byte(interface, 1, index, red ^ red_mask)
+    }
+
+    /// set a pixel in one layer, leaving the other alone
+    #[cfg(feature = "graphics")]
+    fn set_layer_pixel(&mut self, layer: u8, x: u32, y: u32, ink: bool) -> Result<(), S::Error> {
+        if ink {
+            self.stats
+                .count(if layer == 0 { Color::Black } else { Color::Red });
+        }
         let (index, bit) = rotation(
             x,
             y,
@@ -77,29 +2218,50 @@ where
             self.rows() as u32,
             self.rotation(),
         );
-        let index = index as usize;
+        let bit = self.config().bit_order().mask(bit);
+        if self.interleaved() {
+            let (index, shift) = packed_pixel(index, bit);
+            let bit = if layer == 0 { 0b10 } else { 0b01 } << shift;
+            let byte = self.read_packed(index)?;
+            return self.write_packed(index, if ink { byte & !bit } else { byte | bit });
+        }
+        let (black_mask, red_mask) = self.plane_masks();
+        let mask = if layer == 0 { black_mask } else { red_mask };
+        let interface = self.display.interface();
+        let mut byte = self.store.read_byte(interface, layer, index)? ^ mask;
+        // a cleared bit is ink in both layers
+        if ink {
+            byte &= !bit;
+        } else {
+            byte |= bit;
+        }
+        self.store.write_byte(interface, layer, index, byte ^ mask)
+    }
 
-        match color {
-            Color::Black => {
-                self.black_buffer[index] &= !bit;
-                self.red_buffer[index] |= bit;
-            }
-            Color::White => {
-                self.black_buffer[index] |= bit;
-                self.red_buffer[index] |= bit;
-            }
-            Color::Red => {
-                self.black_buffer[index] |= bit;
-                self.red_buffer[index] &= !bit;
+    /// fill one layer, leaving the other alone
+    #[cfg(feature = "graphics")]
+    fn fill_layer(&mut self, layer: u8, ink: bool) -> Result<(), S::Error> {
+        if self.interleaved() {
+            let bits = if layer == 0 { 0xAA } else { 0x55 };
+            for index in 0..2 * self.plane_bytes() {
+                let byte = self.read_packed(index)?;
+                self.write_packed(index, if ink { byte & !bits } else { byte | bits })?;
             }
+            return Ok(());
         }
-        Ok(())
+        let (black_mask, red_mask) = self.plane_masks();
+        let mask = if layer == 0 { black_mask } else { red_mask };
+        let byte = if ink { 0x00 } else { 0xFF };
+        self.store
+            .fill(self.display.interface(), layer, byte ^ mask)
     }
 }
 
-impl<'a, I> Deref for GraphicDisplay<'a, I>
+impl<I, S, C> Deref for GraphicDisplay<I, S, C>
 where
     I: DisplayInterface,
+    S: FrameBufferStore<I>,
+    C: PanelColor,
 {
     type Target = Display<I>;
 
@@ -108,16 +2270,147 @@ where
     }
 }
 
-impl<'a, I> DerefMut for GraphicDisplay<'a, I>
+impl<I, S, C> DerefMut for GraphicDisplay<I, S, C>
 where
     I: DisplayInterface,
+    S: FrameBufferStore<I>,
+    C: PanelColor,
 {
     fn deref_mut(&mut self) -> &mut Display<I> {
         &mut self.display
     }
 }
 
+impl<I, S, C> AsMut<Display<I>> for GraphicDisplay<I, S, C>
+where
+    I: DisplayInterface,
+    S: FrameBufferStore<I>,
+    C: PanelColor,
+{
+    fn as_mut(&mut self) -> &mut Display<I> {
+        &mut self.display
+    }
+}
+
+// the pixels inside a display of a size, in drawing coordinates, applying the policy to the
+// others. With `OutOfBounds::Error` the pixels end at the first outside, which is kept.
+fn bounded<'p, T: PixelColor, P>(
+    size: Size,
+    policy: OutOfBounds,
+    outside: &'p mut Option<Point>,
+    pixels: P,
+) -> impl Iterator<Item = (u32, u32, T)> + 'p
+where
+    P: IntoIterator<Item = Pixel<T>>,
+    P::IntoIter: 'p,
+{
+    pixels
+        .into_iter()
+        .map_while(move |Pixel(point, color)| {
+            let inside = point.x >= 0
+                && point.y >= 0
+                && (point.x as u32) < size.width
+                && (point.y as u32) < size.height;
+            if inside {
+                return Some(Some((point.x as u32, point.y as u32, color)));
+            }
+            match policy {
+                OutOfBounds::Clip => Some(None),
+                OutOfBounds::Error => {
+                    *outside = Some(point);
+                    None
+                }
+                OutOfBounds::Panic => panic!(
+                    "pixel ({}, {}) outside the {}x{} display",
+                    point.x, point.y, size.width, size.height
+                ),
+            }
+        })
+        .flatten()
+}
+
+// the result of drawing, given the pixel that stopped it
+fn out_of_bounds<E>(outside: Option<Point>) -> Result<(), Error<E>> {
+    match outside {
+        Some(Point { x, y }) => Err(Error::OutOfBounds { x, y }),
+        None => Ok(()),
+    }
+}
+
+// the bytes to fill the B/W and Red planes with for a color
+fn color_bytes<C: PanelColor>(color: C) -> (u8, u8) {
+    let fill = |bit| if bit { 0xFF } else { 0x00 };
+    let (black, red) = color.planes();
+    (fill(black), fill(red))
+}
+
+/// Returns the byte index and bit mask of a pixel in a packed buffer.
+///
+/// `x` and `y` are in drawing coordinates, after the rotation in `config`, and the result is the
+/// same addressing the driver uses for the B/W and Red buffers of a [GraphicDisplay]. The index
+/// is less than `rows * cols / 8` and the mask has exactly one bit set.
+///
+/// Returns `None` for coordinates outside the rotated display. Only planar buffers are
+/// addressed this way, also returns `None` for a config with [BufferLayout::Interleaved].
+pub fn coords_to_index(x: u32, y: u32, config: &Config) -> Option<(usize, u8)> {
+    if config.buffer_layout == BufferLayout::Interleaved {
+        return None;
+    }
+    let dimensions = config.dimensions();
+    let (cols, rows) = (u32::from(dimensions.cols), u32::from(dimensions.rows));
+    let (width, height) = match config.rotation() {
+        Rotation::Rotate0 | Rotation::Rotate180 => (cols, rows),
+        Rotation::Rotate90 | Rotation::Rotate270 => (rows, cols),
+    };
+    if x >= width || y >= height {
+        return None;
+    }
+    Some(plane_index(x, y, config))
+}
+
+// the index and mask of a pixel in a plane, whatever the buffer layout
+pub(crate) fn plane_index(x: u32, y: u32, config: &Config) -> (usize, u8) {
+    let dimensions = config.dimensions();
+    let (index, bit) = rotation(
+        x,
+        y,
+        dimensions.cols.into(),
+        dimensions.rows.into(),
+        config.rotation(),
+    );
+    (index as usize, config.bit_order().mask(bit))
+}
+
+/// Returns the drawing coordinates of a bit in a packed buffer, the inverse of
+/// [coords_to_index].
+///
+/// Returns `None` if the index is past the end of the buffer, the mask doesn't have exactly one
+/// bit set, or the config has [BufferLayout::Interleaved].
+pub fn index_to_coords(index: usize, bit: u8, config: &Config) -> Option<(u32, u32)> {
+    if config.buffer_layout == BufferLayout::Interleaved {
+        return None;
+    }
+    let dimensions = config.dimensions();
+    let cols = u32::from(dimensions.cols);
+    let rows = u32::from(dimensions.rows);
+    let stride = (cols / 8) as usize;
+    if !bit.is_power_of_two() || index >= stride * rows as usize {
+        return None;
+    }
+    // position in the controller's (source, gate) order
+    let bit = config.bit_order().mask(bit);
+    let source = (index % stride) as u32 * 8 + bit.leading_zeros();
+    let gate = (index / stride) as u32;
+    Some(match config.rotation() {
+        Rotation::Rotate0 => (source, gate),
+        Rotation::Rotate90 => (gate, cols - 1 - source),
+        Rotation::Rotate180 => (cols - 1 - source, rows - 1 - gate),
+        Rotation::Rotate270 => (rows - 1 - gate, source),
+    })
+}
+
 // return index into array and bit position in that index
+#[inline(always)]
 fn rotation(x: u32, y: u32, width: u32, height: u32, rotation: Rotation) -> (u32, u8) {
     match rotation {
         Rotation::Rotate0 => (x / 8 + (width / 8) * y, 0x80 >> (x % 8)),
@@ -131,220 +2424,412 @@ fn rotation(x: u32, y: u32, width: u32, height: u32, rotation: Rotation) -> (u32
 }
 
 #[cfg(feature = "graphics")]
-extern crate embedded_graphics_core;
+use crate::color::ColorThresholds;
 #[cfg(feature = "graphics")]
-use self::embedded_graphics_core::prelude::*;
+use embedded_graphics::{
+    framebuffer::Framebuffer,
+    pixelcolor::{raw::RawU1, BinaryColor, Rgb888},
+};
 
 #[cfg(feature = "graphics")]
-impl<'a, I> DrawTarget for GraphicDisplay<'a, I>
+impl<'a, I> GraphicDisplay<I, RamBuffers<'a>>
 where
     I: DisplayInterface,
 {
-    type Color = Color;
-    type Error = core::convert::Infallible;
+    /// Promote a `Display` to a `GraphicDisplay` using embedded-graphics framebuffers.
+    ///
+    /// The framebuffers are used directly as the B/W and Red buffers, without copying. Their
+    /// contents are converted in place as [GraphicDisplay::load_adafruit_buf] does, so in the
+    /// B/W framebuffer `BinaryColor::Off` is black and in the Red framebuffer `BinaryColor::Off`
+    /// is red whatever the bit order and plane inversion of the `Config`. After that the
+    /// framebuffers hold the planes as sent to the controller.
+    ///
+    /// The framebuffers must be `cols` wide and `rows` high, and the `Config` must use the
+    /// planar buffer layout, panics otherwise.
+    pub fn from_framebuffers<BO, const WIDTH: usize, const HEIGHT: usize, const N: usize>(
+        display: Display<I>,
+        black: &'a mut Framebuffer<BinaryColor, RawU1, BO, WIDTH, HEIGHT, N>,
+        red: &'a mut Framebuffer<BinaryColor, RawU1, BO, WIDTH, HEIGHT, N>,
+    ) -> Self {
+        assert!(
+            WIDTH == display.cols() as usize && HEIGHT == display.rows() as usize,
+            "framebuffer size must match the display dimensions"
+        );
+        assert!(
+            display.config().buffer_layout == BufferLayout::Planar,
+            "framebuffers can't hold an interleaved buffer layout"
+        );
+        let mut display = Self::new(display, black.data_mut(), red.data_mut());
+        let (black_mask, red_mask) = display.plane_masks();
+        let order = display.config().bit_order();
+        for (layer, mask) in [(0, black_mask), (1, red_mask)] {
+            for index in 0..display.plane_bytes() {
+                // RamBuffers can't fail and every index is inside the planes
+                let byte = display.read_plane_byte(layer, index).unwrap_or_default();
+                display
+                    .write_plane_byte(layer, index, order.mask(byte) ^ mask)
+                    .ok();
+            }
+        }
+        display
+    }
+}
 
-    /// override the clear method
-    fn clear(&mut self, color: Color) -> Result<(), Self::Error> {
-        self.clear(color)?;
-        Ok(())
+#[cfg(feature = "graphics")]
+impl<I, S> GraphicDisplay<I, S>
+where
+    I: DisplayInterface,
+    S: FrameBufferStore<I>,
+{
+    /// Returns a monochrome view of the B/W layer.
+    ///
+    /// `BinaryColor::On` is black and `BinaryColor::Off` is white. The Red layer is not changed.
+    pub fn black_layer(&mut self) -> LayerTarget<'_, I, S> {
+        LayerTarget {
+            display: self,
+            layer: 0,
+        }
+    }
+
+    /// Returns a monochrome view of the Red layer.
+    ///
+    /// `BinaryColor::On` is red and `BinaryColor::Off` leaves the pixel to the B/W layer. The B/W
+    /// layer is not changed.
+    pub fn red_layer(&mut self) -> LayerTarget<'_, I, S> {
+        LayerTarget {
+            display: self,
+            layer: 1,
+        }
+    }
+
+    /// Returns a view of the display that draws in another color type, like `Rgb565`.
+    ///
+    /// Works like `DrawTargetExt::color_converted`, but maps each color to the nearest panel
+    /// color, so code written for color displays runs unchanged. See [ColorAdapter] for the
+    /// mapping.
+    pub fn color_converted<C>(&mut self) -> ColorAdapter<'_, I, S, C>
+    where
+        C: PixelColor + Into<Rgb888>,
+    {
+        ColorAdapter {
+            thresholds: self.config().color_thresholds,
+            display: self,
+            color: PhantomData,
+        }
+    }
+}
+
+/// A single layer of a [GraphicDisplay] as a `BinaryColor` draw target.
+///
+/// Created by [GraphicDisplay::black_layer] and [GraphicDisplay::red_layer], for drawing with code
+/// that only understands monochrome colors.
+#[cfg(feature = "graphics")]
+pub struct LayerTarget<'d, I, S>
+where
+    I: DisplayInterface,
+    S: FrameBufferStore<I>,
+{
+    display: &'d mut GraphicDisplay<I, S>,
+    layer: u8,
+}
+
+#[cfg(feature = "graphics")]
+impl<'d, I, S> DrawTarget for LayerTarget<'d, I, S>
+where
+    I: DisplayInterface,
+    S: FrameBufferStore<I>,
+{
+    type Color = BinaryColor;
+    type Error = Error<S::Error>;
+
+    fn clear(&mut self, color: BinaryColor) -> Result<(), Self::Error> {
+        self.display
+            .fill_layer(self.layer, color.is_on())
+            .map_err(Error::Interface)
     }
 
-    /// required method
     fn draw_iter<ITR>(&mut self, pixels: ITR) -> Result<(), Self::Error>
     where
         ITR: IntoIterator<Item = Pixel<Self::Color>>,
     {
-        for Pixel(point, color) in pixels.into_iter() {
-            self.set_pixel(point.x as u32, point.y as u32, color)?;
+        let mut outside = None;
+        let policy = self.display.config().out_of_bounds;
+        for (x, y, color) in bounded(self.display.size(), policy, &mut outside, pixels) {
+            self.display
+                .set_layer_pixel(self.layer, x, y, color.is_on())
+                .map_err(Error::Interface)?;
         }
-        Ok(())
+        out_of_bounds(outside)
     }
 }
 
-impl<'a, I> OriginDimensions for GraphicDisplay<'a, I>
+#[cfg(feature = "graphics")]
+impl<'d, I, S> OriginDimensions for LayerTarget<'d, I, S>
 where
     I: DisplayInterface,
+    S: FrameBufferStore<I>,
 {
     fn size(&self) -> Size {
-        match self.rotation() {
-            Rotation::Rotate0 | Rotation::Rotate180 => {
-                Size::new(self.cols().into(), self.rows().into())
-            }
-            Rotation::Rotate90 | Rotation::Rotate270 => {
-                Size::new(self.rows().into(), self.cols().into())
-            }
-        }
+        self.display.size()
     }
 }
 
-/// A display that uses SRAM for backing buffers for drawing into and updating the display from.
+/// A [GraphicDisplay] as a draw target for any color that converts to `Rgb888`.
 ///
-/// When the `graphics` feature is enabled `SramGraphicDisplay` implements the `DrawTarget` trait from
-/// [embedded-graphics](https://crates.io/crates/embedded-graphics). This allows basic shapes and
-/// text to be drawn on the display.
-#[cfg(feature = "sram")]
-pub struct SramGraphicDisplay<I>
+/// Created by [GraphicDisplay::color_converted]. A color is red if its red channel is more than
+/// the red threshold above both the green and blue channels, otherwise it is black or white by
+/// luma. The thresholds are taken from the config, see [ColorThresholds].
+#[cfg(feature = "graphics")]
+pub struct ColorAdapter<'d, I, S, C>
 where
     I: DisplayInterface,
+    S: FrameBufferStore<I>,
 {
-    display: Display<I>,
-    buffer_size: u16,
-    black_address: u16,
-    red_address: u16,
+    display: &'d mut GraphicDisplay<I, S>,
+    thresholds: ColorThresholds,
+    color: PhantomData<C>,
 }
 
-#[cfg(feature = "sram")]
-impl<I> SramGraphicDisplay<I>
+#[cfg(feature = "graphics")]
+impl<'d, I, S, C> ColorAdapter<'d, I, S, C>
 where
     I: DisplayInterface,
+    S: FrameBufferStore<I>,
+    C: PixelColor + Into<Rgb888>,
 {
-    /// Promote a `Display` to a `SramGraphicDisplay`.
-    pub fn new(display: Display<I>) -> Self {
-        let sz = ((display.rows() * display.cols() as u16) as u32 / 8) as u16;
-        SramGraphicDisplay {
-            display,
-            buffer_size: sz,
-            black_address: 0,
-            red_address: sz,
-        }
+    /// The red threshold used unless another is set.
+    pub const DEFAULT_RED_THRESHOLD: u8 = ColorThresholds::DEFAULT.red_chroma;
+
+    /// Set how far the red channel must be above green and blue for a color to be red.
+    pub fn red_threshold(self, red_threshold: u8) -> Self {
+        let thresholds = ColorThresholds {
+            red_chroma: red_threshold,
+            ..self.thresholds
+        };
+        Self { thresholds, ..self }
     }
 
-    /// update the display
-    pub fn update(&mut self) -> Result<(), I::Error> {
-        // update black
-        self.display
-            .interface()
-            .sram_epd_update_data(0, self.buffer_size, self.black_address)?;
-        // update red
-        self.display
-            .interface()
-            .sram_epd_update_data(1, self.buffer_size, self.red_address)?;
-        self.display.signal_update()
+    /// Set the thresholds, in place of those from the config.
+    pub fn thresholds(self, thresholds: ColorThresholds) -> Self {
+        Self { thresholds, ..self }
     }
+}
 
-    /// Clear the buffers, filling them a single color.
-    fn clear(&mut self, color: Color) -> Result<(), I::Error> {
-        let (black, red) = match color {
-            Color::White => (0xFF, 0xFF),
-            Color::Black => (0x00, 0xFF),
-            Color::Red => (0xFF, 0x00),
-        };
+// the panel color nearest to an RGB color
+#[cfg(feature = "graphics")]
+fn nearest_color(color: Rgb888, thresholds: &ColorThresholds) -> Color {
+    thresholds.nearest(color.r(), color.g(), color.b())
+}
 
-        self.display
-            .interface()
-            .sram_clear(self.black_address, self.buffer_size, black)?;
-        self.display
-            .interface()
-            .sram_clear(self.red_address, self.buffer_size, red)?;
-        Ok(())
+#[cfg(feature = "graphics")]
+impl<'d, I, S, C> DrawTarget for ColorAdapter<'d, I, S, C>
+where
+    I: DisplayInterface,
+    S: FrameBufferStore<I>,
+    C: PixelColor + Into<Rgb888>,
+{
+    type Color = C;
+    type Error = Error<S::Error>;
+
+    fn clear(&mut self, color: C) -> Result<(), Self::Error> {
+        let color = nearest_color(color.into(), &self.thresholds);
+        self.display.clear(color).map_err(Error::Interface)
     }
 
-    /// set a pixel to a color
-    fn set_pixel(&mut self, x: u32, y: u32, color: Color) -> Result<(), I::Error> {
-        let (index, bit) = rotation(
-            x,
-            y,
-            self.cols() as u32,
-            self.rows() as u32,
-            self.rotation(),
+    fn draw_iter<ITR>(&mut self, pixels: ITR) -> Result<(), Self::Error>
+    where
+        ITR: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let thresholds = self.thresholds;
+        let pixels = pixels
+            .into_iter()
+            .map(|Pixel(point, color)| Pixel(point, nearest_color(color.into(), &thresholds)));
+        let mut outside = None;
+        let policy = self.display.config().out_of_bounds;
+        let pixels = bounded(self.display.size(), policy, &mut outside, pixels);
+        self.display.set_pixels(pixels).map_err(Error::Interface)?;
+        out_of_bounds(outside)
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<'d, I, S, C> OriginDimensions for ColorAdapter<'d, I, S, C>
+where
+    I: DisplayInterface,
+    S: FrameBufferStore<I>,
+{
+    fn size(&self) -> Size {
+        self.display.size()
+    }
+}
+
+impl<I, S, C> DrawTarget for GraphicDisplay<I, S, C>
+where
+    I: DisplayInterface,
+    S: FrameBufferStore<I>,
+    C: PanelColor,
+{
+    type Color = C;
+    type Error = Error<S::Error>;
+
+    /// override the clear method
+    fn clear(&mut self, color: C) -> Result<(), Self::Error> {
+        self.clear(color).map_err(Error::Interface)
+    }
+
+    /// required method
+    fn draw_iter<ITR>(&mut self, pixels: ITR) -> Result<(), Self::Error>
+    where
+        ITR: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let mut outside = None;
+        let pixels = bounded(
+            self.size(),
+            self.config().out_of_bounds,
+            &mut outside,
+            pixels,
         );
-        let index = index as u16;
+        self.set_pixels(pixels).map_err(Error::Interface)?;
+        out_of_bounds(outside)
+    }
+}
 
-        // get the existing buffer bytes
-        let mut black: [u8; 1] = [0];
-        self.display
-            .interface()
-            .sram_read(index + self.black_address, &mut black)?;
-        let mut red: [u8; 1] = [0];
-        self.display
-            .interface()
-            .sram_read(index + self.red_address, &mut red)?;
-        match color {
-            Color::Black => {
-                black[0] &= !bit;
-                red[0] |= bit;
-            }
-            Color::White => {
-                black[0] |= bit;
-                red[0] |= bit;
+impl<I, S, C> OriginDimensions for GraphicDisplay<I, S, C>
+where
+    I: DisplayInterface,
+    S: FrameBufferStore<I>,
+    C: PanelColor,
+{
+    fn size(&self) -> Size {
+        match self.rotation() {
+            Rotation::Rotate0 | Rotation::Rotate180 => {
+                Size::new(self.cols().into(), self.rows().into())
             }
-            Color::Red => {
-                black[0] |= bit;
-                red[0] &= !bit;
+            Rotation::Rotate90 | Rotation::Rotate270 => {
+                Size::new(self.rows().into(), self.cols().into())
             }
         }
-        // write the new buffer bytes
-        self.display
-            .interface()
-            .sram_write(index + self.black_address, &mut black)?;
+    }
+}
+
+/// A [GraphicDisplay] with its rotation fixed when compiling.
+///
+/// Drawing a pixel normally matches on the rotation of the config, which is a noticeable part
+/// of drawing text or filling the screen on small cores. `ROT` is a [Rotation] as `u8`, so the
+/// pixels are located for that rotation alone. The config must have the same rotation.
+/// Dereferences to the [GraphicDisplay] for everything else, such as updating.
+///
+/// ### Example
+///
+/// ```ignore
+/// type Landscape<'a, I> = GraphicDisplayFixed<I, RamBuffers<'a>, { Rotation::Rotate270 as u8 }>;
+/// let mut display = Landscape::new(GraphicDisplay::new(display, &mut black, &mut red))
+///     .ok()
+///     .expect("the config is rotated 270");
+/// Text::new("Hello", Point::new(10, 20), style).draw(&mut display)?;
+/// display.update()?;
+/// ```
+pub struct GraphicDisplayFixed<I, S, const ROT: u8>
+where
+    I: DisplayInterface,
+    S: FrameBufferStore<I>,
+{
+    display: GraphicDisplay<I, S>,
+}
+
+impl<I, S, const ROT: u8> GraphicDisplayFixed<I, S, ROT>
+where
+    I: DisplayInterface,
+    S: FrameBufferStore<I>,
+{
+    /// The rotation given by `ROT`, which fails to compile unless it is a [Rotation] as `u8`.
+    pub const ROTATION: Rotation = match ROT {
+        0 => Rotation::Rotate0,
+        1 => Rotation::Rotate90,
+        2 => Rotation::Rotate180,
+        3 => Rotation::Rotate270,
+        _ => panic!("ROT must be a Rotation as u8"),
+    };
+
+    /// Fix the rotation of a display.
+    ///
+    /// Returns the display back if its config has another rotation.
+    #[allow(clippy::result_large_err)]
+    pub fn new(display: GraphicDisplay<I, S>) -> Result<Self, GraphicDisplay<I, S>> {
+        if display.rotation() == Self::ROTATION {
+            Ok(GraphicDisplayFixed { display })
+        } else {
+            Err(display)
+        }
+    }
+
+    /// release the display
+    pub fn release(self) -> GraphicDisplay<I, S> {
         self.display
-            .interface()
-            .sram_write(index + self.red_address, &mut red)?;
-        Ok(())
     }
 }
 
-#[cfg(feature = "sram")]
-impl<I> Deref for SramGraphicDisplay<I>
+impl<I, S, const ROT: u8> Deref for GraphicDisplayFixed<I, S, ROT>
 where
     I: DisplayInterface,
+    S: FrameBufferStore<I>,
 {
-    type Target = Display<I>;
+    type Target = GraphicDisplay<I, S>;
 
-    fn deref(&self) -> &Display<I> {
+    fn deref(&self) -> &GraphicDisplay<I, S> {
         &self.display
     }
 }
 
-#[cfg(feature = "sram")]
-impl<I> DerefMut for SramGraphicDisplay<I>
+impl<I, S, const ROT: u8> DerefMut for GraphicDisplayFixed<I, S, ROT>
 where
     I: DisplayInterface,
+    S: FrameBufferStore<I>,
 {
-    fn deref_mut(&mut self) -> &mut Display<I> {
+    fn deref_mut(&mut self) -> &mut GraphicDisplay<I, S> {
         &mut self.display
     }
 }
 
-#[cfg(all(feature = "graphics", feature = "sram"))]
-impl<I> DrawTarget for SramGraphicDisplay<I>
+impl<I, S, const ROT: u8> DrawTarget for GraphicDisplayFixed<I, S, ROT>
 where
     I: DisplayInterface,
+    S: FrameBufferStore<I>,
 {
     type Color = Color;
-    type Error = I::Error;
+    type Error = Error<S::Error>;
+
+    fn clear(&mut self, color: Color) -> Result<(), Self::Error> {
+        self.display.clear(color).map_err(Error::Interface)
+    }
 
-    /// required method
     fn draw_iter<ITR>(&mut self, pixels: ITR) -> Result<(), Self::Error>
     where
         ITR: IntoIterator<Item = Pixel<Self::Color>>,
     {
-        for Pixel(point, color) in pixels.into_iter() {
-            self.set_pixel(point.x as u32, point.y as u32, color)?;
-        }
-        Ok(())
-    }
-
-    /// override the default
-    fn clear(&mut self, color: Color) -> Result<(), Self::Error> {
-        self.clear(color)
+        let (cols, rows) = (self.cols() as u32, self.rows() as u32);
+        let mut outside = None;
+        let policy = self.config().out_of_bounds;
+        let pixels = bounded(self.size(), policy, &mut outside, pixels);
+        self.display
+            .set_pixels_located(pixels, move |x, y| {
+                rotation(x, y, cols, rows, Self::ROTATION)
+            })
+            .map_err(Error::Interface)?;
+        out_of_bounds(outside)
     }
 }
 
-#[cfg(all(feature = "graphics", feature = "sram"))]
-impl<I> OriginDimensions for SramGraphicDisplay<I>
+impl<I, S, const ROT: u8> OriginDimensions for GraphicDisplayFixed<I, S, ROT>
 where
     I: DisplayInterface,
+    S: FrameBufferStore<I>,
 {
-    /// required method
     fn size(&self) -> Size {
-        match self.rotation() {
-            Rotation::Rotate0 | Rotation::Rotate180 => {
-                Size::new(self.cols().into(), self.rows().into())
-            }
-            Rotation::Rotate90 | Rotation::Rotate270 => {
-                Size::new(self.rows().into(), self.cols().into())
-            }
+        let (cols, rows) = (self.cols().into(), self.rows().into());
+        match Self::ROTATION {
+            Rotation::Rotate0 | Rotation::Rotate180 => Size::new(cols, rows),
+            Rotation::Rotate90 | Rotation::Rotate270 => Size::new(rows, cols),
         }
     }
 }
@@ -352,75 +2837,17 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::MockInterface;
+    use crate::{Builder, Color, Dimensions, Display, GraphicDisplay};
     use embedded_graphics::{
         prelude::*,
         primitives::{PrimitiveStyleBuilder, Rectangle},
     };
-    use {Builder, Color, Dimensions, Display, DisplayInterface, GraphicDisplay};
 
     const ROWS: u16 = 3;
     const COLS: u8 = 8;
     const BUFFER_SIZE: usize = (ROWS * COLS as u16) as usize / 8;
 
-    struct MockInterface {}
-    struct MockError {}
-
-    impl MockInterface {
-        fn new() -> Self {
-            MockInterface {}
-        }
-    }
-
-    impl DisplayInterface for MockInterface {
-        type Error = MockError;
-
-        fn reset<D: hal::blocking::delay::DelayMs<u8>>(&mut self, _delay: &mut D) {}
-
-        fn send_command(&mut self, _command: u8) -> Result<(), Self::Error> {
-            Ok(())
-        }
-
-        fn send_data(&mut self, _data: &[u8]) -> Result<(), Self::Error> {
-            Ok(())
-        }
-
-        fn busy_wait(&self) {}
-
-        fn epd_update_data(
-            &mut self,
-            _layer: u8,
-            _nbytes: u16,
-            _buf: &[u8],
-        ) -> Result<(), Self::Error> {
-            Ok(())
-        }
-
-        #[cfg(feature = "sram")]
-        fn sram_read(&mut self, _address: u16, _data: &mut [u8]) -> Result<(), Self::Error> {
-            Ok(())
-        }
-
-        #[cfg(feature = "sram")]
-        fn sram_write(&mut self, _address: u16, _data: &[u8]) -> Result<(), Self::Error> {
-            Ok(())
-        }
-
-        #[cfg(feature = "sram")]
-        fn sram_clear(&mut self, _address: u16, _nbytes: u16, _val: u8) -> Result<(), Self::Error> {
-            Ok(())
-        }
-
-        #[cfg(feature = "sram")]
-        fn sram_epd_update_data(
-            &mut self,
-            _layer: u8,
-            _nbytes: u16,
-            _start_address: u16,
-        ) -> Result<(), Self::Error> {
-            Ok(())
-        }
-    }
-
     fn build_mock_display() -> Display<MockInterface> {
         let interface = MockInterface::new();
         let dimensions = Dimensions {
@@ -475,6 +2902,145 @@ mod tests {
         }
     }
 
+    #[test]
+    fn index_round_trip() {
+        for rotation in [
+            Rotation::Rotate0,
+            Rotation::Rotate90,
+            Rotation::Rotate180,
+            Rotation::Rotate270,
+        ]
+        .iter()
+        {
+            let config = Builder::new()
+                .dimensions(Dimensions { rows: 5, cols: 16 })
+                .rotation(*rotation)
+                .build()
+                .unwrap();
+            let (width, height) = match rotation {
+                Rotation::Rotate0 | Rotation::Rotate180 => (16, 5),
+                _ => (5, 16),
+            };
+            let mut seen = [0u8; 10];
+            for y in 0..height {
+                for x in 0..width {
+                    let (index, bit) = super::coords_to_index(x, y, &config).unwrap();
+                    assert_eq!(seen[index] & bit, 0);
+                    seen[index] |= bit;
+                    assert_eq!(super::index_to_coords(index, bit, &config), Some((x, y)));
+                }
+            }
+            assert_eq!(seen, [0xFF; 10]);
+        }
+    }
+
+    #[test]
+    fn bit_order() {
+        let build = |rotation| {
+            Builder::new()
+                .dimensions(Dimensions { rows: 5, cols: 16 })
+                .rotation(rotation)
+                .bit_order(Rotation::Rotate90, BitOrder::LsbFirst)
+                .build()
+                .unwrap()
+        };
+        let config = build(Rotation::Rotate90);
+        assert_eq!(config.bit_order(), BitOrder::LsbFirst);
+        assert_eq!(super::coords_to_index(0, 0, &config), Some((1, 0x80)));
+        assert_eq!(super::coords_to_index(0, 3, &config), Some((1, 0x10)));
+        assert_eq!(super::index_to_coords(1, 0x80, &config), Some((0, 0)));
+
+        // other rotations are unchanged
+        let config = build(Rotation::Rotate270);
+        assert_eq!(config.bit_order(), BitOrder::MsbFirst);
+        assert_eq!(config.bit_order_for(Rotation::Rotate90), BitOrder::LsbFirst);
+        assert_eq!(super::coords_to_index(0, 0, &config), Some((8, 0x80)));
+    }
+
+    #[test]
+    fn bit_order_fill_rect() {
+        let config = Builder::new()
+            .dimensions(Dimensions { rows: 5, cols: 16 })
+            .rotation(Rotation::Rotate90)
+            .bit_order(Rotation::Rotate90, BitOrder::LsbFirst)
+            .build()
+            .unwrap();
+        let area = Rectangle::new(Point::new(1, 2), Size::new(3, 9));
+        let draw = |fill: bool| {
+            let mut black = [0u8; 10];
+            let mut red = [0u8; 10];
+            {
+                let mut display = GraphicDisplay::new(
+                    Display::new(MockInterface::new(), config),
+                    &mut black,
+                    &mut red,
+                );
+                display.clear(Color::White).unwrap();
+                if fill {
+                    display.fill_rect(area, Color::Red).unwrap();
+                    display.invert_rect(area).unwrap();
+                } else {
+                    display
+                        .draw_iter(area.points().map(|point| Pixel(point, Color::Red)))
+                        .unwrap();
+                    for point in area.points() {
+                        Pixel(point, BinaryColor::On)
+                            .draw(&mut display.black_layer())
+                            .unwrap();
+                    }
+                }
+            }
+            (black, red)
+        };
+        assert_eq!(draw(true), draw(false));
+    }
+
+    #[test]
+    fn coords_to_index_outside() {
+        let rotations = [
+            (Rotation::Rotate0, 16, 5),
+            (Rotation::Rotate90, 5, 16),
+            (Rotation::Rotate180, 16, 5),
+            (Rotation::Rotate270, 5, 16),
+        ];
+        for (rotation, width, height) in rotations.iter().copied() {
+            let config = Builder::new()
+                .dimensions(Dimensions { rows: 5, cols: 16 })
+                .rotation(rotation)
+                .build()
+                .unwrap();
+            let corner = super::coords_to_index(width - 1, height - 1, &config);
+            assert!(
+                corner.is_some_and(|(index, _)| index < 10),
+                "{:?}",
+                rotation
+            );
+            assert_eq!(super::coords_to_index(width, 0, &config), None);
+            assert_eq!(super::coords_to_index(0, height, &config), None);
+            assert_eq!(super::coords_to_index(u32::MAX, u32::MAX, &config), None);
+        }
+    }
+
+    #[test]
+    fn index_to_coords_invalid() {
+        let config = Builder::new()
+            .dimensions(Dimensions { rows: 5, cols: 16 })
+            .build()
+            .unwrap();
+        assert_eq!(super::index_to_coords(10, 0x80, &config), None);
+        assert_eq!(super::index_to_coords(0, 0x81, &config), None);
+        assert_eq!(super::index_to_coords(0, 0, &config), None);
+
+        // packed pixels aren't addressed by plane
+        let config = Builder::new()
+            .dimensions(Dimensions { rows: 5, cols: 16 })
+            .buffer_layout(BufferLayout::Interleaved)
+            .build()
+            .unwrap();
+        assert_eq!(super::coords_to_index(0, 0, &config), None);
+        assert_eq!(super::index_to_coords(0, 0x80, &config), None);
+    }
+
     #[test]
     fn clear_white() {
         let mut black_buffer = [0u8; BUFFER_SIZE];
@@ -520,6 +3086,387 @@ mod tests {
         assert_eq!(red_buffer, [0x00, 0x00, 0x00]);
     }
 
+    #[test]
+    fn inverted_black_plane() {
+        let mut black_buffer = [0u8; BUFFER_SIZE];
+        let mut red_buffer = [0u8; BUFFER_SIZE];
+
+        {
+            let config = Builder::new()
+                .dimensions(Dimensions {
+                    rows: ROWS,
+                    cols: COLS,
+                })
+                .invert_black_plane(true)
+                .build()
+                .expect("invalid config");
+            let mut display = GraphicDisplay::new(
+                Display::new(MockInterface::new(), config),
+                &mut black_buffer,
+                &mut red_buffer,
+            );
+            display.clear(Color::White).unwrap();
+            Pixel(Point::new(0, 0), Color::Black)
+                .draw(&mut display)
+                .unwrap();
+        }
+
+        assert_eq!(black_buffer, [0b10000000, 0x00, 0x00]);
+        assert_eq!(red_buffer, [0xFF, 0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn red_layer() {
+        let mut black_buffer = [0u8; BUFFER_SIZE];
+        let mut red_buffer = [0u8; BUFFER_SIZE];
+
+        {
+            let mut display =
+                GraphicDisplay::new(build_mock_display(), &mut black_buffer, &mut red_buffer);
+            display.clear(Color::Black).unwrap();
+            let mut red = display.red_layer();
+            Pixel(Point::new(1, 0), BinaryColor::On)
+                .draw(&mut red)
+                .unwrap();
+            Pixel(Point::new(1, 1), BinaryColor::Off)
+                .draw(&mut red)
+                .unwrap();
+        }
+
+        assert_eq!(black_buffer, [0x00, 0x00, 0x00]);
+        assert_eq!(red_buffer, [0b10111111, 0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn color_converted() {
+        use embedded_graphics::pixelcolor::Rgb565;
+
+        let mut black_buffer = [0u8; BUFFER_SIZE];
+        let mut red_buffer = [0u8; BUFFER_SIZE];
+
+        {
+            let mut display =
+                GraphicDisplay::new(build_mock_display(), &mut black_buffer, &mut red_buffer);
+            let orange = Rgb565::new(31, 40, 0);
+            let mut rgb = display.color_converted::<Rgb565>();
+            rgb.clear(Rgb565::WHITE).unwrap();
+            Pixel(Point::new(0, 0), Rgb565::RED).draw(&mut rgb).unwrap();
+            Pixel(Point::new(1, 0), Rgb565::BLACK)
+                .draw(&mut rgb)
+                .unwrap();
+            Pixel(Point::new(2, 0), orange).draw(&mut rgb).unwrap();
+            let mut rgb = rgb.red_threshold(64);
+            Pixel(Point::new(3, 0), orange).draw(&mut rgb).unwrap();
+        }
+
+        assert_eq!(black_buffer, [0b10111111, 0xFF, 0xFF]);
+        assert_eq!(red_buffer, [0b01101111, 0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn color_thresholds() {
+        use embedded_graphics::pixelcolor::Rgb888;
+
+        let mut black_buffer = [0u8; BUFFER_SIZE];
+        let mut red_buffer = [0u8; BUFFER_SIZE];
+
+        {
+            let mut display =
+                GraphicDisplay::new(build_mock_display(), &mut black_buffer, &mut red_buffer);
+            let gray = Rgb888::new(100, 100, 100);
+            let orange = Rgb888::new(255, 160, 0);
+            let mut rgb = display.color_converted::<Rgb888>().red_threshold(64);
+            rgb.clear(Rgb888::WHITE).unwrap();
+            Pixel(Point::new(0, 0), gray).draw(&mut rgb).unwrap();
+            Pixel(Point::new(1, 0), orange).draw(&mut rgb).unwrap();
+            let mut rgb = rgb.thresholds(ColorThresholds {
+                luminance: 64,
+                red_chroma: 64,
+                red_hue: 30,
+            });
+            Pixel(Point::new(2, 0), gray).draw(&mut rgb).unwrap();
+            Pixel(Point::new(3, 0), orange).draw(&mut rgb).unwrap();
+        }
+
+        assert_eq!(black_buffer, [0b01111111, 0xFF, 0xFF]);
+        assert_eq!(red_buffer, [0b10111111, 0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn adafruit_buf() {
+        let black_in = [0x7F, 0xFF, 0xFE];
+        let red_in = [0xBF, 0xFF, 0xFF];
+        for layout in [BufferLayout::Planar, BufferLayout::Interleaved] {
+            let config = Builder::new()
+                .dimensions(Dimensions {
+                    rows: ROWS,
+                    cols: COLS,
+                })
+                .buffer_layout(layout)
+                .invert_black_plane(true)
+                .bit_order(Rotation::Rotate0, BitOrder::LsbFirst)
+                .build()
+                .unwrap();
+            let mut black_buffer = [0u8; BUFFER_SIZE];
+            let mut red_buffer = [0u8; BUFFER_SIZE];
+            let mut display = GraphicDisplay::new(
+                Display::new(MockInterface::new(), config),
+                &mut black_buffer,
+                &mut red_buffer,
+            );
+            display.load_adafruit_buf(&black_in, &red_in).unwrap();
+            let pixels: std::vec::Vec<_> = display.pixels().collect();
+            assert_eq!(pixels[0], Pixel(Point::new(0, 0), Color::Black));
+            assert_eq!(pixels[1], Pixel(Point::new(1, 0), Color::Red));
+            assert_eq!(pixels[23], Pixel(Point::new(7, 2), Color::Black));
+            assert_eq!(pixels[8], Pixel(Point::new(0, 1), Color::White));
+
+            let mut black_out = [0u8; BUFFER_SIZE];
+            let mut red_out = [0u8; BUFFER_SIZE];
+            display
+                .export_adafruit_buf(&mut black_out, &mut red_out)
+                .unwrap();
+            assert_eq!((black_out, red_out), (black_in, red_in));
+            assert!(matches!(
+                display.load_adafruit_buf(&black_in[1..], &red_in),
+                Err(Error::BufferSize {
+                    expected: 3,
+                    actual: 2
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn glyph_cells() {
+        let glyph: [u8; 16] = core::array::from_fn(|i| (0x5A_u8).rotate_left(i as u32) ^ i as u8);
+        let draw = |rotation, layout, fast: bool| {
+            let config = Builder::new()
+                .dimensions(Dimensions { rows: 24, cols: 32 })
+                .rotation(rotation)
+                .buffer_layout(layout)
+                .invert_red_plane(true)
+                .build()
+                .unwrap();
+            let mut black = [0u8; 96];
+            let mut red = [0u8; 96];
+            let stats;
+            {
+                let mut display = GraphicDisplay::new(
+                    Display::new(MockInterface::new(), config),
+                    &mut black,
+                    &mut red,
+                );
+                display.clear(Color::Red).unwrap();
+                let cells = [
+                    (Point::new(5, 3), Some(Color::White)),
+                    (Point::new(13, 11), None),
+                    (Point::new(20, 20), Some(Color::Black)),
+                ];
+                for (top_left, background) in cells {
+                    if fast {
+                        display
+                            .draw_glyph_cell(&glyph, top_left, Color::Black, background)
+                            .unwrap();
+                        continue;
+                    }
+                    let pixels = (0..16).flat_map(|y| (0..8).map(move |x| (x, y)));
+                    let pixels = pixels.filter_map(|(x, y)| {
+                        let set = glyph[y as usize] & (0x80 >> x) != 0;
+                        let color = if set { Some(Color::Black) } else { background }?;
+                        Some(Pixel(top_left + Point::new(x, y), color))
+                    });
+                    display.draw_iter(pixels).unwrap();
+                }
+                stats = display.stats();
+            }
+            (black, red, stats)
+        };
+        for rotation in [
+            Rotation::Rotate0,
+            Rotation::Rotate90,
+            Rotation::Rotate180,
+            Rotation::Rotate270,
+        ] {
+            for layout in [BufferLayout::Planar, BufferLayout::Interleaved] {
+                assert_eq!(draw(rotation, layout, true), draw(rotation, layout, false));
+            }
+        }
+    }
+
+    #[test]
+    fn iter_rows() {
+        let mut black_buffer = [0u8; BUFFER_SIZE];
+        let mut red_buffer = [0u8; BUFFER_SIZE];
+        let mut display =
+            GraphicDisplay::new(build_mock_display(), &mut black_buffer, &mut red_buffer);
+        display.clear(Color::Red).unwrap();
+        Pixel(Point::new(0, 1), Color::Black)
+            .draw(&mut display)
+            .unwrap();
+
+        let rows: std::vec::Vec<&[u8]> = display.iter_rows(Plane::Black).collect();
+        assert_eq!(rows, [&[0xFF][..], &[0x7F], &[0xFF]]);
+        assert_eq!(display.iter_rows(Plane::Red).nth(1), Some(&[0x80][..]));
+    }
+
+    // counts the bytes read and written
+    #[derive(Default)]
+    struct CountingStore {
+        buffer: [u8; 2 * BUFFER_SIZE],
+        reads: usize,
+        writes: usize,
+    }
+
+    impl FrameBufferStore<MockInterface> for CountingStore {
+        type Error = core::convert::Infallible;
+
+        fn read_byte(
+            &mut self,
+            _interface: &mut MockInterface,
+            layer: u8,
+            index: u32,
+        ) -> Result<u8, Self::Error> {
+            self.reads += 1;
+            Ok(self.buffer[layer as usize * BUFFER_SIZE + index as usize])
+        }
+
+        fn write_byte(
+            &mut self,
+            _interface: &mut MockInterface,
+            layer: u8,
+            index: u32,
+            byte: u8,
+        ) -> Result<(), Self::Error> {
+            self.writes += 1;
+            self.buffer[layer as usize * BUFFER_SIZE + index as usize] = byte;
+            Ok(())
+        }
+
+        fn fill(
+            &mut self,
+            _interface: &mut MockInterface,
+            _layer: u8,
+            _byte: u8,
+        ) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn update_epd(
+            &mut self,
+            _interface: &mut MockInterface,
+            _layer: u8,
+            _nbytes: u32,
+        ) -> Result<(), Error<()>> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn batched_byte_access() {
+        let mut display =
+            GraphicDisplay::with_store(build_mock_display(), CountingStore::default());
+        // a horizontal line is one byte, a vertical line is three
+        Rectangle::new(Point::new(0, 0), Size::new(8, 1))
+            .into_styled(
+                PrimitiveStyleBuilder::new()
+                    .fill_color(Color::Black)
+                    .build(),
+            )
+            .draw(&mut display)
+            .unwrap();
+        assert_eq!((display.store.reads, display.store.writes), (2, 2));
+        Rectangle::new(Point::new(0, 0), Size::new(1, 3))
+            .into_styled(PrimitiveStyleBuilder::new().fill_color(Color::Red).build())
+            .draw(&mut display)
+            .unwrap();
+        assert_eq!((display.store.reads, display.store.writes), (8, 8));
+        assert_eq!(display.store.buffer, [0x80, 0x80, 0x80, 0x7F, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn stats() {
+        let mut black_buffer = [0u8; BUFFER_SIZE];
+        let mut red_buffer = [0u8; BUFFER_SIZE];
+        let mut display =
+            GraphicDisplay::new(build_mock_display(), &mut black_buffer, &mut red_buffer);
+        Rectangle::new(Point::new(0, 0), Size::new(2, 2))
+            .into_styled(PrimitiveStyleBuilder::new().fill_color(Color::Red).build())
+            .draw(&mut display)
+            .unwrap();
+        Pixel(Point::new(4, 0), BinaryColor::On)
+            .draw(&mut display.black_layer())
+            .unwrap();
+        assert_eq!(
+            display.stats(),
+            DrawStats {
+                black: 1,
+                white: 0,
+                red: 4,
+                cleared_to: None
+            }
+        );
+        display.clear(Color::White).unwrap();
+        assert_eq!(display.stats().red, 0);
+        assert_eq!(display.stats().cleared_to, Some(Color::White));
+    }
+
+    #[test]
+    fn pack_round_trip() {
+        assert_eq!(pack(0x00, 0xFF), [0x55, 0x55]);
+        assert_eq!(pack(0xFF, 0x00), [0xAA, 0xAA]);
+        assert_eq!(pack(0x80, 0x01), [0x80, 0x01]);
+        for &(black, red) in [(0x00, 0xFF), (0xA5, 0x3C), (0x81, 0x7E)].iter() {
+            let [first, second] = pack(black, red);
+            assert_eq!(unpack(first, second, 0), black);
+            assert_eq!(unpack(first, second, 1), red);
+        }
+        assert_eq!(packed_pixel(3, 0x80), (6, 6));
+        assert_eq!(packed_pixel(3, 0x01), (7, 0));
+    }
+
+    #[test]
+    fn fill_rect() {
+        let mut black_buffer = [0u8; BUFFER_SIZE];
+        let mut red_buffer = [0u8; BUFFER_SIZE];
+
+        {
+            let mut display =
+                GraphicDisplay::new(build_mock_display(), &mut black_buffer, &mut red_buffer);
+            display.clear(Color::White).unwrap();
+            display
+                .fill_rect(
+                    Rectangle::new(Point::new(2, 1), Size::new(3, 5)),
+                    Color::Red,
+                )
+                .unwrap();
+            display
+                .fill_rect(
+                    Rectangle::new(Point::new(0, 2), Size::new(8, 1)),
+                    Color::Black,
+                )
+                .unwrap();
+        }
+
+        assert_eq!(black_buffer, [0xFF, 0xFF, 0x00]);
+        assert_eq!(red_buffer, [0xFF, 0b11000111, 0xFF]);
+    }
+
+    #[test]
+    fn fill_rect_whole_bytes() {
+        let mut display =
+            GraphicDisplay::with_store(build_mock_display(), CountingStore::default());
+        display
+            .fill_rect(
+                Rectangle::new(Point::new(0, 0), Size::new(8, 3)),
+                Color::Red,
+            )
+            .unwrap();
+        // nothing read
+        assert_eq!((display.store.reads, display.store.writes), (0, 6));
+        assert_eq!(display.store.buffer, [0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00]);
+    }
+
     #[test]
     fn draw_rect_white() {
         let mut black_buffer = [0u8; BUFFER_SIZE];
@@ -550,6 +3497,124 @@ mod tests {
                                   0b11100000]);
     }
 
+    #[test]
+    fn from_framebuffers() {
+        use embedded_graphics::{
+            framebuffer::{buffer_size, Framebuffer},
+            pixelcolor::{raw::BigEndian, BinaryColor},
+        };
+
+        type Fb = Framebuffer<
+            BinaryColor,
+            RawU1,
+            BigEndian,
+            { COLS as usize },
+            { ROWS as usize },
+            { buffer_size::<BinaryColor>(COLS as usize, ROWS as usize) },
+        >;
+        let mut black = Fb::new();
+        let mut red = Fb::new();
+        black.clear(BinaryColor::On).unwrap();
+        red.clear(BinaryColor::On).unwrap();
+        Pixel(Point::new(0, 0), BinaryColor::Off)
+            .draw(&mut black)
+            .unwrap();
+
+        {
+            let mut display =
+                GraphicDisplay::from_framebuffers(build_mock_display(), &mut black, &mut red);
+            Pixel(Point::new(7, 2), Color::Red)
+                .draw(&mut display)
+                .unwrap();
+        }
+
+        assert_eq!(black.data(), &[0b01111111, 0xFF, 0xFF]);
+        assert_eq!(red.data(), &[0xFF, 0xFF, 0b11111110]);
+    }
+
+    #[test]
+    fn from_framebuffers_converts_planes() {
+        use embedded_graphics::{
+            framebuffer::{buffer_size, Framebuffer},
+            pixelcolor::{raw::BigEndian, BinaryColor},
+        };
+
+        type Fb = Framebuffer<
+            BinaryColor,
+            RawU1,
+            BigEndian,
+            { COLS as usize },
+            { ROWS as usize },
+            { buffer_size::<BinaryColor>(COLS as usize, ROWS as usize) },
+        >;
+        let mut black = Fb::new();
+        let mut red = Fb::new();
+        black.clear(BinaryColor::On).unwrap();
+        red.clear(BinaryColor::On).unwrap();
+        Pixel(Point::new(0, 0), BinaryColor::Off)
+            .draw(&mut black)
+            .unwrap();
+
+        let config = Builder::new()
+            .dimensions(Dimensions {
+                rows: ROWS,
+                cols: COLS,
+            })
+            .invert_black_plane(true)
+            .bit_order(Rotation::Rotate0, BitOrder::LsbFirst)
+            .build()
+            .expect("invalid config");
+        let mut display = GraphicDisplay::from_framebuffers(
+            Display::new(MockInterface::new(), config),
+            &mut black,
+            &mut red,
+        );
+        // exporting undoes the conversion
+        let (mut exported_black, mut exported_red) = ([0u8; BUFFER_SIZE], [0u8; BUFFER_SIZE]);
+        display
+            .export_adafruit_buf(&mut exported_black, &mut exported_red)
+            .unwrap();
+        assert_eq!(exported_black, [0b01111111, 0xFF, 0xFF]);
+        assert_eq!(exported_red, [0xFF, 0xFF, 0xFF]);
+        drop(display);
+
+        assert_eq!(black.data(), &[0b00000001, 0x00, 0x00]);
+        assert_eq!(red.data(), &[0xFF, 0xFF, 0xFF]);
+    }
+
+    #[test]
+    #[should_panic(expected = "interleaved")]
+    fn from_framebuffers_rejects_interleaved() {
+        use embedded_graphics::{
+            framebuffer::{buffer_size, Framebuffer},
+            pixelcolor::{raw::BigEndian, BinaryColor},
+        };
+
+        type Fb = Framebuffer<
+            BinaryColor,
+            RawU1,
+            BigEndian,
+            { COLS as usize },
+            { ROWS as usize },
+            { buffer_size::<BinaryColor>(COLS as usize, ROWS as usize) },
+        >;
+        let mut black = Fb::new();
+        let mut red = Fb::new();
+        let config = Builder::new()
+            .dimensions(Dimensions {
+                rows: ROWS,
+                cols: COLS,
+            })
+            .buffer_layout(BufferLayout::Interleaved)
+            .build()
+            .expect("invalid config");
+        GraphicDisplay::from_framebuffers(
+            Display::new(MockInterface::new(), config),
+            &mut black,
+            &mut red,
+        );
+    }
+
     #[test]
     fn draw_rect_red() {
         let mut black_buffer = [0u8; BUFFER_SIZE];
@@ -579,4 +3644,84 @@ mod tests {
                                   0b10100000,
                                   0b11100000]);
     }
+
+    #[cfg(feature = "sram")]
+    #[test]
+    fn sram_clear_and_set_pixel() {
+        let mut display = GraphicDisplay::new_sram(build_mock_display());
+        display.clear(Color::Black).unwrap();
+        Pixel(Point::new(1, 2), Color::Red)
+            .draw(&mut display)
+            .unwrap();
+
+        let sram = &display.interface().sram;
+        assert_eq!(sram[..BUFFER_SIZE], [0x00, 0x00, 0b01000000]);
+        assert_eq!(sram[BUFFER_SIZE..2 * BUFFER_SIZE], [0xFF, 0xFF, 0b10111111]);
+        // nothing is written after the planes
+        assert_eq!(sram[2 * BUFFER_SIZE], 0xA5);
+    }
+
+    #[cfg(feature = "sram")]
+    #[test]
+    fn sram_update_addressing() {
+        use crate::interface::SramAddr;
+
+        let mut display = GraphicDisplay::new_sram(build_mock_display());
+        display.clear(Color::White).unwrap();
+        Pixel(Point::new(0, 0), Color::Black)
+            .draw(&mut display)
+            .unwrap();
+        display.interface().clear();
+        display.update().unwrap();
+
+        let interface = display.interface();
+        assert_eq!(
+            interface.sram_updates,
+            [
+                (0, BUFFER_SIZE as u32, SramAddr::ZERO),
+                (1, BUFFER_SIZE as u32, SramAddr::new(BUFFER_SIZE as u32)),
+            ]
+        );
+        assert_eq!(interface.data[..4], [0x10, 0x7F, 0xFF, 0xFF]);
+        assert_eq!(interface.data[4..8], [0x13, 0xFF, 0xFF, 0xFF]);
+    }
+
+    #[cfg(feature = "sram")]
+    #[test]
+    fn sram_into_ram_and_back() {
+        let mut display = GraphicDisplay::new_sram(build_mock_display());
+        display.clear(Color::Black).unwrap();
+        Pixel(Point::new(1, 2), Color::Red)
+            .draw(&mut display)
+            .unwrap();
+
+        // the wrong size leaves the buffers in the sram
+        let (mut black, mut red) = ([0u8; BUFFER_SIZE], [0u8; BUFFER_SIZE]);
+        let (display, error) = display.into_ram(&mut black[..2], &mut red).err().unwrap();
+        assert_eq!(
+            error,
+            Error::BufferSize {
+                expected: BUFFER_SIZE,
+                actual: 2
+            }
+        );
+
+        let mut display = display.into_ram(&mut black, &mut red).ok().unwrap();
+        assert_eq!(display.iter_rows(Plane::Black).nth(2), Some(&[0x40][..]));
+        Pixel(Point::new(0, 0), Color::White)
+            .draw(&mut display)
+            .unwrap();
+
+        let mut display = display.into_sram().ok().unwrap();
+        let stats = display.stats();
+        let interface = display.interface();
+        assert_eq!(interface.sram[..BUFFER_SIZE], [0x80, 0x00, 0x40]);
+        assert_eq!(
+            interface.sram[BUFFER_SIZE..2 * BUFFER_SIZE],
+            [0xFF, 0xFF, 0xBF]
+        );
+        // nothing is sent to the panel
+        assert!(interface.data.is_empty());
+        assert_eq!((stats.red, stats.white), (1, 1));
+    }
 }
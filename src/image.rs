@@ -0,0 +1,125 @@
+//! Indexed images split into the B/W and Red planes.
+//!
+//! Tri-color artwork is often stored as a 2-bit indexed image, with a palette of white, black
+//! and red. [IndexedImage] draws such an image onto a
+//! [GraphicDisplay](../graphics/struct.GraphicDisplay.html) in a single pass, each pixel setting
+//! both planes at once, instead of drawing the black parts and then the red parts.
+//!
+//! ### Example
+//!
+//! ```ignore
+//! let image = IndexedImage::new(include_bytes!("logo.raw"), 64);
+//! Image::new(&image, Point::new(10, 10)).draw(&mut display)?;
+//! ```
+
+use crate::color::Color;
+use embedded_graphics_core::{image::ImageDrawable, prelude::*, primitives::Rectangle};
+
+/// A 2 bits per pixel indexed image.
+///
+/// Pixels are packed most significant bits first, and each row starts on a byte boundary unless
+/// [IndexedImage::row_stride] is used.
+#[derive(Clone, Copy, Debug)]
+pub struct IndexedImage<'a> {
+    data: &'a [u8],
+    width: u32,
+    stride: usize,
+    palette: [Color; 4],
+}
+
+impl<'a> IndexedImage<'a> {
+    /// Create an image `width` pixels wide from packed data.
+    ///
+    /// The height is the number of whole rows in `data`. The palette defaults to white, black,
+    /// red, white.
+    pub fn new(data: &'a [u8], width: u32) -> Self {
+        IndexedImage {
+            data,
+            width,
+            stride: (width as usize * 2).div_ceil(8),
+            palette: [Color::White, Color::Black, Color::Red, Color::White],
+        }
+    }
+
+    /// Set the colors of the 4 palette indices.
+    pub fn palette(self, palette: [Color; 4]) -> Self {
+        Self { palette, ..self }
+    }
+
+    /// Set the number of bytes in each row, for formats that pad rows, such as BMP.
+    pub fn row_stride(self, stride: usize) -> Self {
+        Self { stride, ..self }
+    }
+
+    fn height(&self) -> u32 {
+        self.data.len().checked_div(self.stride).unwrap_or(0) as u32
+    }
+
+    // the color at a point inside the image
+    fn color(&self, point: Point) -> Color {
+        let x = point.x as usize;
+        let byte = self.data[point.y as usize * self.stride + x / 4];
+        let index = (byte >> (6 - 2 * (x % 4))) & 0x3;
+        self.palette[index as usize]
+    }
+}
+
+impl<'a> OriginDimensions for IndexedImage<'a> {
+    fn size(&self) -> Size {
+        Size::new(self.width, self.height())
+    }
+}
+
+impl<'a> ImageDrawable for IndexedImage<'a> {
+    type Color = Color;
+
+    fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Color>,
+    {
+        self.draw_sub_image(target, &self.bounding_box())
+    }
+
+    fn draw_sub_image<D>(&self, target: &mut D, area: &Rectangle) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Color>,
+    {
+        let area = area.intersection(&self.bounding_box());
+        let bounds = target.bounding_box();
+        target.draw_iter(
+            area.points()
+                .map(|p| Pixel(p - area.top_left, self.color(p)))
+                .filter(|Pixel(p, _)| bounds.contains(*p)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Builder, Dimensions, Display, GraphicDisplay};
+    use embedded_graphics::image::Image;
+
+    #[test]
+    fn splits_planes() {
+        let config = Builder::new()
+            .dimensions(Dimensions { rows: 2, cols: 8 })
+            .build()
+            .unwrap();
+        let mut sink = |_: &[u8]| ();
+        let display = Display::new(crate::encode::EncodeInterface::new(&mut sink), config);
+        let mut black = [0xFFu8; 2];
+        let mut red = [0xFFu8; 2];
+        {
+            let mut display = GraphicDisplay::new(display, &mut black, &mut red);
+            // white, black, red, white / red, red, black, black
+            let image = IndexedImage::new(&[0b00_01_10_11, 0b10_10_01_01], 4);
+            Image::new(&image, Point::new(5, 0))
+                .draw(&mut display)
+                .unwrap();
+        }
+        // the last column is clipped
+        assert_eq!(black, [0b11111101, 0b11111110]);
+        assert_eq!(red, [0b11111110, 0b11111001]);
+    }
+}
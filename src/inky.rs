@@ -0,0 +1,367 @@
+//! The Pimoroni Inky pHAT on a Raspberry Pi.
+//!
+//! An [InkyPhat] opens the SPI device and GPIO pins the pHAT is wired to, configures the panel
+//! with [Builder::inky_phat] and holds the buffers, so it is ready to draw on.
+//!
+//! The pins are opened through sysfs by [InkyPhat::new], or through the GPIO character device by
+//! [InkyPhat::new_cdev]. Sysfs GPIO is deprecated and missing from recent kernels, which only
+//! have the character device.
+//!
+//! The chip select is CE0, driven by the SPI device around each transfer as on any spidev, so the
+//! pins opened are only busy, data/command and reset. GPIO 8 is left to the SPI driver.
+//!
+//! Only available with the `inky` feature, which needs `std` and Linux.
+//!
+//! ### Example
+//!
+//! ```ignore
+//! let mut inky = InkyPhat::new_cdev("/dev/spidev0.0", GPIO_CHIP)?;
+//! Text::new("Hello", Point::new(10, 20), style).draw(&mut inky)?;
+//! inky.show()?;
+//! ```
+//!
+//! [Builder::inky_phat]: ../config/struct.Builder.html#method.inky_phat
+
+use crate::color::Color;
+use crate::config::Builder;
+use crate::display::{self, Display};
+use crate::error::Error;
+use crate::graphics::{FrameBufferStore, GraphicDisplay, RamBuffers};
+use crate::interface::{DisplayInterface, Interface, NoPin};
+use core::ops::{Deref, DerefMut};
+use embedded_graphics::prelude::*;
+use linux_embedded_hal::gpio_cdev::{self, Chip, LineRequestFlags};
+use linux_embedded_hal::spidev::{SpiModeFlags, SpidevOptions};
+use linux_embedded_hal::sysfs_gpio::{self, Direction};
+use linux_embedded_hal::{CdevPin, Pin, Spidev};
+use std::io;
+
+/// The number of gates (rows) of the panel.
+pub const ROWS: u16 = 212;
+/// The number of sources (columns) of the panel.
+pub const COLS: u8 = 104;
+/// The size of each buffer.
+pub const BUFFER_SIZE: usize = display::buffer_size(ROWS, COLS);
+/// The SPI clock, the controller accepts up to 4MHz while writing.
+pub const SPI_HZ: u32 = 4_000_000;
+/// The GPIO character device of the Raspberry Pi header, its line offsets are the BCM numbers.
+pub const GPIO_CHIP: &str = "/dev/gpiochip0";
+
+/// The BCM numbers of the GPIO pins the display is wired to.
+///
+/// The chip select is CE0 of the SPI bus, which the SPI device drives itself, so it isn't one of
+/// the pins opened.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PinMap {
+    /// busy, input
+    pub busy: u64,
+    /// data/command
+    pub dc: u64,
+    /// reset
+    pub reset: u64,
+}
+
+/// The pins used by the Inky pHAT, see <https://pinout.xyz/pinout/inky_phat>.
+pub const PINS: PinMap = PinMap {
+    busy: 17,
+    dc: 22,
+    reset: 27,
+};
+
+/// The interface of an Inky pHAT, with sysfs pins or [CdevPin]s.
+///
+/// The chip select is a [NoPin], as the SPI device drives CE0.
+pub type InkyInterface<P = Pin> = Interface<Spidev, NoPin, P, P, P>;
+
+/// The B/W and Red buffers of an Inky pHAT, owned rather than borrowed.
+pub struct InkyBuffers {
+    black: [u8; BUFFER_SIZE],
+    red: [u8; BUFFER_SIZE],
+}
+
+impl InkyBuffers {
+    fn ram(&mut self) -> RamBuffers<'_> {
+        RamBuffers::new(&mut self.black, &mut self.red)
+    }
+}
+
+impl<I> FrameBufferStore<I> for InkyBuffers
+where
+    I: DisplayInterface,
+{
+    type Error = core::convert::Infallible;
+
+    fn read_byte(&mut self, interface: &mut I, layer: u8, index: u32) -> Result<u8, Self::Error> {
+        self.ram().read_byte(interface, layer, index)
+    }
+
+    fn write_byte(
+        &mut self,
+        interface: &mut I,
+        layer: u8,
+        index: u32,
+        byte: u8,
+    ) -> Result<(), Self::Error> {
+        self.ram().write_byte(interface, layer, index, byte)
+    }
+
+    fn fill(&mut self, interface: &mut I, layer: u8, byte: u8) -> Result<(), Self::Error> {
+        self.ram().fill(interface, layer, byte)
+    }
+
+    fn fill_range(
+        &mut self,
+        interface: &mut I,
+        layer: u8,
+        index: u32,
+        nbytes: u32,
+        byte: u8,
+    ) -> Result<(), Self::Error> {
+        self.ram().fill_range(interface, layer, index, nbytes, byte)
+    }
+
+    fn update_epd(
+        &mut self,
+        interface: &mut I,
+        layer: u8,
+        nbytes: u32,
+    ) -> Result<(), Error<I::Error>> {
+        self.ram().update_epd(interface, layer, nbytes)
+    }
+
+    fn update_epd_interleaved(
+        &mut self,
+        interface: &mut I,
+        layer: u8,
+        nbytes: u32,
+        invert: u8,
+    ) -> Result<(), Error<I::Error>> {
+        self.ram()
+            .update_epd_interleaved(interface, layer, nbytes, invert)
+    }
+
+    fn update_epd_range(
+        &mut self,
+        interface: &mut I,
+        layer: u8,
+        index: u32,
+        nbytes: u32,
+    ) -> Result<(), Error<I::Error>> {
+        self.ram().update_epd_range(interface, layer, index, nbytes)
+    }
+
+    fn check_range(&self, interleaved: bool, end: u32) -> Result<(), Error<I::Error>> {
+        let (end, len) = if interleaved {
+            (2 * end as usize, 2 * BUFFER_SIZE)
+        } else {
+            (end as usize, BUFFER_SIZE)
+        };
+        if end > len {
+            return Err(Error::BufferSize {
+                expected: end,
+                actual: len,
+            });
+        }
+        Ok(())
+    }
+
+    fn update_epd_range_interleaved(
+        &mut self,
+        interface: &mut I,
+        layer: u8,
+        index: u32,
+        nbytes: u32,
+        invert: u8,
+    ) -> Result<(), Error<I::Error>> {
+        self.ram()
+            .update_epd_range_interleaved(interface, layer, index, nbytes, invert)
+    }
+}
+
+/// A Pimoroni Inky pHAT, ready to draw on.
+///
+/// The pins are sysfs pins unless opened with [InkyPhat::new_cdev]. Dereferences to the
+/// [GraphicDisplay], and is a `DrawTarget` itself.
+pub struct InkyPhat<P = Pin>
+where
+    InkyInterface<P>: DisplayInterface<Error = io::Error>,
+{
+    display: GraphicDisplay<InkyInterface<P>, InkyBuffers>,
+}
+
+impl InkyPhat {
+    /// Open the pHAT on an SPI device, such as `/dev/spidev0.0`, with the usual pins.
+    ///
+    /// The controller is reset and the buffers cleared to white.
+    pub fn new(spidev: &str) -> io::Result<Self> {
+        Self::with_pins(spidev, PINS)
+    }
+
+    /// Open the pHAT on an SPI device, with the pins rewired.
+    pub fn with_pins(spidev: &str, pins: PinMap) -> io::Result<Self> {
+        let spi = open_spi(spidev)?;
+        let busy = export(pins.busy, Direction::In)?;
+        let dc = output(pins.dc)?;
+        let reset = output(pins.reset)?;
+        InkyPhat::open(spi, (busy, dc, reset))
+    }
+}
+
+impl InkyPhat<CdevPin> {
+    /// Open the pHAT on an SPI device with the usual pins, requested from a GPIO character
+    /// device such as [GPIO_CHIP].
+    ///
+    /// The lines are held until the display is dropped. Line 8 isn't requested, as the SPI
+    /// driver holds it for CE0 and the request would fail as busy. The controller is reset and
+    /// the buffers cleared to white.
+    pub fn new_cdev(spidev: &str, chip: &str) -> io::Result<Self> {
+        Self::with_cdev_pins(spidev, chip, PINS)
+    }
+
+    /// Open the pHAT on an SPI device, with the pins rewired, requested from a GPIO character
+    /// device.
+    pub fn with_cdev_pins(spidev: &str, chip: &str, pins: PinMap) -> io::Result<Self> {
+        let spi = open_spi(spidev)?;
+        let mut chip = Chip::new(chip).map_err(cdev_error)?;
+        let busy = request(&mut chip, pins.busy, LineRequestFlags::INPUT)?;
+        let dc = request(&mut chip, pins.dc, LineRequestFlags::OUTPUT)?;
+        let reset = request(&mut chip, pins.reset, LineRequestFlags::OUTPUT)?;
+        InkyPhat::open(spi, (busy, dc, reset))
+    }
+}
+
+impl<P> InkyPhat<P>
+where
+    P: hal::digital::v2::OutputPin + hal::digital::v2::InputPin,
+    InkyInterface<P>: DisplayInterface<Error = io::Error>,
+{
+    // configure the display on an opened bus and the busy, data/command and reset pins
+    fn open(spi: Spidev, (busy, dc, reset): (P, P, P)) -> io::Result<Self> {
+        let config = Builder::inky_phat()
+            .build()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, format!("{:?}", e)))?;
+        let mut display = Display::new(Interface::new(spi, (NoPin, busy, dc, reset)), config);
+        display.reset(&mut linux_embedded_hal::Delay)?;
+        let buffers = InkyBuffers {
+            black: [0; BUFFER_SIZE],
+            red: [0; BUFFER_SIZE],
+        };
+        let mut display = GraphicDisplay::with_store(display, buffers);
+        display
+            .clear(Color::White)
+            .map_err(|e| io::Error::other(format!("{:?}", e)))?;
+        Ok(InkyPhat { display })
+    }
+
+    /// Send the buffers and refresh the panel, waiting for the refresh to finish.
+    pub fn show(&mut self) -> io::Result<()> {
+        self.display.update().map_err(|e| match e {
+            Error::Interface(e) => e,
+            e => io::Error::other(format!("{:?}", e)),
+        })?;
+        self.display.finish_refresh();
+        Ok(())
+    }
+
+    /// release the display
+    pub fn release(self) -> GraphicDisplay<InkyInterface<P>, InkyBuffers> {
+        self.display
+    }
+}
+
+// open and configure the SPI device, which drives CE0 around each transfer
+fn open_spi(spidev: &str) -> io::Result<Spidev> {
+    let mut spi = Spidev::open(spidev)?;
+    let options = SpidevOptions::new()
+        .bits_per_word(8)
+        .max_speed_hz(SPI_HZ)
+        .mode(SpiModeFlags::SPI_MODE_0)
+        .build();
+    spi.configure(&options)?;
+    Ok(spi)
+}
+
+// export a pin and wait for sysfs to create it
+fn export(number: u64, direction: Direction) -> io::Result<Pin> {
+    let pin = Pin::new(number);
+    pin.export().map_err(sysfs_error)?;
+    while !pin.is_exported() {}
+    pin.set_direction(direction).map_err(sysfs_error)?;
+    Ok(pin)
+}
+
+// export an output pin, initially high
+fn output(number: u64) -> io::Result<Pin> {
+    export(number, Direction::High)
+}
+
+fn sysfs_error(e: sysfs_gpio::Error) -> io::Error {
+    match e {
+        sysfs_gpio::Error::Io(e) => e,
+        e => io::Error::other(e),
+    }
+}
+
+// request a line of a GPIO character device, outputs initially high
+fn request(chip: &mut Chip, number: u64, flags: LineRequestFlags) -> io::Result<CdevPin> {
+    let offset = u32::try_from(number)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "GPIO line out of range"))?;
+    let default = u8::from(flags.contains(LineRequestFlags::OUTPUT));
+    let handle = chip
+        .get_line(offset)
+        .and_then(|line| line.request(flags, default, "il0373"))
+        .map_err(cdev_error)?;
+    CdevPin::new(handle).map_err(cdev_error)
+}
+
+fn cdev_error(e: gpio_cdev::Error) -> io::Error {
+    io::Error::other(e)
+}
+
+impl<P> Deref for InkyPhat<P>
+where
+    InkyInterface<P>: DisplayInterface<Error = io::Error>,
+{
+    type Target = GraphicDisplay<InkyInterface<P>, InkyBuffers>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.display
+    }
+}
+
+impl<P> DerefMut for InkyPhat<P>
+where
+    InkyInterface<P>: DisplayInterface<Error = io::Error>,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.display
+    }
+}
+
+impl<P> DrawTarget for InkyPhat<P>
+where
+    InkyInterface<P>: DisplayInterface<Error = io::Error>,
+{
+    type Color = Color;
+    type Error = Error<core::convert::Infallible>;
+
+    fn draw_iter<ITR>(&mut self, pixels: ITR) -> Result<(), Self::Error>
+    where
+        ITR: IntoIterator<Item = Pixel<Color>>,
+    {
+        self.display.draw_iter(pixels)
+    }
+
+    fn clear(&mut self, color: Color) -> Result<(), Self::Error> {
+        self.display.clear(color)
+    }
+}
+
+impl<P> OriginDimensions for InkyPhat<P>
+where
+    InkyInterface<P>: DisplayInterface<Error = io::Error>,
+{
+    fn size(&self) -> Size {
+        self.display.size()
+    }
+}
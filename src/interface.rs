@@ -1,10 +1,52 @@
-use command::BufCommand;
+#[cfg(feature = "dual-spi")]
+use crate::command::BusWidth;
+use crate::command::CommandBatch;
+use crate::error::Error;
+use crate::wait::{self, Operation};
 use core::fmt::Debug;
-use hal;
+
+#[cfg(feature = "sram")]
+mod bus;
+#[cfg(feature = "eh1")]
+pub mod compat;
+#[cfg(feature = "dual-spi")]
+mod dual;
+mod raw;
+mod shared;
+#[cfg(feature = "sram")]
+mod sram;
+mod three_wire;
+
+#[cfg(feature = "sram")]
+pub use self::bus::{SpiSramBus, SramSize};
+#[cfg(feature = "dual-spi")]
+pub use self::dual::{DualSpiWrite, InterfaceDualSpi};
+pub use self::raw::{Interface, InterfaceOptions};
+pub use self::shared::{BusMutex, SharedInterface, SharedSpi, SpiOwner};
+#[cfg(feature = "sram")]
+pub use self::sram::{
+    SramAddr, SramBacked, SramDisplayInterface, SramHandle, SramRegion, SramTransfer,
+};
+pub use self::three_wire::Interface3Wire;
 
 // Sample code from Good Displays says to hold for 10ms
 const RESET_DELAY_MS: u8 = 10;
 
+// do a hardware reset 3 times
+fn hardware_reset<P, D>(reset: &mut P, delay: &mut D)
+where
+    P: hal::digital::v2::OutputPin,
+    P::Error: Debug,
+    D: hal::blocking::delay::DelayMs<u8>,
+{
+    for _ in 0..3 {
+        reset.set_low().unwrap();
+        delay.delay_ms(RESET_DELAY_MS);
+        reset.set_high().unwrap();
+        delay.delay_ms(RESET_DELAY_MS);
+    }
+}
+
 /// Trait implemented by displays to provide implementation of core functionality.
 pub trait DisplayInterface {
     type Error;
@@ -18,497 +60,391 @@ pub trait DisplayInterface {
     /// Send data for a command.
     fn send_data(&mut self, data: &[u8]) -> Result<(), Self::Error>;
 
+    /// Send a batch of commands and their data.
+    ///
+    /// Defaults to sending each command with [DisplayInterface::send_command] and
+    /// [DisplayInterface::send_data]. Interfaces override this to send the whole batch in one
+    /// transaction.
+    fn send_batch<const N: usize>(&mut self, batch: &CommandBatch<N>) -> Result<(), Self::Error> {
+        for (command, data) in batch.iter() {
+            self.send_command(command)?;
+            if !data.is_empty() {
+                self.send_data(data)?;
+            }
+        }
+        Ok(())
+    }
+
     /// Reset the controller.
+    ///
+    /// Takes the same embedded-hal 0.2 `DelayMs<u8>` as [Display::reset], so one delay serves
+    /// the whole driver. With the `eh1` feature an embedded-hal 1.0 `DelayNs` is passed wrapped
+    /// in [Eh1](compat/struct.Eh1.html).
+    ///
+    /// [Display::reset]: ../display/struct.Display.html#method.reset
     fn reset<D: hal::blocking::delay::DelayMs<u8>>(&mut self, delay: &mut D);
 
     /// Wait for the controller to indicate it is not busy.
-    fn busy_wait(&self);
+    fn busy_wait(&mut self);
+
+    /// Wait for the controller to finish an operation.
+    ///
+    /// Defaults to [DisplayInterface::busy_wait]. Interfaces override this to wait a fixed time
+    /// when they have no busy pin.
+    fn busy_wait_for(&mut self, operation: Operation) {
+        let _ = operation;
+        self.busy_wait()
+    }
+
+    /// Returns true if the controller is busy, or `None` if the interface can't tell.
+    ///
+    /// Lets a caller poll with a timeout rather than wait indefinitely. Defaults to `None`,
+    /// interfaces with a busy pin return its state. Interfaces with a [NoPin] busy pin or a
+    /// fixed [WaitStrategy](../wait/trait.WaitStrategy.html) return `None`, so callers wait with
+    /// [DisplayInterface::busy_wait_for].
+    fn is_busy(&mut self) -> Option<bool> {
+        None
+    }
+
+    /// Returns the number of data lines the frame data is sent on.
+    ///
+    /// Initialization switches the controller to dual SPI (DUSPI) when this is `Dual`, so it
+    /// can't be enabled for a module wired with one data line. Defaults to `Single`,
+    /// [InterfaceDualSpi] returns `Dual`.
+    #[cfg(feature = "dual-spi")]
+    fn bus_width(&self) -> BusWidth {
+        BusWidth::Single
+    }
 
     //----- Following is only for buffers in RAM
     /// copy display buffer data to epd
-    fn epd_update_data(&mut self, layer: u8, nbytes: u16, buf: &[u8]) -> Result<(), Self::Error>;
-
-    //----- Following is only for buffers in SRAM
-    /// copy display buffer data to epd from sram
-    #[cfg(feature = "sram")]
-    fn sram_epd_update_data(
+    ///
+    /// Returns `Error::BufferSize` if `nbytes` isn't the length of `buf`.
+    fn epd_update_data(
         &mut self,
         layer: u8,
         nbytes: u16,
-        start_address: u16,
-    ) -> Result<(), Self::Error>;
-
-    /// read data from sram
-    #[cfg(feature = "sram")]
-    fn sram_read(&mut self, address: u16, data: &mut [u8]) -> Result<(), Self::Error>;
-
-    /// write data to sram
-    #[cfg(feature = "sram")]
-    fn sram_write(&mut self, address: u16, data: &[u8]) -> Result<(), Self::Error>;
-
-    /// set area in sram to a value, assume nbytes is divisible by 4
-    #[cfg(feature = "sram")]
-    fn sram_clear(&mut self, address: u16, nbytes: u16, val: u8) -> Result<(), Self::Error>;
+        buf: &[u8],
+    ) -> Result<(), Error<Self::Error>>;
 }
 
-/// The hardware interface to a display.
-///
-/// ### Example
-///
-/// This example uses the Linux implementation of the embedded HAL traits to build a display
-/// interface. For a complete example see [the Raspberry Pi Inky pHAT example](https://github.com/wezm/ssd1675/blob/master/examples/raspberry_pi_inky_phat.rs).
-///
-/// ```ignore
-/// extern crate linux_embedded_hal;
-/// use linux_embedded_hal::spidev::{self, SpidevOptions};
-/// use linux_embedded_hal::sysfs_gpio::Direction;
-/// use linux_embedded_hal::Delay;
-/// use linux_embedded_hal::{Pin, Spidev};
-///
-/// extern crate il0373;
-/// use il0373::{Builder, Color, Dimensions, Display, GraphicDisplay, Rotation};
-///
-/// // Configure SPI
-/// let mut spi = Spidev::open("/dev/spidev0.0").expect("SPI device");
-/// let options = SpidevOptions::new()
-///     .bits_per_word(8)
-///     .max_speed_hz(4_000_000)
-///     .mode(spidev::SPI_MODE_0)
-///     .build();
-/// spi.configure(&options).expect("SPI configuration");
-///
-/// // https://pinout.xyz/pinout/inky_phat
-/// // Configure Digital I/O Pins
-/// let cs = Pin::new(8); // BCM8
-/// cs.export().expect("cs export");
-/// while !cs.is_exported() {}
-/// cs.set_direction(Direction::Out).expect("CS Direction");
-/// cs.set_value(1).expect("CS Value set to 1");
-///
-/// let busy = Pin::new(17); // BCM17
-/// busy.export().expect("busy export");
-/// while !busy.is_exported() {}
-/// busy.set_direction(Direction::In).expect("busy Direction");
-///
-/// let dc = Pin::new(22); // BCM22
-/// dc.export().expect("dc export");
-/// while !dc.is_exported() {}
-/// dc.set_direction(Direction::Out).expect("dc Direction");
-/// dc.set_value(1).expect("dc Value set to 1");
-///
-/// let reset = Pin::new(27); // BCM27
-/// reset.export().expect("reset export");
-/// while !reset.is_exported() {}
-/// reset
-///     .set_direction(Direction::Out)
-///     .expect("reset Direction");
-/// reset.set_value(1).expect("reset Value set to 1");
-///
-/// // Build the interface from the pins and SPI device
-/// let controller = il0373::Interface::new(spi, cs, busy, dc, reset);
-
-pub struct Interface<SPI, CS, BUSY, DC, RESET> {
-    /// SPI interface
-    spi: SPI,
-    /// Chip Select, low active (output)
-    cs: CS,
-    /// Active low busy pin (input)
-    busy: BUSY,
-    /// Data/Command Control Pin (High for data, Low for command) (output)
-    dc: DC,
-    /// Pin for resetting the controller (output)
-    reset: RESET,
-}
-
-impl<SPI, CS, BUSY, DC, RESET> Interface<SPI, CS, BUSY, DC, RESET>
+/// Borrowing an interface, so a [Display](../display/struct.Display.html) can use one owned by a
+/// larger device struct, alongside the drivers sharing its bus.
+impl<T> DisplayInterface for &mut T
 where
-    SPI: hal::blocking::spi::Write<u8>,
-    CS: hal::digital::v2::OutputPin,
-    BUSY: hal::digital::v2::InputPin,
-    DC: hal::digital::v2::OutputPin,
-    RESET: hal::digital::v2::OutputPin,
+    T: DisplayInterface + ?Sized,
 {
-    /// Create a new Interface from embedded hal traits.
-    pub fn new(spi: SPI, pins: (CS, BUSY, DC, RESET)) -> Self {
-        Self {
-            spi,
-            cs: pins.0,
-            busy: pins.1,
-            dc: pins.2,
-            reset: pins.3,
-        }
-    }
+    type Error = T::Error;
 
-    /// release the spi and pins
-    pub fn release(self) -> (SPI, (CS, BUSY, DC, RESET)) {
-        (self.spi, (self.cs, self.busy, self.dc, self.reset))
+    fn send_command(&mut self, command: u8) -> Result<(), Self::Error> {
+        (**self).send_command(command)
     }
 
-    fn write(&mut self, data: &[u8]) -> Result<(), SPI::Error> {
-        self.cs.set_low().ok();
-        // Linux has a default limit of 4096 bytes per SPI transfer
-        // https://github.com/torvalds/linux/blob/ccda4af0f4b92f7b4c308d3acc262f4a7e3affad/drivers/spi/spidev.c#L93
-        if cfg!(target_os = "linux") {
-            for data_chunk in data.chunks(4096) {
-                self.spi.write(data_chunk)?;
-            }
-        } else {
-            self.spi.write(data)?;
-        }
-
-        // Release the controller
-        self.cs.set_high().ok();
-
-        Ok(())
+    fn send_data(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        (**self).send_data(data)
     }
-}
 
-impl<SPI, CS, BUSY, DC, RESET> DisplayInterface for Interface<SPI, CS, BUSY, DC, RESET>
-where
-    SPI: hal::blocking::spi::Write<u8>,
-    CS: hal::digital::v2::OutputPin,
-    CS::Error: Debug,
-    BUSY: hal::digital::v2::InputPin,
-    DC: hal::digital::v2::OutputPin,
-    DC::Error: Debug,
-    RESET: hal::digital::v2::OutputPin,
-    RESET::Error: Debug,
-{
-    type Error = SPI::Error;
-
-    fn reset<D: hal::blocking::delay::DelayMs<u8>>(&mut self, delay: &mut D) {
-        // do a hardware reset 3 times
-        self.reset.set_low().unwrap();
-        delay.delay_ms(RESET_DELAY_MS);
-        self.reset.set_high().unwrap();
-        delay.delay_ms(RESET_DELAY_MS);
-        self.reset.set_low().unwrap();
-        delay.delay_ms(RESET_DELAY_MS);
-        self.reset.set_high().unwrap();
-        delay.delay_ms(RESET_DELAY_MS);
-        self.reset.set_low().unwrap();
-        delay.delay_ms(RESET_DELAY_MS);
-        self.reset.set_high().unwrap();
-        delay.delay_ms(RESET_DELAY_MS);
+    fn send_batch<const N: usize>(&mut self, batch: &CommandBatch<N>) -> Result<(), Self::Error> {
+        (**self).send_batch(batch)
     }
 
-    fn send_command(&mut self, command: u8) -> Result<(), Self::Error> {
-        self.dc.set_low().unwrap();
-        self.write(&[command])?;
-        self.dc.set_high().unwrap();
-        Ok(())
+    fn reset<D: hal::blocking::delay::DelayMs<u8>>(&mut self, delay: &mut D) {
+        (**self).reset(delay)
     }
 
-    fn send_data(&mut self, data: &[u8]) -> Result<(), Self::Error> {
-        self.dc.set_high().unwrap();
-        self.write(data)
+    fn busy_wait(&mut self) {
+        (**self).busy_wait()
     }
 
-    #[cfg(feature = "sram")]
-    fn sram_read(&mut self, _address: u16, _data: &mut [u8]) -> Result<(), Self::Error> {
-        panic!()
+    fn busy_wait_for(&mut self, operation: Operation) {
+        (**self).busy_wait_for(operation)
     }
 
-    #[cfg(feature = "sram")]
-    fn sram_write(&mut self, _address: u16, _data: &[u8]) -> Result<(), Self::Error> {
-        panic!()
+    fn is_busy(&mut self) -> Option<bool> {
+        (**self).is_busy()
     }
 
-    #[cfg(feature = "sram")]
-    fn sram_clear(&mut self, _address: u16, _nbytes: u16, _val: u8) -> Result<(), Self::Error> {
-        panic!()
+    #[cfg(feature = "dual-spi")]
+    fn bus_width(&self) -> BusWidth {
+        (**self).bus_width()
     }
 
-    #[cfg(feature = "sram")]
-    fn sram_epd_update_data(
+    fn epd_update_data(
         &mut self,
-        _layer: u8,
-        _nbytes: u16,
-        _start_address: u16,
-    ) -> Result<(), Self::Error> {
-        panic!()
+        layer: u8,
+        nbytes: u16,
+        buf: &[u8],
+    ) -> Result<(), Error<Self::Error>> {
+        (**self).epd_update_data(layer, nbytes, buf)
     }
+}
 
-    fn epd_update_data(&mut self, layer: u8, nbytes: u16, buf: &[u8]) -> Result<(), Self::Error> {
-        let sz: usize = nbytes.into();
-        if layer == 0 {
-            BufCommand::WriteBlackData(&buf[..sz]).execute(self)
+/// A type erased [DisplayInterface] for sending commands, passed to the hook set with
+/// [Builder::with_post_init](../config/struct.Builder.html#method.with_post_init).
+pub trait CommandSink {
+    /// Send a command to the controller.
+    fn send_command(&mut self, command: u8) -> Result<(), SinkError>;
+
+    /// Send data for a command.
+    fn send_data(&mut self, data: &[u8]) -> Result<(), SinkError>;
+
+    /// Send a command followed by its data, if there is any.
+    fn command(&mut self, command: u8, data: &[u8]) -> Result<(), SinkError> {
+        self.send_command(command)?;
+        if data.is_empty() {
+            Ok(())
         } else {
-            BufCommand::WriteRedData(&buf[..sz]).execute(self)
+            self.send_data(data)
         }
     }
-
-    fn busy_wait(&self) {
-        while match self.busy.is_high() {
-            Ok(x) => x,
-            _ => false,
-        } {}
-    }
 }
 
-//const MCPSRAM_RDSR: u8 = 0x05;
-#[cfg(feature = "sram")]
-const MCPSRAM_READ: u8 = 0x03;
-#[cfg(feature = "sram")]
-const MCPSRAM_WRITE: u8 = 0x02;
-#[cfg(feature = "sram")]
-const MCPSRAM_WRSR: u8 = 0x01;
-#[cfg(feature = "sram")]
-const K640_SEQUENTIAL_MODE: u8 = 1 << 6;
+/// Error returned by a [CommandSink] when the interface fails.
+///
+/// Only a sink can create one, the interface error itself is returned by the caller of the hook.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SinkError(());
 
-#[cfg(feature = "sram")]
-pub struct SpiSramBus<SPI, EPDCS, SRAMCS> {
-    spi: SPI,
-    epd_cs: EPDCS,
-    sram_cs: SRAMCS,
+/// A hook run at the end of initialization to send extra commands.
+pub type PostInit = fn(&mut dyn CommandSink) -> Result<(), SinkError>;
+
+// a CommandSink that keeps the first interface error
+struct InterfaceSink<'a, I>
+where
+    I: DisplayInterface,
+{
+    interface: &'a mut I,
+    error: Option<I::Error>,
 }
 
-#[cfg(feature = "sram")]
-impl<SPI, EPDCS, SRAMCS> SpiSramBus<SPI, EPDCS, SRAMCS>
+impl<'a, I> InterfaceSink<'a, I>
 where
-    SPI: hal::blocking::spi::Transfer<u8>,
-    EPDCS: hal::digital::v2::OutputPin,
-    SRAMCS: hal::digital::v2::OutputPin,
+    I: DisplayInterface,
 {
-    /// create a new SpiSramBus from embedded hal traits
-    pub fn new(spi: SPI, mut pins: (EPDCS, SRAMCS)) -> SpiSramBus<SPI, EPDCS, SRAMCS> {
-        pins.0.set_high().ok();
-        pins.1.set_high().ok();
-        SpiSramBus {
-            spi,
-            epd_cs: pins.0,
-            sram_cs: pins.1,
-        }
+    fn keep(&mut self, result: Result<(), I::Error>) -> Result<(), SinkError> {
+        result.map_err(|e| {
+            self.error.get_or_insert(e);
+            SinkError(())
+        })
     }
+}
 
-    /// release the spi and cs pins
-    pub fn release(self) -> (SPI, (EPDCS, SRAMCS)) {
-        (self.spi, (self.epd_cs, self.sram_cs))
+impl<'a, I> CommandSink for InterfaceSink<'a, I>
+where
+    I: DisplayInterface,
+{
+    fn send_command(&mut self, command: u8) -> Result<(), SinkError> {
+        let result = self.interface.send_command(command);
+        self.keep(result)
     }
 
-    /// initialize sram device
-    pub fn sram_init(&mut self) -> Result<(), SPI::Error> {
-        self.sram_cs.set_low().ok();
-        self.spi.transfer(&mut [0xFF, 0xFF, 0xFF])?;
-        self.sram_cs.set_high().ok();
-        Ok(())
+    fn send_data(&mut self, data: &[u8]) -> Result<(), SinkError> {
+        let result = self.interface.send_data(data);
+        self.keep(result)
     }
+}
 
-    /// set sram device to sequential
-    pub fn sram_seq(&mut self) -> Result<(), SPI::Error> {
-        self.sram_cs.set_low().ok();
-        self.spi
-            .transfer(&mut [MCPSRAM_WRSR, K640_SEQUENTIAL_MODE])?;
-        self.sram_cs.set_high().ok();
-        Ok(())
+// run a hook on an interface, returning the interface error if it failed
+pub(crate) fn run_post_init<I>(interface: &mut I, hook: PostInit) -> Result<(), I::Error>
+where
+    I: DisplayInterface,
+{
+    let mut sink = InterfaceSink {
+        interface,
+        error: None,
+    };
+    let result = hook(&mut sink);
+    match (result, sink.error) {
+        (Err(_), Some(e)) => Err(e),
+        _ => Ok(()),
     }
+}
 
-    /// write to the sram
-    pub fn sram_write(&mut self, address: u16, data: &[u8]) -> Result<(), SPI::Error> {
-        self.sram_cs.set_low().ok();
-        let mut cmd: [u8; 3] = [MCPSRAM_WRITE, (address >> 8) as u8, (address & 0xFF) as u8];
-        self.spi.transfer(&mut cmd)?;
-        for byte in data.iter() {
-            self.spi.transfer(&mut [*byte])?;
-        }
-        self.sram_cs.set_high().ok();
-        Ok(())
-    }
+/// Changes the frequency of an SPI bus, see [Interface::spi_speeds].
+///
+/// Implemented for closures taking the bus and the frequency in Hz.
+pub trait SetSpeed<SPI> {
+    /// Set the SPI frequency in Hz.
+    fn set_speed(&mut self, spi: &mut SPI, hz: u32);
+}
 
-    /// read the sram
-    pub fn sram_read(&mut self, address: u16, data: &mut [u8]) -> Result<(), SPI::Error> {
-        self.sram_cs.set_low().ok();
-        let mut cmd: [u8; 3] = [MCPSRAM_READ, (address >> 8) as u8, (address & 0xFF) as u8];
-        self.spi.transfer(&mut cmd)?;
-        self.spi.transfer(data)?;
-        self.sram_cs.set_high().ok();
-        Ok(())
+impl<SPI, F> SetSpeed<SPI> for F
+where
+    F: FnMut(&mut SPI, u32),
+{
+    fn set_speed(&mut self, spi: &mut SPI, hz: u32) {
+        self(spi, hz)
     }
+}
 
-    /// erase buffer in sram, len is expected to be divisible by 4, panics otherwise
-    pub fn sram_erase(&mut self, address: u16, len: u16, val: u8) -> Result<(), SPI::Error> {
-        if len % 4 != 0 {
-            panic!("sram_erase expects a len divisible by 4");
-        }
-        self.sram_cs.set_low().ok();
-        let mut cmd: [u8; 3] = [MCPSRAM_WRITE, (address >> 8) as u8, (address & 0xFF) as u8];
-        self.spi.transfer(&mut cmd)?;
-        for _i in 0..len / 4 {
-            self.spi.transfer(&mut [val, val, val, val])?;
-        }
-        self.sram_cs.set_high().ok();
-        Ok(())
+/// Leaves the SPI frequency as it was configured by the application. The default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FixedSpeed;
+
+impl<SPI> SetSpeed<SPI> for FixedSpeed {
+    fn set_speed(&mut self, _spi: &mut SPI, _hz: u32) {}
+}
+
+/// Reports the progress of a buffer upload, see [Interface::upload_progress].
+///
+/// Implemented for closures taking the bytes sent so far and the total.
+pub trait Progress {
+    /// Called after each chunk of a buffer is sent.
+    fn progress(&mut self, sent: u32, total: u32);
+}
+
+impl<F> Progress for F
+where
+    F: FnMut(u32, u32),
+{
+    fn progress(&mut self, sent: u32, total: u32) {
+        self(sent, total)
     }
+}
 
-    /// start a buffer transfer from the SRAM to the EPD. This needs the beginning address
-    /// in the SRAM, and the location where they will be sent in the EPD.
-    /// While the location is sent to the EPD, the first byte will be pulled from
-    /// the SRAM at the address specified, this is passed to the sram_epd_move_body fn
-    pub fn sram_epd_move_header(
-        &mut self,
-        address: u16,
-        epd_location: u8,
-    ) -> Result<u8, SPI::Error> {
-        self.sram_cs.set_low().ok();
-        // send address and get first byte of data
-        let mut cmd: [u8; 3] = [MCPSRAM_READ, (address >> 8) as u8, (address & 0xFF) as u8];
-        self.spi.transfer(&mut cmd)?;
-        self.epd_cs.set_low().ok();
-        let mut loc = [epd_location];
-        let c = self.spi.transfer(&mut loc)?;
-        Ok(c[0])
-    }
-
-    /// given the first byte from SRAM from sram_epd_move_header, transfer the rest
-    /// of the bytes to the EPD. These functions are split up because another pin
-    /// must be pulled low between them in the protocol
-    pub fn sram_epd_move_body(&mut self, ch: u8, data_len: u16) -> Result<(), SPI::Error> {
-        let mut c = [ch];
-        // have to copy byte by byte
-        for _i in 0..data_len {
-            let recv = self.spi.transfer(&mut c)?;
-            c[0] = recv[0];
-        }
-        self.epd_cs.set_high().ok();
-        self.sram_cs.set_high().ok();
-        Ok(())
+/// Doesn't report progress, buffers are sent in one piece. The default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoProgress;
+
+impl Progress for NoProgress {
+    fn progress(&mut self, _sent: u32, _total: u32) {}
+}
+
+/// Decides whether to retry a failed SPI write, see [Interface::retry_policy].
+pub trait RetryPolicy<E> {
+    /// The error of the interface, returned when the policy gives up.
+    type Error;
+
+    /// Called once before each write.
+    fn start(&mut self) {}
+
+    /// Called each time the write fails, returns true to send it again.
+    ///
+    /// Any backoff is done here, before returning. Policies can inspect the error to only retry
+    /// transient ones.
+    fn retry(&mut self, error: &E) -> bool;
+
+    /// Called with the last error once [RetryPolicy::retry] returns false, returns the error of
+    /// the write.
+    fn give_up(&mut self, error: E) -> Self::Error;
+}
+
+/// Never retries, the first error is returned. The default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoRetry;
+
+impl<E> RetryPolicy<E> for NoRetry {
+    type Error = E;
+
+    fn retry(&mut self, _error: &E) -> bool {
+        false
     }
-    /// write to the epaper display
-    pub fn epd_write(&mut self, data: &[u8]) -> Result<(), SPI::Error> {
-        self.epd_cs.set_low().ok();
-        for byte in data.iter() {
-            self.spi.transfer(&mut [*byte])?;
-        }
-        self.epd_cs.set_high().ok();
-        Ok(())
+
+    fn give_up(&mut self, error: E) -> E {
+        error
     }
 }
 
-#[cfg(feature = "sram")]
-pub struct SramDisplayInterface<SPI, EPDCS, SRAMCS, BUSY, DC, RESET> {
-    spi_bus: SpiSramBus<SPI, EPDCS, SRAMCS>,
-    busy: BUSY,
-    dc: DC,
-    reset: RESET,
+/// The error of an interface or bus with a [DelayRetry] policy, once every retry has failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetriesExhausted<E> {
+    /// The error of the last attempt.
+    pub error: E,
+    /// The number of times the write was sent, including the first.
+    pub attempts: u8,
 }
 
-#[cfg(feature = "sram")]
-impl<SPI, EPDCS, SRAMCS, BUSY, DC, RESET> SramDisplayInterface<SPI, EPDCS, SRAMCS, BUSY, DC, RESET>
+/// Retry a number of times, doubling a delay between attempts.
+pub struct DelayRetry<D> {
+    delay: D,
+    retries: u8,
+    backoff_ms: u8,
+    attempt: u8,
+}
+
+impl<D> DelayRetry<D>
 where
-    SPI: hal::blocking::spi::Transfer<u8>,
-    EPDCS: hal::digital::v2::OutputPin,
-    SRAMCS: hal::digital::v2::OutputPin,
-    BUSY: hal::digital::v2::InputPin,
-    DC: hal::digital::v2::OutputPin,
-    RESET: hal::digital::v2::OutputPin,
+    D: hal::blocking::delay::DelayMs<u8>,
 {
-    /// create a display interface from the embedded hal
-    pub fn new(
-        spi_bus: SpiSramBus<SPI, EPDCS, SRAMCS>,
-        mut pins: (BUSY, DC, RESET),
-    ) -> SramDisplayInterface<SPI, EPDCS, SRAMCS, BUSY, DC, RESET> {
-        // dc inactive low
-        pins.1.set_low().ok();
-        // reset inactive high
-        pins.2.set_high().ok();
-        SramDisplayInterface {
-            spi_bus,
-            busy: pins.0,
-            dc: pins.1,
-            reset: pins.2,
+    /// Create a policy that retries up to `retries` times, waiting `backoff_ms` before the first
+    /// retry and twice as long before each one after.
+    pub fn new(delay: D, retries: u8, backoff_ms: u8) -> Self {
+        DelayRetry {
+            delay,
+            retries,
+            backoff_ms,
+            attempt: 0,
         }
     }
 
-    /// release the spibus and all the associated pins
-    pub fn release(self) -> (SpiSramBus<SPI, EPDCS, SRAMCS>, (BUSY, DC, RESET)) {
-        (self.spi_bus, (self.busy, self.dc, self.reset))
+    /// release the delay
+    pub fn release(self) -> D {
+        self.delay
     }
 }
 
-#[cfg(feature = "sram")]
-impl<SPI, EPDCS, SRAMCS, BUSY, DC, RESET> DisplayInterface
-    for SramDisplayInterface<SPI, EPDCS, SRAMCS, BUSY, DC, RESET>
+impl<D, E> RetryPolicy<E> for DelayRetry<D>
 where
-    SPI: hal::blocking::spi::Transfer<u8>,
-    EPDCS: hal::digital::v2::OutputPin,
-    SRAMCS: hal::digital::v2::OutputPin,
-    BUSY: hal::digital::v2::InputPin,
-    DC: hal::digital::v2::OutputPin,
-    RESET: hal::digital::v2::OutputPin,
+    D: hal::blocking::delay::DelayMs<u8>,
 {
-    type Error = SPI::Error;
+    type Error = RetriesExhausted<E>;
 
-    fn send_command(&mut self, command: u8) -> Result<(), Self::Error> {
-        self.dc.set_low().ok();
-        self.spi_bus.epd_write(&mut [command])
+    fn start(&mut self) {
+        self.attempt = 0;
     }
 
-    fn send_data(&mut self, data: &[u8]) -> Result<(), Self::Error> {
-        self.dc.set_high().ok();
-        self.spi_bus.epd_write(data)
-    }
-
-    fn reset<D: hal::blocking::delay::DelayMs<u8>>(&mut self, delay: &mut D) {
-        // setup the sram
-        self.spi_bus.sram_init().ok();
-
-        // do a hardware reset 3 times
-        self.reset.set_low().ok();
-        delay.delay_ms(RESET_DELAY_MS);
-        self.reset.set_high().ok();
-        delay.delay_ms(RESET_DELAY_MS);
-        self.reset.set_low().ok();
-        delay.delay_ms(RESET_DELAY_MS);
-        self.reset.set_high().ok();
-        delay.delay_ms(RESET_DELAY_MS);
-        self.reset.set_low().ok();
-        delay.delay_ms(RESET_DELAY_MS);
-        self.reset.set_high().ok();
-        delay.delay_ms(RESET_DELAY_MS);
-
-        self.spi_bus.sram_seq().ok();
+    fn retry(&mut self, _error: &E) -> bool {
+        if self.attempt >= self.retries {
+            return false;
+        }
+        let ms = u32::from(self.backoff_ms) << self.attempt.min(24);
+        wait::delay_ms(&mut self.delay, ms);
+        self.attempt += 1;
+        true
     }
 
-    fn busy_wait(&self) {
-        while match self.busy.is_high() {
-            Ok(x) => x,
-            _ => false,
-        } {}
+    fn give_up(&mut self, error: E) -> RetriesExhausted<E> {
+        RetriesExhausted {
+            error,
+            attempts: self.attempt.saturating_add(1),
+        }
     }
+}
 
-    fn epd_update_data(
-        &mut self,
-        _layer: u8,
-        _nbytes: u16,
-        _buf: &[u8],
-    ) -> Result<(), Self::Error> {
-        panic!()
+/// Placeholder for a pin that is not connected.
+///
+/// As an output it ignores all writes. It is the default for the optional chip select held high
+/// by [Interface::hold_high] and [SpiSramBus::hold_high]. As an input every read fails with
+/// [NotConnected], so used as the busy pin the controller never appears busy and
+/// [DisplayInterface::is_busy] returns `None`, use it with a
+/// [TimedWait](../wait/struct.TimedWait.html) strategy.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoPin;
+
+impl hal::digital::v2::OutputPin for NoPin {
+    type Error = core::convert::Infallible;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        Ok(())
     }
 
-    fn sram_read(&mut self, address: u16, data: &mut [u8]) -> Result<(), Self::Error> {
-        self.spi_bus.sram_read(address, data)
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        Ok(())
     }
+}
 
-    fn sram_write(&mut self, address: u16, data: &[u8]) -> Result<(), Self::Error> {
-        self.spi_bus.sram_write(address, data)
-    }
+impl hal::digital::v2::InputPin for NoPin {
+    type Error = NotConnected;
 
-    fn sram_clear(&mut self, address: u16, nbytes: u16, val: u8) -> Result<(), Self::Error> {
-        self.spi_bus.sram_erase(address, nbytes, val)
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        Err(NotConnected)
     }
 
-    fn sram_epd_update_data(
-        &mut self,
-        layer: u8,
-        nbytes: u16,
-        start_address: u16,
-    ) -> Result<(), Self::Error> {
-        let epd_location = if layer == 0 { 0x10 } else { 0x13 };
-        self.dc.set_low().ok();
-        let ch = self
-            .spi_bus
-            .sram_epd_move_header(start_address, epd_location)?;
-        self.dc.set_high().ok();
-        self.spi_bus.sram_epd_move_body(ch, nbytes)
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        Err(NotConnected)
     }
 }
+
+/// The error reading a [NoPin], which has no level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NotConnected;
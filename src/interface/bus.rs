@@ -0,0 +1,440 @@
+use super::{NoPin, NoProgress, NoRetry, Progress, RetryPolicy, SpiOwner, SramAddr};
+use crate::error::Error;
+use core::fmt::Debug;
+
+const MCPSRAM_READ: u8 = 0x03;
+const MCPSRAM_WRITE: u8 = 0x02;
+const MCPSRAM_WRSR: u8 = 0x01;
+const K640_SEQUENTIAL_MODE: u8 = 1 << 6;
+
+/// Capacity of the SRAM device on a [SpiSramBus].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SramSize {
+    /// 64 Kbit devices such as the 23K640, uses 16 bit addresses
+    K64,
+    /// 256 Kbit devices such as the 23K256, uses 16 bit addresses
+    K256,
+    /// 1 Mbit devices such as the 23LC1024, uses 24 bit addresses
+    M1,
+}
+
+impl SramSize {
+    /// Returns the capacity of the device in bytes.
+    pub fn bytes(self) -> u32 {
+        match self {
+            SramSize::K64 => 8 * 1024,
+            SramSize::K256 => 32 * 1024,
+            SramSize::M1 => 128 * 1024,
+        }
+    }
+
+    fn wide_address(self) -> bool {
+        self == SramSize::M1
+    }
+}
+
+impl Default for SramSize {
+    /// Default is the 64 Kbit device found on Adafruit displays (`K64`).
+    fn default() -> Self {
+        SramSize::K64
+    }
+}
+
+pub struct SpiSramBus<SPI, EPDCS, SRAMCS, HOLD = NoPin, RETRY = NoRetry> {
+    spi: SPI,
+    epd_cs: EPDCS,
+    sram_cs: SRAMCS,
+    size: SramSize,
+    hold: HOLD,
+    // longest move from the sram to the epd with the chip selects low, 0 for no limit
+    move_chunk: u32,
+    retry: RETRY,
+}
+
+impl<SPI, EPDCS, SRAMCS> SpiSramBus<SPI, EPDCS, SRAMCS>
+where
+    SPI: hal::blocking::spi::Transfer<u8>,
+    EPDCS: hal::digital::v2::OutputPin,
+    SRAMCS: hal::digital::v2::OutputPin,
+{
+    /// create a new SpiSramBus from embedded hal traits, with the default SRAM size
+    pub fn new(spi: SPI, pins: (EPDCS, SRAMCS)) -> SpiSramBus<SPI, EPDCS, SRAMCS> {
+        Self::new_with_size(spi, pins, SramSize::default())
+    }
+
+    /// create a new SpiSramBus from embedded hal traits, for an SRAM device of the given size
+    pub fn new_with_size(
+        spi: SPI,
+        mut pins: (EPDCS, SRAMCS),
+        size: SramSize,
+    ) -> SpiSramBus<SPI, EPDCS, SRAMCS> {
+        pins.0.set_high().ok();
+        pins.1.set_high().ok();
+        SpiSramBus {
+            spi,
+            epd_cs: pins.0,
+            sram_cs: pins.1,
+            size,
+            hold: NoPin,
+            move_chunk: 0,
+            retry: NoRetry,
+        }
+    }
+}
+
+impl<SPI, EPDCS, SRAMCS, HOLD, RETRY> SpiOwner for SpiSramBus<SPI, EPDCS, SRAMCS, HOLD, RETRY> {
+    type Spi = SPI;
+
+    fn spi(&mut self) -> &mut SPI {
+        &mut self.spi
+    }
+}
+
+impl<SPI, EPDCS, SRAMCS, HOLD, RETRY> SpiSramBus<SPI, EPDCS, SRAMCS, HOLD, RETRY>
+where
+    SPI: hal::blocking::spi::Transfer<u8>,
+    EPDCS: hal::digital::v2::OutputPin,
+    SRAMCS: hal::digital::v2::OutputPin,
+    HOLD: hal::digital::v2::OutputPin,
+    RETRY: RetryPolicy<SPI::Error>,
+{
+    /// Hold the chip select of another device on the bus high.
+    ///
+    /// On the Adafruit eInk FeatherWing and breakouts, a floating SD card chip select corrupts
+    /// the SPI traffic. The pin is driven high now and before every transaction. To use the SD
+    /// card as well, share the bus with a [SharedInterface](struct.SharedInterface.html) instead.
+    pub fn hold_high<P: hal::digital::v2::OutputPin>(
+        self,
+        mut pin: P,
+    ) -> SpiSramBus<SPI, EPDCS, SRAMCS, P, RETRY> {
+        pin.set_high().ok();
+        SpiSramBus {
+            spi: self.spi,
+            epd_cs: self.epd_cs,
+            sram_cs: self.sram_cs,
+            size: self.size,
+            hold: pin,
+            move_chunk: self.move_chunk,
+            retry: self.retry,
+        }
+    }
+
+    /// Set how failed SPI transfers are retried.
+    ///
+    /// SRAM reads and writes are started again from their address, and each byte written to the
+    /// EPD is sent again, as with [Interface::retry_policy](super::Interface::retry_policy).
+    /// A move from the SRAM to the EPD can't be sent again once the SRAM has clocked data out,
+    /// so it fails with the policy's error straight away. Defaults to [NoRetry], which returns
+    /// the SPI error.
+    pub fn retry_policy<R: RetryPolicy<SPI::Error>>(
+        self,
+        retry: R,
+    ) -> SpiSramBus<SPI, EPDCS, SRAMCS, HOLD, R> {
+        SpiSramBus {
+            spi: self.spi,
+            epd_cs: self.epd_cs,
+            sram_cs: self.sram_cs,
+            size: self.size,
+            hold: self.hold,
+            move_chunk: self.move_chunk,
+            retry,
+        }
+    }
+
+    /// Limit how many bytes are moved from the SRAM to the EPD in one transaction.
+    ///
+    /// A move normally keeps both chip selects low for a whole plane, which can exceed the
+    /// maximum chip select low time of some SRAMs, and blocks other devices on the bus. With a
+    /// limit, the chip selects are released every `bytes` bytes and the SRAM read is started
+    /// again at the next address. Defaults to 0, no limit.
+    pub fn move_chunk_size(self, bytes: u32) -> Self {
+        SpiSramBus {
+            move_chunk: bytes,
+            ..self
+        }
+    }
+
+    /// release the spi and cs pins
+    pub fn release(self) -> (SPI, (EPDCS, SRAMCS)) {
+        (self.spi, (self.epd_cs, self.sram_cs))
+    }
+
+    /// release the spi and cs pins, including the pin held high
+    pub fn release_all(self) -> (SPI, (EPDCS, SRAMCS), HOLD) {
+        (self.spi, (self.epd_cs, self.sram_cs), self.hold)
+    }
+
+    /// the size of the sram device
+    pub fn size(&self) -> SramSize {
+        self.size
+    }
+
+    /// initialize sram device
+    pub fn sram_init(&mut self) -> Result<(), RETRY::Error> {
+        self.sram_transaction(|bus| {
+            bus.spi.transfer(&mut [0xFF, 0xFF, 0xFF])?;
+            Ok(())
+        })
+    }
+
+    /// set sram device to sequential
+    pub fn sram_seq(&mut self) -> Result<(), RETRY::Error> {
+        self.sram_transaction(|bus| {
+            bus.spi
+                .transfer(&mut [MCPSRAM_WRSR, K640_SEQUENTIAL_MODE])?;
+            Ok(())
+        })
+    }
+
+    // run the transfers of an sram transaction with the chip select low, starting again from
+    // the beginning while the retry policy allows
+    fn sram_transaction<F>(&mut self, mut transfers: F) -> Result<(), RETRY::Error>
+    where
+        F: FnMut(&mut Self) -> Result<(), SPI::Error>,
+    {
+        self.retry.start();
+        loop {
+            self.hold.set_high().ok();
+            self.sram_cs.set_low().ok();
+            let result = transfers(self);
+            self.sram_cs.set_high().ok();
+            match result {
+                Ok(()) => return Ok(()),
+                Err(e) if self.retry.retry(&e) => {
+                    log_debug!("retrying sram transaction");
+                }
+                Err(e) => return Err(self.retry.give_up(e)),
+            }
+        }
+    }
+
+    // the range must fit in the sram device
+    pub(super) fn check_range(
+        &self,
+        address: SramAddr,
+        len: u32,
+    ) -> Result<(), Error<RETRY::Error>> {
+        let capacity = self.size.bytes();
+        if !address.fits(len, capacity) {
+            return Err(Error::BufferSize {
+                expected: len as usize,
+                actual: capacity.saturating_sub(address.get()) as usize,
+            });
+        }
+        Ok(())
+    }
+
+    // send an instruction and address, the sram chip select must already be low
+    fn sram_instruction(&mut self, instruction: u8, address: SramAddr) -> Result<(), SPI::Error> {
+        let address = address.get();
+        let hi = (address >> 8) as u8;
+        let lo = (address & 0xFF) as u8;
+        if self.size.wide_address() {
+            let mut cmd: [u8; 4] = [instruction, (address >> 16) as u8, hi, lo];
+            self.spi.transfer(&mut cmd)?;
+        } else {
+            let mut cmd: [u8; 3] = [instruction, hi, lo];
+            self.spi.transfer(&mut cmd)?;
+        }
+        Ok(())
+    }
+
+    /// write to the sram
+    ///
+    /// Returns `Error::BufferSize` if the data doesn't fit in the sram device.
+    pub fn sram_write(
+        &mut self,
+        address: SramAddr,
+        data: &[u8],
+    ) -> Result<(), Error<RETRY::Error>> {
+        self.check_range(address, data.len() as u32)?;
+        self.sram_transaction(|bus| {
+            bus.sram_instruction(MCPSRAM_WRITE, address)?;
+            for byte in data.iter() {
+                bus.spi.transfer(&mut [*byte])?;
+            }
+            Ok(())
+        })
+        .map_err(Error::Interface)
+    }
+
+    /// read the sram
+    ///
+    /// Returns `Error::BufferSize` if the data doesn't fit in the sram device.
+    pub fn sram_read(
+        &mut self,
+        address: SramAddr,
+        data: &mut [u8],
+    ) -> Result<(), Error<RETRY::Error>> {
+        self.check_range(address, data.len() as u32)?;
+        self.sram_transaction(|bus| {
+            bus.sram_instruction(MCPSRAM_READ, address)?;
+            bus.spi.transfer(data)?;
+            Ok(())
+        })
+        .map_err(Error::Interface)
+    }
+
+    /// set len bytes of the sram to a value, four bytes per transfer
+    ///
+    /// Returns `Error::BufferSize` if the range doesn't fit in the sram device.
+    pub fn sram_erase(
+        &mut self,
+        address: SramAddr,
+        len: u32,
+        val: u8,
+    ) -> Result<(), Error<RETRY::Error>> {
+        self.check_range(address, len)?;
+        self.sram_transaction(|bus| {
+            bus.sram_instruction(MCPSRAM_WRITE, address)?;
+            for _i in 0..len / 4 {
+                bus.spi.transfer(&mut [val, val, val, val])?;
+            }
+            // the bytes past the last multiple of 4
+            let mut tail = [val; 4];
+            bus.spi.transfer(&mut tail[..(len % 4) as usize])?;
+            Ok(())
+        })
+        .map_err(Error::Interface)
+    }
+
+    /// start a buffer transfer from the SRAM to the EPD. This needs the beginning address
+    /// in the SRAM, and the location where they will be sent in the EPD.
+    /// While the location is sent to the EPD, the first byte will be pulled from
+    /// the SRAM at the address specified, this is passed to the sram_epd_move_body fn
+    pub fn sram_epd_move_header(
+        &mut self,
+        address: SramAddr,
+        epd_location: u8,
+    ) -> Result<u8, RETRY::Error> {
+        let result = self.move_header(address, epd_location);
+        result.map_err(|e| self.abandon_move(e))
+    }
+
+    fn move_header(&mut self, address: SramAddr, epd_location: u8) -> Result<u8, SPI::Error> {
+        self.hold.set_high().ok();
+        self.sram_cs.set_low().ok();
+        // send address and get first byte of data
+        self.sram_instruction(MCPSRAM_READ, address)?;
+        self.epd_cs.set_low().ok();
+        let mut loc = [epd_location];
+        let c = self.spi.transfer(&mut loc)?;
+        Ok(c[0])
+    }
+
+    /// given the first byte from SRAM from sram_epd_move_header, transfer the rest
+    /// of the bytes to the EPD. These functions are split up because another pin
+    /// must be pulled low between them in the protocol
+    ///
+    /// The chip selects stay low for the whole transfer, see [SpiSramBus::sram_epd_move_body_at]
+    /// to limit the transaction length.
+    pub fn sram_epd_move_body(&mut self, ch: u8, data_len: u32) -> Result<(), RETRY::Error> {
+        let result = self.move_bytes(ch, data_len, 0, &mut NoProgress, |_, _| Ok(()));
+        self.release_move();
+        result.map_err(|e| self.abandon_move(e))
+    }
+
+    /// As [SpiSramBus::sram_epd_move_body], releasing the chip selects every
+    /// [move_chunk_size](SpiSramBus::move_chunk_size) bytes. `address` is the one passed to
+    /// sram_epd_move_header.
+    pub fn sram_epd_move_body_at(
+        &mut self,
+        ch: u8,
+        address: SramAddr,
+        data_len: u32,
+    ) -> Result<(), RETRY::Error> {
+        self.sram_epd_move_body_reporting(ch, address, data_len, 0, &mut NoProgress)
+    }
+
+    // sram_epd_move_body_at, reporting progress every chunk_size bytes if it isn't 0
+    pub(super) fn sram_epd_move_body_reporting<P: Progress>(
+        &mut self,
+        ch: u8,
+        address: SramAddr,
+        data_len: u32,
+        chunk_size: u32,
+        progress: &mut P,
+    ) -> Result<(), RETRY::Error> {
+        let move_chunk = self.move_chunk;
+        let result = self.move_bytes(ch, data_len, chunk_size, progress, |bus, sent| {
+            if move_chunk == 0 || sent % move_chunk != 0 {
+                return Ok(());
+            }
+            // the byte for the epd is already read, start reading again after it
+            bus.release_move();
+            bus.hold.set_high().ok();
+            bus.sram_cs.set_low().ok();
+            bus.sram_instruction(MCPSRAM_READ, SramAddr::new(address.get() + sent + 1))?;
+            bus.epd_cs.set_low().ok();
+            Ok(())
+        });
+        self.release_move();
+        result.map_err(|e| self.abandon_move(e))
+    }
+
+    // clock each byte read from the sram into the epd, calling between after each byte but the
+    // last with the bytes sent so far
+    fn move_bytes<P, F>(
+        &mut self,
+        ch: u8,
+        data_len: u32,
+        chunk_size: u32,
+        progress: &mut P,
+        mut between: F,
+    ) -> Result<(), SPI::Error>
+    where
+        P: Progress,
+        F: FnMut(&mut Self, u32) -> Result<(), SPI::Error>,
+    {
+        let mut c = [ch];
+        // have to copy byte by byte
+        for i in 0..data_len {
+            let recv = self.spi.transfer(&mut c)?;
+            c[0] = recv[0];
+            let sent = i + 1;
+            if chunk_size != 0 && (sent % chunk_size == 0 || sent == data_len) {
+                progress.progress(sent, data_len);
+            }
+            if sent < data_len {
+                between(self, sent)?;
+            }
+        }
+        Ok(())
+    }
+
+    // end a move from the sram to the epd
+    fn release_move(&mut self) {
+        self.epd_cs.set_high().ok();
+        self.sram_cs.set_high().ok();
+    }
+
+    // a failed move isn't retried, the sram read has already moved past the lost byte
+    fn abandon_move(&mut self, error: SPI::Error) -> RETRY::Error {
+        self.release_move();
+        self.retry.start();
+        self.retry.give_up(error)
+    }
+
+    /// write to the epaper display
+    pub fn epd_write(&mut self, data: &[u8]) -> Result<(), RETRY::Error> {
+        self.hold.set_high().ok();
+        self.epd_cs.set_low().ok();
+        let result = data.iter().try_for_each(|byte| self.epd_byte(*byte));
+        self.epd_cs.set_high().ok();
+        result
+    }
+
+    // send a byte to the epd, sending it again while the retry policy allows
+    fn epd_byte(&mut self, byte: u8) -> Result<(), RETRY::Error> {
+        self.retry.start();
+        loop {
+            match self.spi.transfer(&mut [byte]) {
+                Ok(_) => return Ok(()),
+                Err(e) if self.retry.retry(&e) => {
+                    log_debug!("retrying epd write");
+                }
+                Err(e) => return Err(self.retry.give_up(e)),
+            }
+        }
+    }
+}
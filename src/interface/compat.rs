@@ -0,0 +1,413 @@
+//! Adapters between embedded-hal 0.2 and 1.0.
+//!
+//! The driver uses the embedded-hal 0.2 traits. [Eh1] wraps an embedded-hal 1.0 pin, SPI bus or
+//! delay so it can be passed to [Interface](../struct.Interface.html) and the other 0.2 based
+//! types. [Eh02] goes the other way, so 0.2 types from an older BSP can be shared with code that
+//! already uses 1.0.
+//!
+//! Only available with the `eh1` feature.
+//!
+//! ### Example
+//!
+//! ```ignore
+//! let interface = Interface::new(Eh1::new(spi_bus), (Eh1::new(cs), Eh1::new(busy), Eh1::new(dc), Eh1::new(reset)));
+//! ```
+
+use core::cell::RefCell;
+use core::fmt::Debug;
+use embedded_hal_1 as eh1;
+
+/// Wraps an embedded-hal 1.0 type to implement the embedded-hal 0.2 traits.
+///
+/// Implements `OutputPin`, `InputPin`, blocking SPI `Write` and `Transfer` (for a 1.0 `SpiBus`),
+/// and `DelayMs`.
+pub struct Eh1<T>(RefCell<T>);
+
+impl<T> Eh1<T> {
+    /// Wrap an embedded-hal 1.0 type.
+    pub fn new(inner: T) -> Self {
+        Eh1(RefCell::new(inner))
+    }
+
+    /// Returns the wrapped type.
+    pub fn into_inner(self) -> T {
+        self.0.into_inner()
+    }
+}
+
+impl<T: eh1::digital::OutputPin> hal::digital::v2::OutputPin for Eh1<T> {
+    type Error = T::Error;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.0.get_mut().set_low()
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.0.get_mut().set_high()
+    }
+}
+
+impl<T: eh1::digital::InputPin> hal::digital::v2::InputPin for Eh1<T> {
+    type Error = T::Error;
+
+    // 1.0 pins are read through &mut self
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        self.0.borrow_mut().is_high()
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        self.0.borrow_mut().is_low()
+    }
+}
+
+impl<T: eh1::spi::SpiBus<u8>> hal::blocking::spi::Write<u8> for Eh1<T> {
+    type Error = T::Error;
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        let bus = self.0.get_mut();
+        bus.write(words)?;
+        bus.flush()
+    }
+}
+
+impl<T: eh1::spi::SpiBus<u8>> hal::blocking::spi::Transfer<u8> for Eh1<T> {
+    type Error = T::Error;
+
+    fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Self::Error> {
+        let bus = self.0.get_mut();
+        bus.transfer_in_place(words)?;
+        bus.flush()?;
+        Ok(words)
+    }
+}
+
+impl<T: eh1::delay::DelayNs> hal::blocking::delay::DelayMs<u8> for Eh1<T> {
+    fn delay_ms(&mut self, ms: u8) {
+        self.0.get_mut().delay_ms(ms.into())
+    }
+}
+
+/// Error from an embedded-hal 0.2 type wrapped by [Eh02].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Eh02Error<E>(pub E);
+
+impl<E: Debug> eh1::digital::Error for Eh02Error<E> {
+    fn kind(&self) -> eh1::digital::ErrorKind {
+        eh1::digital::ErrorKind::Other
+    }
+}
+
+impl<E: Debug> eh1::spi::Error for Eh02Error<E> {
+    fn kind(&self) -> eh1::spi::ErrorKind {
+        eh1::spi::ErrorKind::Other
+    }
+}
+
+/// Wraps an embedded-hal 0.2 output pin to implement the embedded-hal 1.0 `OutputPin`.
+///
+/// See [Eh02Input], [Eh02Spi] and [Eh02Delay] for the other types.
+pub struct Eh02<T>(T);
+
+impl<T> Eh02<T> {
+    /// Wrap an embedded-hal 0.2 output pin.
+    pub fn new(inner: T) -> Self {
+        Eh02(inner)
+    }
+
+    /// Returns the wrapped type.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> eh1::digital::ErrorType for Eh02<T>
+where
+    T: hal::digital::v2::OutputPin,
+    T::Error: Debug,
+{
+    type Error = Eh02Error<T::Error>;
+}
+
+impl<T> eh1::digital::OutputPin for Eh02<T>
+where
+    T: hal::digital::v2::OutputPin,
+    T::Error: Debug,
+{
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.0.set_low().map_err(Eh02Error)
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.0.set_high().map_err(Eh02Error)
+    }
+}
+
+/// Wraps an embedded-hal 0.2 input pin to implement the embedded-hal 1.0 `InputPin`.
+pub struct Eh02Input<T>(T);
+
+impl<T> Eh02Input<T> {
+    /// Wrap an embedded-hal 0.2 input pin.
+    pub fn new(inner: T) -> Self {
+        Eh02Input(inner)
+    }
+
+    /// Returns the wrapped pin.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> eh1::digital::ErrorType for Eh02Input<T>
+where
+    T: hal::digital::v2::InputPin,
+    T::Error: Debug,
+{
+    type Error = Eh02Error<T::Error>;
+}
+
+impl<T> eh1::digital::InputPin for Eh02Input<T>
+where
+    T: hal::digital::v2::InputPin,
+    T::Error: Debug,
+{
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        self.0.is_high().map_err(Eh02Error)
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        self.0.is_low().map_err(Eh02Error)
+    }
+}
+
+/// Wraps an embedded-hal 0.2 SPI bus to implement the embedded-hal 1.0 `SpiBus`.
+pub struct Eh02Spi<T>(T);
+
+impl<T> Eh02Spi<T> {
+    /// Wrap an embedded-hal 0.2 SPI bus.
+    pub fn new(inner: T) -> Self {
+        Eh02Spi(inner)
+    }
+
+    /// Returns the wrapped bus.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T, E> eh1::spi::ErrorType for Eh02Spi<T>
+where
+    T: hal::blocking::spi::Write<u8, Error = E> + hal::blocking::spi::Transfer<u8, Error = E>,
+    E: Debug,
+{
+    type Error = Eh02Error<E>;
+}
+
+impl<T, E> eh1::spi::SpiBus<u8> for Eh02Spi<T>
+where
+    T: hal::blocking::spi::Write<u8, Error = E> + hal::blocking::spi::Transfer<u8, Error = E>,
+    E: Debug,
+{
+    fn read(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+        for word in words.iter_mut() {
+            *word = 0;
+        }
+        self.0.transfer(words).map_err(Eh02Error)?;
+        Ok(())
+    }
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        self.0.write(words).map_err(Eh02Error)
+    }
+
+    fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Self::Error> {
+        // clock out the longer of the two, padding the write with zeros
+        let mut chunk = [0u8; 16];
+        let len = read.len().max(write.len());
+        let mut pos = 0;
+        while pos < len {
+            let sz = (len - pos).min(chunk.len());
+            for (i, word) in chunk[..sz].iter_mut().enumerate() {
+                *word = write.get(pos + i).cloned().unwrap_or(0);
+            }
+            let rx = self.0.transfer(&mut chunk[..sz]).map_err(Eh02Error)?;
+            for (i, word) in rx.iter().enumerate() {
+                if let Some(r) = read.get_mut(pos + i) {
+                    *r = *word;
+                }
+            }
+            pos += sz;
+        }
+        Ok(())
+    }
+
+    fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+        self.0.transfer(words).map_err(Eh02Error)?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// Wraps an embedded-hal 0.2 delay to implement the embedded-hal 1.0 `DelayNs`.
+pub struct Eh02Delay<T>(T);
+
+impl<T> Eh02Delay<T> {
+    /// Wrap an embedded-hal 0.2 delay.
+    pub fn new(inner: T) -> Self {
+        Eh02Delay(inner)
+    }
+
+    /// Returns the wrapped delay.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: hal::blocking::delay::DelayUs<u32>> eh1::delay::DelayNs for Eh02Delay<T> {
+    fn delay_ns(&mut self, ns: u32) {
+        // round up to the next microsecond
+        self.0.delay_us(ns.div_ceil(1000))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::convert::Infallible;
+    use hal::blocking::spi::{Transfer, Write};
+    use hal::digital::v2::{InputPin, OutputPin};
+
+    #[derive(Default)]
+    struct Pin {
+        high: bool,
+    }
+
+    impl eh1::digital::ErrorType for Pin {
+        type Error = Infallible;
+    }
+
+    impl eh1::digital::OutputPin for Pin {
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            self.high = false;
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            self.high = true;
+            Ok(())
+        }
+    }
+
+    impl eh1::digital::InputPin for Pin {
+        fn is_high(&mut self) -> Result<bool, Self::Error> {
+            Ok(self.high)
+        }
+
+        fn is_low(&mut self) -> Result<bool, Self::Error> {
+            Ok(!self.high)
+        }
+    }
+
+    // 1.0 bus that returns each byte written plus one
+    #[derive(Default)]
+    struct Bus {
+        written: std::vec::Vec<u8>,
+    }
+
+    impl eh1::spi::ErrorType for Bus {
+        type Error = Infallible;
+    }
+
+    impl eh1::spi::SpiBus<u8> for Bus {
+        fn read(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+            for word in words.iter_mut() {
+                *word = 1;
+            }
+            Ok(())
+        }
+
+        fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+            self.written.extend_from_slice(words);
+            Ok(())
+        }
+
+        fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Self::Error> {
+            self.written.extend_from_slice(write);
+            for (r, w) in read.iter_mut().zip(write) {
+                *r = w + 1;
+            }
+            Ok(())
+        }
+
+        fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+            self.written.extend_from_slice(words);
+            for word in words.iter_mut() {
+                *word += 1;
+            }
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn eh1_as_eh02() {
+        let mut pin = Eh1::new(Pin::default());
+        pin.set_high().unwrap();
+        assert!(pin.is_high().unwrap());
+
+        let mut bus = Eh1::new(Bus::default());
+        bus.write(&[0x10]).unwrap();
+        let mut buf = [0x20, 0x30];
+        assert_eq!(bus.transfer(&mut buf).unwrap(), [0x21, 0x31]);
+        assert_eq!(bus.into_inner().written, [0x10, 0x20, 0x30]);
+    }
+
+    // 1.0 delay totalling the time waited
+    #[derive(Default)]
+    struct Delay {
+        ns: u64,
+    }
+
+    impl eh1::delay::DelayNs for Delay {
+        fn delay_ns(&mut self, ns: u32) {
+            self.ns += u64::from(ns);
+        }
+    }
+
+    #[test]
+    fn eh1_delay_for_reset() {
+        use crate::config::Builder;
+        use crate::display::{Dimensions, Display};
+        use crate::interface::Interface;
+        use crate::test_support::{MockPin, MockSpi};
+
+        let config = Builder::new()
+            .dimensions(Dimensions { rows: 8, cols: 8 })
+            .build()
+            .unwrap();
+        let interface = Interface::new(MockSpi::default(), (MockPin, MockPin, MockPin, MockPin));
+        let mut display = Display::new(interface, config);
+        let mut delay = Delay::default();
+        display.reset(&mut Eh1::new(&mut delay)).unwrap();
+        // six 10ms reset phases, the 200ms power on delay and 20ms after VDCS
+        assert_eq!(delay.ns, 280_000_000);
+    }
+
+    #[test]
+    fn eh02_as_eh1() {
+        use self::eh1::spi::SpiBus;
+
+        // round trip through both adapters
+        let mut bus = Eh02Spi::new(Eh1::new(Bus::default()));
+        let mut read = [0u8; 20];
+        bus.transfer(&mut read, &[5; 18]).unwrap();
+        assert_eq!(read[..18], [6; 18]);
+        assert_eq!(read[18..], [1, 1]);
+        assert_eq!(bus.into_inner().into_inner().written.len(), 20);
+    }
+}
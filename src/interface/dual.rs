@@ -0,0 +1,254 @@
+use super::hardware_reset;
+use crate::command::BusWidth;
+use crate::error::Error;
+use crate::interface::DisplayInterface;
+use crate::regs;
+use crate::wait::{Operation, SpinWait, WaitStrategy};
+use core::fmt::Debug;
+
+/// An SPI bus that can also send data on two lines, provided by the platform.
+///
+/// Dual output needs a second data pin, wired to the MM pin of the module, which embedded-hal
+/// has no trait for. It is usually a QSPI peripheral in dual mode, or a PIO program.
+pub trait DualSpiWrite {
+    type Error;
+
+    /// Send bytes on one data line, as `spi::Write`.
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error>;
+
+    /// Send bytes on two data lines, two bits per clock, the most significant pair first.
+    fn write_dual(&mut self, words: &[u8]) -> Result<(), Self::Error>;
+}
+
+/// The hardware interface to a display wired for dual SPI.
+///
+/// Commands and their parameters are sent on one data line, and the planes on two, halving the
+/// time to upload a frame. A `Display` switches the controller to dual SPI during
+/// initialization when it uses this interface.
+pub struct InterfaceDualSpi<SPI, CS, BUSY, DC, RESET, WAIT = SpinWait> {
+    /// SPI interface, with dual output
+    spi: SPI,
+    /// Chip Select, low active (output)
+    cs: CS,
+    /// Active low busy pin (input)
+    busy: BUSY,
+    /// Data/Command Control Pin (High for data, Low for command) (output)
+    dc: DC,
+    /// Pin for resetting the controller (output)
+    reset: RESET,
+    /// How to wait while the controller is busy
+    wait: WAIT,
+}
+
+impl<SPI, CS, BUSY, DC, RESET> InterfaceDualSpi<SPI, CS, BUSY, DC, RESET>
+where
+    SPI: DualSpiWrite,
+    CS: hal::digital::v2::OutputPin,
+    BUSY: hal::digital::v2::InputPin,
+    DC: hal::digital::v2::OutputPin,
+    RESET: hal::digital::v2::OutputPin,
+{
+    /// Create a new dual SPI Interface from a dual capable bus and embedded hal pins.
+    pub fn new(spi: SPI, pins: (CS, BUSY, DC, RESET)) -> Self {
+        Self {
+            spi,
+            cs: pins.0,
+            busy: pins.1,
+            dc: pins.2,
+            reset: pins.3,
+            wait: SpinWait,
+        }
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RESET, WAIT> InterfaceDualSpi<SPI, CS, BUSY, DC, RESET, WAIT>
+where
+    SPI: DualSpiWrite,
+    CS: hal::digital::v2::OutputPin,
+    BUSY: hal::digital::v2::InputPin,
+    DC: hal::digital::v2::OutputPin,
+    RESET: hal::digital::v2::OutputPin,
+{
+    /// Set how to wait while the controller is busy.
+    ///
+    /// Defaults to [SpinWait](../wait/struct.SpinWait.html).
+    pub fn wait_strategy<W: WaitStrategy>(
+        self,
+        wait: W,
+    ) -> InterfaceDualSpi<SPI, CS, BUSY, DC, RESET, W> {
+        InterfaceDualSpi {
+            spi: self.spi,
+            cs: self.cs,
+            busy: self.busy,
+            dc: self.dc,
+            reset: self.reset,
+            wait,
+        }
+    }
+
+    /// release the spi and pins
+    pub fn release(self) -> (SPI, (CS, BUSY, DC, RESET)) {
+        (self.spi, (self.cs, self.busy, self.dc, self.reset))
+    }
+
+    // write bytes on one or two data lines
+    fn write(&mut self, dual: bool, data: &[u8]) -> Result<(), SPI::Error> {
+        self.cs.set_low().ok();
+        let result = if dual {
+            self.spi.write_dual(data)
+        } else {
+            self.spi.write(data)
+        };
+        // Release the controller
+        self.cs.set_high().ok();
+        result
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RESET, WAIT> DisplayInterface
+    for InterfaceDualSpi<SPI, CS, BUSY, DC, RESET, WAIT>
+where
+    SPI: DualSpiWrite,
+    CS: hal::digital::v2::OutputPin,
+    BUSY: hal::digital::v2::InputPin,
+    DC: hal::digital::v2::OutputPin,
+    DC::Error: Debug,
+    RESET: hal::digital::v2::OutputPin,
+    RESET::Error: Debug,
+    WAIT: WaitStrategy,
+{
+    type Error = SPI::Error;
+
+    fn reset<D: hal::blocking::delay::DelayMs<u8>>(&mut self, delay: &mut D) {
+        hardware_reset(&mut self.reset, delay);
+    }
+
+    fn send_command(&mut self, command: u8) -> Result<(), Self::Error> {
+        log_trace!("command {:#04x}", command);
+        self.dc.set_low().unwrap();
+        self.write(false, &[command])?;
+        self.dc.set_high().unwrap();
+        Ok(())
+    }
+
+    fn send_data(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        self.dc.set_high().unwrap();
+        self.write(false, data)
+    }
+
+    fn epd_update_data(
+        &mut self,
+        layer: u8,
+        nbytes: u16,
+        buf: &[u8],
+    ) -> Result<(), Error<Self::Error>> {
+        let sz: usize = nbytes.into();
+        if sz != buf.len() {
+            return Err(Error::BufferSize {
+                expected: sz,
+                actual: buf.len(),
+            });
+        }
+        self.send_command(regs::data_transmission(layer))
+            .and_then(|_| {
+                self.dc.set_high().unwrap();
+                self.write(true, buf)
+            })
+            .map_err(Error::Interface)
+    }
+
+    fn busy_wait(&mut self) {
+        self.wait.start();
+        while self.busy.is_high().unwrap_or_default() {
+            self.wait.wait();
+        }
+    }
+
+    fn busy_wait_for(&mut self, operation: Operation) {
+        if !self.wait.fixed_wait(operation) {
+            self.busy_wait()
+        }
+    }
+
+    fn is_busy(&mut self) -> Option<bool> {
+        if self.wait.is_fixed() {
+            return None;
+        }
+        self.busy.is_high().ok()
+    }
+
+    fn bus_width(&self) -> BusWidth {
+        BusWidth::Dual
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::MockPin;
+    use std::vec::Vec;
+
+    // records each write and whether it was on two lines
+    #[derive(Default)]
+    struct MockDualSpi {
+        writes: Vec<(bool, Vec<u8>)>,
+    }
+
+    impl DualSpiWrite for MockDualSpi {
+        type Error = ();
+
+        fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+            self.writes.push((false, words.to_vec()));
+            Ok(())
+        }
+
+        fn write_dual(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+            self.writes.push((true, words.to_vec()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn planes_sent_on_two_lines() {
+        let mut interface =
+            InterfaceDualSpi::new(MockDualSpi::default(), (MockPin, MockPin, MockPin, MockPin));
+        interface.send_command(0x50).unwrap();
+        interface.send_data(&[0x37]).unwrap();
+        interface.epd_update_data(1, 2, &[0xAA, 0x55]).unwrap();
+        assert_eq!(
+            interface.epd_update_data(0, 3, &[0xFF]),
+            Err(Error::BufferSize {
+                expected: 3,
+                actual: 1
+            })
+        );
+        let writes = interface.release().0.writes;
+        assert_eq!(
+            writes,
+            [
+                (false, vec![0x50]),
+                (false, vec![0x37]),
+                (false, vec![0x13]),
+                (true, vec![0xAA, 0x55]),
+            ]
+        );
+    }
+
+    #[test]
+    fn init_enables_dual_spi() {
+        use crate::test_support::RecordingDelay;
+        use crate::{Builder, Dimensions, Display};
+
+        let config = Builder::new()
+            .dimensions(Dimensions { rows: 8, cols: 8 })
+            .build()
+            .unwrap();
+        let interface =
+            InterfaceDualSpi::new(MockDualSpi::default(), (MockPin, MockPin, MockPin, MockPin));
+        let mut display = Display::new(interface, config);
+        display.reset(&mut RecordingDelay(&mut Vec::new())).unwrap();
+        // enabled before anything else
+        let writes = display.release().0.release().0.writes;
+        assert_eq!(writes[..2], [(false, vec![0x15]), (false, vec![0x30])]);
+    }
+}
@@ -0,0 +1,712 @@
+use super::{
+    hardware_reset, DisplayInterface, FixedSpeed, NoPin, NoProgress, NoRetry, Progress,
+    RetryPolicy, SetSpeed, SpiOwner,
+};
+use crate::command::{BufCommand, CommandBatch};
+use crate::error::Error;
+use crate::regs;
+use crate::wait::{Operation, SpinWait, WaitStrategy};
+use core::fmt::Debug;
+
+/// The hardware interface to a display.
+///
+/// ### Example
+///
+/// This example uses the Linux implementation of the embedded HAL traits to build a display
+/// interface. For a complete example see [the Raspberry Pi Inky pHAT example](https://github.com/wezm/ssd1675/blob/master/examples/raspberry_pi_inky_phat.rs).
+///
+/// ```ignore
+/// extern crate linux_embedded_hal;
+/// use linux_embedded_hal::spidev::{self, SpidevOptions};
+/// use linux_embedded_hal::sysfs_gpio::Direction;
+/// use linux_embedded_hal::Delay;
+/// use linux_embedded_hal::{Pin, Spidev};
+///
+/// extern crate il0373;
+/// use il0373::{Builder, Color, Dimensions, Display, GraphicDisplay, Rotation};
+///
+/// // Configure SPI
+/// let mut spi = Spidev::open("/dev/spidev0.0").expect("SPI device");
+/// let options = SpidevOptions::new()
+///     .bits_per_word(8)
+///     .max_speed_hz(4_000_000)
+///     .mode(spidev::SPI_MODE_0)
+///     .build();
+/// spi.configure(&options).expect("SPI configuration");
+///
+/// // https://pinout.xyz/pinout/inky_phat
+/// // Configure Digital I/O Pins
+/// let cs = Pin::new(8); // BCM8
+/// cs.export().expect("cs export");
+/// while !cs.is_exported() {}
+/// cs.set_direction(Direction::Out).expect("CS Direction");
+/// cs.set_value(1).expect("CS Value set to 1");
+///
+/// let busy = Pin::new(17); // BCM17
+/// busy.export().expect("busy export");
+/// while !busy.is_exported() {}
+/// busy.set_direction(Direction::In).expect("busy Direction");
+///
+/// let dc = Pin::new(22); // BCM22
+/// dc.export().expect("dc export");
+/// while !dc.is_exported() {}
+/// dc.set_direction(Direction::Out).expect("dc Direction");
+/// dc.set_value(1).expect("dc Value set to 1");
+///
+/// let reset = Pin::new(27); // BCM27
+/// reset.export().expect("reset export");
+/// while !reset.is_exported() {}
+/// reset
+///     .set_direction(Direction::Out)
+///     .expect("reset Direction");
+/// reset.set_value(1).expect("reset Value set to 1");
+///
+/// // Build the interface from the pins and SPI device
+/// let controller = il0373::Interface::new(spi, (cs, busy, dc, reset));
+/// ```
+pub struct Interface<SPI, CS, BUSY, DC, RESET, HOLD = NoPin, OPTIONS = InterfaceOptions> {
+    /// SPI interface
+    spi: SPI,
+    /// Chip Select, low active (output)
+    cs: CS,
+    /// Active low busy pin (input)
+    busy: BUSY,
+    /// Data/Command Control Pin (High for data, Low for command) (output)
+    dc: DC,
+    /// Pin for resetting the controller (output)
+    reset: RESET,
+    /// Chip select of another device on the bus, held high (output)
+    hold: HOLD,
+    /// The frequency last set
+    current_hz: u32,
+    /// The last command sends display data, its data is sent at the data frequency
+    bulk: bool,
+    /// Everything set by the builder methods other than the pins
+    options: OPTIONS,
+}
+
+/// The options of an [Interface], set with its builder methods.
+///
+/// Each option is a type parameter, so an interface built with
+/// [wait_strategy](Interface::wait_strategy) and [retry_policy](Interface::retry_policy) is an
+/// `Interface<SPI, CS, BUSY, DC, RESET, NoPin, InterfaceOptions<W, FixedSpeed, NoProgress, R>>`.
+pub struct InterfaceOptions<
+    WAIT = SpinWait,
+    SPEED = FixedSpeed,
+    PROGRESS = NoProgress,
+    RETRY = NoRetry,
+> {
+    /// How to wait while the controller is busy
+    wait: WAIT,
+    /// How to change the SPI frequency
+    speed: SPEED,
+    /// SPI frequency for commands and data, in Hz
+    speeds: (u32, u32),
+    /// Reports upload progress
+    progress: PROGRESS,
+    /// Bytes sent between progress reports, 0 to send buffers in one piece
+    chunk_size: u32,
+    /// When to retry a failed SPI write
+    retry: RETRY,
+}
+
+impl Default for InterfaceOptions {
+    fn default() -> Self {
+        InterfaceOptions {
+            wait: SpinWait,
+            speed: FixedSpeed,
+            speeds: (0, 0),
+            progress: NoProgress,
+            chunk_size: 0,
+            retry: NoRetry,
+        }
+    }
+}
+
+impl<WAIT, SPEED, PROGRESS, RETRY> InterfaceOptions<WAIT, SPEED, PROGRESS, RETRY> {
+    fn wait<W>(self, wait: W) -> InterfaceOptions<W, SPEED, PROGRESS, RETRY> {
+        let InterfaceOptions {
+            speed,
+            speeds,
+            progress,
+            chunk_size,
+            retry,
+            ..
+        } = self;
+        InterfaceOptions {
+            wait,
+            speed,
+            speeds,
+            progress,
+            chunk_size,
+            retry,
+        }
+    }
+
+    fn speed<S>(self, speed: S, speeds: (u32, u32)) -> InterfaceOptions<WAIT, S, PROGRESS, RETRY> {
+        let InterfaceOptions {
+            wait,
+            progress,
+            chunk_size,
+            retry,
+            ..
+        } = self;
+        InterfaceOptions {
+            wait,
+            speed,
+            speeds,
+            progress,
+            chunk_size,
+            retry,
+        }
+    }
+
+    fn progress<P>(self, progress: P, chunk_size: u32) -> InterfaceOptions<WAIT, SPEED, P, RETRY> {
+        let InterfaceOptions {
+            wait,
+            speed,
+            speeds,
+            retry,
+            ..
+        } = self;
+        InterfaceOptions {
+            wait,
+            speed,
+            speeds,
+            progress,
+            chunk_size,
+            retry,
+        }
+    }
+
+    fn retry<R>(self, retry: R) -> InterfaceOptions<WAIT, SPEED, PROGRESS, R> {
+        let InterfaceOptions {
+            wait,
+            speed,
+            speeds,
+            progress,
+            chunk_size,
+            ..
+        } = self;
+        InterfaceOptions {
+            wait,
+            speed,
+            speeds,
+            progress,
+            chunk_size,
+            retry,
+        }
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RESET> Interface<SPI, CS, BUSY, DC, RESET>
+where
+    SPI: hal::blocking::spi::Write<u8>,
+    CS: hal::digital::v2::OutputPin,
+    BUSY: hal::digital::v2::InputPin,
+    DC: hal::digital::v2::OutputPin,
+    RESET: hal::digital::v2::OutputPin,
+{
+    /// Create a new Interface from embedded hal traits.
+    pub fn new(spi: SPI, pins: (CS, BUSY, DC, RESET)) -> Self {
+        Self {
+            spi,
+            cs: pins.0,
+            busy: pins.1,
+            dc: pins.2,
+            reset: pins.3,
+            hold: NoPin,
+            current_hz: 0,
+            bulk: false,
+            options: InterfaceOptions::default(),
+        }
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RESET, HOLD, WAIT, SPEED, PROGRESS, RETRY>
+    Interface<SPI, CS, BUSY, DC, RESET, HOLD, InterfaceOptions<WAIT, SPEED, PROGRESS, RETRY>>
+where
+    SPI: hal::blocking::spi::Write<u8>,
+    CS: hal::digital::v2::OutputPin,
+    BUSY: hal::digital::v2::InputPin,
+    DC: hal::digital::v2::OutputPin,
+    RESET: hal::digital::v2::OutputPin,
+    HOLD: hal::digital::v2::OutputPin,
+{
+    /// Hold the chip select of another device on the bus high.
+    ///
+    /// On boards such as the Adafruit eInk breakout, a floating SD card chip select corrupts the
+    /// SPI traffic. The pin is driven high now and before every transaction.
+    #[allow(clippy::type_complexity)]
+    pub fn hold_high<P: hal::digital::v2::OutputPin>(
+        self,
+        mut pin: P,
+    ) -> Interface<SPI, CS, BUSY, DC, RESET, P, InterfaceOptions<WAIT, SPEED, PROGRESS, RETRY>>
+    {
+        pin.set_high().ok();
+        Interface {
+            spi: self.spi,
+            cs: self.cs,
+            busy: self.busy,
+            dc: self.dc,
+            reset: self.reset,
+            hold: pin,
+            current_hz: self.current_hz,
+            bulk: self.bulk,
+            options: self.options,
+        }
+    }
+
+    // change the options, keeping the bus and pins
+    fn map_options<O, F>(self, f: F) -> Interface<SPI, CS, BUSY, DC, RESET, HOLD, O>
+    where
+        F: FnOnce(InterfaceOptions<WAIT, SPEED, PROGRESS, RETRY>) -> O,
+    {
+        Interface {
+            spi: self.spi,
+            cs: self.cs,
+            busy: self.busy,
+            dc: self.dc,
+            reset: self.reset,
+            hold: self.hold,
+            current_hz: self.current_hz,
+            bulk: self.bulk,
+            options: f(self.options),
+        }
+    }
+
+    /// Set how to wait while the controller is busy.
+    ///
+    /// Defaults to [SpinWait](../wait/struct.SpinWait.html).
+    #[allow(clippy::type_complexity)]
+    pub fn wait_strategy<W: WaitStrategy>(
+        self,
+        wait: W,
+    ) -> Interface<SPI, CS, BUSY, DC, RESET, HOLD, InterfaceOptions<W, SPEED, PROGRESS, RETRY>>
+    {
+        self.map_options(|options| options.wait(wait))
+    }
+
+    /// Use a different SPI frequency for commands and for data.
+    ///
+    /// Commands and their parameters are sent at `command_hz`, leaving margin on long wires, and
+    /// the bulk display data of DTM1 and DTM2 at `data_hz`. `speed` changes the frequency,
+    /// usually a closure reconfiguring the SPI peripheral. The frequency is only changed when
+    /// switching between commands and display data.
+    ///
+    /// ### Example
+    ///
+    /// ```ignore
+    /// let interface = Interface::new(spi, (cs, busy, dc, reset)).spi_speeds(
+    ///     |spi: &mut Spi, hz: u32| spi.reconfigure(hz.hz()),
+    ///     1_000_000,
+    ///     8_000_000,
+    /// );
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn spi_speeds<S: SetSpeed<SPI>>(
+        self,
+        speed: S,
+        command_hz: u32,
+        data_hz: u32,
+    ) -> Interface<SPI, CS, BUSY, DC, RESET, HOLD, InterfaceOptions<WAIT, S, PROGRESS, RETRY>> {
+        Interface {
+            current_hz: 0,
+            ..self.map_options(|options| options.speed(speed, (command_hz, data_hz)))
+        }
+    }
+
+    /// Report progress while uploading display buffers.
+    ///
+    /// Buffers are sent in chunks of `chunk_size` bytes, and `progress` is called with the bytes
+    /// sent so far and the total after each one, for example to blink an LED during a long
+    /// upload.
+    ///
+    /// ### Example
+    ///
+    /// ```ignore
+    /// let interface = Interface::new(spi, (cs, busy, dc, reset))
+    ///     .upload_progress(|sent: u32, total: u32| led.toggle(), 512);
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn upload_progress<P: Progress>(
+        self,
+        progress: P,
+        chunk_size: u32,
+    ) -> Interface<SPI, CS, BUSY, DC, RESET, HOLD, InterfaceOptions<WAIT, SPEED, P, RETRY>> {
+        self.map_options(|options| options.progress(progress, chunk_size))
+    }
+
+    /// Retry SPI writes that fail, for buses with transient errors.
+    ///
+    /// Shared buses, like a PIO SPI on the RP2040, can lose arbitration. A failed write is passed
+    /// to `retry`, which can wait and return true to send it again, see
+    /// [DelayRetry](super::DelayRetry). Once the policy gives up the write fails with the error
+    /// of the policy, [RetriesExhausted](super::RetriesExhausted) for `DelayRetry`, so the
+    /// interface's error type is set by the policy. Defaults to [NoRetry], which returns the SPI
+    /// error.
+    ///
+    /// ### Example
+    ///
+    /// ```ignore
+    /// let interface = Interface::new(spi, (cs, busy, dc, reset))
+    ///     .retry_policy(DelayRetry::new(delay, 3, 1));
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn retry_policy<R: RetryPolicy<SPI::Error>>(
+        self,
+        retry: R,
+    ) -> Interface<SPI, CS, BUSY, DC, RESET, HOLD, InterfaceOptions<WAIT, SPEED, PROGRESS, R>> {
+        self.map_options(|options| options.retry(retry))
+    }
+
+    /// release the spi and pins
+    pub fn release(self) -> (SPI, (CS, BUSY, DC, RESET)) {
+        (self.spi, (self.cs, self.busy, self.dc, self.reset))
+    }
+
+    /// release the spi and pins, including the pin held high
+    pub fn release_all(self) -> (SPI, (CS, BUSY, DC, RESET), HOLD) {
+        (
+            self.spi,
+            (self.cs, self.busy, self.dc, self.reset),
+            self.hold,
+        )
+    }
+
+    // switch to the frequency for commands or display data, if it has changed
+    fn set_phase(&mut self, bulk: bool)
+    where
+        SPEED: SetSpeed<SPI>,
+    {
+        let hz = if bulk {
+            self.options.speeds.1
+        } else {
+            self.options.speeds.0
+        };
+        if hz != self.current_hz {
+            self.options.speed.set_speed(&mut self.spi, hz);
+            self.current_hz = hz;
+        }
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<(), RETRY::Error>
+    where
+        RETRY: RetryPolicy<SPI::Error>,
+    {
+        self.hold.set_high().ok();
+        self.cs.set_low().ok();
+        self.write_selected(data)?;
+
+        // Release the controller
+        self.cs.set_high().ok();
+
+        Ok(())
+    }
+
+    // write with the controller already selected
+    fn write_selected(&mut self, data: &[u8]) -> Result<(), RETRY::Error>
+    where
+        RETRY: RetryPolicy<SPI::Error>,
+    {
+        // Linux has a default limit of 4096 bytes per SPI transfer
+        // https://github.com/torvalds/linux/blob/ccda4af0f4b92f7b4c308d3acc262f4a7e3affad/drivers/spi/spidev.c#L93
+        if cfg!(target_os = "linux") {
+            for data_chunk in data.chunks(4096) {
+                self.write_retrying(data_chunk)?;
+            }
+        } else {
+            self.write_retrying(data)?;
+        }
+        Ok(())
+    }
+
+    // write, retrying as long as the policy allows
+    fn write_retrying(&mut self, data: &[u8]) -> Result<(), RETRY::Error>
+    where
+        RETRY: RetryPolicy<SPI::Error>,
+    {
+        self.options.retry.start();
+        loop {
+            match self.spi.write(data) {
+                Ok(()) => return Ok(()),
+                Err(e) if self.options.retry.retry(&e) => {
+                    log_debug!("retrying spi write");
+                }
+                Err(e) => return Err(self.options.retry.give_up(e)),
+            }
+        }
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RESET, HOLD, OPTIONS> SpiOwner
+    for Interface<SPI, CS, BUSY, DC, RESET, HOLD, OPTIONS>
+{
+    type Spi = SPI;
+
+    fn spi(&mut self) -> &mut SPI {
+        &mut self.spi
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RESET, HOLD, WAIT, SPEED, PROGRESS, RETRY> DisplayInterface
+    for Interface<SPI, CS, BUSY, DC, RESET, HOLD, InterfaceOptions<WAIT, SPEED, PROGRESS, RETRY>>
+where
+    SPI: hal::blocking::spi::Write<u8>,
+    CS: hal::digital::v2::OutputPin,
+    CS::Error: Debug,
+    BUSY: hal::digital::v2::InputPin,
+    DC: hal::digital::v2::OutputPin,
+    DC::Error: Debug,
+    RESET: hal::digital::v2::OutputPin,
+    RESET::Error: Debug,
+    HOLD: hal::digital::v2::OutputPin,
+    WAIT: WaitStrategy,
+    SPEED: SetSpeed<SPI>,
+    PROGRESS: Progress,
+    RETRY: RetryPolicy<SPI::Error>,
+{
+    type Error = RETRY::Error;
+
+    fn reset<D: hal::blocking::delay::DelayMs<u8>>(&mut self, delay: &mut D) {
+        hardware_reset(&mut self.reset, delay);
+    }
+
+    fn send_command(&mut self, command: u8) -> Result<(), Self::Error> {
+        log_trace!("command {:#04x}", command);
+        self.set_phase(false);
+        self.bulk = is_bulk(command);
+        self.dc.set_low().unwrap();
+        self.write(&[command])?;
+        self.dc.set_high().unwrap();
+        Ok(())
+    }
+
+    fn send_data(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        self.set_phase(self.bulk);
+        self.dc.set_high().unwrap();
+        self.write(data)
+    }
+
+    fn send_batch<const N: usize>(&mut self, batch: &CommandBatch<N>) -> Result<(), Self::Error> {
+        self.hold.set_high().ok();
+        self.cs.set_low().ok();
+        let mut result = Ok(());
+        for (command, data) in batch.iter() {
+            log_trace!("command {:#04x}", command);
+            self.set_phase(false);
+            self.dc.set_low().unwrap();
+            result = self.write_selected(&[command]);
+            self.dc.set_high().unwrap();
+            if result.is_ok() && !data.is_empty() {
+                self.set_phase(is_bulk(command));
+                result = self.write_selected(data);
+            }
+            if result.is_err() {
+                break;
+            }
+        }
+        self.bulk = false;
+        // Release the controller
+        self.cs.set_high().ok();
+        result
+    }
+
+    fn epd_update_data(
+        &mut self,
+        layer: u8,
+        nbytes: u16,
+        buf: &[u8],
+    ) -> Result<(), Error<Self::Error>> {
+        let sz: usize = nbytes.into();
+        if sz != buf.len() {
+            return Err(Error::BufferSize {
+                expected: sz,
+                actual: buf.len(),
+            });
+        }
+        if self.options.chunk_size == 0 {
+            return if layer == 0 {
+                BufCommand::WriteBlackData(buf).execute(self)
+            } else {
+                BufCommand::WriteRedData(buf).execute(self)
+            }
+            .map_err(Error::Interface);
+        }
+        self.send_command(regs::data_transmission(layer))
+            .map_err(Error::Interface)?;
+        let mut sent = 0;
+        for chunk in buf.chunks(self.options.chunk_size as usize) {
+            self.send_data(chunk).map_err(Error::Interface)?;
+            sent += chunk.len() as u32;
+            self.options.progress.progress(sent, buf.len() as u32);
+        }
+        Ok(())
+    }
+
+    fn busy_wait(&mut self) {
+        self.options.wait.start();
+        while self.busy.is_high().unwrap_or_default() {
+            self.options.wait.wait();
+        }
+    }
+
+    fn busy_wait_for(&mut self, operation: Operation) {
+        if !self.options.wait.fixed_wait(operation) {
+            self.busy_wait()
+        }
+    }
+
+    fn is_busy(&mut self) -> Option<bool> {
+        if self.options.wait.is_fixed() {
+            return None;
+        }
+        self.busy.is_high().ok()
+    }
+}
+
+//const MCPSRAM_RDSR: u8 = 0x05;
+
+// the command is followed by display data rather than parameters
+fn is_bulk(command: u8) -> bool {
+    command == regs::DTM1 || command == regs::DTM2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::{DelayRetry, RetriesExhausted};
+    use crate::test_support::{MockPin, MockSpi, RecordingDelay};
+    use core::cell::Cell;
+    use std::vec::Vec;
+
+    #[test]
+    fn epd_update_data_size_mismatch() {
+        let mut interface =
+            Interface::new(MockSpi::default(), (MockPin, MockPin, MockPin, MockPin));
+        let buf = [0u8; 3];
+
+        assert_eq!(
+            interface.epd_update_data(0, 4, &buf),
+            Err(Error::BufferSize {
+                expected: 4,
+                actual: 3
+            })
+        );
+        assert_eq!(
+            interface.epd_update_data(1, 2, &buf),
+            Err(Error::BufferSize {
+                expected: 2,
+                actual: 3
+            })
+        );
+        assert!(interface.release().0.written.is_empty());
+
+        interface = Interface::new(MockSpi::default(), (MockPin, MockPin, MockPin, MockPin));
+        assert_eq!(interface.epd_update_data(1, 3, &buf), Ok(()));
+        assert_eq!(interface.release().0.written, [0x13, 0, 0, 0]);
+    }
+
+    #[test]
+    fn spi_speeds() {
+        let mut speeds = Vec::new();
+        let mut interface =
+            Interface::new(MockSpi::default(), (MockPin, MockPin, MockPin, MockPin)).spi_speeds(
+                |_: &mut MockSpi, hz: u32| speeds.push(hz),
+                1_000_000,
+                8_000_000,
+            );
+        interface.send_command(0x10).unwrap();
+        interface.send_data(&[0; 4]).unwrap();
+        interface.send_data(&[0; 4]).unwrap();
+        // parameters are sent at the command frequency
+        interface.send_command(0x01).unwrap();
+        interface.send_data(&[0x03, 0x00]).unwrap();
+        interface.send_command(0x13).unwrap();
+        interface.send_data(&[0; 4]).unwrap();
+        drop(interface);
+        assert_eq!(speeds, [1_000_000, 8_000_000, 1_000_000, 8_000_000]);
+    }
+
+    #[test]
+    fn retry_policy() {
+        let spi = MockSpi {
+            failures: 3,
+            ..MockSpi::default()
+        };
+        let mut delays = Vec::new();
+        let mut interface = Interface::new(spi, (MockPin, MockPin, MockPin, MockPin))
+            .retry_policy(DelayRetry::new(RecordingDelay(&mut delays), 3, 2));
+        interface.send_command(0x12).unwrap();
+        interface.spi.failures = 4;
+        assert_eq!(
+            interface.send_data(&[0x01]),
+            Err(RetriesExhausted {
+                error: (),
+                attempts: 4
+            })
+        );
+        assert_eq!(interface.release().0.written, [0x12]);
+        assert_eq!(delays, [2, 4, 8, 2, 4, 8]);
+
+        let spi = MockSpi {
+            failures: 1,
+            ..MockSpi::default()
+        };
+        let mut interface = Interface::new(spi, (MockPin, MockPin, MockPin, MockPin));
+        assert_eq!(interface.send_command(0x12), Err(()));
+    }
+
+    #[test]
+    fn upload_progress() {
+        let mut reports = Vec::new();
+        let mut interface =
+            Interface::new(MockSpi::default(), (MockPin, MockPin, MockPin, MockPin))
+                .upload_progress(|sent: u32, total: u32| reports.push((sent, total)), 4);
+        let data: Vec<u8> = (0..10).collect();
+        interface.epd_update_data(1, 10, &data).unwrap();
+        let written = interface.release().0.written;
+        assert_eq!(written[0], 0x13);
+        assert_eq!(written[1..], data[..]);
+        assert_eq!(reports, [(4, 10), (8, 10), (10, 10)]);
+    }
+
+    // an output pin counting how often it is driven low
+    struct CountingPin<'a>(&'a Cell<u32>);
+
+    impl<'a> hal::digital::v2::OutputPin for CountingPin<'a> {
+        type Error = ();
+
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            self.0.set(self.0.get() + 1);
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn send_batch_selects_once() {
+        let (selects, commands) = (Cell::new(0), Cell::new(0));
+        let pins = (
+            CountingPin(&selects),
+            MockPin,
+            CountingPin(&commands),
+            MockPin,
+        );
+        let mut interface = Interface::new(MockSpi::default(), pins);
+        let mut batch = CommandBatch::<16>::new();
+        batch
+            .push_raw(0x00, &[0xCF])
+            .push_raw(0x50, &[0x37])
+            .push_raw(0x04, &[]);
+        interface.send_batch(&batch).unwrap();
+        assert_eq!((selects.get(), commands.get()), (1, 3));
+        assert_eq!(
+            interface.release().0.written,
+            [0x00, 0xCF, 0x50, 0x37, 0x04]
+        );
+    }
+}
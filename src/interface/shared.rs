@@ -0,0 +1,325 @@
+use super::DisplayInterface;
+#[cfg(feature = "sram")]
+use super::{SramAddr, SramBacked};
+#[cfg(feature = "dual-spi")]
+use crate::command::BusWidth;
+use crate::command::CommandBatch;
+use crate::error::Error;
+use crate::wait::Operation;
+use core::cell::RefCell;
+
+/// A mutex around a display interface whose SPI bus is shared with other devices, such as the
+/// SD card on the Adafruit eInk FeatherWing.
+///
+/// This is the same shape as the `BusMutex` of the shared-bus crate, so a mutex of an RTOS or
+/// one built on a critical section can implement it in a few lines. `RefCell` implements it for
+/// devices used from a single context, such as a super-loop.
+pub trait BusMutex {
+    /// The interface held by the mutex.
+    type Bus;
+
+    /// Lock the mutex and call `f` with the interface.
+    fn lock<R, F: FnOnce(&mut Self::Bus) -> R>(&self, f: F) -> R;
+}
+
+impl<T> BusMutex for RefCell<T> {
+    type Bus = T;
+
+    /// Panics if the interface is already borrowed, by a call from inside `f`.
+    fn lock<R, F: FnOnce(&mut T) -> R>(&self, f: F) -> R {
+        f(&mut self.borrow_mut())
+    }
+}
+
+/// An interface or bus that owns an SPI bus, which a [SharedSpi] lends to other devices.
+pub trait SpiOwner {
+    /// The SPI bus.
+    type Spi;
+
+    /// Returns the SPI bus, with every chip select of the owner high.
+    fn spi(&mut self) -> &mut Self::Spi;
+}
+
+/// A display interface in a [BusMutex], used by a [Display](../display/struct.Display.html) in
+/// place of the interface.
+///
+/// Every call locks the mutex for the whole transaction, such as a command with its data or a
+/// move from the SRAM, so the chip select of the display is only low while the mutex is held. A
+/// device using the bus through a [SharedSpi] waits for the transaction to finish, even from an
+/// interrupt or a task that preempts the display. Busy waits use the wait strategy of the
+/// interface with the mutex held, so a long refresh keeps other devices off the bus, use
+/// [Display::wait_for_refresh](../display/struct.Display.html#method.wait_for_refresh) to lock
+/// the mutex only for each poll of the busy pin.
+///
+/// ### Example
+///
+/// ```ignore
+/// let bus = RefCell::new(SramDisplayInterface::new(sram_bus, (busy, dc, reset)));
+/// let display = Display::new(SharedInterface::new(&bus), config);
+/// let sd_card = SdCard::new(SharedSpi::new(&bus), sd_cs, delay);
+/// ```
+pub struct SharedInterface<'a, M> {
+    mutex: &'a M,
+}
+
+impl<'a, M: BusMutex> SharedInterface<'a, M> {
+    /// Create a handle to the interface in `mutex`.
+    pub fn new(mutex: &'a M) -> Self {
+        SharedInterface { mutex }
+    }
+}
+
+impl<'a, M> DisplayInterface for SharedInterface<'a, M>
+where
+    M: BusMutex,
+    M::Bus: DisplayInterface,
+{
+    type Error = <M::Bus as DisplayInterface>::Error;
+
+    fn send_command(&mut self, command: u8) -> Result<(), Self::Error> {
+        self.mutex.lock(|bus| bus.send_command(command))
+    }
+
+    fn send_data(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        self.mutex.lock(|bus| bus.send_data(data))
+    }
+
+    fn send_batch<const N: usize>(&mut self, batch: &CommandBatch<N>) -> Result<(), Self::Error> {
+        self.mutex.lock(|bus| bus.send_batch(batch))
+    }
+
+    fn reset<D: hal::blocking::delay::DelayMs<u8>>(&mut self, delay: &mut D) {
+        self.mutex.lock(|bus| bus.reset(delay))
+    }
+
+    fn busy_wait(&mut self) {
+        self.mutex.lock(|bus| bus.busy_wait())
+    }
+
+    fn busy_wait_for(&mut self, operation: Operation) {
+        self.mutex.lock(|bus| bus.busy_wait_for(operation))
+    }
+
+    fn is_busy(&mut self) -> Option<bool> {
+        self.mutex.lock(|bus| bus.is_busy())
+    }
+
+    #[cfg(feature = "dual-spi")]
+    fn bus_width(&self) -> BusWidth {
+        self.mutex.lock(|bus| bus.bus_width())
+    }
+
+    fn epd_update_data(
+        &mut self,
+        layer: u8,
+        nbytes: u16,
+        buf: &[u8],
+    ) -> Result<(), Error<Self::Error>> {
+        self.mutex
+            .lock(|bus| bus.epd_update_data(layer, nbytes, buf))
+    }
+}
+
+#[cfg(feature = "sram")]
+impl<'a, M> SramBacked for SharedInterface<'a, M>
+where
+    M: BusMutex,
+    M::Bus: SramBacked,
+{
+    fn sram_epd_update_data(
+        &mut self,
+        layer: u8,
+        nbytes: u32,
+        start_address: SramAddr,
+    ) -> Result<(), Error<Self::Error>> {
+        self.mutex
+            .lock(|bus| bus.sram_epd_update_data(layer, nbytes, start_address))
+    }
+
+    fn sram_read(&mut self, address: SramAddr, data: &mut [u8]) -> Result<(), Error<Self::Error>> {
+        self.mutex.lock(|bus| bus.sram_read(address, data))
+    }
+
+    fn sram_write(&mut self, address: SramAddr, data: &[u8]) -> Result<(), Error<Self::Error>> {
+        self.mutex.lock(|bus| bus.sram_write(address, data))
+    }
+
+    fn sram_clear(
+        &mut self,
+        address: SramAddr,
+        nbytes: u32,
+        val: u8,
+    ) -> Result<(), Error<Self::Error>> {
+        self.mutex.lock(|bus| bus.sram_clear(address, nbytes, val))
+    }
+
+    fn sram_capacity(&self) -> u32 {
+        self.mutex.lock(|bus| bus.sram_capacity())
+    }
+}
+
+/// A handle to the SPI bus of a display interface in a [BusMutex], used in place of the bus by
+/// another device.
+///
+/// Each transfer locks the mutex, so it never lands inside a transaction of the display. The
+/// chip select of the other device must be driven high while the display uses the bus, which
+/// drivers such as embedded-sdmmc do between their own transactions.
+pub struct SharedSpi<'a, M> {
+    mutex: &'a M,
+}
+
+impl<'a, M: BusMutex> SharedSpi<'a, M> {
+    /// Create a handle to the bus of the interface in `mutex`, any number can be created.
+    pub fn new(mutex: &'a M) -> Self {
+        SharedSpi { mutex }
+    }
+}
+
+impl<'a, M> Clone for SharedSpi<'a, M> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, M> Copy for SharedSpi<'a, M> {}
+
+impl<'a, M> hal::blocking::spi::Transfer<u8> for SharedSpi<'a, M>
+where
+    M: BusMutex,
+    M::Bus: SpiOwner,
+    <M::Bus as SpiOwner>::Spi: hal::blocking::spi::Transfer<u8>,
+{
+    type Error = <<M::Bus as SpiOwner>::Spi as hal::blocking::spi::Transfer<u8>>::Error;
+
+    fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Self::Error> {
+        self.mutex
+            .lock(|bus| bus.spi().transfer(&mut *words).map(|_| ()))?;
+        Ok(words)
+    }
+}
+
+impl<'a, M> hal::blocking::spi::Write<u8> for SharedSpi<'a, M>
+where
+    M: BusMutex,
+    M::Bus: SpiOwner,
+    <M::Bus as SpiOwner>::Spi: hal::blocking::spi::Write<u8>,
+{
+    type Error = <<M::Bus as SpiOwner>::Spi as hal::blocking::spi::Write<u8>>::Error;
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        self.mutex.lock(|bus| bus.spi().write(words))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::{Interface, NoPin};
+    use crate::test_support::{MockPin, MockSpi, RecordingDelay};
+    use crate::wait::{BusyDelays, TimedWait};
+    use core::cell::Cell;
+    use hal::blocking::spi::Write;
+    use std::vec::Vec;
+
+    // a mutex counting how often it is locked
+    struct CountingMutex<T> {
+        bus: RefCell<T>,
+        locks: Cell<u32>,
+    }
+
+    impl<T> BusMutex for CountingMutex<T> {
+        type Bus = T;
+
+        fn lock<R, F: FnOnce(&mut T) -> R>(&self, f: F) -> R {
+            self.locks.set(self.locks.get() + 1);
+            f(&mut self.bus.borrow_mut())
+        }
+    }
+
+    #[test]
+    fn handles_share_the_bus() {
+        let bus = RefCell::new(Interface::new(
+            MockSpi::default(),
+            (MockPin, MockPin, MockPin, MockPin),
+        ));
+        let mut interface = SharedInterface::new(&bus);
+        let mut other = SharedSpi::new(&bus);
+        interface.send_command(0x12).unwrap();
+        other.write(&[0xAA, 0xBB]).unwrap();
+        interface.send_data(&[0x01]).unwrap();
+        assert_eq!(bus.borrow_mut().spi().written, [0x12, 0xAA, 0xBB, 0x01]);
+    }
+
+    #[test]
+    fn transaction_holds_the_lock() {
+        let mutex = CountingMutex {
+            bus: RefCell::new(Interface::new(
+                MockSpi::default(),
+                (MockPin, MockPin, MockPin, MockPin),
+            )),
+            locks: Cell::new(0),
+        };
+        let mut interface = SharedInterface::new(&mutex);
+        let mut other = SharedSpi::new(&mutex);
+        let mut batch = CommandBatch::<16>::new();
+        batch.push_raw(0x01, &[0x03, 0x00]).push_raw(0x06, &[0x17]);
+        // the whole batch is sent in one lock, the other device only gets in after it
+        interface.send_batch(&batch).unwrap();
+        other.write(&[0xAA]).unwrap();
+        interface.send_command(0x04).unwrap();
+        assert_eq!(mutex.locks.get(), 3);
+        assert_eq!(
+            mutex.bus.borrow_mut().spi().written,
+            [0x01, 0x03, 0x00, 0x06, 0x17, 0xAA, 0x04]
+        );
+    }
+
+    #[test]
+    fn busy_waits_use_the_wait_strategy() {
+        fn waits<I: DisplayInterface>(interface: &mut I) {
+            interface.busy_wait_for(Operation::PowerOn);
+            interface.busy_wait_for(Operation::Refresh);
+            interface.busy_wait_for(Operation::PowerOff);
+        }
+
+        let (mut shared, mut unshared) = (Vec::new(), Vec::new());
+        let wait = TimedWait::new(RecordingDelay(&mut shared), BusyDelays::default());
+        let bus = RefCell::new(
+            Interface::new(MockSpi::default(), (MockPin, NoPin, MockPin, MockPin))
+                .wait_strategy(wait),
+        );
+        waits(&mut SharedInterface::new(&bus));
+        drop(bus);
+        let wait = TimedWait::new(RecordingDelay(&mut unshared), BusyDelays::default());
+        waits(
+            &mut Interface::new(MockSpi::default(), (MockPin, NoPin, MockPin, MockPin))
+                .wait_strategy(wait),
+        );
+        assert_eq!(shared, unshared);
+        let waited: u32 = shared.iter().map(|ms| u32::from(*ms)).sum();
+        assert_eq!(waited, 100 + 20_000 + 100);
+    }
+
+    #[cfg(feature = "sram")]
+    #[test]
+    fn sram_move_holds_the_lock() {
+        use crate::interface::{SpiSramBus, SramDisplayInterface};
+        use hal::blocking::spi::Transfer;
+
+        let bus = SpiSramBus::new(MockSpi::default(), (MockPin, MockPin));
+        let mutex = CountingMutex {
+            bus: RefCell::new(SramDisplayInterface::new(bus, (MockPin, MockPin, MockPin))),
+            locks: Cell::new(0),
+        };
+        let mut interface = SharedInterface::new(&mutex);
+        let mut other = SharedSpi::new(&mutex);
+        interface
+            .sram_epd_update_data(0, 4, SramAddr::new(0x10))
+            .unwrap();
+        other.transfer(&mut [0xAA]).unwrap();
+        assert_eq!(mutex.locks.get(), 2);
+        let written = core::mem::take(&mut mutex.bus.borrow_mut().spi().written);
+        assert_eq!(written.last(), Some(&0xAA));
+        assert_eq!(written.len(), 1 + 3 + 4 + 1);
+    }
+}
@@ -0,0 +1,1006 @@
+use super::{
+    hardware_reset, DisplayInterface, NoPin, NoProgress, NoRetry, Progress, RetryPolicy, SpiOwner,
+    SpiSramBus,
+};
+use crate::command::BufCommand;
+use crate::error::Error;
+use crate::regs;
+use crate::wait::{Operation, SpinWait, WaitStrategy};
+use core::fmt::Debug;
+
+/// Implemented by interfaces that keep the display buffers in an SRAM device on the bus.
+///
+/// Only interfaces with an SRAM implement this, so SRAM backed buffers can't be used with a
+/// RAM-only interface such as [Interface](super::Interface).
+pub trait SramBacked: DisplayInterface {
+    /// copy display buffer data to epd from sram
+    ///
+    /// Returns `Error::BufferSize` if the transfer doesn't fit in the sram device.
+    fn sram_epd_update_data(
+        &mut self,
+        layer: u8,
+        nbytes: u32,
+        start_address: SramAddr,
+    ) -> Result<(), Error<Self::Error>>;
+
+    /// read data from sram
+    ///
+    /// Returns `Error::BufferSize` if the data doesn't fit in the sram device.
+    fn sram_read(&mut self, address: SramAddr, data: &mut [u8]) -> Result<(), Error<Self::Error>>;
+
+    /// write data to sram
+    ///
+    /// Returns `Error::BufferSize` if the data doesn't fit in the sram device.
+    fn sram_write(&mut self, address: SramAddr, data: &[u8]) -> Result<(), Error<Self::Error>>;
+
+    /// set area in sram to a value
+    ///
+    /// Returns `Error::BufferSize` if the area doesn't fit in the sram device.
+    fn sram_clear(
+        &mut self,
+        address: SramAddr,
+        nbytes: u32,
+        val: u8,
+    ) -> Result<(), Error<Self::Error>>;
+
+    /// capacity of the sram in bytes
+    fn sram_capacity(&self) -> u32;
+}
+
+impl<T> SramBacked for &mut T
+where
+    T: SramBacked + ?Sized,
+{
+    fn sram_epd_update_data(
+        &mut self,
+        layer: u8,
+        nbytes: u32,
+        start_address: SramAddr,
+    ) -> Result<(), Error<Self::Error>> {
+        (**self).sram_epd_update_data(layer, nbytes, start_address)
+    }
+
+    fn sram_read(&mut self, address: SramAddr, data: &mut [u8]) -> Result<(), Error<Self::Error>> {
+        (**self).sram_read(address, data)
+    }
+
+    fn sram_write(&mut self, address: SramAddr, data: &[u8]) -> Result<(), Error<Self::Error>> {
+        (**self).sram_write(address, data)
+    }
+
+    fn sram_clear(
+        &mut self,
+        address: SramAddr,
+        nbytes: u32,
+        val: u8,
+    ) -> Result<(), Error<Self::Error>> {
+        (**self).sram_clear(address, nbytes, val)
+    }
+
+    fn sram_capacity(&self) -> u32 {
+        (**self).sram_capacity()
+    }
+}
+
+/// An address in the SRAM device.
+///
+/// Addresses of display buffers and storage are taken from a [SramRegion], so an offset can't be
+/// added to the wrong base address by hand.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SramAddr(u32);
+
+impl SramAddr {
+    /// The first address of the device.
+    pub const ZERO: SramAddr = SramAddr(0);
+
+    /// Create an address.
+    pub const fn new(address: u32) -> Self {
+        SramAddr(address)
+    }
+
+    /// Returns the address as a number.
+    pub const fn get(self) -> u32 {
+        self.0
+    }
+
+    /// Returns true if `len` bytes starting at the address fit in a device of `capacity` bytes.
+    pub fn fits(self, len: u32, capacity: u32) -> bool {
+        self.0.checked_add(len).is_some_and(|end| end <= capacity)
+    }
+}
+
+/// A range of the SRAM device, such as one plane of the display buffers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SramRegion {
+    start: SramAddr,
+    len: u32,
+}
+
+impl SramRegion {
+    /// Create a region of `len` bytes, starting at `start`.
+    pub const fn new(start: SramAddr, len: u32) -> Self {
+        SramRegion { start, len }
+    }
+
+    /// Returns the first address of the region.
+    pub const fn start(&self) -> SramAddr {
+        self.start
+    }
+
+    /// Returns the length of the region in bytes.
+    pub const fn len(&self) -> u32 {
+        self.len
+    }
+
+    /// Returns true if the region is empty.
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the first address after the region.
+    pub const fn end(&self) -> SramAddr {
+        SramAddr(self.start.0 + self.len)
+    }
+
+    /// Returns the address `offset` bytes into the region, if `nbytes` from there fit in it.
+    pub fn address(&self, offset: u32, nbytes: u32) -> Option<SramAddr> {
+        match offset.checked_add(nbytes) {
+            Some(end) if end <= self.len => Some(SramAddr(self.start.0 + offset)),
+            _ => None,
+        }
+    }
+}
+
+/// A region of the sram that isn't used for display buffers, for use as general storage.
+///
+/// Borrowed with [SramGraphicDisplay::storage](../graphics/struct.SramGraphicDisplay.html#method.storage),
+/// which starts the region after the display buffers, or [SramDisplayInterface::storage].
+///
+/// Offsets are relative to the start of the region. Accesses outside the region return
+/// `Error::BufferSize`.
+pub struct SramHandle<'a, I>
+where
+    I: SramBacked,
+{
+    interface: &'a mut I,
+    region: SramRegion,
+}
+
+impl<'a, I> SramHandle<'a, I>
+where
+    I: SramBacked,
+{
+    // a handle to len bytes of the sram from start, which the caller has checked is free
+    pub(crate) fn new(interface: &'a mut I, start: SramAddr, len: u32) -> Self {
+        SramHandle {
+            interface,
+            region: SramRegion::new(start, len),
+        }
+    }
+
+    /// Returns the length of the region in bytes.
+    pub fn len(&self) -> u32 {
+        self.region.len()
+    }
+
+    /// Returns true if the region is empty.
+    pub fn is_empty(&self) -> bool {
+        self.region.is_empty()
+    }
+
+    // returns the sram address of offset, if nbytes at offset fit in the region
+    fn address(&self, offset: u32, nbytes: usize) -> Result<SramAddr, Error<I::Error>> {
+        self.region
+            .address(offset, nbytes as u32)
+            .ok_or(Error::BufferSize {
+                expected: nbytes,
+                actual: self.region.len().saturating_sub(offset) as usize,
+            })
+    }
+
+    /// read data from the region
+    pub fn read(&mut self, offset: u32, data: &mut [u8]) -> Result<(), Error<I::Error>> {
+        let address = self.address(offset, data.len())?;
+        self.interface.sram_read(address, data)
+    }
+
+    /// write data to the region
+    pub fn write(&mut self, offset: u32, data: &[u8]) -> Result<(), Error<I::Error>> {
+        let address = self.address(offset, data.len())?;
+        self.interface.sram_write(address, data)
+    }
+
+    /// set an area of the region to a value
+    pub fn erase(&mut self, offset: u32, nbytes: u32, val: u8) -> Result<(), Error<I::Error>> {
+        let address = self.address(offset, nbytes as usize)?;
+        self.interface.sram_clear(address, nbytes, val)
+    }
+}
+
+/// A copy of a display buffer from the sram to the controller, made a chunk at a time.
+///
+/// Created by [SramDisplayInterface::sram_transfer]. Unlike
+/// [SramBacked::sram_epd_update_data], which moves the whole buffer in one long transaction, an
+/// application can do other work between chunks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SramTransfer {
+    region: SramRegion,
+    sent: u32,
+}
+
+impl SramTransfer {
+    /// Returns the number of bytes copied so far.
+    pub fn sent(&self) -> u32 {
+        self.sent
+    }
+
+    /// Returns the number of bytes left to copy.
+    pub fn remaining(&self) -> u32 {
+        self.region.len() - self.sent
+    }
+
+    /// Returns true once every byte has been copied.
+    pub fn is_done(&self) -> bool {
+        self.remaining() == 0
+    }
+}
+
+// bytes read, written or cleared between yields by the async sram functions
+#[cfg(feature = "async")]
+const ASYNC_CHUNK: usize = 256;
+
+// a future that is pending once, so the executor can run other tasks
+#[cfg(feature = "async")]
+struct YieldNow(bool);
+
+#[cfg(feature = "async")]
+impl core::future::Future for YieldNow {
+    type Output = ();
+
+    fn poll(
+        mut self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+    ) -> core::task::Poll<()> {
+        if self.0 {
+            core::task::Poll::Ready(())
+        } else {
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            core::task::Poll::Pending
+        }
+    }
+}
+
+pub struct SramDisplayInterface<
+    SPI,
+    EPDCS,
+    SRAMCS,
+    BUSY,
+    DC,
+    RESET,
+    HOLD = NoPin,
+    WAIT = SpinWait,
+    PROGRESS = NoProgress,
+    RETRY = NoRetry,
+> {
+    spi_bus: SpiSramBus<SPI, EPDCS, SRAMCS, HOLD, RETRY>,
+    busy: BUSY,
+    dc: DC,
+    reset: RESET,
+    wait: WAIT,
+    progress: PROGRESS,
+    chunk_size: u32,
+}
+
+impl<SPI, EPDCS, SRAMCS, BUSY, DC, RESET, HOLD, RETRY>
+    SramDisplayInterface<SPI, EPDCS, SRAMCS, BUSY, DC, RESET, HOLD, SpinWait, NoProgress, RETRY>
+where
+    SPI: hal::blocking::spi::Transfer<u8>,
+    EPDCS: hal::digital::v2::OutputPin,
+    SRAMCS: hal::digital::v2::OutputPin,
+    BUSY: hal::digital::v2::InputPin,
+    DC: hal::digital::v2::OutputPin,
+    RESET: hal::digital::v2::OutputPin,
+    HOLD: hal::digital::v2::OutputPin,
+    RETRY: RetryPolicy<SPI::Error>,
+{
+    /// create a display interface from the embedded hal
+    ///
+    /// Failed SPI transfers are retried by the bus, see [SpiSramBus::retry_policy].
+    pub fn new(
+        spi_bus: SpiSramBus<SPI, EPDCS, SRAMCS, HOLD, RETRY>,
+        mut pins: (BUSY, DC, RESET),
+    ) -> Self {
+        // dc inactive low
+        pins.1.set_low().ok();
+        // reset inactive high
+        pins.2.set_high().ok();
+        SramDisplayInterface {
+            spi_bus,
+            busy: pins.0,
+            dc: pins.1,
+            reset: pins.2,
+            wait: SpinWait,
+            progress: NoProgress,
+            chunk_size: 0,
+        }
+    }
+}
+
+impl<SPI, EPDCS, SRAMCS, BUSY, DC, RESET, HOLD, WAIT, PROGRESS, RETRY>
+    SramDisplayInterface<SPI, EPDCS, SRAMCS, BUSY, DC, RESET, HOLD, WAIT, PROGRESS, RETRY>
+where
+    SPI: hal::blocking::spi::Transfer<u8>,
+    EPDCS: hal::digital::v2::OutputPin,
+    SRAMCS: hal::digital::v2::OutputPin,
+    BUSY: hal::digital::v2::InputPin,
+    DC: hal::digital::v2::OutputPin,
+    RESET: hal::digital::v2::OutputPin,
+    HOLD: hal::digital::v2::OutputPin,
+    RETRY: RetryPolicy<SPI::Error>,
+{
+    /// Set how to wait while the controller is busy.
+    ///
+    /// Defaults to [SpinWait](../wait/struct.SpinWait.html).
+    pub fn wait_strategy<W: WaitStrategy>(
+        self,
+        wait: W,
+    ) -> SramDisplayInterface<SPI, EPDCS, SRAMCS, BUSY, DC, RESET, HOLD, W, PROGRESS, RETRY> {
+        SramDisplayInterface {
+            spi_bus: self.spi_bus,
+            busy: self.busy,
+            dc: self.dc,
+            reset: self.reset,
+            wait,
+            progress: self.progress,
+            chunk_size: self.chunk_size,
+        }
+    }
+
+    /// Report progress while moving display buffers from the sram to the controller.
+    ///
+    /// `progress` is called with the bytes sent so far and the total every `chunk_size` bytes,
+    /// see [Interface::upload_progress](super::Interface::upload_progress).
+    pub fn upload_progress<P: Progress>(
+        self,
+        progress: P,
+        chunk_size: u32,
+    ) -> SramDisplayInterface<SPI, EPDCS, SRAMCS, BUSY, DC, RESET, HOLD, WAIT, P, RETRY> {
+        SramDisplayInterface {
+            spi_bus: self.spi_bus,
+            busy: self.busy,
+            dc: self.dc,
+            reset: self.reset,
+            wait: self.wait,
+            progress,
+            chunk_size,
+        }
+    }
+
+    /// Borrow the sram from `start` to the end of the device as general storage.
+    ///
+    /// `start` must be after the display buffers, see
+    /// [SramGraphicDisplay::storage](../graphics/struct.SramGraphicDisplay.html#method.storage).
+    pub fn storage(&mut self, start: SramAddr) -> SramHandle<'_, Self>
+    where
+        RESET::Error: Debug,
+        WAIT: WaitStrategy,
+        PROGRESS: Progress,
+    {
+        let len = self.spi_bus.size().bytes().saturating_sub(start.get());
+        SramHandle::new(self, start, len)
+    }
+
+    /// Start copying a display buffer from the sram to the controller a chunk at a time.
+    ///
+    /// Sends the data command for `layer` and returns the transfer, which is advanced with
+    /// [SramDisplayInterface::sram_transfer_step] until it is done. No other command may be sent
+    /// until then. Returns `Error::BufferSize` if the buffer doesn't fit in the sram device.
+    ///
+    /// ### Example
+    ///
+    /// ```ignore
+    /// let mut buf = [0u8; 256];
+    /// let mut transfer = interface.sram_transfer(0, nbytes, start)?;
+    /// while !transfer.is_done() {
+    ///     interface.sram_transfer_step(&mut transfer, &mut buf)?;
+    ///     // let other tasks run, for example embassy_futures::yield_now().await
+    /// }
+    /// ```
+    pub fn sram_transfer(
+        &mut self,
+        layer: u8,
+        nbytes: u32,
+        start: SramAddr,
+    ) -> Result<SramTransfer, Error<RETRY::Error>> {
+        let capacity = self.spi_bus.size().bytes();
+        if !start.fits(nbytes, capacity) {
+            return Err(Error::BufferSize {
+                expected: nbytes as usize,
+                actual: capacity.saturating_sub(start.get()) as usize,
+            });
+        }
+        self.dc.set_low().ok();
+        self.spi_bus
+            .epd_write(&[regs::data_transmission(layer)])
+            .map_err(Error::Interface)?;
+        self.dc.set_high().ok();
+        Ok(SramTransfer {
+            region: SramRegion::new(start, nbytes),
+            sent: 0,
+        })
+    }
+
+    /// Copy the next chunk of a transfer, up to the length of `buf`, returning its length.
+    ///
+    /// The chunk is read from the sram into `buf` then written to the controller, as two plain
+    /// transactions that suit DMA. Progress is reported after every chunk.
+    pub fn sram_transfer_step(
+        &mut self,
+        transfer: &mut SramTransfer,
+        buf: &mut [u8],
+    ) -> Result<u32, Error<RETRY::Error>>
+    where
+        PROGRESS: Progress,
+    {
+        let len = transfer.remaining().min(buf.len() as u32);
+        let address = match transfer.region.address(transfer.sent, len) {
+            Some(address) if len > 0 => address,
+            _ => return Ok(0),
+        };
+        let chunk = &mut buf[..len as usize];
+        self.spi_bus.sram_read(address, chunk)?;
+        self.dc.set_high().ok();
+        self.spi_bus.epd_write(chunk).map_err(Error::Interface)?;
+        transfer.sent += len;
+        self.progress.progress(transfer.sent, transfer.region.len());
+        Ok(len)
+    }
+
+    /// Copy a display buffer from the sram to the controller, letting other tasks run between
+    /// chunks.
+    ///
+    /// Starts a [SramDisplayInterface::sram_transfer] and copies it a chunk of up to `buf.len()`
+    /// bytes at a time, yielding to the executor after each. No other command may be sent until
+    /// the future completes. Returns `Error::BufferSize` if the buffer doesn't fit in the sram
+    /// device or `buf` is empty.
+    ///
+    /// Only available with the `async` feature.
+    ///
+    /// ### Example
+    ///
+    /// ```ignore
+    /// let mut buf = [0u8; 256];
+    /// interface.sram_transfer_async(0, nbytes, start, &mut buf).await?;
+    /// ```
+    #[cfg(feature = "async")]
+    pub async fn sram_transfer_async(
+        &mut self,
+        layer: u8,
+        nbytes: u32,
+        start: SramAddr,
+        buf: &mut [u8],
+    ) -> Result<(), Error<RETRY::Error>>
+    where
+        PROGRESS: Progress,
+    {
+        if buf.is_empty() {
+            return Err(Error::BufferSize {
+                expected: 1,
+                actual: 0,
+            });
+        }
+        let mut transfer = self.sram_transfer(layer, nbytes, start)?;
+        while !transfer.is_done() {
+            self.sram_transfer_step(&mut transfer, buf)?;
+            YieldNow(false).await;
+        }
+        Ok(())
+    }
+
+    /// Read data from the sram, letting other tasks run between chunks.
+    ///
+    /// As [SramBacked::sram_read], reading up to 256 bytes at a time and yielding to the executor
+    /// after each. Returns `Error::BufferSize`, before reading anything, if the data doesn't fit in
+    /// the sram device.
+    ///
+    /// Only available with the `async` feature.
+    #[cfg(feature = "async")]
+    pub async fn sram_read_async(
+        &mut self,
+        address: SramAddr,
+        data: &mut [u8],
+    ) -> Result<(), Error<RETRY::Error>> {
+        self.spi_bus.check_range(address, data.len() as u32)?;
+        let mut chunk_address = address;
+        for chunk in data.chunks_mut(ASYNC_CHUNK) {
+            self.spi_bus.sram_read(chunk_address, chunk)?;
+            chunk_address = SramAddr::new(chunk_address.get() + chunk.len() as u32);
+            YieldNow(false).await;
+        }
+        Ok(())
+    }
+
+    /// Write data to the sram, letting other tasks run between chunks.
+    ///
+    /// As [SramBacked::sram_write], writing up to 256 bytes at a time and yielding to the
+    /// executor after each. Returns `Error::BufferSize`, before writing anything, if the data
+    /// doesn't fit in the sram device.
+    ///
+    /// Only available with the `async` feature.
+    #[cfg(feature = "async")]
+    pub async fn sram_write_async(
+        &mut self,
+        address: SramAddr,
+        data: &[u8],
+    ) -> Result<(), Error<RETRY::Error>> {
+        self.spi_bus.check_range(address, data.len() as u32)?;
+        let mut chunk_address = address;
+        for chunk in data.chunks(ASYNC_CHUNK) {
+            self.spi_bus.sram_write(chunk_address, chunk)?;
+            chunk_address = SramAddr::new(chunk_address.get() + chunk.len() as u32);
+            YieldNow(false).await;
+        }
+        Ok(())
+    }
+
+    /// Set `nbytes` of the sram to `val`, letting other tasks run between chunks.
+    ///
+    /// As [SramBacked::sram_clear], clearing up to 256 bytes at a time and yielding to the
+    /// executor after each. Returns `Error::BufferSize`, before clearing anything, if the range
+    /// doesn't fit in the sram device.
+    ///
+    /// Only available with the `async` feature.
+    ///
+    /// ### Example
+    ///
+    /// ```ignore
+    /// interface.sram_clear_async(SramAddr::ZERO, 5808, 0xFF).await?;
+    /// ```
+    #[cfg(feature = "async")]
+    pub async fn sram_clear_async(
+        &mut self,
+        address: SramAddr,
+        nbytes: u32,
+        val: u8,
+    ) -> Result<(), Error<RETRY::Error>> {
+        self.spi_bus.check_range(address, nbytes)?;
+        let mut cleared = 0;
+        while cleared < nbytes {
+            let len = (nbytes - cleared).min(ASYNC_CHUNK as u32);
+            self.spi_bus
+                .sram_erase(SramAddr::new(address.get() + cleared), len, val)?;
+            cleared += len;
+            YieldNow(false).await;
+        }
+        Ok(())
+    }
+
+    /// release the spibus and all the associated pins
+    #[allow(clippy::type_complexity)]
+    pub fn release(
+        self,
+    ) -> (
+        SpiSramBus<SPI, EPDCS, SRAMCS, HOLD, RETRY>,
+        (BUSY, DC, RESET),
+    ) {
+        (self.spi_bus, (self.busy, self.dc, self.reset))
+    }
+}
+
+impl<SPI, EPDCS, SRAMCS, BUSY, DC, RESET, HOLD, WAIT, PROGRESS, RETRY> SpiOwner
+    for SramDisplayInterface<SPI, EPDCS, SRAMCS, BUSY, DC, RESET, HOLD, WAIT, PROGRESS, RETRY>
+{
+    type Spi = SPI;
+
+    fn spi(&mut self) -> &mut SPI {
+        self.spi_bus.spi()
+    }
+}
+
+impl<SPI, EPDCS, SRAMCS, BUSY, DC, RESET, HOLD, WAIT, PROGRESS, RETRY> DisplayInterface
+    for SramDisplayInterface<SPI, EPDCS, SRAMCS, BUSY, DC, RESET, HOLD, WAIT, PROGRESS, RETRY>
+where
+    SPI: hal::blocking::spi::Transfer<u8>,
+    EPDCS: hal::digital::v2::OutputPin,
+    SRAMCS: hal::digital::v2::OutputPin,
+    BUSY: hal::digital::v2::InputPin,
+    DC: hal::digital::v2::OutputPin,
+    RESET: hal::digital::v2::OutputPin,
+    RESET::Error: Debug,
+    HOLD: hal::digital::v2::OutputPin,
+    WAIT: WaitStrategy,
+    PROGRESS: Progress,
+    RETRY: RetryPolicy<SPI::Error>,
+{
+    type Error = RETRY::Error;
+
+    fn send_command(&mut self, command: u8) -> Result<(), Self::Error> {
+        log_trace!("command {:#04x}", command);
+        self.dc.set_low().ok();
+        self.spi_bus.epd_write(&[command])
+    }
+
+    fn send_data(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        self.dc.set_high().ok();
+        self.spi_bus.epd_write(data)
+    }
+
+    fn reset<D: hal::blocking::delay::DelayMs<u8>>(&mut self, delay: &mut D) {
+        // setup the sram
+        self.spi_bus.sram_init().ok();
+
+        hardware_reset(&mut self.reset, delay);
+
+        self.spi_bus.sram_seq().ok();
+    }
+
+    fn busy_wait(&mut self) {
+        self.wait.start();
+        while self.busy.is_high().unwrap_or_default() {
+            self.wait.wait();
+        }
+    }
+
+    fn busy_wait_for(&mut self, operation: Operation) {
+        if !self.wait.fixed_wait(operation) {
+            self.busy_wait()
+        }
+    }
+
+    fn is_busy(&mut self) -> Option<bool> {
+        if self.wait.is_fixed() {
+            return None;
+        }
+        self.busy.is_high().ok()
+    }
+
+    fn epd_update_data(
+        &mut self,
+        layer: u8,
+        nbytes: u16,
+        buf: &[u8],
+    ) -> Result<(), Error<Self::Error>> {
+        let sz: usize = nbytes.into();
+        if sz != buf.len() {
+            return Err(Error::BufferSize {
+                expected: sz,
+                actual: buf.len(),
+            });
+        }
+        if layer == 0 {
+            BufCommand::WriteBlackData(buf).execute(self)
+        } else {
+            BufCommand::WriteRedData(buf).execute(self)
+        }
+        .map_err(Error::Interface)
+    }
+}
+
+impl<SPI, EPDCS, SRAMCS, BUSY, DC, RESET, HOLD, WAIT, PROGRESS, RETRY> SramBacked
+    for SramDisplayInterface<SPI, EPDCS, SRAMCS, BUSY, DC, RESET, HOLD, WAIT, PROGRESS, RETRY>
+where
+    SPI: hal::blocking::spi::Transfer<u8>,
+    EPDCS: hal::digital::v2::OutputPin,
+    SRAMCS: hal::digital::v2::OutputPin,
+    BUSY: hal::digital::v2::InputPin,
+    DC: hal::digital::v2::OutputPin,
+    RESET: hal::digital::v2::OutputPin,
+    RESET::Error: Debug,
+    HOLD: hal::digital::v2::OutputPin,
+    WAIT: WaitStrategy,
+    PROGRESS: Progress,
+    RETRY: RetryPolicy<SPI::Error>,
+{
+    fn sram_read(&mut self, address: SramAddr, data: &mut [u8]) -> Result<(), Error<Self::Error>> {
+        self.spi_bus.sram_read(address, data)
+    }
+
+    fn sram_write(&mut self, address: SramAddr, data: &[u8]) -> Result<(), Error<Self::Error>> {
+        self.spi_bus.sram_write(address, data)
+    }
+
+    fn sram_clear(
+        &mut self,
+        address: SramAddr,
+        nbytes: u32,
+        val: u8,
+    ) -> Result<(), Error<Self::Error>> {
+        self.spi_bus.sram_erase(address, nbytes, val)
+    }
+
+    fn sram_capacity(&self) -> u32 {
+        self.spi_bus.size().bytes()
+    }
+
+    fn sram_epd_update_data(
+        &mut self,
+        layer: u8,
+        nbytes: u32,
+        start_address: SramAddr,
+    ) -> Result<(), Error<Self::Error>> {
+        let epd_location = regs::data_transmission(layer);
+        let capacity = self.spi_bus.size().bytes();
+        if !start_address.fits(nbytes, capacity) {
+            return Err(Error::BufferSize {
+                expected: nbytes as usize,
+                actual: capacity.saturating_sub(start_address.get()) as usize,
+            });
+        }
+        self.dc.set_low().ok();
+        let ch = self
+            .spi_bus
+            .sram_epd_move_header(start_address, epd_location)
+            .map_err(Error::Interface)?;
+        self.dc.set_high().ok();
+        self.spi_bus
+            .sram_epd_move_body_reporting(
+                ch,
+                start_address,
+                nbytes,
+                self.chunk_size,
+                &mut self.progress,
+            )
+            .map_err(Error::Interface)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::{DelayRetry, RetriesExhausted};
+    use crate::test_support::{MockPin, MockSpi, RecordingDelay};
+    use std::vec::Vec;
+
+    #[test]
+    fn sram_upload_progress() {
+        let mut reports = Vec::new();
+        let bus = SpiSramBus::new(MockSpi::default(), (MockPin, MockPin));
+        let mut interface = SramDisplayInterface::new(bus, (MockPin, MockPin, MockPin))
+            .upload_progress(|sent: u32, total: u32| reports.push((sent, total)), 100);
+        interface
+            .sram_epd_update_data(0, 250, SramAddr::ZERO)
+            .unwrap();
+        drop(interface);
+        assert_eq!(reports, [(100, 250), (200, 250), (250, 250)]);
+    }
+
+    #[test]
+    fn sram_retry_policy() {
+        let spi = MockSpi {
+            failures: 2,
+            ..MockSpi::default()
+        };
+        let mut delays = Vec::new();
+        let bus = SpiSramBus::new(spi, (MockPin, MockPin)).retry_policy(DelayRetry::new(
+            RecordingDelay(&mut delays),
+            3,
+            1,
+        ));
+        let mut interface = SramDisplayInterface::new(bus, (MockPin, MockPin, MockPin));
+        // the failed instruction and the write are sent again from the start
+        interface.sram_write(SramAddr::new(0x10), &[0xAA]).unwrap();
+        interface.send_command(0x12).unwrap();
+        interface.spi().failures = 1;
+        assert_eq!(
+            interface.sram_epd_update_data(0, 1, SramAddr::ZERO),
+            Err(Error::Interface(RetriesExhausted {
+                error: (),
+                attempts: 1
+            }))
+        );
+        let written = interface.release().0.release().0.written;
+        assert_eq!(written, [0x02, 0x00, 0x10, 0xAA, 0x12]);
+        assert_eq!(delays, [1, 2]);
+    }
+
+    #[test]
+    fn chunked_move() {
+        let bus = SpiSramBus::new(MockSpi::default(), (MockPin, MockPin)).move_chunk_size(2);
+        let mut interface = SramDisplayInterface::new(bus, (MockPin, MockPin, MockPin));
+        interface
+            .sram_epd_update_data(1, 5, SramAddr::new(0x10))
+            .unwrap();
+        let written = interface.release().0.release().0.written;
+        // the mock bus echoes, so every byte moved is the epd location
+        assert_eq!(
+            written,
+            [
+                0x03, 0x00, 0x10, 0x13, 0x13, 0x13, // read from 0x10, two bytes
+                0x03, 0x00, 0x13, 0x13, 0x13, // the third byte was read, continue from 0x13
+                0x03, 0x00, 0x15, 0x13,
+            ]
+        );
+    }
+
+    #[test]
+    fn sram_epd_update_data_out_of_range() {
+        let bus = SpiSramBus::new(MockSpi::default(), (MockPin, MockPin));
+        let mut interface = SramDisplayInterface::new(bus, (MockPin, MockPin, MockPin));
+
+        assert_eq!(
+            interface.sram_epd_update_data(0, 8000, SramAddr::new(1000)),
+            Err(Error::BufferSize {
+                expected: 8000,
+                actual: 7192
+            })
+        );
+        assert!(interface.release().0.release().0.written.is_empty());
+    }
+
+    #[test]
+    fn sram_transfer_steps() {
+        let mut reports = Vec::new();
+        let bus = SpiSramBus::new(MockSpi::default(), (MockPin, MockPin));
+        let mut interface = SramDisplayInterface::new(bus, (MockPin, MockPin, MockPin))
+            .upload_progress(|sent: u32, total: u32| reports.push((sent, total)), 0);
+        assert!(interface
+            .sram_transfer(1, 8000, SramAddr::new(1000))
+            .is_err());
+        let mut transfer = interface.sram_transfer(1, 10, SramAddr::new(0x20)).unwrap();
+        let mut buf = [0u8; 4];
+        let mut steps = 0;
+        while !transfer.is_done() {
+            interface
+                .sram_transfer_step(&mut transfer, &mut buf)
+                .unwrap();
+            steps += 1;
+        }
+        assert_eq!(interface.sram_transfer_step(&mut transfer, &mut buf), Ok(0));
+        let written = interface.release().0.release().0.written;
+        assert_eq!(steps, 3);
+        // the data command, then a read and a write per chunk
+        assert_eq!(written[..4], [0x13, 0x03, 0x00, 0x20]);
+        assert_eq!(written[12..15], [0x03, 0x00, 0x24]);
+        assert_eq!(written.len(), 1 + 2 * (3 + 4 + 4) + (3 + 2 + 2));
+        assert_eq!(reports, [(4, 10), (8, 10), (10, 10)]);
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn sram_transfer_async() {
+        use core::future::Future;
+        use core::task::{Context, Poll, Waker};
+
+        let bus = SpiSramBus::new(MockSpi::default(), (MockPin, MockPin));
+        let mut interface = SramDisplayInterface::new(bus, (MockPin, MockPin, MockPin));
+        let mut buf = [0u8; 4];
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut polls = 0;
+        {
+            let mut transfer = core::pin::pin!(interface.sram_transfer_async(
+                0,
+                10,
+                SramAddr::new(0x20),
+                &mut buf
+            ));
+            loop {
+                match transfer.as_mut().poll(&mut cx) {
+                    Poll::Pending => polls += 1,
+                    Poll::Ready(result) => break result.unwrap(),
+                }
+            }
+        }
+        // pending after each of the three chunks
+        assert_eq!(polls, 3);
+        let written = interface.release().0.release().0.written;
+        assert_eq!(written[..4], [0x10, 0x03, 0x00, 0x20]);
+        assert_eq!(written.len(), 1 + 2 * (3 + 4 + 4) + (3 + 2 + 2));
+
+        let bus = SpiSramBus::new(MockSpi::default(), (MockPin, MockPin));
+        let mut interface = SramDisplayInterface::new(bus, (MockPin, MockPin, MockPin));
+        let transfer =
+            core::pin::pin!(interface.sram_transfer_async(0, 10, SramAddr::ZERO, &mut []));
+        assert_eq!(
+            transfer.poll(&mut cx),
+            Poll::Ready(Err(Error::BufferSize {
+                expected: 1,
+                actual: 0
+            }))
+        );
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn sram_async_chunks() {
+        use core::future::Future;
+        use core::task::{Context, Poll, Waker};
+
+        // poll to completion, returning the result and the number of times it was pending
+        fn run<F: Future>(future: F) -> (F::Output, usize) {
+            let mut future = core::pin::pin!(future);
+            let mut cx = Context::from_waker(Waker::noop());
+            let mut polls = 0;
+            loop {
+                match future.as_mut().poll(&mut cx) {
+                    Poll::Pending => polls += 1,
+                    Poll::Ready(result) => return (result, polls),
+                }
+            }
+        }
+
+        let bus = SpiSramBus::new(MockSpi::default(), (MockPin, MockPin));
+        let mut interface = SramDisplayInterface::new(bus, (MockPin, MockPin, MockPin));
+        let data = [0x5A; 600];
+        let (result, polls) = run(interface.sram_write_async(SramAddr::new(0x100), &data));
+        assert_eq!((result, polls), (Ok(()), 3));
+        let mut data = [0u8; 300];
+        let (result, polls) = run(interface.sram_read_async(SramAddr::new(0x100), &mut data));
+        assert_eq!((result, polls), (Ok(()), 2));
+        let (result, polls) = run(interface.sram_clear_async(SramAddr::new(0x100), 256, 0xFF));
+        assert_eq!((result, polls), (Ok(()), 1));
+        let (result, polls) = run(interface.sram_clear_async(SramAddr::new(8000), 500, 0xFF));
+        assert_eq!(
+            (result, polls),
+            (
+                Err(Error::BufferSize {
+                    expected: 500,
+                    actual: 192
+                }),
+                0
+            )
+        );
+
+        let written = interface.release().0.release().0.written;
+        // each chunk starts again at the next address
+        assert_eq!(written[..4], [0x02, 0x01, 0x00, 0x5A]);
+        assert_eq!(written[259..262], [0x02, 0x02, 0x00]);
+        assert_eq!(written[518..521], [0x02, 0x03, 0x00]);
+        assert_eq!(written[609..612], [0x03, 0x01, 0x00]);
+        assert_eq!(written[868..871], [0x03, 0x02, 0x00]);
+        assert_eq!(written[915..918], [0x02, 0x01, 0x00]);
+        assert_eq!(written.len(), 918 + 256);
+    }
+
+    #[test]
+    fn sram_region() {
+        let black = SramRegion::new(SramAddr::ZERO, 100);
+        let red = SramRegion::new(black.end(), 100);
+        assert_eq!(red.address(0, 1), Some(SramAddr::new(100)));
+        assert_eq!(red.address(99, 1), Some(SramAddr::new(199)));
+        assert_eq!(red.address(99, 2), None);
+        assert_eq!(red.address(u32::MAX, 1), None);
+        assert!(red.end().fits(0, 200));
+        assert!(!red.end().fits(1, 200));
+    }
+
+    #[test]
+    fn sram_storage() {
+        let bus = SpiSramBus::new(MockSpi::default(), (MockPin, MockPin));
+        let mut interface = SramDisplayInterface::new(bus, (MockPin, MockPin, MockPin));
+        {
+            let mut storage = interface.storage(SramAddr::new(0x1000));
+            assert_eq!(storage.len(), 0x1000);
+            storage.write(0x10, &[0xAB]).unwrap();
+            assert_eq!(
+                storage.write(0xFFF, &[0, 0]),
+                Err(Error::BufferSize {
+                    expected: 2,
+                    actual: 1
+                })
+            );
+        }
+        assert_eq!(
+            interface.release().0.release().0.written,
+            [0x02, 0x10, 0x10, 0xAB]
+        );
+    }
+
+    #[test]
+    fn sram_bus_range() {
+        let mut bus = SpiSramBus::new(MockSpi::default(), (MockPin, MockPin));
+        assert_eq!(
+            bus.sram_write(SramAddr::new(8190), &[0; 4]),
+            Err(Error::BufferSize {
+                expected: 4,
+                actual: 2
+            })
+        );
+        // an erase that is not a multiple of 4 writes the tail too
+        bus.sram_erase(SramAddr::ZERO, 6, 0xAA).unwrap();
+        assert_eq!(
+            bus.release().0.written,
+            [0x02, 0x00, 0x00, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA]
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use super::hardware_reset;
+use crate::command::BufCommand;
+use crate::error::Error;
+use crate::interface::DisplayInterface;
+use crate::wait::{Operation, SpinWait, WaitStrategy};
+use core::fmt::Debug;
+
+/// The hardware interface to a display using the 3-wire serial mode.
+///
+/// In this mode the controller has no Data/Command pin, instead every byte is preceded by a
+/// Data/Command bit, making 9-bit words. The words are packed into bytes for an 8-bit SPI
+/// peripheral. The last byte of a transaction is padded with zeros, which the controller ignores
+/// when chip select is released. The panel must be strapped for 3-wire mode (BS1 high).
+pub struct Interface3Wire<SPI, CS, BUSY, RESET, WAIT = SpinWait> {
+    /// SPI interface
+    spi: SPI,
+    /// Chip Select, low active (output)
+    cs: CS,
+    /// Active low busy pin (input)
+    busy: BUSY,
+    /// Pin for resetting the controller (output)
+    reset: RESET,
+    /// How to wait while the controller is busy
+    wait: WAIT,
+}
+
+impl<SPI, CS, BUSY, RESET> Interface3Wire<SPI, CS, BUSY, RESET>
+where
+    SPI: hal::blocking::spi::Write<u8>,
+    CS: hal::digital::v2::OutputPin,
+    BUSY: hal::digital::v2::InputPin,
+    RESET: hal::digital::v2::OutputPin,
+{
+    /// Create a new 3-wire Interface from embedded hal traits.
+    pub fn new(spi: SPI, pins: (CS, BUSY, RESET)) -> Self {
+        Self {
+            spi,
+            cs: pins.0,
+            busy: pins.1,
+            reset: pins.2,
+            wait: SpinWait,
+        }
+    }
+}
+
+impl<SPI, CS, BUSY, RESET, WAIT> Interface3Wire<SPI, CS, BUSY, RESET, WAIT>
+where
+    SPI: hal::blocking::spi::Write<u8>,
+    CS: hal::digital::v2::OutputPin,
+    BUSY: hal::digital::v2::InputPin,
+    RESET: hal::digital::v2::OutputPin,
+{
+    /// Set how to wait while the controller is busy.
+    ///
+    /// Defaults to [SpinWait](../wait/struct.SpinWait.html).
+    pub fn wait_strategy<W: WaitStrategy>(
+        self,
+        wait: W,
+    ) -> Interface3Wire<SPI, CS, BUSY, RESET, W> {
+        Interface3Wire {
+            spi: self.spi,
+            cs: self.cs,
+            busy: self.busy,
+            reset: self.reset,
+            wait,
+        }
+    }
+
+    /// release the spi and pins
+    pub fn release(self) -> (SPI, (CS, BUSY, RESET)) {
+        (self.spi, (self.cs, self.busy, self.reset))
+    }
+
+    // write bytes as 9-bit words with the D/C bit set to `dc`
+    fn write(&mut self, dc: bool, data: &[u8]) -> Result<(), SPI::Error> {
+        self.cs.set_low().ok();
+        let result = self.write_words(dc, data);
+        // Release the controller
+        self.cs.set_high().ok();
+        result
+    }
+
+    fn write_words(&mut self, dc: bool, data: &[u8]) -> Result<(), SPI::Error> {
+        // 8 words fill 9 bytes exactly
+        let mut out = [0u8; 18];
+        let mut len = 0;
+        let mut acc: u16 = 0;
+        let mut bits = 0;
+        for byte in data {
+            acc = (acc << 9) | (u16::from(dc) << 8) | u16::from(*byte);
+            bits += 9;
+            while bits >= 8 {
+                bits -= 8;
+                out[len] = (acc >> bits) as u8;
+                len += 1;
+            }
+            acc &= (1 << bits) - 1;
+            if len == out.len() {
+                self.spi.write(&out)?;
+                len = 0;
+            }
+        }
+        if bits > 0 {
+            out[len] = (acc << (8 - bits)) as u8;
+            len += 1;
+        }
+        if len > 0 {
+            self.spi.write(&out[..len])?;
+        }
+        Ok(())
+    }
+}
+
+impl<SPI, CS, BUSY, RESET, WAIT> DisplayInterface for Interface3Wire<SPI, CS, BUSY, RESET, WAIT>
+where
+    SPI: hal::blocking::spi::Write<u8>,
+    CS: hal::digital::v2::OutputPin,
+    BUSY: hal::digital::v2::InputPin,
+    RESET: hal::digital::v2::OutputPin,
+    RESET::Error: Debug,
+    WAIT: WaitStrategy,
+{
+    type Error = SPI::Error;
+
+    fn reset<D: hal::blocking::delay::DelayMs<u8>>(&mut self, delay: &mut D) {
+        hardware_reset(&mut self.reset, delay);
+    }
+
+    fn send_command(&mut self, command: u8) -> Result<(), Self::Error> {
+        self.write(false, &[command])
+    }
+
+    fn send_data(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        self.write(true, data)
+    }
+
+    fn epd_update_data(
+        &mut self,
+        layer: u8,
+        nbytes: u16,
+        buf: &[u8],
+    ) -> Result<(), Error<Self::Error>> {
+        let sz: usize = nbytes.into();
+        if sz != buf.len() {
+            return Err(Error::BufferSize {
+                expected: sz,
+                actual: buf.len(),
+            });
+        }
+        if layer == 0 {
+            BufCommand::WriteBlackData(buf).execute(self)
+        } else {
+            BufCommand::WriteRedData(buf).execute(self)
+        }
+        .map_err(Error::Interface)
+    }
+
+    fn busy_wait(&mut self) {
+        self.wait.start();
+        while self.busy.is_high().unwrap_or_default() {
+            self.wait.wait();
+        }
+    }
+
+    fn busy_wait_for(&mut self, operation: Operation) {
+        if !self.wait.fixed_wait(operation) {
+            self.busy_wait()
+        }
+    }
+
+    fn is_busy(&mut self) -> Option<bool> {
+        if self.wait.is_fixed() {
+            return None;
+        }
+        self.busy.is_high().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{MockPin, MockSpi};
+
+    #[test]
+    fn three_wire_packing() {
+        let mut interface = Interface3Wire::new(MockSpi::default(), (MockPin, MockPin, MockPin));
+        interface.send_command(0x12).unwrap();
+        interface.send_data(&[0xFF]).unwrap();
+        interface.send_data(&[0xA5; 8]).unwrap();
+        let written = interface.release().0.written;
+        // 0 0001_0010, padded
+        assert_eq!(written[..2], [0b0000_1001, 0b0000_0000]);
+        // 1 1111_1111, padded
+        assert_eq!(written[2..4], [0xFF, 0x80]);
+        // eight 1 1010_0101 words fill nine bytes
+        assert_eq!(
+            written[4..],
+            [0xD2, 0xE9, 0x74, 0xBA, 0x5D, 0x2E, 0x97, 0x4B, 0xA5]
+        );
+    }
+}
@@ -0,0 +1,190 @@
+//! Rectangles for laying out a screen, in drawing coordinates after rotation.
+//!
+//! A [Region] starts as the whole display for a `Config`, with its width and height swapped for
+//! `Rotate90` and `Rotate270`, and is cut into bars and columns. The rectangles suit
+//! `DrawTargetExt::clipped` and [GraphicDisplay::fill_rect], so the same layout code works in
+//! every rotation without hard-coded coordinates.
+//!
+//! ### Example
+//!
+//! ```ignore
+//! let screen = Region::display(display.config());
+//! let status = screen.top_bar(12);
+//! let (left, right) = screen.below(status).split_h(screen.width() / 2);
+//! Text::with_baseline("12:30", status.text_origin(10), style, Baseline::Top)
+//!     .draw(&mut display.clipped(&status.rect()))?;
+//! ```
+//!
+//! [GraphicDisplay::fill_rect]: ../graphics/struct.GraphicDisplay.html#method.fill_rect
+
+use crate::config::Config;
+use crate::display::Rotation;
+use embedded_graphics_core::prelude::*;
+use embedded_graphics_core::primitives::Rectangle;
+
+/// A rectangle of the display, in drawing coordinates.
+///
+/// Lengths larger than the region are clamped, so a region never extends outside the one it was
+/// cut from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Region(Rectangle);
+
+impl Region {
+    /// The whole display, after the rotation in `config`.
+    pub fn display(config: &Config) -> Region {
+        let dimensions = config.dimensions();
+        let (rows, cols) = (u32::from(dimensions.rows), u32::from(dimensions.cols));
+        let size = match config.rotation() {
+            Rotation::Rotate0 | Rotation::Rotate180 => Size::new(cols, rows),
+            Rotation::Rotate90 | Rotation::Rotate270 => Size::new(rows, cols),
+        };
+        Region(Rectangle::new(Point::zero(), size))
+    }
+
+    /// A region covering a rectangle.
+    pub const fn new(rect: Rectangle) -> Region {
+        Region(rect)
+    }
+
+    /// Returns the rectangle of the region.
+    pub const fn rect(self) -> Rectangle {
+        self.0
+    }
+
+    /// Returns the width of the region.
+    pub const fn width(self) -> u32 {
+        self.0.size.width
+    }
+
+    /// Returns the height of the region.
+    pub const fn height(self) -> u32 {
+        self.0.size.height
+    }
+
+    /// The top `height` rows of the region.
+    pub fn top_bar(self, height: u32) -> Region {
+        self.split_v(height).0
+    }
+
+    /// The bottom `height` rows of the region.
+    pub fn bottom_bar(self, height: u32) -> Region {
+        self.split_v(self.height().saturating_sub(height)).1
+    }
+
+    /// The part of the region below `bar`, which was cut from the top of it.
+    pub fn below(self, bar: Region) -> Region {
+        self.split_v(bar.height()).1
+    }
+
+    /// The part of the region above `bar`, which was cut from the bottom of it.
+    pub fn above(self, bar: Region) -> Region {
+        self.split_v(self.height().saturating_sub(bar.height())).0
+    }
+
+    /// Split the region side by side, into the left `width` columns and the rest.
+    pub fn split_h(self, width: u32) -> (Region, Region) {
+        let Rectangle { top_left, size } = self.0;
+        let width = width.min(size.width);
+        (
+            Region(Rectangle::new(top_left, Size::new(width, size.height))),
+            Region(Rectangle::new(
+                top_left + Point::new(width as i32, 0),
+                Size::new(size.width - width, size.height),
+            )),
+        )
+    }
+
+    /// Split the region one above the other, into the top `height` rows and the rest.
+    pub fn split_v(self, height: u32) -> (Region, Region) {
+        let Rectangle { top_left, size } = self.0;
+        let height = height.min(size.height);
+        (
+            Region(Rectangle::new(top_left, Size::new(size.width, height))),
+            Region(Rectangle::new(
+                top_left + Point::new(0, height as i32),
+                Size::new(size.width, size.height - height),
+            )),
+        )
+    }
+
+    /// Returns where to draw text `text_height` pixels high to center it vertically.
+    ///
+    /// The point is on the left edge, at the top of the text, so draw with `Baseline::Top`. Text
+    /// taller than the region starts at its top.
+    pub fn text_origin(self, text_height: u32) -> Point {
+        let margin = self.height().saturating_sub(text_height) / 2;
+        self.0.top_left + Point::new(0, margin as i32)
+    }
+}
+
+impl From<Region> for Rectangle {
+    fn from(region: Region) -> Rectangle {
+        region.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Builder;
+    use crate::display::Dimensions;
+
+    fn screen(rotation: Rotation) -> Region {
+        let config = Builder::new()
+            .dimensions(Dimensions {
+                rows: 212,
+                cols: 104,
+            })
+            .rotation(rotation)
+            .build()
+            .expect("invalid config");
+        Region::display(&config)
+    }
+
+    #[test]
+    fn rotation() {
+        assert_eq!(
+            screen(Rotation::Rotate0).rect(),
+            Rectangle::new(Point::zero(), Size::new(104, 212))
+        );
+        assert_eq!(
+            screen(Rotation::Rotate270).rect(),
+            Rectangle::new(Point::zero(), Size::new(212, 104))
+        );
+    }
+
+    #[test]
+    fn bars_and_splits() {
+        let screen = screen(Rotation::Rotate90);
+        let top = screen.top_bar(12);
+        let bottom = screen.bottom_bar(10);
+        assert_eq!(
+            top.rect(),
+            Rectangle::new(Point::zero(), Size::new(212, 12))
+        );
+        assert_eq!(
+            bottom.rect(),
+            Rectangle::new(Point::new(0, 94), Size::new(212, 10))
+        );
+        let body = screen.below(top).above(bottom);
+        assert_eq!(
+            body.rect(),
+            Rectangle::new(Point::new(0, 12), Size::new(212, 82))
+        );
+        let (left, right) = body.split_h(100);
+        assert_eq!(
+            left.rect(),
+            Rectangle::new(Point::new(0, 12), Size::new(100, 82))
+        );
+        assert_eq!(
+            right.rect(),
+            Rectangle::new(Point::new(100, 12), Size::new(112, 82))
+        );
+        assert_eq!(top.text_origin(8), Point::new(0, 2));
+
+        // clamped to the region
+        assert_eq!(screen.top_bar(500), screen);
+        assert_eq!(left.split_h(200).1.width(), 0);
+        assert_eq!(top.text_origin(20), Point::zero());
+    }
+}
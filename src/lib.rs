@@ -34,7 +34,9 @@
 //! MCU. The SRAM device must be on the the same SPI port as the
 //! il0373. For this option, use the feature `sram`. Instead of using
 //! a [Interface] and [GraphicDisplay], use a [SpiSramBus], and an
-//! associated [SramDisplayInterface], then a [SramGraphicDisplay].
+//! associated [SramDisplayInterface], then a [SramGraphicDisplay]. Both
+//! are a [GraphicDisplay], with the buffers in a different
+//! [FrameBufferStore].
 //!
 //!
 //! To update the display you will typically follow this flow:
@@ -49,37 +51,109 @@
 //! [SramDisplayInterface]: interface/struct.SramDisplayInterface.html
 //! [Display]: display/struct.Display.html
 //! [GraphicDisplay]: display/struct.GraphicDisplay.html
-//! [SramGraphicDisplay]: display/struct.SramGraphicDisplay.html
+//! [SramGraphicDisplay]: graphics/type.SramGraphicDisplay.html
+//! [FrameBufferStore]: graphics/trait.FrameBufferStore.html
 //! [Config]: config/struct.Config.html
 //! [Builder]: config/struct.Builder.html
 //! [embedded-graphics]: https://crates.io/crates/embedded-graphics
 
-#[cfg(test)]
-extern crate embedded_graphics;
-
-extern crate embedded_graphics_core;
 extern crate embedded_hal as hal;
 
-#[cfg(test)]
+#[cfg(any(
+    test,
+    feature = "test",
+    feature = "eh-mock-tests",
+    feature = "golden-tests",
+    feature = "inky"
+))]
 #[macro_use]
 extern crate std;
 
+#[macro_use]
+mod macros;
+
+#[cfg(feature = "test")]
+pub mod bench;
+pub mod codec;
 mod color;
 pub mod command;
 pub mod config;
 pub mod display;
+pub mod encode;
+mod error;
+#[cfg(any(test, feature = "test"))]
+pub mod flaky;
+pub mod glyph;
+#[cfg(feature = "golden-tests")]
+pub mod golden;
 pub mod graphics;
+#[cfg(feature = "graphics")]
+pub mod image;
+#[cfg(feature = "inky")]
+pub mod inky;
 pub mod interface;
+pub mod layout;
+pub mod luts;
+#[cfg(feature = "eh-mock-tests")]
+pub mod mock;
+pub mod odometer;
+#[cfg(feature = "graphics")]
+pub mod paged;
+pub mod profile;
+pub mod regs;
+pub mod scheduler;
+pub mod slideshow;
+pub mod sprite;
+#[cfg(feature = "async")]
+pub mod telemetry;
+#[cfg(test)]
+mod test_support;
+pub mod thermal;
+#[cfg(feature = "graphics")]
+pub mod ticker;
+pub mod timing;
+pub mod vcom;
+pub mod wait;
 
-pub use color::Color;
+pub use color::{Color, ColorThresholds, PanelColor};
 pub use config::Builder;
-pub use display::{Dimensions, Display, Rotation};
-pub use graphics::GraphicDisplay;
+pub use display::{
+    buffer_size, Dimensions, Display, DriverState, PanelState, PartialWindow, Rotation, WakeGuard,
+};
+pub use error::Error;
+pub use graphics::{
+    coords_to_index, index_to_coords, BitOrder, BufferLayout, Compositing, DrawStats,
+    FrameBufferStore, GraphicDisplay, GraphicDisplayFixed, OutOfBounds, Pixels, Plane, RamBuffers,
+    UpdateStrategy,
+};
+#[cfg(feature = "graphics")]
+pub use graphics::{ColorAdapter, LayerTarget};
 #[cfg(feature = "sram")]
-pub use graphics::SramGraphicDisplay;
-pub use interface::DisplayInterface;
+pub use graphics::{SramBuffers, SramGraphicDisplay};
 pub use interface::Interface;
+pub use interface::Interface3Wire;
+#[cfg(feature = "dual-spi")]
+pub use interface::InterfaceDualSpi;
+pub use interface::InterfaceOptions;
+pub use interface::NoPin;
 #[cfg(feature = "sram")]
 pub use interface::SpiSramBus;
 #[cfg(feature = "sram")]
+pub use interface::SramAddr;
+#[cfg(feature = "sram")]
+pub use interface::SramBacked;
+#[cfg(feature = "sram")]
 pub use interface::SramDisplayInterface;
+#[cfg(feature = "sram")]
+pub use interface::SramHandle;
+#[cfg(feature = "sram")]
+pub use interface::SramRegion;
+#[cfg(feature = "sram")]
+pub use interface::SramSize;
+#[cfg(feature = "sram")]
+pub use interface::SramTransfer;
+pub use interface::{BusMutex, SharedInterface, SharedSpi, SpiOwner};
+pub use interface::{CommandSink, DisplayInterface, PostInit, SinkError};
+pub use interface::{DelayRetry, NoRetry, RetriesExhausted, RetryPolicy};
+#[cfg(feature = "graphics")]
+pub use paged::PagedDisplay;
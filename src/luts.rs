@@ -0,0 +1,249 @@
+//! Waveform look up tables (LUTs) for the controller.
+//!
+//! By default the controller uses the waveforms in the panel's OTP memory. A [LutProfile] other
+//! than `Otp` loads one of the tables here into the LUT registers during initialization, see
+//! [Builder::lut_profile](../config/struct.Builder.html#method.lut_profile).
+//!
+//! The tables are from the Waveshare sample code for the 2.13" flexible IL0373 panel, and drive
+//! the panel in black and white (KW) mode. In that mode the B/W data (DTM1) is the previous image
+//! and the Red data (DTM2) is the new image, so they are only for black and white panels; a
+//! tri-color panel must use `Otp`.
+
+use crate::interface::DisplayInterface;
+use crate::regs;
+
+/// A set of waveform tables, one per register.
+pub struct Lut {
+    /// VCOM waveform (LUTC)
+    pub vcom: [u8; 44],
+    /// white to white waveform (LUTWW)
+    pub ww: [u8; 42],
+    /// black to white waveform (LUTBW)
+    pub bw: [u8; 42],
+    /// white to black waveform (LUTWB)
+    pub wb: [u8; 42],
+    /// black to black waveform (LUTBB)
+    pub bb: [u8; 42],
+}
+
+impl Lut {
+    /// Send the tables to the LUT registers.
+    pub fn execute<I: DisplayInterface>(&self, interface: &mut I) -> Result<(), I::Error> {
+        for (command, table) in self.tables().iter() {
+            interface.send_command(*command)?;
+            interface.send_data(table)?;
+        }
+        Ok(())
+    }
+
+    // the command writing each table, and the table
+    pub(crate) fn tables(&self) -> [(u8, &[u8]); 5] {
+        [
+            (regs::LUTC, &self.vcom),
+            (regs::LUTWW, &self.ww),
+            (regs::LUTBW, &self.bw),
+            (regs::LUTWB, &self.wb),
+            (regs::LUTBB, &self.bb),
+        ]
+    }
+}
+
+/// The waveforms to refresh with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LutProfile {
+    /// The waveforms programmed into the panel (OTP). Required for tri-color panels.
+    Otp,
+    /// Full refresh of a black and white panel, flashes the whole panel.
+    FullMono,
+    /// Fast refresh of a black and white panel, only driving pixels that change. Ghosting
+    /// builds up, so follow a few fast refreshes with a full one.
+    FastMono,
+    /// The full refresh waveform with every phase repeated twice as often, to clear ghosting
+    /// left by many fast refreshes. Takes twice as long as `FullMono`.
+    DeepClean,
+}
+
+impl Default for LutProfile {
+    /// Default is the panel's own waveforms (`Otp`).
+    fn default() -> Self {
+        LutProfile::Otp
+    }
+}
+
+impl LutProfile {
+    /// Returns the tables to load, `None` for `Otp`.
+    pub const fn lut(self) -> Option<&'static Lut> {
+        match self {
+            LutProfile::Otp => None,
+            LutProfile::FullMono => Some(&FULL_MONO),
+            LutProfile::FastMono => Some(&FAST_MONO),
+            LutProfile::DeepClean => Some(&DEEP_CLEAN),
+        }
+    }
+}
+
+/// Full refresh waveform for black and white panels.
+#[rustfmt::skip]
+pub static FULL_MONO: Lut = Lut {
+    vcom: [
+        0x00, 0x08, 0x00, 0x00, 0x00, 0x02,
+        0x60, 0x28, 0x28, 0x00, 0x00, 0x01,
+        0x00, 0x14, 0x00, 0x00, 0x00, 0x01,
+        0x00, 0x12, 0x12, 0x00, 0x00, 0x01,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00,
+    ],
+    ww: [
+        0x40, 0x08, 0x00, 0x00, 0x00, 0x02,
+        0x90, 0x28, 0x28, 0x00, 0x00, 0x01,
+        0x40, 0x14, 0x00, 0x00, 0x00, 0x01,
+        0xA0, 0x12, 0x12, 0x00, 0x00, 0x01,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ],
+    bw: [
+        0x40, 0x17, 0x00, 0x00, 0x00, 0x02,
+        0x90, 0x0F, 0x0F, 0x00, 0x00, 0x03,
+        0x40, 0x0A, 0x01, 0x00, 0x00, 0x01,
+        0xA0, 0x0E, 0x0E, 0x00, 0x00, 0x02,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ],
+    wb: [
+        0x80, 0x08, 0x00, 0x00, 0x00, 0x02,
+        0x90, 0x28, 0x28, 0x00, 0x00, 0x01,
+        0x80, 0x14, 0x00, 0x00, 0x00, 0x01,
+        0x50, 0x12, 0x12, 0x00, 0x00, 0x01,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ],
+    bb: [
+        0x80, 0x08, 0x00, 0x00, 0x00, 0x02,
+        0x90, 0x28, 0x28, 0x00, 0x00, 0x01,
+        0x80, 0x14, 0x00, 0x00, 0x00, 0x01,
+        0x50, 0x12, 0x12, 0x00, 0x00, 0x01,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ],
+};
+
+/// Fast (partial) refresh waveform for black and white panels.
+#[rustfmt::skip]
+pub static FAST_MONO: Lut = Lut {
+    vcom: [
+        0x00, 0x19, 0x01, 0x00, 0x00, 0x01,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00,
+    ],
+    ww: [0x00; 42],
+    bw: [
+        0x80, 0x19, 0x01, 0x00, 0x00, 0x01,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ],
+    wb: [
+        0x40, 0x19, 0x01, 0x00, 0x00, 0x01,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ],
+    bb: [0x00; 42],
+};
+
+/// `FULL_MONO` with the repeat count of every phase doubled.
+#[rustfmt::skip]
+pub static DEEP_CLEAN: Lut = Lut {
+    vcom: [
+        0x00, 0x08, 0x00, 0x00, 0x00, 0x04,
+        0x60, 0x28, 0x28, 0x00, 0x00, 0x02,
+        0x00, 0x14, 0x00, 0x00, 0x00, 0x02,
+        0x00, 0x12, 0x12, 0x00, 0x00, 0x02,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00,
+    ],
+    ww: [
+        0x40, 0x08, 0x00, 0x00, 0x00, 0x04,
+        0x90, 0x28, 0x28, 0x00, 0x00, 0x02,
+        0x40, 0x14, 0x00, 0x00, 0x00, 0x02,
+        0xA0, 0x12, 0x12, 0x00, 0x00, 0x02,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ],
+    bw: [
+        0x40, 0x17, 0x00, 0x00, 0x00, 0x04,
+        0x90, 0x0F, 0x0F, 0x00, 0x00, 0x06,
+        0x40, 0x0A, 0x01, 0x00, 0x00, 0x02,
+        0xA0, 0x0E, 0x0E, 0x00, 0x00, 0x04,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ],
+    wb: [
+        0x80, 0x08, 0x00, 0x00, 0x00, 0x04,
+        0x90, 0x28, 0x28, 0x00, 0x00, 0x02,
+        0x80, 0x14, 0x00, 0x00, 0x00, 0x02,
+        0x50, 0x12, 0x12, 0x00, 0x00, 0x02,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ],
+    bb: [
+        0x80, 0x08, 0x00, 0x00, 0x00, 0x04,
+        0x90, 0x28, 0x28, 0x00, 0x00, 0x02,
+        0x80, 0x14, 0x00, 0x00, 0x00, 0x02,
+        0x50, 0x12, 0x12, 0x00, 0x00, 0x02,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ],
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // each phase is 6 bytes, the last is the repeat count
+    fn repeats(table: &[u8]) -> impl Iterator<Item = u8> + '_ {
+        table.chunks(6).filter(|c| c.len() == 6).map(|c| c[5])
+    }
+
+    #[test]
+    fn deep_clean_doubles_full() {
+        let pairs = [
+            (&FULL_MONO.vcom[..], &DEEP_CLEAN.vcom[..]),
+            (&FULL_MONO.ww[..], &DEEP_CLEAN.ww[..]),
+            (&FULL_MONO.bw[..], &DEEP_CLEAN.bw[..]),
+            (&FULL_MONO.wb[..], &DEEP_CLEAN.wb[..]),
+            (&FULL_MONO.bb[..], &DEEP_CLEAN.bb[..]),
+        ];
+        for (full, clean) in pairs.iter() {
+            for (i, (a, b)) in full.iter().zip(clean.iter()).enumerate() {
+                if i % 6 != 5 || i >= 42 {
+                    assert_eq!(a, b);
+                }
+            }
+            assert!(repeats(full).zip(repeats(clean)).all(|(a, b)| b == 2 * a));
+        }
+    }
+}
@@ -0,0 +1,32 @@
+//! Logging through the [log] facade, enabled with the `log` feature.
+//!
+//! Without the feature the macros expand to nothing, so their arguments are not evaluated and
+//! logging costs nothing.
+//!
+//! [log]: https://crates.io/crates/log
+
+// trace level event, for each command sent
+#[cfg(feature = "log")]
+macro_rules! log_trace {
+    ($($arg:tt)+) => {
+        log::trace!(target: "il0373", $($arg)+)
+    };
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! log_trace {
+    ($($arg:tt)+) => {};
+}
+
+// debug level event, for state transitions and busy waits
+#[cfg(feature = "log")]
+macro_rules! log_debug {
+    ($($arg:tt)+) => {
+        log::debug!(target: "il0373", $($arg)+)
+    };
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! log_debug {
+    ($($arg:tt)+) => {};
+}
@@ -0,0 +1,145 @@
+//! Helpers for testing display code with [embedded-hal-mock].
+//!
+//! [Expectations] builds the SPI and pin transactions an [Interface] makes for a sequence of
+//! commands, so application code can be unit tested against the mocks without working out the
+//! chip select and data/command toggling by hand. Requires the `eh-mock-tests` feature.
+//!
+//! ### Example
+//!
+//! ```ignore
+//! let mut mocks = Expectations::new()
+//!     .command(0x12) // DRF
+//!     .busy_wait()
+//!     .build();
+//! let mut display = Display::new(mocks.interface(), config);
+//! display.signal_update()?;
+//! display.busy_wait_timed(&mut clock);
+//! mocks.done();
+//! ```
+//!
+//! [embedded-hal-mock]: https://crates.io/crates/embedded-hal-mock
+//! [Interface]: ../interface/struct.Interface.html
+
+use crate::interface::Interface;
+use embedded_hal_mock::eh0::digital::{Mock as PinMock, State, Transaction as PinTransaction};
+use embedded_hal_mock::eh0::spi::{Mock as SpiMock, Transaction as SpiTransaction};
+use std::vec::Vec;
+
+/// An [Interface] built from embedded-hal-mock devices.
+///
+/// [Interface]: ../interface/struct.Interface.html
+pub type MockInterface = Interface<SpiMock, PinMock, PinMock, PinMock, PinMock>;
+
+/// Builds the transactions expected on the SPI bus and pins.
+#[derive(Clone, Debug, Default)]
+pub struct Expectations {
+    spi: Vec<SpiTransaction>,
+    cs: Vec<PinTransaction>,
+    busy: Vec<PinTransaction>,
+    dc: Vec<PinTransaction>,
+    reset: Vec<PinTransaction>,
+}
+
+impl Expectations {
+    /// Create an empty set of expectations.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expect a command byte.
+    pub fn command(mut self, command: u8) -> Self {
+        self.dc.push(PinTransaction::set(State::Low));
+        self.write(&[command]);
+        self.dc.push(PinTransaction::set(State::High));
+        self
+    }
+
+    /// Expect data for a command.
+    pub fn data(mut self, data: &[u8]) -> Self {
+        self.dc.push(PinTransaction::set(State::High));
+        self.write(data);
+        self
+    }
+
+    /// Expect a wait on the busy pin, which is already not busy.
+    pub fn busy_wait(mut self) -> Self {
+        self.busy.push(PinTransaction::get(State::Low));
+        self
+    }
+
+    /// Expect a hardware reset.
+    pub fn reset(mut self) -> Self {
+        for _ in 0..3 {
+            self.reset.push(PinTransaction::set(State::Low));
+            self.reset.push(PinTransaction::set(State::High));
+        }
+        self
+    }
+
+    /// Create mock devices that expect the transactions.
+    pub fn build(&self) -> MockDevices {
+        MockDevices {
+            spi: SpiMock::new(&self.spi),
+            cs: PinMock::new(&self.cs),
+            busy: PinMock::new(&self.busy),
+            dc: PinMock::new(&self.dc),
+            reset: PinMock::new(&self.reset),
+        }
+    }
+
+    // a write with chip select, split as the Interface splits it
+    fn write(&mut self, data: &[u8]) {
+        self.cs.push(PinTransaction::set(State::Low));
+        if cfg!(target_os = "linux") {
+            for chunk in data.chunks(4096) {
+                self.spi.push(SpiTransaction::write(chunk.to_vec()));
+            }
+        } else {
+            self.spi.push(SpiTransaction::write(data.to_vec()));
+        }
+        self.cs.push(PinTransaction::set(State::High));
+    }
+}
+
+/// The mock devices for an [Interface], created by [Expectations::build].
+///
+/// [Interface]: ../interface/struct.Interface.html
+pub struct MockDevices {
+    /// the SPI bus
+    pub spi: SpiMock,
+    /// the chip select pin
+    pub cs: PinMock,
+    /// the busy pin
+    pub busy: PinMock,
+    /// the data/command pin
+    pub dc: PinMock,
+    /// the reset pin
+    pub reset: PinMock,
+}
+
+impl MockDevices {
+    /// Create an interface using the mock devices.
+    ///
+    /// The devices share their expectations with the interface, so [MockDevices::done] checks
+    /// what the interface did.
+    pub fn interface(&self) -> MockInterface {
+        Interface::new(
+            self.spi.clone(),
+            (
+                self.cs.clone(),
+                self.busy.clone(),
+                self.dc.clone(),
+                self.reset.clone(),
+            ),
+        )
+    }
+
+    /// Check that all the expected transactions happened, panics otherwise.
+    pub fn done(&mut self) {
+        self.spi.done();
+        self.cs.done();
+        self.busy.done();
+        self.dc.done();
+        self.reset.done();
+    }
+}
@@ -0,0 +1,159 @@
+//! Panel usage tracking.
+//!
+//! Every refresh wears the panel a little. An [Odometer] counts the refreshes a [Display] starts,
+//! by their [RefreshKind], so an application can persist the counts across power cycles with
+//! [Odometer::to_bytes] and enforce maintenance, for example a deep clean after a number of fast
+//! or partial refreshes.
+//!
+//! ### Example
+//!
+//! ```ignore
+//! display.set_odometer(Odometer::from_bytes(&eeprom.read()));
+//! // ...
+//! if display.odometer().since_full >= 50 {
+//!     // rebuild the display with LutProfile::DeepClean and refresh
+//! }
+//! eeprom.write(&display.odometer().to_bytes());
+//! ```
+//!
+//! [Display]: ../display/struct.Display.html
+//! [LutProfile]: ../luts/enum.LutProfile.html
+
+use crate::luts::LutProfile;
+
+/// The kind of a refresh, as counted by an [Odometer].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RefreshKind {
+    /// a full refresh, with the `Otp` or `FullMono` profile
+    Full,
+    /// a full frame refreshed with the `FastMono` profile
+    Fast,
+    /// a refresh of partial windows
+    Partial,
+    /// a full refresh with the `DeepClean` profile
+    DeepClean,
+}
+
+impl RefreshKind {
+    /// Returns the kind of a full frame refresh with a profile.
+    pub const fn for_profile(profile: LutProfile) -> Self {
+        match profile {
+            LutProfile::Otp | LutProfile::FullMono => RefreshKind::Full,
+            LutProfile::FastMono => RefreshKind::Fast,
+            LutProfile::DeepClean => RefreshKind::DeepClean,
+        }
+    }
+}
+
+/// Cumulative refresh counts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Odometer {
+    /// full refreshes, with the `Otp` or `FullMono` profile
+    pub full_refreshes: u32,
+    /// fast refreshes, with the `FastMono` profile
+    pub fast_refreshes: u32,
+    /// partial refreshes, of one or more windows
+    pub partial_refreshes: u32,
+    /// deep cleans, with the `DeepClean` profile
+    pub deep_cleans: u32,
+    /// fast and partial refreshes since the last full refresh or deep clean
+    pub since_full: u32,
+}
+
+impl Odometer {
+    /// Stress of a fast refresh, the unit of [Odometer::stress].
+    pub const FAST_STRESS: u32 = 1;
+    /// Stress of a partial refresh, which only drives the pixels in its windows.
+    pub const PARTIAL_STRESS: u32 = 1;
+    /// Stress of a full refresh, which drives every pixel through several phases.
+    pub const FULL_STRESS: u32 = 4;
+    /// Stress of a deep clean, a full refresh repeated twice as often.
+    pub const DEEP_CLEAN_STRESS: u32 = 8;
+
+    /// Count a refresh.
+    pub fn record(&mut self, kind: RefreshKind) {
+        match kind {
+            RefreshKind::Full => {
+                self.full_refreshes = self.full_refreshes.saturating_add(1);
+                self.since_full = 0;
+            }
+            RefreshKind::Fast => {
+                self.fast_refreshes = self.fast_refreshes.saturating_add(1);
+                self.since_full = self.since_full.saturating_add(1);
+            }
+            RefreshKind::Partial => {
+                self.partial_refreshes = self.partial_refreshes.saturating_add(1);
+                self.since_full = self.since_full.saturating_add(1);
+            }
+            RefreshKind::DeepClean => {
+                self.deep_cleans = self.deep_cleans.saturating_add(1);
+                self.since_full = 0;
+            }
+        }
+    }
+
+    /// Returns the total number of refreshes.
+    pub fn refreshes(&self) -> u32 {
+        self.full_refreshes
+            .saturating_add(self.fast_refreshes)
+            .saturating_add(self.partial_refreshes)
+            .saturating_add(self.deep_cleans)
+    }
+
+    /// Returns a rough estimate of the wear on the panel, in fast refreshes.
+    pub fn stress(&self) -> u32 {
+        self.fast_refreshes
+            .saturating_mul(Self::FAST_STRESS)
+            .saturating_add(self.partial_refreshes.saturating_mul(Self::PARTIAL_STRESS))
+            .saturating_add(self.full_refreshes.saturating_mul(Self::FULL_STRESS))
+            .saturating_add(self.deep_cleans.saturating_mul(Self::DEEP_CLEAN_STRESS))
+    }
+
+    /// Returns the counts as bytes for storage, each little endian.
+    pub fn to_bytes(&self) -> [u8; 20] {
+        let mut bytes = [0u8; 20];
+        let counts = [
+            self.full_refreshes,
+            self.fast_refreshes,
+            self.deep_cleans,
+            self.since_full,
+            self.partial_refreshes,
+        ];
+        for (chunk, count) in bytes.chunks_mut(4).zip(counts.iter()) {
+            chunk.copy_from_slice(&count.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Restore counts stored with [Odometer::to_bytes].
+    pub fn from_bytes(bytes: &[u8; 20]) -> Self {
+        let count =
+            |i: usize| u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+        Odometer {
+            full_refreshes: count(0),
+            fast_refreshes: count(4),
+            deep_cleans: count(8),
+            since_full: count(12),
+            partial_refreshes: count(16),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_and_restore() {
+        let mut odometer = Odometer::default();
+        odometer.record(RefreshKind::for_profile(LutProfile::Otp));
+        odometer.record(RefreshKind::for_profile(LutProfile::FastMono));
+        odometer.record(RefreshKind::Partial);
+        assert_eq!(odometer.since_full, 2);
+        assert_eq!(odometer.stress(), 6);
+        odometer.record(RefreshKind::for_profile(LutProfile::DeepClean));
+        assert_eq!(odometer.since_full, 0);
+        assert_eq!(odometer.refreshes(), 4);
+        assert_eq!(Odometer::from_bytes(&odometer.to_bytes()), odometer);
+    }
+}
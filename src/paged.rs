@@ -0,0 +1,260 @@
+//! Rendering in bands, for panels too large to buffer on small MCUs.
+//!
+//! A [PagedDisplay] only needs a buffer for a band of rows instead of two full frame buffers.
+//! The frame is drawn again for every band of each plane, and each band is streamed to the
+//! controller as it is finished, so drawing must be repeatable and trades time for memory.
+
+use core::convert::Infallible;
+use core::ops::{Deref, DerefMut};
+
+use crate::color::Color;
+use crate::config::Config;
+use crate::display::{Display, Rotation};
+use crate::error::Error;
+use crate::graphics::plane_index;
+use crate::interface::DisplayInterface;
+use crate::regs;
+use embedded_graphics_core::prelude::*;
+
+/// A display that renders in bands of rows, with a band sized buffer.
+///
+/// ### Example
+///
+/// ```ignore
+/// // 16 rows of a 128 column panel
+/// let mut band = [0u8; 16 * 128 / 8];
+/// let mut display = PagedDisplay::new(display, &mut band);
+/// display.draw(|target| {
+///     Text::new("Hello", Point::new(0, 10), style).draw(target)?;
+///     Ok(())
+/// })?;
+/// ```
+pub struct PagedDisplay<'a, I>
+where
+    I: DisplayInterface,
+{
+    display: Display<I>,
+    buffer: &'a mut [u8],
+}
+
+impl<'a, I> PagedDisplay<'a, I>
+where
+    I: DisplayInterface,
+{
+    /// Promote a `Display` to a `PagedDisplay`.
+    ///
+    /// The buffer holds a band of whole rows in the controller's native orientation, so its
+    /// length must be a non-zero multiple of `cols` / `8`, panics otherwise.
+    pub fn new(display: Display<I>, buffer: &'a mut [u8]) -> Self {
+        let stride = display.cols() as usize / 8;
+        assert!(
+            !buffer.is_empty() && buffer.len().is_multiple_of(stride),
+            "band buffer must hold whole rows"
+        );
+        PagedDisplay { display, buffer }
+    }
+
+    /// Release the display.
+    pub fn release(self) -> Display<I> {
+        self.display
+    }
+
+    /// Draw the frame and refresh the display.
+    ///
+    /// `draw` is called once for every band of each plane, and must draw the same frame every
+    /// time. Pixels outside the current band are dropped, so it can draw the whole frame each
+    /// time, but skipping drawables outside [Band::bounding_box] saves time.
+    pub fn draw<F>(&mut self, mut draw: F) -> Result<(), Error<I::Error>>
+    where
+        F: FnMut(&mut Band<'_>) -> Result<(), Infallible>,
+    {
+        let frame = self.display.rows() as usize * self.display.cols() as usize / 8;
+        for layer in 0..2 {
+            let uploaded = self.upload_layer(layer, frame, &mut draw);
+            if uploaded.is_err() {
+                self.display.record_bus_error();
+                self.display.record_refresh(false);
+            }
+            uploaded.map_err(Error::Interface)?;
+            self.display.record_upload(layer);
+        }
+        self.display.signal_update().map_err(Error::Interface)
+    }
+
+    // draw and send each band of a layer
+    fn upload_layer<F>(&mut self, layer: u8, frame: usize, draw: &mut F) -> Result<(), I::Error>
+    where
+        F: FnMut(&mut Band<'_>) -> Result<(), Infallible>,
+    {
+        let config = *self.display.config();
+        let invert = if layer == 0 {
+            config.invert_black_plane()
+        } else {
+            config.invert_red_plane()
+        };
+        self.display
+            .interface()
+            .send_command(regs::data_transmission(layer))?;
+        let mut start = 0;
+        while start < frame {
+            let len = self.buffer.len().min(frame - start);
+            let buffer = &mut self.buffer[..len];
+            for b in buffer.iter_mut() {
+                *b = 0xFF;
+            }
+            let mut band = Band {
+                buffer,
+                start,
+                layer,
+                config: &config,
+            };
+            draw(&mut band).ok();
+            if invert {
+                for b in band.buffer.iter_mut() {
+                    *b = !*b;
+                }
+            }
+            self.display.interface().send_data(&self.buffer[..len])?;
+            start += len;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, I> Deref for PagedDisplay<'a, I>
+where
+    I: DisplayInterface,
+{
+    type Target = Display<I>;
+
+    fn deref(&self) -> &Display<I> {
+        &self.display
+    }
+}
+
+impl<'a, I> DerefMut for PagedDisplay<'a, I>
+where
+    I: DisplayInterface,
+{
+    fn deref_mut(&mut self) -> &mut Display<I> {
+        &mut self.display
+    }
+}
+
+/// One band of one plane, the draw target passed to [PagedDisplay::draw].
+pub struct Band<'b> {
+    buffer: &'b mut [u8],
+    start: usize,
+    layer: u8,
+    config: &'b Config,
+}
+
+impl<'b> DrawTarget for Band<'b> {
+    type Color = Color;
+    type Error = Infallible;
+
+    fn draw_iter<ITR>(&mut self, pixels: ITR) -> Result<(), Self::Error>
+    where
+        ITR: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let size = self.size();
+        for Pixel(point, color) in pixels.into_iter() {
+            if point.x < 0
+                || point.y < 0
+                || point.x as u32 >= size.width
+                || point.y as u32 >= size.height
+            {
+                continue;
+            }
+            let (index, bit) = plane_index(point.x as u32, point.y as u32, self.config);
+            let byte = match index.checked_sub(self.start) {
+                Some(offset) if offset < self.buffer.len() => &mut self.buffer[offset],
+                _ => continue,
+            };
+            let ink = match self.layer {
+                0 => color == Color::Black,
+                _ => color == Color::Red,
+            };
+            if ink {
+                *byte &= !bit;
+            } else {
+                *byte |= bit;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'b> OriginDimensions for Band<'b> {
+    fn size(&self) -> Size {
+        let dimensions = self.config.dimensions();
+        match self.config.rotation() {
+            Rotation::Rotate0 | Rotation::Rotate180 => {
+                Size::new(dimensions.cols.into(), dimensions.rows.into())
+            }
+            Rotation::Rotate90 | Rotation::Rotate270 => {
+                Size::new(dimensions.rows.into(), dimensions.cols.into())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::MockInterface;
+    use crate::{buffer_size, Builder, Dimensions, GraphicDisplay};
+    use embedded_graphics::primitives::{Circle, Primitive, PrimitiveStyle, Rectangle};
+
+    const ROWS: u16 = 24;
+    const COLS: u8 = 16;
+    const BUFFER_SIZE: usize = buffer_size(ROWS, COLS);
+
+    fn build_display(rotation: Rotation) -> Display<MockInterface> {
+        let config = Builder::new()
+            .dimensions(Dimensions {
+                rows: ROWS,
+                cols: COLS,
+            })
+            .rotation(rotation)
+            .build()
+            .expect("invalid config");
+        Display::new(MockInterface::new(), config)
+    }
+
+    fn scene<D: DrawTarget<Color = Color>>(target: &mut D) -> Result<(), D::Error> {
+        Rectangle::new(Point::new(1, 2), Size::new(9, 13))
+            .into_styled(PrimitiveStyle::with_fill(Color::Black))
+            .draw(target)?;
+        Circle::new(Point::new(4, 6), 8)
+            .into_styled(PrimitiveStyle::with_fill(Color::Red))
+            .draw(target)?;
+        Pixel(Point::new(15, 0), Color::Black).draw(target)
+    }
+
+    #[test]
+    fn matches_full_buffers() {
+        for rotation in [Rotation::Rotate0, Rotation::Rotate90].iter() {
+            let mut black = [0u8; BUFFER_SIZE];
+            let mut red = [0u8; BUFFER_SIZE];
+            {
+                let mut display =
+                    GraphicDisplay::new(build_display(*rotation), &mut black, &mut red);
+                display.clear(Color::White).ok();
+                scene(&mut display).ok();
+            }
+
+            // 5 rows per band, the last band is short
+            let mut band = [0u8; 10];
+            let mut display = PagedDisplay::new(build_display(*rotation), &mut band);
+            display.draw(|target| scene(target)).ok();
+            let data = &display.interface().data;
+
+            assert_eq!(data[0], 0x10);
+            assert_eq!(data[1..=BUFFER_SIZE], black[..]);
+            assert_eq!(data[BUFFER_SIZE + 1], 0x13);
+            assert_eq!(data[BUFFER_SIZE + 2..2 * BUFFER_SIZE + 2], red[..]);
+            assert_eq!(data[2 * BUFFER_SIZE + 2..], [0x12]);
+        }
+    }
+}
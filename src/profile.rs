@@ -0,0 +1,301 @@
+//! Panel profiles stored alongside the firmware.
+//!
+//! A product built with panels from several suppliers, or several sizes, can keep a
+//! [PanelProfile] for its panel in EEPROM or a flash sector, written at the factory, and build
+//! the [Config] from it with
+//! [Builder::from_profile](../config/struct.Builder.html#method.from_profile). One firmware image
+//! then drives every SKU.
+//!
+//! The stored form is [PanelProfile::LEN] bytes:
+//!
+//! | Offset | Bytes | Contents |
+//! |--------|-------|----------|
+//! | 0 | 2 | [MAGIC], `"IL"` |
+//! | 2 | 1 | [VERSION] |
+//! | 3 | 1 | the length, [PanelProfile::LEN] |
+//! | 4 | 2 | rows, little endian |
+//! | 6 | 1 | columns |
+//! | 7 | 1 | panel setting (PSR), the bits other than the resolution must be those of `psr::DEFAULT` |
+//! | 8 | 1 | PLL control (PLL) |
+//! | 9 | 3 | power setting (PWR), VDH, VDL and VDHR |
+//! | 12 | 3 | booster soft start (BTST) |
+//! | 15 | 1 | VCOM and data interval setting (CDI), in the one byte form |
+//! | 16 | 1 | VCOM DC setting (VDCS) |
+//! | 17 | 1 | the [LutProfile], 0 for `Otp`, 1 `FullMono`, 2 `FastMono` and 3 `DeepClean` |
+//! | 18 | 1 | reserved, 0 |
+//! | 19 | 1 | checksum, the wrapping sum of the bytes before it |
+//!
+//! ### Example
+//!
+//! ```ignore
+//! let profile = PanelProfile::from_bytes(&eeprom.read())?;
+//! let config = Builder::from_profile(&profile)
+//!     .rotation(Rotation::Rotate90)
+//!     .build()?;
+//! ```
+//!
+//! [Config]: ../config/struct.Config.html
+//! [LutProfile]: ../luts/enum.LutProfile.html
+
+use crate::command::{self, DataInterval, DataPolarity, DisplayResolution, VcomDc, Vdh, Vdhr, Vdl};
+use crate::config::Builder;
+use crate::display::Dimensions;
+use crate::luts::LutProfile;
+use crate::regs::{self, cdi, psr};
+
+/// The first bytes of a stored profile.
+pub const MAGIC: [u8; 2] = *b"IL";
+
+/// The version of the stored form written by [PanelProfile::to_bytes].
+pub const VERSION: u8 = 1;
+
+/// Error returned if stored bytes aren't a valid profile.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProfileError {
+    /// Fewer than [PanelProfile::LEN] bytes were given.
+    Truncated,
+    /// The bytes don't start with [MAGIC], such as erased EEPROM.
+    BadMagic,
+    /// The profile was written by another version, or with another length.
+    UnsupportedVersion {
+        /// The stored version.
+        version: u8,
+        /// The stored length.
+        len: u8,
+    },
+    /// The checksum doesn't match, the profile was corrupted.
+    Checksum,
+    /// A field is out of the range the controller accepts.
+    InvalidField {
+        /// The offset of the field in the stored bytes.
+        offset: usize,
+    },
+}
+
+/// The settings that differ between panels, see the [module documentation](index.html) for the
+/// stored form.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PanelProfile {
+    /// the panel dimensions, in the controller's native orientation
+    pub dimensions: Dimensions,
+    /// the resolution of the panel setting (PSR)
+    pub resolution: DisplayResolution,
+    /// the PLL control (PLL) value
+    pub pll: u8,
+    /// the power setting (PWR) levels
+    pub power: (Vdh, Vdl, Vdhr),
+    /// the booster soft start (BTST) values
+    pub booster_soft_start: [u8; 3],
+    /// the border data of the VCOM and data interval setting (CDI)
+    pub border_data: u8,
+    /// the data polarity of the VCOM and data interval setting (CDI)
+    pub data_polarity: DataPolarity,
+    /// the data interval of the VCOM and data interval setting (CDI)
+    pub data_interval: DataInterval,
+    /// the VCOM DC setting (VDCS)
+    pub vcom_dc: VcomDc,
+    /// the waveforms used to refresh
+    pub lut_profile: LutProfile,
+}
+
+impl PanelProfile {
+    /// The length of the stored form.
+    pub const LEN: usize = 20;
+
+    /// Create a profile for a panel of `dimensions` with the defaults of
+    /// [Builder::new](../config/struct.Builder.html#method.new).
+    ///
+    /// The resolution is the smallest containing the dimensions, or the largest if none does.
+    pub const fn new(dimensions: Dimensions) -> Self {
+        Builder::new().to_profile(dimensions)
+    }
+
+    /// Returns the profile as bytes for storage.
+    pub fn to_bytes(&self) -> [u8; Self::LEN] {
+        let mut bytes = [0u8; Self::LEN];
+        bytes[0..2].copy_from_slice(&MAGIC);
+        bytes[2] = VERSION;
+        bytes[3] = Self::LEN as u8;
+        bytes[4..6].copy_from_slice(&self.dimensions.rows.to_le_bytes());
+        bytes[6] = self.dimensions.cols;
+        bytes[7] = command::resolution_bits(self.resolution) | psr::DEFAULT;
+        bytes[8] = self.pll;
+        bytes[9] = self.power.0.value();
+        bytes[10] = self.power.1.value();
+        bytes[11] = self.power.2.value();
+        bytes[12..15].copy_from_slice(&self.booster_soft_start);
+        bytes[15] = regs::cdi_value(
+            self.border_data,
+            command::ddx_bits(self.data_polarity),
+            self.data_interval.value(),
+        );
+        bytes[16] = self.vcom_dc.value();
+        bytes[17] = match self.lut_profile {
+            LutProfile::Otp => 0,
+            LutProfile::FullMono => 1,
+            LutProfile::FastMono => 2,
+            LutProfile::DeepClean => 3,
+        };
+        bytes[Self::LEN - 1] = checksum(&bytes[..Self::LEN - 1]);
+        bytes
+    }
+
+    /// Restore a profile stored with [PanelProfile::to_bytes].
+    ///
+    /// Bytes past [PanelProfile::LEN] are ignored, so a whole EEPROM page can be passed. The
+    /// dimensions are checked when the config is built, not here.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ProfileError> {
+        if bytes.len() < Self::LEN {
+            return Err(ProfileError::Truncated);
+        }
+        if bytes[0..2] != MAGIC {
+            return Err(ProfileError::BadMagic);
+        }
+        if bytes[2] != VERSION || bytes[3] as usize != Self::LEN {
+            return Err(ProfileError::UnsupportedVersion {
+                version: bytes[2],
+                len: bytes[3],
+            });
+        }
+        if checksum(&bytes[..Self::LEN - 1]) != bytes[Self::LEN - 1] {
+            return Err(ProfileError::Checksum);
+        }
+        let invalid = |offset| ProfileError::InvalidField { offset };
+        // the driver always scans up and right with the booster on, so a profile asking for
+        // anything else can't be honoured
+        if bytes[7] & !psr::RES_160X296 != psr::DEFAULT {
+            return Err(invalid(7));
+        }
+        // both RES bits are set for the largest resolution
+        let resolution = match bytes[7] & psr::RES_160X296 {
+            psr::RES_96X230 => DisplayResolution::R96x230,
+            psr::RES_96X252 => DisplayResolution::R96x252,
+            psr::RES_128X296 => DisplayResolution::R128x296,
+            _ => DisplayResolution::R160x296,
+        };
+        let data_polarity = match bytes[15] & cdi::DDX_BOTH {
+            cdi::DDX_BW => DataPolarity::BWOnly,
+            cdi::DDX_RED => DataPolarity::RedOnly,
+            cdi::DDX_BOTH => DataPolarity::Both,
+            _ => return Err(invalid(15)),
+        };
+        // the register counts down from 17 frames
+        let data_interval = match DataInterval::from_frames(17 - (bytes[15] & cdi::INTERVAL_MASK)) {
+            Some(interval) => interval,
+            None => return Err(invalid(15)),
+        };
+        Ok(PanelProfile {
+            dimensions: Dimensions {
+                rows: u16::from_le_bytes([bytes[4], bytes[5]]),
+                cols: bytes[6],
+            },
+            resolution,
+            pll: bytes[8],
+            power: (
+                Vdh::new(bytes[9]).ok_or(invalid(9))?,
+                Vdl::new(bytes[10]).ok_or(invalid(10))?,
+                Vdhr::new(bytes[11]).ok_or(invalid(11))?,
+            ),
+            booster_soft_start: [bytes[12], bytes[13], bytes[14]],
+            border_data: bytes[15] >> cdi::VBD_SHIFT,
+            data_polarity,
+            data_interval,
+            vcom_dc: VcomDc::new(bytes[16]).ok_or(invalid(16))?,
+            lut_profile: match bytes[17] {
+                0 => LutProfile::Otp,
+                1 => LutProfile::FullMono,
+                2 => LutProfile::FastMono,
+                3 => LutProfile::DeepClean,
+                _ => return Err(invalid(17)),
+            },
+        })
+    }
+}
+
+// the wrapping sum of the bytes
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::Command;
+
+    fn profile() -> PanelProfile {
+        PanelProfile {
+            power: (
+                Vdh::new_unchecked(0x26),
+                Vdl::new_unchecked(0x26),
+                Vdhr::new_unchecked(0x3),
+            ),
+            border_data: 0b10,
+            data_interval: DataInterval::V7,
+            vcom_dc: VcomDc::new_unchecked(0x12),
+            lut_profile: LutProfile::FastMono,
+            ..PanelProfile::new(Dimensions {
+                rows: 212,
+                cols: 104,
+            })
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let bytes = profile().to_bytes();
+        assert_eq!(bytes[..8], [b'I', b'L', 1, 20, 212, 0, 104, 0x8F]);
+        assert_eq!(bytes[15], 0xBA);
+        let mut page = [0xFFu8; 32];
+        page[..PanelProfile::LEN].copy_from_slice(&bytes);
+        assert_eq!(PanelProfile::from_bytes(&page), Ok(profile()));
+    }
+
+    #[test]
+    fn errors() {
+        let bytes = profile().to_bytes();
+        assert_eq!(
+            PanelProfile::from_bytes(&bytes[..19]),
+            Err(ProfileError::Truncated)
+        );
+        assert_eq!(
+            PanelProfile::from_bytes(&[0xFF; 20]),
+            Err(ProfileError::BadMagic)
+        );
+        let mut corrupt = bytes;
+        corrupt[8] ^= 0x01;
+        assert_eq!(
+            PanelProfile::from_bytes(&corrupt),
+            Err(ProfileError::Checksum)
+        );
+        let mut invalid = bytes;
+        invalid[16] = 0x3F;
+        invalid[19] = checksum(&invalid[..19]);
+        assert_eq!(
+            PanelProfile::from_bytes(&invalid),
+            Err(ProfileError::InvalidField { offset: 16 })
+        );
+        // scanning down can't be set in the config
+        let mut invalid = bytes;
+        invalid[7] &= !psr::UD;
+        invalid[19] = checksum(&invalid[..19]);
+        assert_eq!(
+            PanelProfile::from_bytes(&invalid),
+            Err(ProfileError::InvalidField { offset: 7 })
+        );
+    }
+
+    #[test]
+    fn builds_config() {
+        let config = Builder::from_profile(&profile()).build().unwrap();
+        assert_eq!(config.dimensions(), profile().dimensions);
+        assert_eq!(config.lut_profile(), LutProfile::FastMono);
+        assert!(matches!(
+            config.vcom_data_interval(),
+            Command::VCOMDataIntervalSetting(0b10, DataPolarity::Both, DataInterval::V7)
+        ));
+        assert!(matches!(
+            config.power_setting(),
+            Command::PowerSetting(vdh, _, vdhr) if vdh.value() == 0x26 && vdhr.value() == 0x3
+        ));
+    }
+}
@@ -0,0 +1,193 @@
+//! Command opcodes and register fields of the IL0373.
+//!
+//! The names follow the datasheet, and [Command](../command/enum.Command.html) is built from
+//! them. They are public for writing raw sequences, such as an init table for
+//! [Display::init_from_table](../display/struct.Display.html#method.init_from_table), or for
+//! checking what was sent in tests, without repeating magic numbers.
+//!
+//! ### Example
+//!
+//! ```ignore
+//! use il0373::regs::{self, cdi, psr, CDI, PSR};
+//!
+//! batch
+//!     .push_raw(PSR, &[psr::RES_96X230 | psr::DEFAULT])
+//!     .push_raw(CDI, &[regs::cdi_value(3, cdi::DDX_BOTH, 7)]);
+//! ```
+
+/// Panel setting.
+pub const PSR: u8 = 0x00;
+/// Power setting.
+pub const PWR: u8 = 0x01;
+/// Power off.
+pub const POF: u8 = 0x02;
+/// Power off sequence setting.
+pub const PFS: u8 = 0x03;
+/// Power on.
+pub const PON: u8 = 0x04;
+/// Power on measure.
+pub const PMES: u8 = 0x05;
+/// Booster soft start.
+pub const BTST: u8 = 0x06;
+/// Deep sleep, followed by [DSLP_CHECK].
+pub const DSLP: u8 = 0x07;
+/// Display start transmission 1, the B/W plane.
+pub const DTM1: u8 = 0x10;
+/// Data stop.
+pub const DSP: u8 = 0x11;
+/// Display refresh.
+pub const DRF: u8 = 0x12;
+/// Display start transmission 2, the Red plane.
+pub const DTM2: u8 = 0x13;
+/// Dual SPI.
+pub const DUSPI: u8 = 0x15;
+/// VCOM LUT.
+pub const LUTC: u8 = 0x20;
+/// White to white LUT.
+pub const LUTWW: u8 = 0x21;
+/// Black to white LUT.
+pub const LUTBW: u8 = 0x22;
+/// White to black LUT.
+pub const LUTWB: u8 = 0x23;
+/// Black to black LUT.
+pub const LUTBB: u8 = 0x24;
+/// PLL control, the frame rate.
+pub const PLL: u8 = 0x30;
+/// Temperature sensor calibration.
+pub const TSC: u8 = 0x40;
+/// Temperature sensor selection.
+pub const TSE: u8 = 0x41;
+/// Temperature sensor write.
+pub const TSW: u8 = 0x42;
+/// Temperature sensor read.
+pub const TSR: u8 = 0x43;
+/// VCOM and data interval setting.
+pub const CDI: u8 = 0x50;
+/// Low power detection.
+pub const LPD: u8 = 0x51;
+/// TCON setting.
+pub const TCON: u8 = 0x60;
+/// Resolution setting.
+pub const TRES: u8 = 0x61;
+/// Gate/source start setting.
+pub const GSST: u8 = 0x65;
+/// Revision.
+pub const REV: u8 = 0x70;
+/// Get status.
+pub const FLG: u8 = 0x71;
+/// Auto measure VCOM.
+pub const AMV: u8 = 0x80;
+/// VCOM value.
+pub const VV: u8 = 0x81;
+/// VCOM DC setting.
+pub const VDCS: u8 = 0x82;
+/// Partial window.
+pub const PTL: u8 = 0x90;
+/// Partial in.
+pub const PTIN: u8 = 0x91;
+/// Partial out.
+pub const PTOUT: u8 = 0x92;
+/// Program mode.
+pub const PGM: u8 = 0xA0;
+/// Active programming.
+pub const APG: u8 = 0xA1;
+/// Read OTP.
+pub const ROTP: u8 = 0xA2;
+/// Power saving.
+pub const PWS: u8 = 0xE3;
+
+/// The check code following [DSLP].
+pub const DSLP_CHECK: u8 = 0xA5;
+
+/// Fields of [PSR].
+pub mod psr {
+    /// 96x230 resolution.
+    pub const RES_96X230: u8 = 0b0000_0000;
+    /// 96x252 resolution.
+    pub const RES_96X252: u8 = 0b0100_0000;
+    /// 128x296 resolution.
+    pub const RES_128X296: u8 = 0b1000_0000;
+    /// 160x296 resolution.
+    pub const RES_160X296: u8 = 0b1100_0000;
+    /// LUT from the registers rather than OTP.
+    pub const REG_EN: u8 = 0b0010_0000;
+    /// Black and white mode, without the Red plane.
+    pub const BWR: u8 = 0b0001_0000;
+    /// Gates scan up.
+    pub const UD: u8 = 0b0000_1000;
+    /// Sources shift right.
+    pub const SHL: u8 = 0b0000_0100;
+    /// Booster on.
+    pub const SHD_N: u8 = 0b0000_0010;
+    /// Not in soft reset, clear to reset.
+    pub const RST_N: u8 = 0b0000_0001;
+    /// Scanning up and right with the booster on, as used for every panel.
+    pub const DEFAULT: u8 = UD | SHL | SHD_N | RST_N;
+}
+
+/// Fields of [PWR].
+pub mod pwr {
+    /// Internal source power.
+    pub const VDS_EN: u8 = 0b0000_0010;
+    /// Internal gate power.
+    pub const VDG_EN: u8 = 0b0000_0001;
+}
+
+/// Fields of [DUSPI].
+pub mod duspi {
+    /// MM input pin enabled.
+    pub const MM_EN: u8 = 0b0010_0000;
+    /// Dual SPI enabled.
+    pub const DUSPI_EN: u8 = 0b0001_0000;
+}
+
+/// Fields of [CDI].
+pub mod cdi {
+    /// The shift of the border data, VBD.
+    pub const VBD_SHIFT: u8 = 6;
+    /// Only the B/W plane is used.
+    pub const DDX_BW: u8 = 0b0001_0000;
+    /// Only the Red plane is used.
+    pub const DDX_RED: u8 = 0b0010_0000;
+    /// Both planes are used.
+    pub const DDX_BOTH: u8 = 0b0011_0000;
+    /// The mask of the data interval, CDI.
+    pub const INTERVAL_MASK: u8 = 0b0000_1111;
+}
+
+/// Fields of [PTL].
+pub mod ptl {
+    /// Scan outside the window as well.
+    pub const PT_SCAN: u8 = 0b0000_0001;
+}
+
+/// Returns the [CDI] byte for a border data, polarity and interval.
+///
+/// `border` is the two bits of VBD, `ddx` one of the DDX fields of [cdi] and `interval` is masked
+/// to its four bits.
+pub const fn cdi_value(border: u8, ddx: u8, interval: u8) -> u8 {
+    ((border & 0b11) << cdi::VBD_SHIFT) | ddx | (interval & cdi::INTERVAL_MASK)
+}
+
+/// Returns the data start transmission command of a layer, [DTM1] for layer 0, the B/W plane,
+/// and [DTM2] for the Red plane.
+pub const fn data_transmission(layer: u8) -> u8 {
+    if layer == 0 {
+        DTM1
+    } else {
+        DTM2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields() {
+        assert_eq!(psr::RES_160X296 | psr::DEFAULT, 0xCF);
+        assert_eq!(cdi_value(0, cdi::DDX_BOTH, 7), 0x37);
+        assert_eq!(cdi_value(3, cdi::DDX_BW, 0x1F), 0xDF);
+        assert_eq!((data_transmission(0), data_transmission(1)), (DTM1, DTM2));
+    }
+}
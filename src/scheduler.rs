@@ -0,0 +1,276 @@
+//! Pacing refreshes to protect the panel.
+//!
+//! E-paper panels are damaged by refreshing too often, tri-color panels should be refreshed no
+//! more than once every [MIN_REFRESH_INTERVAL_MS], and fast refreshes of black and white panels
+//! leave ghosts unless followed by a full refresh every few updates. An [UpdatePolicy] describes
+//! these limits, along with hours when the display shouldn't flash at all, and a
+//! [DisplayScheduler] applies it.
+//!
+//! The application draws whenever it has something new to show. The scheduler only keeps the
+//! latest frame, in the buffers of the display, and sends it from [DisplayScheduler::poll] once
+//! the policy allows, so a super-loop can call `poll` on every pass.
+//!
+//! ### Example
+//!
+//! ```ignore
+//! let policy = UpdatePolicy::new().full_refresh_every(5).quiet_hours(22, 7);
+//! let mut scheduler = DisplayScheduler::new(display, policy);
+//! loop {
+//!     if let Some(reading) = sensor.poll() {
+//!         draw_reading(scheduler.frame(), reading)?;
+//!     }
+//!     scheduler.poll(&mut delay, Now::ms(clock.now_ms()).at_hour(rtc.hour()))?;
+//! }
+//! ```
+
+use crate::display::PanelState;
+use crate::error::Error;
+use crate::graphics::{FrameBufferStore, GraphicDisplay};
+use crate::interface::DisplayInterface;
+use crate::luts::LutProfile;
+use crate::slideshow::MIN_REFRESH_INTERVAL_MS;
+
+/// When a [DisplayScheduler] may refresh the panel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UpdatePolicy {
+    min_interval_ms: u32,
+    full_every: u32,
+    quiet_hours: Option<(u8, u8)>,
+}
+
+impl Default for UpdatePolicy {
+    fn default() -> Self {
+        UpdatePolicy::new()
+    }
+}
+
+impl UpdatePolicy {
+    /// Create a policy refreshing at most every [MIN_REFRESH_INTERVAL_MS], at any hour.
+    pub const fn new() -> Self {
+        UpdatePolicy {
+            min_interval_ms: MIN_REFRESH_INTERVAL_MS,
+            full_every: 0,
+            quiet_hours: None,
+        }
+    }
+
+    /// Set the shortest time between refreshes.
+    ///
+    /// Black and white panels with a fast LUT can be refreshed more often than the default, down
+    /// to no interval at all.
+    pub const fn min_interval_ms(self, min_interval_ms: u32) -> Self {
+        Self {
+            min_interval_ms,
+            ..self
+        }
+    }
+
+    /// Make every `n`th refresh a full one, on a display using `LutProfile::FastMono`.
+    ///
+    /// Zero, the default, never forces a full refresh. Displays using any other profile always
+    /// refresh fully.
+    pub const fn full_refresh_every(self, n: u32) -> Self {
+        Self {
+            full_every: n,
+            ..self
+        }
+    }
+
+    /// Don't refresh from hour `start` until hour `end`, in the 24 hour clock of the [Now]
+    /// passed to [DisplayScheduler::poll].
+    ///
+    /// The quiet hours wrap past midnight if `start` is after `end`, `quiet_hours(22, 7)` is
+    /// quiet from 22:00 to 06:59. A frame drawn during the quiet hours is shown once they end.
+    pub const fn quiet_hours(self, start: u8, end: u8) -> Self {
+        Self {
+            quiet_hours: Some((start, end)),
+            ..self
+        }
+    }
+
+    /// Returns the shortest time between refreshes.
+    pub const fn interval_ms(&self) -> u32 {
+        self.min_interval_ms
+    }
+
+    /// Returns how many refreshes there are for each full one, zero if they aren't forced.
+    pub const fn full_every(&self) -> u32 {
+        self.full_every
+    }
+
+    /// Returns true if `hour` is in the quiet hours.
+    pub const fn is_quiet(&self, hour: u8) -> bool {
+        match self.quiet_hours {
+            Some((start, end)) if start <= end => hour >= start && hour < end,
+            Some((start, end)) => hour >= start || hour < end,
+            None => false,
+        }
+    }
+}
+
+/// The time passed to [DisplayScheduler::poll].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Now {
+    /// milliseconds from a free running clock, as [Clock::now_ms], wrapping
+    ///
+    /// [Clock::now_ms]: ../timing/trait.Clock.html#tymethod.now_ms
+    pub ms: u32,
+    /// the hour of the day, `None` if there is no real time clock
+    pub hour: Option<u8>,
+}
+
+impl Now {
+    /// Create a time without an hour of the day, the quiet hours are ignored.
+    pub const fn ms(ms: u32) -> Self {
+        Now { ms, hour: None }
+    }
+
+    /// Set the hour of the day, from 0 to 23.
+    pub const fn at_hour(self, hour: u8) -> Self {
+        Self {
+            hour: Some(hour),
+            ..self
+        }
+    }
+}
+
+/// The kind of refresh started by [DisplayScheduler::poll].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Refresh {
+    /// the whole panel was driven, clearing any ghosting
+    Full,
+    /// only changed pixels were driven, with `LutProfile::FastMono`
+    Fast,
+}
+
+/// Shows the latest frame drawn on a display, as often as an [UpdatePolicy] allows.
+pub struct DisplayScheduler<I, S>
+where
+    I: DisplayInterface,
+    S: FrameBufferStore<I>,
+{
+    display: GraphicDisplay<I, S>,
+    policy: UpdatePolicy,
+    pending: bool,
+    sleep: bool,
+    last_ms: Option<u32>,
+    since_full: u32,
+}
+
+impl<I, S> DisplayScheduler<I, S>
+where
+    I: DisplayInterface,
+    S: FrameBufferStore<I>,
+{
+    /// Create a scheduler with nothing to show.
+    pub fn new(display: GraphicDisplay<I, S>, policy: UpdatePolicy) -> Self {
+        DisplayScheduler {
+            display,
+            policy,
+            pending: false,
+            sleep: false,
+            last_ms: None,
+            since_full: 0,
+        }
+    }
+
+    /// Put the controller into deep sleep after each refresh, and reset it before the next.
+    ///
+    /// Saves power when refreshes are minutes apart. Off by default.
+    pub fn sleep_between(self, sleep: bool) -> Self {
+        Self { sleep, ..self }
+    }
+
+    /// Returns the display to draw the next frame on, which is then shown by [Self::poll].
+    ///
+    /// Anything drawn before the frame is shown replaces the earlier drawing, only the latest
+    /// frame is kept.
+    pub fn frame(&mut self) -> &mut GraphicDisplay<I, S> {
+        self.pending = true;
+        &mut self.display
+    }
+
+    /// Returns the display, without queueing a frame.
+    pub fn display(&self) -> &GraphicDisplay<I, S> {
+        &self.display
+    }
+
+    /// Returns the policy.
+    pub fn policy(&self) -> &UpdatePolicy {
+        &self.policy
+    }
+
+    /// Returns true if a frame has been drawn but not shown.
+    pub fn is_pending(&self) -> bool {
+        self.pending
+    }
+
+    /// Returns true if the policy allows a refresh at `now`.
+    ///
+    /// The first refresh is allowed at any time outside the quiet hours.
+    pub fn ready(&self, now: Now) -> bool {
+        if now.hour.is_some_and(|hour| self.policy.is_quiet(hour)) {
+            return false;
+        }
+        match self.last_ms {
+            Some(last) => now.ms.wrapping_sub(last) >= self.policy.min_interval_ms,
+            None => true,
+        }
+    }
+
+    /// Show the queued frame if the policy allows a refresh at `now`.
+    ///
+    /// Resets the controller if it isn't awake, sends the buffers and waits for the refresh to
+    /// finish. The first refresh is always a full one. Returns the kind of refresh, or `None` if
+    /// there was nothing to show or it is too early. The interval is counted from the attempt,
+    /// so a failing refresh isn't retried sooner than a successful one would be.
+    pub fn poll<D: hal::blocking::delay::DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+        now: Now,
+    ) -> Result<Option<Refresh>, Error<I::Error>> {
+        if !self.pending || !self.ready(now) {
+            return Ok(None);
+        }
+        // what the panel shows is unknown before the first refresh, so it is a full one
+        let first = self.last_ms.replace(now.ms).is_none();
+        let profile = self.display.config().lut_profile();
+        let refresh = match profile {
+            LutProfile::FastMono if !first && !self.full_due() => Refresh::Fast,
+            _ => Refresh::Full,
+        };
+        if let PanelState::Unknown | PanelState::DeepSleep = self.display.panel_state() {
+            self.display.reset(delay).map_err(Error::Interface)?;
+        }
+        if profile == LutProfile::FastMono && refresh == Refresh::Full {
+            self.display.set_lut_profile(LutProfile::FullMono)?;
+        }
+        let result = self.display.update();
+        if result.is_ok() {
+            self.display.finish_refresh();
+        }
+        if self.display.config().lut_profile() != profile {
+            self.display.set_lut_profile(profile)?;
+        }
+        result?;
+        self.pending = false;
+        self.since_full = match refresh {
+            Refresh::Full => 0,
+            Refresh::Fast => self.since_full.saturating_add(1),
+        };
+        if self.sleep {
+            self.display.deep_sleep().map_err(Error::Interface)?;
+        }
+        Ok(Some(refresh))
+    }
+
+    // the next refresh completes a cycle of full_every
+    fn full_due(&self) -> bool {
+        self.policy.full_every != 0 && self.since_full + 1 >= self.policy.full_every
+    }
+
+    /// release the display
+    pub fn release(self) -> GraphicDisplay<I, S> {
+        self.display
+    }
+}
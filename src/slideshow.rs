@@ -0,0 +1,173 @@
+//! Showing a sequence of stored frames.
+//!
+//! A [Slideshow] takes frames from a [FrameSource], such as images in external flash, and shows
+//! them in turn. The application calls [Slideshow::next_frame] from its scheduler, the slideshow
+//! wakes the controller, updates the display and puts it back into deep sleep, no more often than
+//! the configured interval.
+//!
+//! ### Example
+//!
+//! ```ignore
+//! let mut slideshow = Slideshow::new(display, &mut black, &mut red, images)
+//!     .interval_ms(600_000);
+//! loop {
+//!     slideshow.next_frame(&mut delay, &mut clock)?;
+//!     // sleep until the next tick
+//! }
+//! ```
+
+use crate::display::Display;
+use crate::error::Error;
+use crate::interface::DisplayInterface;
+use crate::timing::Clock;
+
+/// The shortest time between refreshes recommended for tri-color panels.
+pub const MIN_REFRESH_INTERVAL_MS: u32 = 180_000;
+
+/// Provides the frames shown by a [Slideshow].
+pub trait FrameSource {
+    type Error;
+
+    /// Returns the number of frames.
+    fn len(&self) -> usize;
+
+    /// Returns true if there are no frames.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Fill the packed B/W and Red buffers with frame `index`.
+    ///
+    /// The planes are in the controller's usual polarity, the slideshow inverts them if the
+    /// `Config` inverts the plane.
+    fn load(&mut self, index: usize, black: &mut [u8], red: &mut [u8]) -> Result<(), Self::Error>;
+}
+
+/// Error from a [Slideshow].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlideshowError<E, S> {
+    /// The display failed.
+    Display(Error<E>),
+    /// The frame source failed.
+    Source(S),
+}
+
+/// Shows the frames from a [FrameSource] in turn, pacing the refreshes.
+pub struct Slideshow<'a, I, F>
+where
+    I: DisplayInterface,
+    F: FrameSource,
+{
+    display: Display<I>,
+    black: &'a mut [u8],
+    red: &'a mut [u8],
+    source: F,
+    index: usize,
+    interval_ms: u32,
+    last_ms: Option<u32>,
+}
+
+impl<'a, I, F> Slideshow<'a, I, F>
+where
+    I: DisplayInterface,
+    F: FrameSource,
+{
+    /// Create a slideshow, frames are loaded into the buffers before being sent.
+    ///
+    /// The buffers should be `rows` * `cols` / `8` in length. The interval defaults to
+    /// [MIN_REFRESH_INTERVAL_MS].
+    pub fn new(display: Display<I>, black: &'a mut [u8], red: &'a mut [u8], source: F) -> Self {
+        Slideshow {
+            display,
+            black,
+            red,
+            source,
+            index: 0,
+            interval_ms: MIN_REFRESH_INTERVAL_MS,
+            last_ms: None,
+        }
+    }
+
+    /// Set the time between frames, values below [MIN_REFRESH_INTERVAL_MS] are raised to it.
+    pub fn interval_ms(self, interval_ms: u32) -> Self {
+        Self {
+            interval_ms: interval_ms.max(MIN_REFRESH_INTERVAL_MS),
+            ..self
+        }
+    }
+
+    /// Returns the index of the next frame to be shown.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Show the next frame if the interval has passed since the last one.
+    ///
+    /// The first call always shows a frame. Returns the index of the frame shown, or `None` if
+    /// it is too early or there are no frames. After the last frame the slideshow starts again
+    /// from the first.
+    pub fn next_frame<D, C>(
+        &mut self,
+        delay: &mut D,
+        clock: &mut C,
+    ) -> Result<Option<usize>, SlideshowError<I::Error, F::Error>>
+    where
+        D: hal::blocking::delay::DelayMs<u8>,
+        C: Clock,
+    {
+        let now = clock.now_ms();
+        if let Some(last) = self.last_ms {
+            if now.wrapping_sub(last) < self.interval_ms {
+                return Ok(None);
+            }
+        }
+        if self.source.is_empty() {
+            return Ok(None);
+        }
+        if self.index >= self.source.len() {
+            self.index = 0;
+        }
+        let index = self.index;
+        self.source
+            .load(index, self.black, self.red)
+            .map_err(SlideshowError::Source)?;
+        self.show(delay).map_err(SlideshowError::Display)?;
+        self.last_ms = Some(now);
+        self.index = index + 1;
+        Ok(Some(index))
+    }
+
+    // wake the controller, send the buffers, refresh and sleep
+    fn show<D: hal::blocking::delay::DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<(), Error<I::Error>> {
+        let config = *self.display.config();
+        for (buf, invert) in [
+            (&mut *self.black, config.invert_black_plane()),
+            (&mut *self.red, config.invert_red_plane()),
+        ] {
+            if invert {
+                for b in buf.iter_mut() {
+                    *b = !*b;
+                }
+            }
+        }
+        self.display.reset(delay).map_err(Error::Interface)?;
+        let nbytes = (self.display.rows() as u32 * self.display.cols() as u32 / 8) as u16;
+        self.display
+            .interface()
+            .epd_update_data(0, nbytes, self.black)?;
+        self.display
+            .interface()
+            .epd_update_data(1, nbytes, self.red)?;
+        self.display.signal_update().map_err(Error::Interface)?;
+        // waits for the refresh before powering off
+        self.display.deep_sleep().map_err(Error::Interface)
+    }
+
+    /// Returns the display and the frame source.
+    pub fn release(self) -> (Display<I>, F) {
+        (self.display, self.source)
+    }
+}
@@ -0,0 +1,122 @@
+//! Packed sprites, drawn over the buffers with a transparent color.
+//!
+//! A [Sprite] holds its pixels as B/W and Red planes, packed like the buffers of a
+//! [GraphicDisplay](../graphics/struct.GraphicDisplay.html), so
+//! [GraphicDisplay::draw_sprite](../graphics/struct.GraphicDisplay.html#method.draw_sprite) can
+//! copy them a byte at a time. Pixels of the transparent color are skipped, so icons with an
+//! irregular outline, such as weather glyphs or a battery gauge, can be laid over a background
+//! without erasing a rectangle around them.
+//!
+//! ### Example
+//!
+//! ```ignore
+//! const SUN: Sprite = Sprite::new(&SUN_BLACK, 16).with_red(&SUN_RED);
+//! display.draw_sprite(&SUN, Point::new(100, 4), Some(Color::White))?;
+//! ```
+
+use crate::color::Color;
+
+/// A packed two plane image.
+///
+/// The planes use the convention of the controller, a set bit in the B/W plane is white and a
+/// cleared bit in the Red plane is red. Pixels are packed most significant bit first and each
+/// row starts on a byte boundary.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sprite<'a> {
+    black: &'a [u8],
+    red: Option<&'a [u8]>,
+    width: u32,
+}
+
+impl<'a> Sprite<'a> {
+    /// Create a black and white sprite `width` pixels wide from a packed B/W plane.
+    ///
+    /// The height is the number of whole rows in `black`.
+    pub const fn new(black: &'a [u8], width: u32) -> Self {
+        Sprite {
+            black,
+            red: None,
+            width,
+        }
+    }
+
+    /// Add a Red plane, the same size as the B/W plane.
+    ///
+    /// Pixels missing from a short plane aren't red.
+    pub const fn with_red(self, red: &'a [u8]) -> Self {
+        Self {
+            red: Some(red),
+            ..self
+        }
+    }
+
+    /// Returns the width in pixels.
+    pub const fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the height in pixels.
+    pub const fn height(&self) -> u32 {
+        match self.black.len().checked_div(self.stride()) {
+            Some(rows) => rows as u32,
+            None => 0,
+        }
+    }
+
+    /// Returns the color of a pixel, `None` outside the sprite.
+    pub fn pixel(&self, x: u32, y: u32) -> Option<Color> {
+        if x >= self.width || y >= self.height() {
+            return None;
+        }
+        let (black, red) = self.bits(y, x as i32);
+        Some(Color::from_planes(black & 0x80 != 0, red & 0x80 != 0))
+    }
+
+    // the number of bytes in a row
+    const fn stride(&self) -> usize {
+        (self.width as usize).div_ceil(8)
+    }
+
+    // the B/W and Red bits of the 8 pixels of row y from column x, which may be negative, in
+    // the most significant bit first. Bits outside the row are white.
+    pub(crate) fn bits(&self, y: u32, x: i32) -> (u8, u8) {
+        let start = y as usize * self.stride();
+        let stride = self.stride() as i32;
+        let window = |plane: &[u8]| {
+            let byte = |i: i32| {
+                if (0..stride).contains(&i) {
+                    plane.get(start + i as usize).copied().unwrap_or(0xFF)
+                } else {
+                    0xFF
+                }
+            };
+            let (index, shift) = (x.div_euclid(8), x.rem_euclid(8));
+            let word = (u16::from(byte(index)) << 8) | u16::from(byte(index + 1));
+            ((word << shift) >> 8) as u8
+        };
+        let red = self.red.map_or(0xFF, window);
+        (window(self.black), red)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bits() {
+        // 10 pixels wide, two rows
+        let black = [0b1010_0000, 0b0100_0000, 0b1111_1111, 0b1100_0000];
+        let red = [0xFF, 0b0111_1111, 0xFF, 0xFF];
+        let sprite = Sprite::new(&black, 10).with_red(&red);
+        assert_eq!(sprite.height(), 2);
+        assert_eq!(sprite.bits(0, 0), (0b1010_0000, 0xFF));
+        assert_eq!(sprite.bits(0, 4), (0b0000_0100, 0b1111_0111));
+        // before the start of the row
+        assert_eq!(sprite.bits(0, -3), (0b1111_0100, 0xFF));
+        assert_eq!(sprite.pixel(0, 0), Some(Color::White));
+        assert_eq!(sprite.pixel(1, 0), Some(Color::Black));
+        assert_eq!(sprite.pixel(8, 0), Some(Color::Red));
+        assert_eq!(sprite.pixel(10, 0), None);
+    }
+}
@@ -0,0 +1,167 @@
+//! Timing refreshes from the busy pin, for telemetry.
+//!
+//! A refresh takes longer as a panel ages, and a refresh that takes much longer, or shorter, than
+//! usual is an early sign of a failing panel or a low supply. [busy_events] watches the busy pin
+//! with the `Wait` trait of embedded-hal-async and yields a [BusyEvent] each time the controller
+//! becomes busy or idle, with the time it was busy, so an application can record the real
+//! duration of each refresh and alert when it drifts.
+//!
+//! The busy pin is owned by the interface, so the events are read from a second handle to the
+//! same pin, such as an interrupt capable input on most async HALs.
+//!
+//! Only available with the `async` feature.
+//!
+//! ### Example
+//!
+//! ```ignore
+//! let mut events = pin!(busy_events(&mut busy_exti, &mut clock));
+//! while let Some(event) = events.next().await {
+//!     if let Ok(BusyEvent::Released { busy_ms, .. }) = event {
+//!         metrics.record_refresh(busy_ms);
+//!     }
+//! }
+//! ```
+
+use crate::timing::Clock;
+use embedded_hal_async::digital::Wait;
+use futures_util::stream::{self, Stream};
+
+/// A change of the busy pin, with the time from the clock.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BusyEvent {
+    /// the controller became busy
+    Asserted {
+        /// when, in milliseconds
+        at_ms: u32,
+    },
+    /// the controller became idle
+    Released {
+        /// when, in milliseconds
+        at_ms: u32,
+        /// how long it was busy, in milliseconds
+        busy_ms: u32,
+    },
+}
+
+impl BusyEvent {
+    /// Returns when the event happened, in milliseconds.
+    pub fn at_ms(&self) -> u32 {
+        match *self {
+            BusyEvent::Asserted { at_ms } | BusyEvent::Released { at_ms, .. } => at_ms,
+        }
+    }
+}
+
+/// Returns a stream of the busy pin becoming busy and idle, timed with `clock`.
+///
+/// The busy pin is high while the controller is busy. The stream starts by waiting for it to
+/// be busy, so a controller that is already busy gives an `Asserted` event straight away,
+/// timed from the first poll. It never ends, errors reading the pin are yielded and the wait is
+/// tried again.
+pub fn busy_events<'a, W, C>(
+    busy: &'a mut W,
+    clock: &'a mut C,
+) -> impl Stream<Item = Result<BusyEvent, W::Error>> + 'a
+where
+    W: Wait,
+    C: Clock,
+{
+    // the time the controller became busy, while it is
+    let since: Option<u32> = None;
+    stream::unfold((busy, clock, since), |(busy, clock, since)| async move {
+        let event = match since {
+            None => busy.wait_for_high().await.map(|_| BusyEvent::Asserted {
+                at_ms: clock.now_ms(),
+            }),
+            Some(start) => busy.wait_for_low().await.map(|_| {
+                let at_ms = clock.now_ms();
+                BusyEvent::Released {
+                    at_ms,
+                    busy_ms: at_ms.wrapping_sub(start),
+                }
+            }),
+        };
+        let since = match event {
+            Ok(BusyEvent::Asserted { at_ms }) => Some(at_ms),
+            Ok(BusyEvent::Released { .. }) => None,
+            Err(_) => since,
+        };
+        Some((event, (busy, clock, since)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::future::Future;
+    use core::pin::pin;
+    use core::task::{Context, Poll, Waker};
+    use futures_util::StreamExt;
+
+    // a busy pin that changes level as soon as it is waited on
+    struct TogglingPin;
+
+    impl embedded_hal_1::digital::ErrorType for TogglingPin {
+        type Error = core::convert::Infallible;
+    }
+
+    impl Wait for TogglingPin {
+        async fn wait_for_high(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        async fn wait_for_low(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        async fn wait_for_rising_edge(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        async fn wait_for_falling_edge(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        async fn wait_for_any_edge(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    // a clock that advances 100ms each time it is read
+    struct StepClock(u32);
+
+    impl Clock for StepClock {
+        fn now_ms(&mut self) -> u32 {
+            self.0 += 100;
+            self.0
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    fn times_busy_periods() {
+        let (mut pin, mut clock) = (TogglingPin, StepClock(0));
+        let events = busy_events(&mut pin, &mut clock).take(3);
+        let events: std::vec::Vec<_> = block_on(events.collect());
+        assert_eq!(
+            events,
+            [
+                Ok(BusyEvent::Asserted { at_ms: 100 }),
+                Ok(BusyEvent::Released {
+                    at_ms: 200,
+                    busy_ms: 100
+                }),
+                Ok(BusyEvent::Asserted { at_ms: 300 }),
+            ]
+        );
+    }
+}
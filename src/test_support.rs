@@ -0,0 +1,229 @@
+//! A recording [DisplayInterface] and bus devices shared by the unit tests.
+
+use crate::error::Error;
+use crate::interface::DisplayInterface;
+#[cfg(feature = "sram")]
+use crate::interface::{SramAddr, SramBacked};
+use crate::regs;
+use std::vec::Vec;
+
+/// Capacity of the simulated sram, the size of the smallest device.
+#[cfg(feature = "sram")]
+pub const SRAM_CAPACITY: usize = 8 * 1024;
+
+/// Records everything sent to the controller, and keeps a simulated sram.
+pub struct MockInterface {
+    /// Every command and data byte, in the order sent.
+    pub data: Vec<u8>,
+    /// The commands alone.
+    pub commands: Vec<u8>,
+    pub resets: usize,
+    pub busy_waits: usize,
+    /// The contents of the sram, not the value of a cleared buffer so a missed write shows.
+    #[cfg(feature = "sram")]
+    pub sram: Vec<u8>,
+    /// The layer, length and start address of each update sent from the sram.
+    #[cfg(feature = "sram")]
+    pub sram_updates: Vec<(u8, u32, SramAddr)>,
+}
+
+impl MockInterface {
+    pub fn new() -> Self {
+        MockInterface {
+            data: Vec::new(),
+            commands: Vec::new(),
+            resets: 0,
+            busy_waits: 0,
+            #[cfg(feature = "sram")]
+            sram: vec![0xA5; SRAM_CAPACITY],
+            #[cfg(feature = "sram")]
+            sram_updates: Vec::new(),
+        }
+    }
+}
+
+impl Default for MockInterface {
+    fn default() -> Self {
+        MockInterface::new()
+    }
+}
+
+impl DisplayInterface for MockInterface {
+    type Error = ();
+
+    fn send_command(&mut self, command: u8) -> Result<(), Self::Error> {
+        self.data.push(command);
+        self.commands.push(command);
+        Ok(())
+    }
+
+    fn send_data(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        self.data.extend_from_slice(data);
+        Ok(())
+    }
+
+    fn reset<D: hal::blocking::delay::DelayMs<u8>>(&mut self, _delay: &mut D) {
+        self.resets += 1;
+    }
+
+    fn busy_wait(&mut self) {
+        self.busy_waits += 1;
+    }
+
+    fn epd_update_data(
+        &mut self,
+        layer: u8,
+        nbytes: u16,
+        buf: &[u8],
+    ) -> Result<(), Error<Self::Error>> {
+        if usize::from(nbytes) != buf.len() {
+            return Err(Error::BufferSize {
+                expected: nbytes.into(),
+                actual: buf.len(),
+            });
+        }
+        self.send_command(regs::data_transmission(layer))
+            .and_then(|_| self.send_data(buf))
+            .map_err(Error::Interface)
+    }
+}
+
+#[cfg(feature = "sram")]
+impl MockInterface {
+    /// Forget what was sent, keeping the sram.
+    pub fn clear(&mut self) {
+        self.data.clear();
+        self.commands.clear();
+    }
+
+    fn sram_range(&self, address: SramAddr, nbytes: u32) -> core::ops::Range<usize> {
+        assert!(
+            address.fits(nbytes, self.sram_capacity()),
+            "sram access out of range"
+        );
+        let start = address.get() as usize;
+        start..start + nbytes as usize
+    }
+}
+
+#[cfg(feature = "sram")]
+impl SramBacked for MockInterface {
+    fn sram_epd_update_data(
+        &mut self,
+        layer: u8,
+        nbytes: u32,
+        start_address: SramAddr,
+    ) -> Result<(), Error<Self::Error>> {
+        if !start_address.fits(nbytes, self.sram_capacity()) {
+            return Err(Error::BufferSize {
+                expected: (start_address.get() + nbytes) as usize,
+                actual: self.sram.len(),
+            });
+        }
+        self.sram_updates.push((layer, nbytes, start_address));
+        let range = self.sram_range(start_address, nbytes);
+        let plane = self.sram[range].to_vec();
+        self.send_command(regs::data_transmission(layer))
+            .and_then(|_| self.send_data(&plane))
+            .map_err(Error::Interface)
+    }
+
+    fn sram_read(&mut self, address: SramAddr, data: &mut [u8]) -> Result<(), Error<Self::Error>> {
+        let range = self.sram_range(address, data.len() as u32);
+        data.copy_from_slice(&self.sram[range]);
+        Ok(())
+    }
+
+    fn sram_write(&mut self, address: SramAddr, data: &[u8]) -> Result<(), Error<Self::Error>> {
+        let range = self.sram_range(address, data.len() as u32);
+        self.sram[range].copy_from_slice(data);
+        Ok(())
+    }
+
+    fn sram_clear(
+        &mut self,
+        address: SramAddr,
+        nbytes: u32,
+        val: u8,
+    ) -> Result<(), Error<Self::Error>> {
+        let range = self.sram_range(address, nbytes);
+        for byte in self.sram[range].iter_mut() {
+            *byte = val;
+        }
+        Ok(())
+    }
+
+    fn sram_capacity(&self) -> u32 {
+        self.sram.len() as u32
+    }
+}
+
+/// An SPI bus recording the bytes written.
+#[derive(Default)]
+pub struct MockSpi {
+    pub written: Vec<u8>,
+    /// Writes and transfers to fail before succeeding.
+    pub failures: usize,
+}
+
+impl hal::blocking::spi::Write<u8> for MockSpi {
+    type Error = ();
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        if self.failures > 0 {
+            self.failures -= 1;
+            return Err(());
+        }
+        self.written.extend_from_slice(words);
+        Ok(())
+    }
+}
+
+/// Records each delay, in milliseconds.
+pub struct RecordingDelay<'a>(pub &'a mut Vec<u8>);
+
+impl<'a> hal::blocking::delay::DelayMs<u8> for RecordingDelay<'a> {
+    fn delay_ms(&mut self, ms: u8) {
+        self.0.push(ms)
+    }
+}
+
+impl hal::blocking::spi::Transfer<u8> for MockSpi {
+    type Error = ();
+
+    fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Self::Error> {
+        if self.failures > 0 {
+            self.failures -= 1;
+            return Err(());
+        }
+        self.written.extend_from_slice(words);
+        Ok(words)
+    }
+}
+
+/// A pin that ignores writes and is never busy.
+pub struct MockPin;
+
+impl hal::digital::v2::OutputPin for MockPin {
+    type Error = ();
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl hal::digital::v2::InputPin for MockPin {
+    type Error = ();
+
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        Ok(false)
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        Ok(true)
+    }
+}
@@ -0,0 +1,54 @@
+//! Temperature compensation.
+//!
+//! E-paper panels respond slowly below 0°C and too quickly above 40°C, leaving washed out or
+//! ghosted images. A [ThermalPolicy] maps a temperature measured by the application to changes
+//! to the configured frame rate and data interval, applied with
+//! [Display::apply_thermal](../display/struct.Display.html#method.apply_thermal). The changes are
+//! kept and sent again whenever the controller is initialized.
+
+use crate::command::DataInterval;
+
+/// Changes to the `Config` for a temperature. `None` keeps the configured value.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ThermalAdjustment {
+    /// the PLL control (PLL) clock byte
+    pub pll: Option<u8>,
+    /// the data interval of the VCOM and data interval setting (CDI)
+    pub data_interval: Option<DataInterval>,
+}
+
+/// Decides how to compensate for the panel temperature.
+pub trait ThermalPolicy {
+    /// Returns the adjustment for a temperature in °C.
+    fn adjust(&mut self, temp_c: i8) -> ThermalAdjustment;
+}
+
+/// Below [DefaultThermalPolicy::COLD_C] slows the frame rate and lengthens the data interval,
+/// above [DefaultThermalPolicy::HOT_C] speeds up the frame rate, and otherwise keeps the `Config`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultThermalPolicy;
+
+impl DefaultThermalPolicy {
+    /// Temperatures below this are cold.
+    pub const COLD_C: i8 = 0;
+    /// Temperatures above this are hot.
+    pub const HOT_C: i8 = 40;
+}
+
+impl ThermalPolicy for DefaultThermalPolicy {
+    fn adjust(&mut self, temp_c: i8) -> ThermalAdjustment {
+        if temp_c < Self::COLD_C {
+            ThermalAdjustment {
+                pll: Some(0x3A),
+                data_interval: Some(DataInterval::V12),
+            }
+        } else if temp_c > Self::HOT_C {
+            ThermalAdjustment {
+                pll: Some(0x39),
+                data_interval: None,
+            }
+        } else {
+            ThermalAdjustment::default()
+        }
+    }
+}
@@ -0,0 +1,205 @@
+//! A line of text scrolling across a region, shown with partial refreshes.
+//!
+//! A [Ticker] owns its text and scrolls it horizontally through a [Region], such as a news or
+//! status line at the bottom of the screen. [Ticker::poll] moves the text on by a number of
+//! pixels each interval and refreshes only the region, with
+//! [GraphicDisplay::update_window](../graphics/struct.GraphicDisplay.html#method.update_window),
+//! so the rest of the panel doesn't flash. Steps missed while the application was busy are
+//! combined into a single refresh, keeping the speed of the text without refreshing more often.
+//!
+//! ### Example
+//!
+//! ```ignore
+//! let screen = Region::display(display.config());
+//! let mut ticker = Ticker::new("Flood warning in effect until 6pm", screen.bottom_bar(12), &FONT_6X10)
+//!     .step(12)
+//!     .interval_ms(3_000);
+//! loop {
+//!     ticker.poll(&mut display, clock.now_ms())?;
+//! }
+//! ```
+
+use crate::color::Color;
+use crate::error::Error;
+use crate::graphics::{FrameBufferStore, GraphicDisplay};
+use crate::interface::DisplayInterface;
+use crate::layout::Region;
+use embedded_graphics::mono_font::{MonoFont, MonoTextStyle};
+use embedded_graphics::prelude::*;
+use embedded_graphics::text::renderer::TextRenderer;
+use embedded_graphics::text::{Baseline, Text};
+
+/// The default time between steps.
+pub const DEFAULT_INTERVAL_MS: u32 = 2_000;
+
+/// Error from a [Ticker].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TickerError<E, S> {
+    /// Drawing into the buffers failed.
+    Draw(Error<S>),
+    /// Sending or refreshing the region failed.
+    Display(Error<E>),
+}
+
+/// Text scrolling from right to left through a region, repeating with a gap.
+pub struct Ticker<'f, T> {
+    text: T,
+    region: Region,
+    style: MonoTextStyle<'f, Color>,
+    background: Color,
+    gap: u32,
+    step: u32,
+    interval_ms: u32,
+    offset: u32,
+    last_ms: Option<u32>,
+}
+
+impl<'f, T> Ticker<'f, T>
+where
+    T: AsRef<str>,
+{
+    /// Create a ticker drawing `text` in black on white, starting at the left of `region`.
+    ///
+    /// The text moves one character each [DEFAULT_INTERVAL_MS], with a gap of half the region
+    /// before it repeats.
+    pub fn new(text: T, region: Region, font: &'f MonoFont<'f>) -> Self {
+        Ticker {
+            text,
+            region,
+            style: MonoTextStyle::new(font, Color::Black),
+            background: Color::White,
+            gap: region.width() / 2,
+            step: font.character_size.width + font.character_spacing,
+            interval_ms: DEFAULT_INTERVAL_MS,
+            offset: 0,
+            last_ms: None,
+        }
+    }
+
+    /// Set the color of the text and the background.
+    pub fn colors(self, text: Color, background: Color) -> Self {
+        let mut style = self.style;
+        style.text_color = Some(text);
+        Self {
+            style,
+            background,
+            ..self
+        }
+    }
+
+    /// Set the pixels the text moves each interval.
+    pub fn step(self, step: u32) -> Self {
+        Self { step, ..self }
+    }
+
+    /// Set the time between steps.
+    ///
+    /// Each step is a partial refresh of the region, which takes a second or more.
+    pub fn interval_ms(self, interval_ms: u32) -> Self {
+        Self {
+            interval_ms,
+            ..self
+        }
+    }
+
+    /// Set the pixels between the end of the text and its next repeat.
+    pub fn gap(self, gap: u32) -> Self {
+        Self { gap, ..self }
+    }
+
+    /// Returns the text.
+    pub fn text(&self) -> &str {
+        self.text.as_ref()
+    }
+
+    /// Replace the text, which starts again from the left at the next poll.
+    pub fn set_text(&mut self, text: T) {
+        self.text = text;
+        self.offset = 0;
+        self.last_ms = None;
+    }
+
+    /// Returns the region the text scrolls through.
+    pub fn region(&self) -> Region {
+        self.region
+    }
+
+    /// Returns how far the text has scrolled, in pixels, less than [Ticker::period].
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
+    /// Returns the width of the text and the gap after it, the distance it scrolls before
+    /// repeating.
+    pub fn period(&self) -> u32 {
+        let size = self
+            .style
+            .measure_string(self.text(), Point::zero(), Baseline::Top)
+            .bounding_box
+            .size;
+        (size.width + self.gap).max(1)
+    }
+
+    /// Draw the region at the current offset, without refreshing.
+    pub fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Color>,
+    {
+        let area = self.region.rect();
+        let mut target = target.clipped(&area);
+        target.fill_solid(&area, self.background)?;
+        let height = self.style.font.character_size.height;
+        let top = self.region.text_origin(height).y;
+        let right = area.top_left.x + area.size.width as i32;
+        let period = self.period() as i32;
+        let mut x = area.top_left.x - self.offset as i32;
+        while x < right {
+            Text::with_baseline(self.text(), Point::new(x, top), self.style, Baseline::Top)
+                .draw(&mut target)?;
+            x += period;
+        }
+        Ok(())
+    }
+
+    /// Scroll the text if an interval has passed, then draw and refresh the region.
+    ///
+    /// The first poll shows the text without scrolling. After that the text moves one step for
+    /// each interval since the last step, in a single refresh, and the remainder of an interval
+    /// carries over to the next poll. Returns true if the region was refreshed. `now_ms` is from
+    /// a free running clock, as [Clock::now_ms], and may wrap.
+    ///
+    /// [Clock::now_ms]: ../timing/trait.Clock.html#tymethod.now_ms
+    pub fn poll<I, S>(
+        &mut self,
+        display: &mut GraphicDisplay<I, S>,
+        now_ms: u32,
+    ) -> Result<bool, TickerError<I::Error, S::Error>>
+    where
+        I: DisplayInterface,
+        S: FrameBufferStore<I>,
+    {
+        match self.last_ms {
+            None => self.last_ms = Some(now_ms),
+            Some(last) => {
+                let steps = now_ms.wrapping_sub(last) / self.interval_ms.max(1);
+                if steps == 0 {
+                    return Ok(false);
+                }
+                let period = self.period();
+                let distance = (u64::from(steps) * u64::from(self.step)) % u64::from(period);
+                self.offset = (self.offset + distance as u32) % period;
+                self.last_ms = Some(last.wrapping_add(steps.wrapping_mul(self.interval_ms)));
+            }
+        }
+        self.draw(display).map_err(TickerError::Draw)?;
+        display
+            .update_window(self.region.rect())
+            .map_err(TickerError::Display)?;
+        Ok(true)
+    }
+
+    /// release the text
+    pub fn release(self) -> T {
+        self.text
+    }
+}
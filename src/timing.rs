@@ -0,0 +1,108 @@
+//! Refresh timing helpers.
+//!
+//! The time the controller holds BUSY during a refresh grows as the panel gets colder. Timing
+//! refreshes with [Display::busy_wait_timed](../display/struct.Display.html#method.busy_wait_timed)
+//! and passing the result to a [RefreshMonitor] gives an estimate of whether the panel is below
+//! its operating temperature, so an application can decide to skip updates rather than produce
+//! a smeared image.
+
+/// A free running millisecond clock used to time refreshes.
+pub trait Clock {
+    /// Returns the current time in milliseconds. The value is allowed to wrap.
+    fn now_ms(&mut self) -> u32;
+}
+
+/// Classification of a measured refresh duration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RefreshHealth {
+    /// The refresh took about as long as expected.
+    Normal,
+    /// The refresh took unusually long, the panel is probably too cold.
+    TooCold,
+}
+
+/// Typical tri-color refresh duration at room temperature.
+pub const DEFAULT_NOMINAL_REFRESH_MS: u32 = 15_000;
+
+/// Compares refresh durations against the nominal duration for a panel.
+#[derive(Clone, Copy, Debug)]
+pub struct RefreshMonitor {
+    nominal_ms: u32,
+    cold_percent: u32,
+    last_ms: Option<u32>,
+}
+
+impl Default for RefreshMonitor {
+    fn default() -> Self {
+        RefreshMonitor::new(DEFAULT_NOMINAL_REFRESH_MS)
+    }
+}
+
+impl RefreshMonitor {
+    /// Create a monitor for a panel that refreshes in `nominal_ms` at room temperature.
+    ///
+    /// Refreshes taking longer than 150% of nominal are reported as `TooCold`.
+    pub fn new(nominal_ms: u32) -> Self {
+        RefreshMonitor {
+            nominal_ms,
+            cold_percent: 150,
+            last_ms: None,
+        }
+    }
+
+    /// Set the percentage of the nominal duration above which a refresh is considered too cold.
+    pub fn cold_threshold_percent(self, cold_percent: u32) -> Self {
+        Self {
+            cold_percent,
+            ..self
+        }
+    }
+
+    /// Record a measured refresh duration and classify it.
+    pub fn record(&mut self, duration_ms: u32) -> RefreshHealth {
+        self.last_ms = Some(duration_ms);
+        self.assess(duration_ms)
+    }
+
+    /// Classify a refresh duration without recording it.
+    pub fn assess(&self, duration_ms: u32) -> RefreshHealth {
+        let limit = (self.nominal_ms as u64 * self.cold_percent as u64) / 100;
+        if duration_ms as u64 > limit {
+            RefreshHealth::TooCold
+        } else {
+            RefreshHealth::Normal
+        }
+    }
+
+    /// Returns the last recorded refresh duration.
+    pub fn last_ms(&self) -> Option<u32> {
+        self.last_ms
+    }
+
+    /// Returns true if the last recorded refresh suggests the panel is too cold to update.
+    pub fn too_cold(&self) -> bool {
+        match self.last_ms {
+            Some(ms) => self.assess(ms) == RefreshHealth::TooCold,
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn assess_durations() {
+        let mut monitor = RefreshMonitor::new(10_000);
+        assert!(!monitor.too_cold());
+        assert_eq!(monitor.record(12_000), RefreshHealth::Normal);
+        assert_eq!(monitor.record(15_000), RefreshHealth::Normal);
+        assert_eq!(monitor.record(15_001), RefreshHealth::TooCold);
+        assert!(monitor.too_cold());
+        assert_eq!(monitor.last_ms(), Some(15_001));
+
+        let monitor = RefreshMonitor::new(10_000).cold_threshold_percent(200);
+        assert_eq!(monitor.assess(15_001), RefreshHealth::Normal);
+    }
+}
@@ -0,0 +1,116 @@
+//! Stepping the VCOM DC level as a panel ages.
+//!
+//! The contrast of a panel drifts after many refreshes, leaving faint ghosts of earlier images.
+//! A common mitigation is to nudge the VCOM DC level (VDCS) a step every few hundred refreshes.
+//! A [VcomStepper] does this with
+//! [Display::set_vcom_dc](../display/struct.Display.html#method.set_vcom_dc), so a long running
+//! display can be tuned without a full initialization.
+//!
+//! ### Example
+//!
+//! ```ignore
+//! // one step more negative every 500 refreshes, up to -2.0V
+//! let mut stepper = VcomStepper::new(500, 1, VcomDc::new(0x26).unwrap());
+//! loop {
+//!     display.update()?;
+//!     stepper.update(&mut display)?;
+//! }
+//! ```
+
+use crate::command::{Command, VcomDc};
+use crate::display::Display;
+use crate::interface::DisplayInterface;
+
+/// Steps the VCOM DC level every `every` refreshes, until it reaches a limit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VcomStepper {
+    every: u32,
+    step: i8,
+    limit: VcomDc,
+    next_at: u32,
+}
+
+impl VcomStepper {
+    /// Create a stepper that changes the level by `step` every `every` refreshes.
+    ///
+    /// The level never passes `limit`, in the direction of `step`. A positive step makes VCOM
+    /// more negative. Nothing is stepped if `every` or `step` is zero.
+    pub const fn new(every: u32, step: i8, limit: VcomDc) -> Self {
+        VcomStepper {
+            every,
+            step,
+            limit,
+            next_at: every,
+        }
+    }
+
+    /// Returns the refresh count, as in [DriverState::refreshes], at which the next step is due.
+    ///
+    /// [DriverState::refreshes]: ../display/struct.DriverState.html#structfield.refreshes
+    pub const fn next_at(&self) -> u32 {
+        self.next_at
+    }
+
+    /// Returns the level after one step from `value`.
+    pub fn next_value(&self, value: VcomDc) -> VcomDc {
+        let (value, limit) = (i16::from(value.value()), i16::from(self.limit.value()));
+        let stepped = value + i16::from(self.step);
+        let stepped = if self.step > 0 {
+            stepped.min(limit.max(value))
+        } else {
+            stepped.max(limit.min(value))
+        };
+        VcomDc::new(stepped.clamp(0, 0xFF) as u8).unwrap_or(self.limit)
+    }
+
+    /// Step the level of `display` if enough refreshes have been started since the last step.
+    ///
+    /// Call after each refresh. Returns true if VDCS was sent.
+    pub fn update<I>(&mut self, display: &mut Display<I>) -> Result<bool, I::Error>
+    where
+        I: DisplayInterface,
+    {
+        let refreshes = display.state().refreshes;
+        if self.every == 0 || self.step == 0 || refreshes < self.next_at {
+            return Ok(false);
+        }
+        // skip any steps missed while update wasn't called
+        let behind = (refreshes - self.next_at) / self.every;
+        self.next_at = self
+            .next_at
+            .saturating_add(behind.saturating_add(1).saturating_mul(self.every));
+        let current = match display.config().vcom_dc() {
+            Command::VCMDCSetting(value) => value,
+            _ => return Ok(false),
+        };
+        let value = self.next_value(current);
+        if value == current {
+            return Ok(false);
+        }
+        display.set_vcom_dc(value)?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level(value: u8) -> VcomDc {
+        VcomDc::new(value).unwrap()
+    }
+
+    #[test]
+    fn next_value() {
+        let up = VcomStepper::new(10, 2, level(0x0F));
+        assert_eq!(up.next_value(level(0x0A)), level(0x0C));
+        assert_eq!(up.next_value(level(0x0E)), level(0x0F));
+        assert_eq!(up.next_value(level(0x0F)), level(0x0F));
+        // already past the limit, left alone
+        assert_eq!(up.next_value(level(0x12)), level(0x12));
+
+        let down = VcomStepper::new(10, -3, level(0x05));
+        assert_eq!(down.next_value(level(0x0A)), level(0x07));
+        assert_eq!(down.next_value(level(0x06)), level(0x05));
+    }
+}
@@ -0,0 +1,246 @@
+//! Strategies for waiting while the controller is busy.
+//!
+//! By default the interfaces spin on the busy pin. A [WaitStrategy] selected with
+//! `wait_strategy` on an interface is called between polls of the busy pin instead, so power
+//! sensitive applications can sleep rather than spin.
+//!
+//! Boards that don't connect the busy pin can use [NoPin](../interface/struct.NoPin.html) for it,
+//! together with a [TimedWait] strategy that waits a fixed time after each operation.
+
+use crate::regs;
+
+/// Operations the controller signals completion of with the busy pin.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operation {
+    /// Power ON (PON)
+    PowerOn,
+    /// Display Refresh (DRF)
+    Refresh,
+    /// Power OFF (POF)
+    PowerOff,
+}
+
+impl Operation {
+    /// Returns the operation started by a command byte, if it is PON, DRF or POF.
+    pub fn for_command(command: u8) -> Option<Operation> {
+        match command {
+            regs::PON => Some(Operation::PowerOn),
+            regs::DRF => Some(Operation::Refresh),
+            regs::POF => Some(Operation::PowerOff),
+            _ => None,
+        }
+    }
+
+    /// Returns the command byte that starts the operation.
+    pub const fn command(self) -> u8 {
+        match self {
+            Operation::PowerOn => regs::PON,
+            Operation::Refresh => regs::DRF,
+            Operation::PowerOff => regs::POF,
+        }
+    }
+}
+
+/// Called between polls of the busy pin.
+pub trait WaitStrategy {
+    /// Called once at the start of each busy wait.
+    fn start(&mut self) {}
+
+    /// Called each time the busy pin is polled and the controller is still busy.
+    fn wait(&mut self);
+
+    /// Wait a fixed time for an operation instead of polling the busy pin.
+    ///
+    /// Returns false if the busy pin should be polled, which is the default.
+    fn fixed_wait(&mut self, _operation: Operation) -> bool {
+        false
+    }
+
+    /// Returns true if every operation is waited for with [WaitStrategy::fixed_wait].
+    ///
+    /// The busy pin isn't read then, so interfaces report it as unknown. Defaults to false.
+    fn is_fixed(&self) -> bool {
+        false
+    }
+}
+
+/// Poll the busy pin continuously. This is the default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SpinWait;
+
+impl WaitStrategy for SpinWait {
+    fn wait(&mut self) {}
+}
+
+/// Sleep between polls using a delay, doubling the sleep each poll up to a maximum.
+pub struct DelayBackoffWait<D> {
+    delay: D,
+    min_ms: u8,
+    max_ms: u8,
+    current_ms: u8,
+}
+
+impl<D> DelayBackoffWait<D>
+where
+    D: hal::blocking::delay::DelayMs<u8>,
+{
+    /// Create a new strategy, sleeping between `min_ms` and `max_ms` between polls.
+    pub fn new(delay: D, min_ms: u8, max_ms: u8) -> Self {
+        DelayBackoffWait {
+            delay,
+            min_ms,
+            max_ms,
+            current_ms: min_ms,
+        }
+    }
+
+    /// release the delay
+    pub fn release(self) -> D {
+        self.delay
+    }
+}
+
+impl<D> WaitStrategy for DelayBackoffWait<D>
+where
+    D: hal::blocking::delay::DelayMs<u8>,
+{
+    fn start(&mut self) {
+        self.current_ms = self.min_ms;
+    }
+
+    fn wait(&mut self) {
+        self.delay.delay_ms(self.current_ms);
+        self.current_ms = self.current_ms.saturating_mul(2).min(self.max_ms);
+    }
+}
+
+/// Wait for an interrupt between polls.
+///
+/// The closure should put the MCU to sleep until the next interrupt, for example by calling
+/// `cortex_m::asm::wfi`. An interrupt must be configured on the busy pin, or some other
+/// periodic interrupt, to wake the MCU.
+pub struct IrqWait<F>(pub F);
+
+impl<F> WaitStrategy for IrqWait<F>
+where
+    F: FnMut(),
+{
+    fn wait(&mut self) {
+        (self.0)()
+    }
+}
+
+/// Time to wait for each operation when the busy pin isn't connected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BusyDelays {
+    /// Time to wait after Power ON, in milliseconds
+    pub power_on_ms: u32,
+    /// Time to wait after Display Refresh, in milliseconds
+    pub refresh_ms: u32,
+    /// Time to wait after Power OFF, in milliseconds
+    pub power_off_ms: u32,
+}
+
+impl Default for BusyDelays {
+    /// Conservative delays for a tri-color refresh at room temperature.
+    fn default() -> Self {
+        BusyDelays {
+            power_on_ms: 100,
+            refresh_ms: 20_000,
+            power_off_ms: 100,
+        }
+    }
+}
+
+/// Wait a fixed time for each operation, without reading the busy pin.
+///
+/// Use with a busy pin of [NoPin](../interface/struct.NoPin.html) on boards that don't connect it.
+pub struct TimedWait<D> {
+    delay: D,
+    delays: BusyDelays,
+}
+
+impl<D> TimedWait<D>
+where
+    D: hal::blocking::delay::DelayMs<u8>,
+{
+    /// Create a new strategy, waiting for the times in `delays`.
+    pub fn new(delay: D, delays: BusyDelays) -> Self {
+        TimedWait { delay, delays }
+    }
+
+    /// release the delay
+    pub fn release(self) -> D {
+        self.delay
+    }
+}
+
+impl<D> WaitStrategy for TimedWait<D>
+where
+    D: hal::blocking::delay::DelayMs<u8>,
+{
+    fn wait(&mut self) {}
+
+    fn fixed_wait(&mut self, operation: Operation) -> bool {
+        let ms = match operation {
+            Operation::PowerOn => self.delays.power_on_ms,
+            Operation::Refresh => self.delays.refresh_ms,
+            Operation::PowerOff => self.delays.power_off_ms,
+        };
+        delay_ms(&mut self.delay, ms);
+        true
+    }
+
+    fn is_fixed(&self) -> bool {
+        true
+    }
+}
+
+// delay for longer than a DelayMs<u8> allows, in 255 ms steps
+pub(crate) fn delay_ms<D: hal::blocking::delay::DelayMs<u8>>(delay: &mut D, mut ms: u32) {
+    while ms > 0 {
+        let step = ms.min(u8::MAX as u32);
+        delay.delay_ms(step as u8);
+        ms -= step;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    struct RecordingDelay(Vec<u8>);
+
+    impl hal::blocking::delay::DelayMs<u8> for RecordingDelay {
+        fn delay_ms(&mut self, ms: u8) {
+            self.0.push(ms);
+        }
+    }
+
+    #[test]
+    fn delay_backoff() {
+        let mut wait = DelayBackoffWait::new(RecordingDelay(Vec::new()), 5, 30);
+        wait.start();
+        for _ in 0..4 {
+            wait.wait();
+        }
+        wait.start();
+        wait.wait();
+        assert_eq!(wait.release().0, [5, 10, 20, 30, 5]);
+    }
+
+    #[test]
+    fn timed_wait() {
+        let delays = BusyDelays {
+            power_on_ms: 0,
+            refresh_ms: 600,
+            power_off_ms: 10,
+        };
+        let mut wait = TimedWait::new(RecordingDelay(Vec::new()), delays);
+        assert!(wait.fixed_wait(Operation::PowerOn));
+        assert!(wait.fixed_wait(Operation::Refresh));
+        assert!(wait.fixed_wait(Operation::PowerOff));
+        assert_eq!(wait.release().0, [255, 255, 90, 10]);
+    }
+}
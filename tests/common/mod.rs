@@ -0,0 +1,78 @@
+#![allow(dead_code)]
+
+use embedded_hal as hal;
+use il0373::{regs, DisplayInterface, Error};
+
+/// Records every command and data byte sent to the controller.
+pub struct RecordingInterface {
+    pub data: Vec<u8>,
+    /// A command that fails to send, to simulate a bus error.
+    pub fail_command: Option<u8>,
+    /// The number of polls the busy pin reads busy for, `None` if there is no busy pin.
+    pub busy_polls: Option<u32>,
+    /// The number of busy waits.
+    pub busy_waits: u32,
+}
+
+impl RecordingInterface {
+    pub fn new() -> Self {
+        RecordingInterface {
+            data: Vec::new(),
+            fail_command: None,
+            busy_polls: None,
+            busy_waits: 0,
+        }
+    }
+}
+
+impl DisplayInterface for RecordingInterface {
+    type Error = ();
+
+    fn send_command(&mut self, command: u8) -> Result<(), Self::Error> {
+        if self.fail_command == Some(command) {
+            return Err(());
+        }
+        self.data.push(command);
+        Ok(())
+    }
+
+    fn send_data(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        self.data.extend_from_slice(data);
+        Ok(())
+    }
+
+    fn reset<D: hal::blocking::delay::DelayMs<u8>>(&mut self, _delay: &mut D) {
+        self.data.clear();
+    }
+
+    fn busy_wait(&mut self) {
+        self.busy_waits += 1;
+    }
+
+    fn is_busy(&mut self) -> Option<bool> {
+        let polls = self.busy_polls.as_mut()?;
+        *polls = polls.saturating_sub(1);
+        Some(*polls > 0)
+    }
+
+    fn epd_update_data(
+        &mut self,
+        layer: u8,
+        _nbytes: u16,
+        buf: &[u8],
+    ) -> Result<(), Error<Self::Error>> {
+        let command = regs::data_transmission(layer);
+        if self.fail_command == Some(command) {
+            return Err(Error::Interface(()));
+        }
+        self.data.push(command);
+        self.data.extend_from_slice(buf);
+        Ok(())
+    }
+}
+
+pub struct NoDelay;
+
+impl hal::blocking::delay::DelayMs<u8> for NoDelay {
+    fn delay_ms(&mut self, _ms: u8) {}
+}
@@ -0,0 +1,221 @@
+//! Checks the command sequences sent to the controller against the power sequencing required by
+//! the IL0373 datasheet.
+
+extern crate embedded_hal as hal;
+extern crate il0373;
+
+use il0373::wait::Operation;
+use il0373::{Builder, Dimensions, Display, DisplayInterface, Error, GraphicDisplay};
+
+mod common;
+use common::NoDelay;
+
+const ROWS: u16 = 16;
+const COLS: u8 = 16;
+const BUFFER_SIZE: usize = ROWS as usize * COLS as usize / 8;
+
+const PSR: u8 = 0x00;
+const PWR: u8 = 0x01;
+const POF: u8 = 0x02;
+const PON: u8 = 0x04;
+const BTST: u8 = 0x06;
+const DSLP: u8 = 0x07;
+const DTM1: u8 = 0x10;
+const DRF: u8 = 0x12;
+const DTM2: u8 = 0x13;
+const PLL: u8 = 0x30;
+const CDI: u8 = 0x50;
+const TRES: u8 = 0x61;
+const VDCS: u8 = 0x82;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Event {
+    Reset,
+    Command(u8),
+    Data(u8),
+    Busy(Operation),
+    Upload(u8),
+}
+
+/// Records everything the driver does to the controller, in order.
+struct SequenceInterface {
+    events: Vec<Event>,
+}
+
+impl DisplayInterface for SequenceInterface {
+    type Error = ();
+
+    fn send_command(&mut self, command: u8) -> Result<(), Self::Error> {
+        self.events.push(Event::Command(command));
+        Ok(())
+    }
+
+    fn send_data(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        self.events.extend(data.iter().map(|b| Event::Data(*b)));
+        Ok(())
+    }
+
+    fn reset<D: hal::blocking::delay::DelayMs<u8>>(&mut self, _delay: &mut D) {
+        self.events.push(Event::Reset);
+    }
+
+    fn busy_wait(&mut self) {
+        panic!("busy waits must say what they wait for");
+    }
+
+    fn busy_wait_for(&mut self, operation: Operation) {
+        self.events.push(Event::Busy(operation));
+    }
+
+    fn epd_update_data(
+        &mut self,
+        layer: u8,
+        _nbytes: u16,
+        _buf: &[u8],
+    ) -> Result<(), Error<Self::Error>> {
+        self.events
+            .push(Event::Upload(if layer == 0 { DTM1 } else { DTM2 }));
+        Ok(())
+    }
+}
+
+fn build_display() -> Display<SequenceInterface> {
+    let config = Builder::new()
+        .dimensions(Dimensions {
+            rows: ROWS,
+            cols: COLS,
+        })
+        .build()
+        .expect("invalid config");
+    Display::new(SequenceInterface { events: Vec::new() }, config)
+}
+
+// the events without command data
+fn commands(events: &[Event]) -> Vec<Event> {
+    events
+        .iter()
+        .filter(|e| !matches!(e, Event::Data(_)))
+        .cloned()
+        .collect()
+}
+
+// position of the first event after `from` that matches
+fn find(events: &[Event], from: usize, event: Event) -> usize {
+    from + events[from..]
+        .iter()
+        .position(|e| *e == event)
+        .unwrap_or_else(|| panic!("{:?} not sent after event {}", event, from))
+}
+
+#[test]
+fn full_sequence() {
+    let mut black = [0xFFu8; BUFFER_SIZE];
+    let mut red = [0xFFu8; BUFFER_SIZE];
+    let mut display = GraphicDisplay::new(build_display(), &mut black, &mut red);
+    display.reset(&mut NoDelay).unwrap();
+    display.update().unwrap();
+    display.deep_sleep().unwrap();
+
+    use Event::*;
+    assert_eq!(
+        commands(&display.interface().events),
+        [
+            Reset,
+            Command(PWR),
+            Command(BTST),
+            Command(PON),
+            Busy(Operation::PowerOn),
+            Command(PSR),
+            Command(CDI),
+            Command(PLL),
+            Command(VDCS),
+            Command(TRES),
+            Upload(DTM1),
+            Upload(DTM2),
+            Command(DRF),
+            Busy(Operation::Refresh),
+            Command(CDI),
+            Command(VDCS),
+            Command(POF),
+            Busy(Operation::PowerOff),
+            Command(DSLP),
+        ]
+    );
+}
+
+#[test]
+fn power_on_waits_before_next_command() {
+    let mut display = build_display();
+    display.reset(&mut NoDelay).unwrap();
+    let events = &display.interface().events;
+
+    let pon = find(events, 0, Event::Command(PON));
+    assert_eq!(events[pon + 1], Event::Busy(Operation::PowerOn));
+}
+
+#[test]
+fn refresh_finishes_before_power_off() {
+    let mut display = build_display();
+    display.reset(&mut NoDelay).unwrap();
+    display.signal_update().unwrap();
+    display.deep_sleep().unwrap();
+    let events = &display.interface().events;
+
+    let drf = find(events, 0, Event::Command(DRF));
+    assert_eq!(events[drf + 1], Event::Busy(Operation::Refresh));
+    let pof = find(events, drf, Event::Command(POF));
+    // border floating and VCOM at 0V before powering off
+    assert_eq!(
+        events[drf + 2..pof],
+        [
+            Event::Command(CDI),
+            Event::Data(0x17),
+            Event::Command(VDCS),
+            Event::Data(0x00),
+        ]
+    );
+}
+
+#[test]
+fn deep_sleep_after_power_off() {
+    let mut display = build_display();
+    display.reset(&mut NoDelay).unwrap();
+    display.deep_sleep().unwrap();
+    let events = &display.interface().events;
+
+    let pof = find(events, 0, Event::Command(POF));
+    assert_eq!(
+        events[pof..],
+        [
+            Event::Command(POF),
+            Event::Busy(Operation::PowerOff),
+            Event::Command(DSLP),
+            Event::Data(0xA5),
+        ]
+    );
+}
+
+#[test]
+fn standby_and_wake() {
+    let mut display = build_display();
+    display.reset(&mut NoDelay).unwrap();
+    display.standby().unwrap();
+    let start = display.interface().events.len();
+    display.wake_from_standby(&mut NoDelay).unwrap();
+    let events = &display.interface().events;
+
+    let pof = find(events, 0, Event::Command(POF));
+    assert_eq!(events[pof + 1], Event::Busy(Operation::PowerOff));
+    assert!(!events.contains(&Event::Command(DSLP)));
+    // the settings changed by power off are restored before power on
+    use Event::*;
+    assert_eq!(
+        commands(&events[start..]),
+        [
+            Command(CDI),
+            Command(VDCS),
+            Command(PON),
+            Busy(Operation::PowerOn),
+        ]
+    );
+}
@@ -0,0 +1,551 @@
+extern crate embedded_hal as hal;
+extern crate il0373;
+
+use il0373::command::{CdiEncoding, DataPolarity, VcomDc};
+use il0373::luts::LutProfile;
+use il0373::odometer::Odometer;
+use il0373::vcom::VcomStepper;
+use il0373::{
+    Builder, CommandSink, Dimensions, Display, DriverState, Error, GraphicDisplay, PanelState,
+    Plane, SinkError,
+};
+
+mod common;
+use common::{NoDelay, RecordingInterface};
+
+fn build_display(rows: u16, cols: u8) -> Display<RecordingInterface> {
+    let config = Builder::new()
+        .dimensions(Dimensions { rows, cols })
+        .build()
+        .expect("invalid config");
+    Display::new(RecordingInterface::new(), config)
+}
+
+#[test]
+fn clear_screen_streams_white() {
+    let mut display = build_display(212, 104);
+    display.clear_screen(&mut NoDelay).unwrap();
+
+    let nbytes = 212 * 104 / 8;
+    let data = &display.interface().data;
+    // skip the init sequence
+    let start = data.len() - (2 + 2 * nbytes + 1);
+    let data = &data[start..];
+    assert_eq!(data[0], 0x10);
+    assert!(data[1..=nbytes].iter().all(|b| *b == 0xFF));
+    assert_eq!(data[nbytes + 1], 0x13);
+    assert!(data[nbytes + 2..=2 * nbytes + 1].iter().all(|b| *b == 0xFF));
+    assert_eq!(data[2 * nbytes + 2], 0x12);
+}
+
+#[test]
+fn clear_screen_inverted_planes() {
+    let config = Builder::new()
+        .dimensions(Dimensions { rows: 8, cols: 8 })
+        .invert_black_plane(true)
+        .invert_red_plane(true)
+        .build()
+        .expect("invalid config");
+    let mut display = Display::new(RecordingInterface::new(), config);
+    // recovering with a deghost clears the screen too
+    display.recover(&mut NoDelay, true).unwrap();
+
+    let data = &display.interface().data;
+    let data = &data[data.len() - (2 + 2 * 8 + 1)..];
+    assert_eq!(data[0], 0x10);
+    assert_eq!(data[1..=8], [0x00; 8]);
+    assert_eq!(data[9], 0x13);
+    assert_eq!(data[10..=17], [0x00; 8]);
+    assert_eq!(data[18], 0x12);
+}
+
+#[test]
+fn init_from_table() {
+    let mut display = build_display(212, 104);
+    display
+        .init_from_table(
+            &[(0x06, &[0x17, 0x17, 0x17]), (0x04, &[]), (0x00, &[0x8F])],
+            &mut NoDelay,
+        )
+        .unwrap();
+
+    assert_eq!(
+        display.interface().data,
+        [0x06, 0x17, 0x17, 0x17, 0x04, 0x00, 0x8F]
+    );
+}
+
+#[test]
+fn soft_reset() {
+    let config = Builder::new()
+        .dimensions(Dimensions {
+            rows: 212,
+            cols: 104,
+        })
+        .soft_reset(true)
+        .build()
+        .expect("invalid config");
+    let mut display = Display::new(RecordingInterface::new(), config);
+    display.reset(&mut NoDelay).unwrap();
+
+    assert_eq!(display.interface().data[..4], [0x00, 0x0E, 0x01, 0x03]);
+}
+
+#[cfg(feature = "dual-spi")]
+#[test]
+fn dual_spi_needs_dual_interface() {
+    let config = Builder::new()
+        .dimensions(Dimensions { rows: 8, cols: 8 })
+        .build()
+        .expect("invalid config");
+    let mut display = Display::new(RecordingInterface::new(), config);
+    display.reset(&mut NoDelay).unwrap();
+    // a single line interface never switches the controller to dual SPI
+    assert_ne!(display.interface().data[0], 0x15);
+}
+
+#[test]
+fn post_init() {
+    fn tcon(sink: &mut dyn CommandSink) -> Result<(), SinkError> {
+        sink.command(0x60, &[0x22])?;
+        sink.command(0x65, &[])
+    }
+
+    let config = Builder::new()
+        .dimensions(Dimensions {
+            rows: 212,
+            cols: 104,
+        })
+        .with_post_init(tcon)
+        .build()
+        .expect("invalid config");
+    let mut display = Display::new(RecordingInterface::new(), config);
+    display.reset(&mut NoDelay).unwrap();
+
+    // after the resolution setting
+    let data = &display.interface().data;
+    assert_eq!(
+        data[data.len() - 7..],
+        [0x61, 0x68, 0x00, 0xD4, 0x60, 0x22, 0x65]
+    );
+}
+
+#[test]
+fn wake_from_standby() {
+    let mut display = build_display(212, 104);
+    display.reset(&mut NoDelay).unwrap();
+    display.standby().unwrap();
+    display.interface().data.clear();
+    display.wake_from_standby(&mut NoDelay).unwrap();
+    // VCOM settings restored then power on, no reset
+    assert_eq!(display.interface().data, [0x50, 0x37, 0x82, 0x0A, 0x04]);
+}
+
+#[test]
+fn reset_guarded() {
+    fn early_return(display: &mut Display<RecordingInterface>) -> Result<(), ()> {
+        let mut display = display.reset_guarded(&mut NoDelay)?;
+        display.signal_update()?;
+        Err(())
+    }
+
+    let mut display = build_display(212, 104);
+    assert_eq!(early_return(&mut display), Err(()));
+    assert_eq!(display.panel_state(), PanelState::DeepSleep);
+    assert!(display.interface().data.ends_with(&[0x02, 0x07, 0xA5]));
+
+    // no second deep sleep after sleep()
+    let guard = display.reset_guarded(&mut NoDelay).unwrap();
+    guard.sleep().unwrap();
+    let data = &display.interface().data;
+    assert_eq!(data.iter().filter(|b| **b == 0xA5).count(), 1);
+}
+
+struct RecordingDelay(Vec<u8>);
+
+impl hal::blocking::delay::DelayMs<u8> for RecordingDelay {
+    fn delay_ms(&mut self, ms: u8) {
+        self.0.push(ms);
+    }
+}
+
+#[test]
+fn init_delays() {
+    let config = Builder::new()
+        .dimensions(Dimensions { rows: 8, cols: 8 })
+        .power_on_delay_ms(300)
+        .post_vcm_delay_ms(5)
+        .build()
+        .unwrap();
+    let mut display = Display::new(RecordingInterface::new(), config);
+    let mut delay = RecordingDelay(Vec::new());
+    display.reset(&mut delay).unwrap();
+    assert_eq!(delay.0, [255, 45, 5]);
+}
+
+#[test]
+fn release_and_resume() {
+    let mut display = build_display(8, 8);
+    assert_eq!(display.panel_state(), PanelState::Unknown);
+    display.reset(&mut NoDelay).unwrap();
+    display.standby().unwrap();
+
+    let (interface, config, state) = display.release();
+    assert_eq!(state, PanelState::Standby);
+    let mut display = Display::resume(interface, config, state);
+    display.wake_from_standby(&mut NoDelay).unwrap();
+    assert_eq!(display.panel_state(), PanelState::Awake);
+    display.deep_sleep().unwrap();
+    assert_eq!(display.panel_state(), PanelState::DeepSleep);
+}
+
+#[test]
+fn update_guarded() {
+    let mut black = [0xFFu8; 8];
+    let mut red = [0xFFu8; 8];
+    let mut display = GraphicDisplay::new(build_display(8, 8), &mut black, &mut red);
+    display.interface().data.clear();
+    assert_eq!(display.update_guarded(|| false), Err(Error::SupplyLow));
+    // no DisplayRefresh
+    assert!(!display.interface().data.contains(&0x12));
+    assert_eq!(display.update_guarded(|| true), Ok(()));
+    assert_eq!(display.interface().data.last(), Some(&0x12));
+}
+
+#[test]
+fn driver_state() {
+    let mut black = [0xFFu8; 8];
+    let mut red = [0xFFu8; 8];
+    let mut display = GraphicDisplay::new(build_display(8, 8), &mut black, &mut red);
+    assert_eq!(
+        display.state(),
+        DriverState {
+            panel: PanelState::Unknown,
+            last_refresh_ok: None,
+            refreshes: 0,
+            planes_uploaded: [false; 2],
+        }
+    );
+    display.reset(&mut NoDelay).unwrap();
+    assert!(display.state().is_powered());
+    display.update().unwrap();
+    assert_eq!(display.state().last_refresh_ok, Some(true));
+    assert_eq!(display.update_guarded(|| false), Err(Error::SupplyLow));
+    assert_eq!(display.state().last_refresh_ok, Some(false));
+    assert_eq!(display.state().refreshes, 1);
+    display.deep_sleep().unwrap();
+    assert!(display.state().is_asleep());
+    assert!(!display.state().is_powered());
+}
+
+#[test]
+fn upload_planes_separately() {
+    let mut black = [0xFFu8; 8];
+    let mut red = [0xFFu8; 8];
+    let mut display = GraphicDisplay::new(build_display(8, 8), &mut black, &mut red);
+    assert_eq!(display.upload_plane(Plane::Black), Err(Error::InvalidState));
+    display.reset(&mut NoDelay).unwrap();
+    display.upload_plane(Plane::Black).unwrap();
+    assert_eq!(display.refresh(), Err(Error::InvalidState));
+    display.standby().unwrap();
+    display.upload_plane(Plane::Red).unwrap();
+    assert_eq!(display.state().planes_uploaded, [true, true]);
+    // panel drivers are off
+    assert_eq!(display.refresh(), Err(Error::InvalidState));
+    display.wake_from_standby(&mut NoDelay).unwrap();
+    display.interface().data.clear();
+    display.refresh().unwrap();
+    assert_eq!(display.interface().data, [0x12]);
+    assert_eq!(display.state().planes_uploaded, [false, false]);
+    // deep sleep loses the planes
+    display.upload_plane(Plane::Black).unwrap();
+    display.upload_plane(Plane::Red).unwrap();
+    display.deep_sleep().unwrap();
+    display.reset(&mut NoDelay).unwrap();
+    assert_eq!(display.refresh(), Err(Error::InvalidState));
+}
+
+#[test]
+fn apply_thermal() {
+    let mut display = build_display(8, 8);
+    display.apply_thermal(-10).unwrap();
+    // slower frame rate, 12 frame interval
+    assert_eq!(display.interface().data, [0x30, 0x3A, 0x50, 0x35]);
+    display.reset(&mut NoDelay).unwrap();
+    let data = &display.interface().data;
+    assert!(data.windows(4).any(|w| w == [0x50, 0x35, 0x30, 0x3A]));
+
+    display.interface().data.clear();
+    display.apply_thermal(25).unwrap();
+    assert_eq!(display.interface().data, [0x30, 0x29, 0x50, 0x37]);
+}
+
+#[test]
+fn set_data_polarity() {
+    let mut display = build_display(8, 8);
+    display.set_data_polarity(DataPolarity::BWOnly).unwrap();
+    // border 0, BWOnly, interval 10
+    assert_eq!(display.interface().data, [0x50, 0x17]);
+    display.reset(&mut NoDelay).unwrap();
+    assert!(display
+        .interface()
+        .data
+        .windows(2)
+        .any(|w| w == [0x50, 0x17]));
+}
+
+#[test]
+fn extended_cdi_encoding() {
+    let config = Builder::new()
+        .dimensions(Dimensions { rows: 8, cols: 8 })
+        .cdi_encoding(CdiEncoding::Extended)
+        .build()
+        .expect("invalid config");
+    let mut display = Display::new(RecordingInterface::new(), config);
+    display.reset(&mut NoDelay).unwrap();
+    // border 0 and Both, then interval 10
+    assert!(display
+        .interface()
+        .data
+        .windows(3)
+        .any(|w| w == [0x50, 0x30, 0x07]));
+    display.interface().data.clear();
+    display.set_data_polarity(DataPolarity::BWOnly).unwrap();
+    assert_eq!(display.interface().data, [0x50, 0x10, 0x07]);
+}
+
+#[test]
+fn set_vcom_dc() {
+    let mut display = build_display(8, 8);
+    display.set_vcom_dc(VcomDc::new(0x12).unwrap()).unwrap();
+    assert_eq!(display.interface().data, [0x82, 0x12]);
+    display.reset(&mut NoDelay).unwrap();
+    assert!(display
+        .interface()
+        .data
+        .windows(2)
+        .any(|w| w == [0x82, 0x12]));
+}
+
+#[test]
+fn vcom_stepper() {
+    let mut display = build_display(8, 8);
+    // the default level is 0x0A
+    let mut stepper = VcomStepper::new(2, 1, VcomDc::new(0x0B).unwrap());
+    display.signal_update().unwrap();
+    assert_eq!(stepper.update(&mut display), Ok(false));
+    display.signal_update().unwrap();
+    display.interface().data.clear();
+    assert_eq!(stepper.update(&mut display), Ok(true));
+    assert_eq!(display.interface().data, [0x82, 0x0B]);
+    assert_eq!(stepper.next_at(), 4);
+
+    // at the limit
+    display.signal_update().unwrap();
+    display.signal_update().unwrap();
+    display.interface().data.clear();
+    assert_eq!(stepper.update(&mut display), Ok(false));
+    assert!(display.interface().data.is_empty());
+}
+
+#[test]
+fn recover() {
+    let mut display = build_display(8, 8);
+    display.reset(&mut NoDelay).unwrap();
+    display.interface().fail_command = Some(0x12);
+    let mut black = [0xFFu8; 8];
+    let mut red = [0xFFu8; 8];
+    let mut display = GraphicDisplay::new(display, &mut black, &mut red);
+    let e = display.update().unwrap_err();
+    assert_eq!(e, Error::Interface(()));
+    assert!(e.recoverable());
+    // not trusted until recovered
+    assert_eq!(display.panel_state(), PanelState::Unknown);
+    assert_eq!(display.upload_plane(Plane::Black), Err(Error::InvalidState));
+
+    display.interface().fail_command = None;
+    display.recover(&mut NoDelay, false).unwrap();
+    assert_eq!(display.panel_state(), PanelState::Awake);
+    display.update().unwrap();
+    assert_eq!(display.state().last_refresh_ok, Some(true));
+
+    // blanked as well
+    display.recover(&mut NoDelay, true).unwrap();
+    assert_eq!(display.interface().data.last(), Some(&0x12));
+    assert!(!Error::<()>::Unsupported.recoverable());
+}
+
+#[test]
+fn update_checked() {
+    let mut display = build_display(8, 8);
+    display.reset(&mut NoDelay).unwrap();
+    let mut black = [0xFFu8; 8];
+    let mut red = [0xFFu8; 8];
+    let mut display = GraphicDisplay::new(display, &mut black, &mut red);
+    display.interface().busy_polls = Some(3);
+    display.interface().busy_waits = 0;
+    display.update_checked(&mut NoDelay, 100).unwrap();
+    assert_eq!(display.interface().busy_polls, Some(0));
+    // the polling saw the refresh finish, so there's no fixed wait
+    assert_eq!(display.interface().busy_waits, 0);
+
+    // never finishes, polled every 10ms
+    display.interface().busy_polls = Some(u32::MAX);
+    assert_eq!(
+        display.update_checked(&mut NoDelay, 50),
+        Err(Error::RefreshFailed)
+    );
+    assert_eq!(display.interface().busy_polls, Some(u32::MAX - 6));
+    assert_eq!(display.panel_state(), PanelState::Unknown);
+    assert_eq!(display.state().last_refresh_ok, Some(false));
+}
+
+static BLACK: [u8; 8] = [0x00; 8];
+static RED: [u8; 8] = [0xFF; 8];
+
+#[test]
+fn show_const_image() {
+    let mut display = build_display(8, 8);
+    display.reset(&mut NoDelay).unwrap();
+    display.interface().data.clear();
+    let awake = display.state();
+    assert_eq!(awake.panel, PanelState::Awake);
+    assert_eq!(awake.refreshes, 0);
+    assert_eq!(awake.last_refresh_ok, None);
+
+    assert_eq!(
+        display.show_const_image(&BLACK, &RED[..4]),
+        Err(Error::BufferSize {
+            expected: 8,
+            actual: 4
+        })
+    );
+    // nothing is sent and nothing changes
+    assert!(display.interface().data.is_empty());
+    assert_eq!(display.state(), awake);
+
+    display.show_const_image(&BLACK, &RED).unwrap();
+    let mut expected = vec![0x10];
+    expected.extend_from_slice(&BLACK);
+    expected.push(0x13);
+    expected.extend_from_slice(&RED);
+    expected.push(0x12);
+    assert_eq!(display.interface().data, expected);
+    assert_eq!(
+        display.state(),
+        DriverState {
+            panel: PanelState::Awake,
+            last_refresh_ok: Some(true),
+            refreshes: 1,
+            planes_uploaded: [false, false],
+        }
+    );
+
+    // a failed refresh is recorded and leaves the controller in an unknown state
+    display.interface().fail_command = Some(0x12);
+    assert_eq!(
+        display.show_const_image(&BLACK, &RED),
+        Err(Error::Interface(()))
+    );
+    let failed = display.state();
+    assert_eq!(failed.panel, PanelState::Unknown);
+    assert_eq!(failed.last_refresh_ok, Some(false));
+    assert_eq!(failed.refreshes, 1);
+
+    // as does a failed upload
+    display.reset(&mut NoDelay).unwrap();
+    display.interface().fail_command = Some(0x13);
+    assert_eq!(
+        display.show_const_image(&BLACK, &RED),
+        Err(Error::Interface(()))
+    );
+    let failed = display.state();
+    assert_eq!(failed.panel, PanelState::Unknown);
+    assert_eq!(failed.last_refresh_ok, Some(false));
+    assert_eq!(failed.refreshes, 1);
+}
+
+#[test]
+fn show_const_image_inverted_planes() {
+    let config = Builder::new()
+        .dimensions(Dimensions { rows: 8, cols: 8 })
+        .invert_red_plane(true)
+        .build()
+        .expect("invalid config");
+    let mut display = Display::new(RecordingInterface::new(), config);
+    display.reset(&mut NoDelay).unwrap();
+    display.interface().data.clear();
+    display.show_const_image(&BLACK, &RED).unwrap();
+    let mut expected = vec![0x10];
+    expected.extend_from_slice(&BLACK);
+    expected.push(0x13);
+    expected.extend_from_slice(&[0x00; 8]);
+    expected.push(0x12);
+    assert_eq!(display.interface().data, expected);
+}
+
+#[test]
+fn odometer() {
+    let config = Builder::new()
+        .dimensions(Dimensions { rows: 8, cols: 8 })
+        .lut_profile(LutProfile::FastMono)
+        .build()
+        .expect("invalid config");
+    let mut display = Display::new(RecordingInterface::new(), config);
+    display.set_odometer(Odometer {
+        full_refreshes: 3,
+        ..Odometer::default()
+    });
+    display.signal_update().unwrap();
+    display.signal_update().unwrap();
+    let odometer = display.odometer();
+    assert_eq!(odometer.full_refreshes, 3);
+    assert_eq!(odometer.fast_refreshes, 2);
+    assert_eq!(odometer.since_full, 2);
+}
+
+#[test]
+fn set_lut_profile() {
+    let mut display = build_display(8, 8);
+    display.set_lut_profile(LutProfile::FastMono).unwrap();
+    assert_eq!(display.config().lut_profile(), LutProfile::FastMono);
+    // PSR in black and white mode, then the tables
+    assert_eq!(display.interface().data[..3], [0x00, 0x3F, 0x20]);
+
+    // tri-color panels keep the waveforms programmed into them
+    let config = Builder::tricolor_2in9().build().expect("invalid config");
+    let mut display = Display::new(RecordingInterface::new(), config);
+    assert_eq!(
+        display.set_lut_profile(LutProfile::FullMono),
+        Err(Error::Unsupported)
+    );
+    assert_eq!(display.config().lut_profile(), LutProfile::Otp);
+    assert!(display.interface().data.is_empty());
+}
+
+#[test]
+fn borrowed_interface() {
+    struct Device {
+        interface: RecordingInterface,
+    }
+
+    let mut device = Device {
+        interface: RecordingInterface::new(),
+    };
+    let config = Builder::new()
+        .dimensions(Dimensions { rows: 8, cols: 8 })
+        .build()
+        .expect("invalid config");
+    let mut black = [0xFFu8; 8];
+    let mut red = [0xFFu8; 8];
+    {
+        let display = Display::new(&mut device.interface, config);
+        let mut display = GraphicDisplay::new(display, &mut black, &mut red);
+        display.update().unwrap();
+    }
+    // the device still owns the interface
+    let data = &device.interface.data;
+    assert_eq!(data[0], 0x10);
+    assert_eq!(data[9], 0x13);
+    assert_eq!(data[18], 0x12);
+}
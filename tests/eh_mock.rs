@@ -0,0 +1,42 @@
+#![cfg(feature = "eh-mock-tests")]
+
+extern crate embedded_hal as hal;
+extern crate il0373;
+
+use il0373::mock::Expectations;
+use il0373::{Builder, Dimensions, Display};
+
+mod common;
+use common::NoDelay;
+
+#[test]
+fn refresh_and_sleep() {
+    let config = Builder::new()
+        .dimensions(Dimensions { rows: 8, cols: 8 })
+        .build()
+        .expect("invalid config");
+    let mut mocks = Expectations::new()
+        .command(0x12)
+        .busy_wait()
+        .command(0x50)
+        .data(&[0x17])
+        .command(0x82)
+        .data(&[0x00])
+        .command(0x02)
+        .busy_wait()
+        .command(0x07)
+        .data(&[0xA5])
+        .build();
+    let mut display = Display::new(mocks.interface(), config);
+    display.signal_update().unwrap();
+    display.deep_sleep().unwrap();
+    mocks.done();
+}
+
+#[test]
+fn reset_pulses() {
+    let mut mocks = Expectations::new().reset().build();
+    let mut interface = mocks.interface();
+    il0373::DisplayInterface::reset(&mut interface, &mut NoDelay);
+    mocks.done();
+}
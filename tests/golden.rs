@@ -0,0 +1,87 @@
+#![cfg(feature = "golden-tests")]
+
+extern crate il0373;
+
+use il0373::config::Config;
+use il0373::golden::{self, Frame, Mismatch};
+use il0373::{BufferLayout, Builder, Dimensions, Rotation};
+use std::path::PathBuf;
+
+const ROWS: u16 = 64;
+const COLS: u8 = 32;
+
+fn config(rotation: Rotation, layout: BufferLayout) -> Config {
+    Builder::new()
+        .dimensions(Dimensions {
+            rows: ROWS,
+            cols: COLS,
+        })
+        .rotation(rotation)
+        .buffer_layout(layout)
+        .build()
+        .expect("invalid config")
+}
+
+fn scene(rotation: Rotation, layout: BufferLayout) -> Frame {
+    golden::render(config(rotation, layout), |display| {
+        golden::example_scene(display).unwrap()
+    })
+}
+
+fn golden_file(name: &str) -> PathBuf {
+    [env!("CARGO_MANIFEST_DIR"), "tests", "golden", name]
+        .iter()
+        .collect()
+}
+
+#[test]
+fn example_scene() {
+    let rotations = [
+        (Rotation::Rotate0, "scene_rotate0.bin"),
+        (Rotation::Rotate90, "scene_rotate90.bin"),
+        (Rotation::Rotate180, "scene_rotate180.bin"),
+        (Rotation::Rotate270, "scene_rotate270.bin"),
+    ];
+    for (rotation, name) in rotations.iter() {
+        let frame = scene(*rotation, BufferLayout::Planar);
+        assert_eq!(frame.black.len(), il0373::buffer_size(ROWS, COLS));
+        assert_eq!(frame, scene(*rotation, BufferLayout::Interleaved));
+        if let Err(e) = golden::check(&frame, golden_file(name)) {
+            panic!("{}", e);
+        }
+    }
+}
+
+#[test]
+fn mismatch_writes_diff() {
+    let dir = std::env::temp_dir().join(format!("il0373-golden-{}", std::process::id()));
+    let path = dir.join("scene.bin");
+    let frame = scene(Rotation::Rotate0, BufferLayout::Planar);
+    let mut changed = frame.clone();
+    changed.black[0] ^= 0x81;
+    changed.red[1] ^= 0x01;
+
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(&path, frame.to_bytes()).unwrap();
+    match golden::compare(&changed, &path) {
+        Err(Mismatch::Pixels { count, diff, .. }) => {
+            assert_eq!(count, 3);
+            let pbm = std::fs::read(diff).unwrap();
+            let header = b"P4\n32 64\n";
+            assert_eq!(&pbm[..header.len()], header);
+            assert_eq!(&pbm[header.len()..header.len() + 2], [0x81, 0x01]);
+        }
+        other => panic!("expected a mismatch, got {:?}", other),
+    }
+
+    // a missing golden file fails rather than being written
+    let missing = dir.join("missing.bin");
+    match golden::check(&frame, &missing) {
+        Err(Mismatch::Missing { actual, .. }) => {
+            assert_eq!(std::fs::read(actual).unwrap(), frame.to_bytes());
+        }
+        other => panic!("expected a missing file, got {:?}", other),
+    }
+    assert!(!missing.exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
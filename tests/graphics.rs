@@ -0,0 +1,799 @@
+#![cfg(feature = "graphics")]
+
+extern crate embedded_graphics;
+extern crate embedded_hal as hal;
+extern crate il0373;
+
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::{Circle, PrimitiveStyle, Rectangle};
+use il0373::luts::LutProfile;
+use il0373::sprite::Sprite;
+use il0373::{
+    codec, BufferLayout, Builder, Color, Compositing, Dimensions, Display, Error, GraphicDisplay,
+    GraphicDisplayFixed, OutOfBounds, PanelColor, PanelState, Plane, RamBuffers, Rotation,
+    UpdateStrategy,
+};
+
+mod common;
+use common::{NoDelay, RecordingInterface};
+
+const ROWS: u16 = 24;
+const COLS: u8 = 16;
+const BUFFER_SIZE: usize = il0373::buffer_size(ROWS, COLS);
+
+fn build_display(rotation: Rotation, layout: BufferLayout) -> Display<RecordingInterface> {
+    build_display_with(rotation, layout, OutOfBounds::Clip)
+}
+
+fn build_display_with(
+    rotation: Rotation,
+    layout: BufferLayout,
+    policy: OutOfBounds,
+) -> Display<RecordingInterface> {
+    let config = Builder::new()
+        .dimensions(Dimensions {
+            rows: ROWS,
+            cols: COLS,
+        })
+        .rotation(rotation)
+        .invert_red_plane(true)
+        .buffer_layout(layout)
+        .out_of_bounds(policy)
+        .build()
+        .expect("invalid config");
+    Display::new(RecordingInterface::new(), config)
+}
+
+// draw a scene touching every drawing path, and return what is sent to the controller
+fn render(rotation: Rotation, layout: BufferLayout) -> Vec<u8> {
+    let mut black = [0u8; BUFFER_SIZE];
+    let mut red = [0u8; BUFFER_SIZE];
+    let mut display = GraphicDisplay::new(build_display(rotation, layout), &mut black, &mut red);
+    display.clear(Color::White).ok();
+    Rectangle::new(Point::new(1, 2), Size::new(9, 13))
+        .into_styled(PrimitiveStyle::with_fill(Color::Black))
+        .draw(&mut display)
+        .ok();
+    Circle::new(Point::new(4, 6), 8)
+        .into_styled(PrimitiveStyle::with_fill(Color::Red))
+        .draw(&mut display)
+        .ok();
+    display
+        .fill_rect(
+            Rectangle::new(Point::new(3, 1), Size::new(12, 3)),
+            Color::Red,
+        )
+        .ok();
+    display
+        .fill_rect(
+            Rectangle::new(Point::new(2, 18), Size::new(3, 2)),
+            Color::Black,
+        )
+        .ok();
+    Pixel(Point::new(15, 0), BinaryColor::On)
+        .draw(&mut display.red_layer())
+        .ok();
+    Pixel(Point::new(5, 5), BinaryColor::Off)
+        .draw(&mut display.black_layer())
+        .ok();
+    display.update().unwrap();
+    display.interface().data.clone()
+}
+
+#[test]
+fn interleaved_matches_planar() {
+    for rotation in [
+        Rotation::Rotate0,
+        Rotation::Rotate90,
+        Rotation::Rotate180,
+        Rotation::Rotate270,
+    ]
+    .iter()
+    {
+        let planar = render(*rotation, BufferLayout::Planar);
+        let interleaved = render(*rotation, BufferLayout::Interleaved);
+        assert_eq!(planar.len(), 2 * BUFFER_SIZE + 3);
+        assert_eq!(planar, interleaved);
+    }
+}
+
+#[test]
+fn interleaved_fill_layer() {
+    let mut black = [0u8; BUFFER_SIZE];
+    let mut red = [0u8; BUFFER_SIZE];
+    let display = build_display(Rotation::Rotate0, BufferLayout::Interleaved);
+    let mut display = GraphicDisplay::new(display, &mut black, &mut red);
+    display.clear(Color::Red).ok();
+    display.black_layer().clear(BinaryColor::On).ok();
+    display.red_layer().clear(BinaryColor::Off).ok();
+    display.update().unwrap();
+    let data = &display.interface().data;
+    assert!(data[1..=BUFFER_SIZE].iter().all(|b| *b == 0x00));
+    // the red plane is inverted
+    assert!(data[BUFFER_SIZE + 2..=2 * BUFFER_SIZE + 1]
+        .iter()
+        .all(|b| *b == 0x00));
+}
+
+// a 4-color panel where ink in both planes is yellow
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Bwry {
+    Black,
+    White,
+    Red,
+    Yellow,
+}
+
+impl PixelColor for Bwry {
+    type Raw = ();
+}
+
+impl PanelColor for Bwry {
+    fn planes(self) -> (bool, bool) {
+        match self {
+            Bwry::Black => (false, true),
+            Bwry::White => (true, true),
+            Bwry::Red => (true, false),
+            Bwry::Yellow => (false, false),
+        }
+    }
+}
+
+#[test]
+fn custom_panel_color() {
+    let mut black = [0u8; BUFFER_SIZE];
+    let mut red = [0u8; BUFFER_SIZE];
+    {
+        let display = build_display(Rotation::Rotate0, BufferLayout::Planar);
+        let mut display =
+            GraphicDisplay::new(display, &mut black, &mut red).with_panel_color::<Bwry>();
+        display.clear(Bwry::White).ok();
+        Pixel(Point::new(0, 0), Bwry::Yellow)
+            .draw(&mut display)
+            .ok();
+        Pixel(Point::new(1, 0), Bwry::Black).draw(&mut display).ok();
+        Pixel(Point::new(2, 0), Bwry::Red).draw(&mut display).ok();
+        let stats = display.stats();
+        assert_eq!((stats.black, stats.red), (1, 2));
+        assert_eq!(stats.cleared_to, Some(Color::White));
+    }
+    assert_eq!(black[0], 0b0011_1111);
+    // the red plane is inverted
+    assert_eq!(red[0], 0b1010_0000);
+}
+
+#[test]
+fn red_overlay_keeps_black() {
+    for layout in [BufferLayout::Planar, BufferLayout::Interleaved].iter() {
+        let mut black = [0u8; BUFFER_SIZE];
+        let mut red = [0u8; BUFFER_SIZE];
+        let display = build_display(Rotation::Rotate0, *layout);
+        let mut display = GraphicDisplay::new(display, &mut black, &mut red);
+        display.clear(Color::White).ok();
+        Rectangle::new(Point::new(0, 0), Size::new(16, 4))
+            .into_styled(PrimitiveStyle::with_fill(Color::Black))
+            .draw(&mut display)
+            .ok();
+        display.set_compositing(Compositing::RedOverlay);
+        display
+            .fill_rect(
+                Rectangle::new(Point::new(2, 1), Size::new(12, 2)),
+                Color::Red,
+            )
+            .ok();
+        Pixel(Point::new(0, 3), Color::Red).draw(&mut display).ok();
+        // other colors still replace
+        Pixel(Point::new(15, 3), Color::White)
+            .draw(&mut display)
+            .ok();
+        display.set_compositing(Compositing::Replace);
+        Pixel(Point::new(15, 0), Color::Red).draw(&mut display).ok();
+        display.update().unwrap();
+
+        let data = &display.interface().data;
+        let (bw, rd) = (&data[1..=BUFFER_SIZE], &data[BUFFER_SIZE + 2..]);
+        assert_eq!(
+            &bw[..8],
+            &[0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01],
+            "{:?}",
+            layout
+        );
+        // the red plane is inverted, set bits are red
+        assert_eq!(
+            &rd[..8],
+            &[0x00, 0x01, 0x3F, 0xFC, 0x3F, 0xFC, 0x80, 0x00],
+            "{:?}",
+            layout
+        );
+    }
+}
+
+#[test]
+fn invert_rect_toggles() {
+    for rotation in [
+        Rotation::Rotate0,
+        Rotation::Rotate90,
+        Rotation::Rotate180,
+        Rotation::Rotate270,
+    ]
+    .iter()
+    {
+        for layout in [BufferLayout::Planar, BufferLayout::Interleaved].iter() {
+            let mut black = [0u8; BUFFER_SIZE];
+            let mut red = [0u8; BUFFER_SIZE];
+            let display = build_display(*rotation, *layout);
+            let mut display = GraphicDisplay::new(display, &mut black, &mut red);
+            display.clear(Color::White).ok();
+            let area = Rectangle::new(Point::new(1, 2), Size::new(11, 5));
+            display.invert_rect(area).ok();
+            // the same as drawing the rectangle black
+            let mut expected = [0u8; BUFFER_SIZE];
+            let mut expected_red = [0u8; BUFFER_SIZE];
+            let mut drawn = GraphicDisplay::new(
+                build_display(*rotation, *layout),
+                &mut expected,
+                &mut expected_red,
+            );
+            drawn.clear(Color::White).ok();
+            drawn.fill_rect(area, Color::Black).ok();
+            display.update().unwrap();
+            drawn.update().unwrap();
+            assert_eq!(display.interface().data, drawn.interface().data);
+
+            // and inverting again restores white
+            display.interface().data.clear();
+            display.invert_rect(area).ok();
+            display.update().unwrap();
+            let data = &display.interface().data;
+            assert!(data[1..=BUFFER_SIZE].iter().all(|b| *b == 0xFF));
+        }
+    }
+}
+
+#[test]
+fn apply_rle_round_trip() {
+    for layout in [BufferLayout::Planar, BufferLayout::Interleaved].iter() {
+        let frame = render(Rotation::Rotate90, *layout);
+        let (black, red) = (&frame[1..=BUFFER_SIZE], &frame[BUFFER_SIZE + 2..]);
+        let mut encoded = [[0u8; codec::max_encoded_len(BUFFER_SIZE)]; 2];
+        let black_len = codec::encode(black, &mut encoded[0]).unwrap();
+        let red_len = codec::encode(&red[..BUFFER_SIZE], &mut encoded[1]).unwrap();
+
+        let mut black_buffer = [0u8; BUFFER_SIZE];
+        let mut red_buffer = [0u8; BUFFER_SIZE];
+        let display = build_display(Rotation::Rotate90, *layout);
+        let mut display = GraphicDisplay::new(display, &mut black_buffer, &mut red_buffer);
+        display
+            .apply_rle(Plane::Black, &encoded[0][..black_len])
+            .unwrap();
+        display
+            .apply_rle(Plane::Red, &encoded[1][..red_len])
+            .unwrap();
+        assert_eq!(
+            display.apply_rle(Plane::Red, &[0x80, 0xFF]),
+            Err(Error::BufferSize {
+                expected: BUFFER_SIZE,
+                actual: 2
+            })
+        );
+        assert_eq!(
+            display.apply_rle(Plane::Red, &[0x05, 0xFF]),
+            Err(Error::InvalidStream)
+        );
+        display.update().unwrap();
+        assert_eq!(display.interface().data, frame);
+    }
+}
+
+#[test]
+fn pixels_in_drawing_coordinates() {
+    for rotation in [Rotation::Rotate0, Rotation::Rotate90, Rotation::Rotate270].iter() {
+        for layout in [BufferLayout::Planar, BufferLayout::Interleaved].iter() {
+            let mut black = [0u8; BUFFER_SIZE];
+            let mut red = [0u8; BUFFER_SIZE];
+            let mut display =
+                GraphicDisplay::new(build_display(*rotation, *layout), &mut black, &mut red);
+            let size = display.size();
+            let corner = Point::new(size.width as i32 - 1, size.height as i32 - 1);
+            let drawn = [
+                Pixel(Point::new(0, 0), Color::Black),
+                Pixel(Point::new(1, 0), Color::Red),
+                Pixel(Point::new(7, 9), Color::Red),
+                Pixel(Point::new(5, 3), Color::Black),
+                Pixel(corner, Color::Red),
+            ];
+            display.clear(Color::White).unwrap();
+            display.draw_iter(drawn.iter().cloned()).unwrap();
+
+            let pixels: Vec<Pixel<Color>> = display.pixels().collect();
+            assert_eq!(pixels.len(), (size.width * size.height) as usize);
+            assert_eq!(pixels[1], Pixel(Point::new(1, 0), Color::Red));
+            assert_eq!(pixels.last(), Some(&Pixel(corner, Color::Red)));
+            for Pixel(point, color) in pixels {
+                let expected = drawn
+                    .iter()
+                    .find(|pixel| pixel.0 == point)
+                    .map_or(Color::White, |pixel| pixel.1);
+                assert_eq!(color, expected, "{:?} {:?} {:?}", rotation, layout, point);
+            }
+        }
+    }
+}
+
+#[test]
+fn draw_sprite_skips_transparent() {
+    // 11 pixels wide, black with a red bar, white in the corners and the end of the bar
+    #[rustfmt::skip]
+    let black = [
+        0b1000_0000, 0b0010_0000,
+        0b0000_0000, 0b0000_0000,
+        0b0111_1111, 0b1100_0000,
+        0b0000_0000, 0b0000_0000,
+    ];
+    let red = [0xFF, 0xFF, 0xFF, 0xFF, 0b1000_0000, 0b0111_1111, 0xFF, 0xFF];
+    let sprite = Sprite::new(&black, 11).with_red(&red);
+    let top_left = Point::new(-3, 4);
+    for rotation in [
+        Rotation::Rotate0,
+        Rotation::Rotate90,
+        Rotation::Rotate180,
+        Rotation::Rotate270,
+    ]
+    .iter()
+    {
+        for layout in [BufferLayout::Planar, BufferLayout::Interleaved].iter() {
+            let mut black = [0u8; BUFFER_SIZE];
+            let mut red = [0u8; BUFFER_SIZE];
+            let mut display =
+                GraphicDisplay::new(build_display(*rotation, *layout), &mut black, &mut red);
+            display.clear(Color::Red).unwrap();
+            display
+                .draw_sprite(&sprite, top_left, Some(Color::White))
+                .unwrap();
+
+            for Pixel(point, color) in display.pixels() {
+                let offset = point - top_left;
+                let expected = match sprite.pixel(offset.x as u32, offset.y as u32) {
+                    Some(Color::White) | None => Color::Red,
+                    Some(color) => color,
+                };
+                assert_eq!(color, expected, "{:?} {:?} {:?}", rotation, layout, point);
+            }
+            // only the pixels on the display, without the white corners
+            let stats = display.stats();
+            assert_eq!((stats.black, stats.red, stats.white), (24, 6, 0));
+        }
+    }
+}
+
+const ROTATIONS: [Rotation; 4] = [
+    Rotation::Rotate0,
+    Rotation::Rotate90,
+    Rotation::Rotate180,
+    Rotation::Rotate270,
+];
+
+// the corners of a display, and the points just outside each of them
+fn edges(size: Size) -> (Vec<Point>, Vec<Point>) {
+    let (right, bottom) = (size.width as i32 - 1, size.height as i32 - 1);
+    let corners = vec![
+        Point::new(0, 0),
+        Point::new(right, 0),
+        Point::new(0, bottom),
+        Point::new(right, bottom),
+    ];
+    let outside = vec![
+        Point::new(-1, 0),
+        Point::new(0, -1),
+        Point::new(right + 1, 0),
+        Point::new(right, -1),
+        Point::new(-1, bottom),
+        Point::new(0, bottom + 1),
+        Point::new(right + 1, bottom),
+        Point::new(right, bottom + 1),
+    ];
+    (corners, outside)
+}
+
+#[test]
+fn out_of_bounds_clip() {
+    for rotation in ROTATIONS.iter() {
+        let mut black = [0u8; BUFFER_SIZE];
+        let mut red = [0u8; BUFFER_SIZE];
+        let display = build_display_with(*rotation, BufferLayout::Planar, OutOfBounds::Clip);
+        let mut display = GraphicDisplay::new(display, &mut black, &mut red);
+        display.clear(Color::White).unwrap();
+        let (corners, outside) = edges(display.size());
+        let pixels = outside
+            .iter()
+            .chain(corners.iter())
+            .map(|point| Pixel(*point, Color::Black));
+        display.draw_iter(pixels).unwrap();
+
+        for Pixel(point, color) in display.pixels() {
+            let expected = if corners.contains(&point) {
+                Color::Black
+            } else {
+                Color::White
+            };
+            assert_eq!(color, expected, "{:?} {:?}", rotation, point);
+        }
+    }
+}
+
+#[test]
+fn out_of_bounds_error() {
+    for rotation in ROTATIONS.iter() {
+        let mut black = [0u8; BUFFER_SIZE];
+        let mut red = [0u8; BUFFER_SIZE];
+        let display = build_display_with(*rotation, BufferLayout::Planar, OutOfBounds::Error);
+        let mut display = GraphicDisplay::new(display, &mut black, &mut red);
+        display.clear(Color::White).unwrap();
+        let (corners, outside) = edges(display.size());
+        display
+            .draw_iter(corners.iter().map(|point| Pixel(*point, Color::Red)))
+            .unwrap();
+        for point in outside.iter() {
+            // the corner before the pixel outside is drawn, the one after isn't
+            let pixels = [
+                Pixel(corners[0], Color::Black),
+                Pixel(*point, Color::Black),
+                Pixel(corners[3], Color::Black),
+            ];
+            assert_eq!(
+                display.draw_iter(pixels.iter().cloned()),
+                Err(Error::OutOfBounds {
+                    x: point.x,
+                    y: point.y
+                }),
+                "{:?}",
+                rotation
+            );
+        }
+        for Pixel(point, color) in display.pixels() {
+            let expected = match corners.iter().position(|corner| *corner == point) {
+                Some(0) => Color::Black,
+                Some(_) => Color::Red,
+                None => Color::White,
+            };
+            assert_eq!(color, expected, "{:?} {:?}", rotation, point);
+        }
+    }
+}
+
+#[test]
+fn out_of_bounds_panic() {
+    for rotation in ROTATIONS.iter() {
+        let size = {
+            let display = build_display_with(*rotation, BufferLayout::Planar, OutOfBounds::Panic);
+            let mut black = [0u8; BUFFER_SIZE];
+            let mut red = [0u8; BUFFER_SIZE];
+            GraphicDisplay::new(display, &mut black, &mut red).size()
+        };
+        let (corners, outside) = edges(size);
+        for point in corners.iter().chain(outside.iter()) {
+            let point = *point;
+            let result = std::panic::catch_unwind(|| {
+                let display =
+                    build_display_with(*rotation, BufferLayout::Planar, OutOfBounds::Panic);
+                let mut black = [0u8; BUFFER_SIZE];
+                let mut red = [0u8; BUFFER_SIZE];
+                let mut display = GraphicDisplay::new(display, &mut black, &mut red);
+                Pixel(point, Color::Black).draw(&mut display).ok();
+            });
+            assert_eq!(
+                result.is_err(),
+                !corners.contains(&point),
+                "{:?} {:?}",
+                rotation,
+                point
+            );
+        }
+    }
+}
+
+#[test]
+fn update_window_sends_rows() {
+    let mut sent = Vec::new();
+    for layout in [BufferLayout::Planar, BufferLayout::Interleaved].iter() {
+        let mut black = [0u8; BUFFER_SIZE];
+        let mut red = [0u8; BUFFER_SIZE];
+        let mut display = GraphicDisplay::new(
+            build_display(Rotation::Rotate0, *layout),
+            &mut black,
+            &mut red,
+        );
+        display.clear(Color::White).unwrap();
+        Pixel(Point::new(3, 5), Color::Black)
+            .draw(&mut display)
+            .unwrap();
+        Pixel(Point::new(4, 6), Color::Red)
+            .draw(&mut display)
+            .unwrap();
+        display
+            .update_window(Rectangle::new(Point::new(3, 5), Size::new(4, 2)))
+            .unwrap();
+        assert_eq!(display.odometer().partial_refreshes, 1);
+        let data = std::mem::take(&mut display.interface().data);
+        drop(display);
+        if *layout == BufferLayout::Planar {
+            // sources 0 to 7 of gates 5 and 6, the first byte of rows 5 and 6
+            let expected = [
+                0x91, 0x90, 0x00, 0x07, 0x00, 0x05, 0x00, 0x06, 0x01, 0x10, black[10], black[12],
+                0x13, red[10], red[12], 0x12, 0x92,
+            ];
+            assert_eq!(data, expected);
+            assert_eq!((black[10], red[12]), (0b1110_1111, 0b0000_1000));
+        }
+        sent.push(data);
+    }
+    assert_eq!(sent[0], sent[1]);
+
+    // nothing to refresh outside the display
+    let mut black = [0u8; BUFFER_SIZE];
+    let mut red = [0u8; BUFFER_SIZE];
+    let display = build_display(Rotation::Rotate0, BufferLayout::Planar);
+    let mut display = GraphicDisplay::new(display, &mut black, &mut red);
+    display
+        .update_window(Rectangle::new(Point::new(-8, 0), Size::new(8, 8)))
+        .unwrap();
+    assert!(display.interface().data.is_empty());
+}
+
+#[test]
+fn update_window_failure_leaves_partial_mode() {
+    let window = Rectangle::new(Point::new(3, 5), Size::new(4, 2));
+    let mut black = [0u8; BUFFER_SIZE];
+    let mut red = [0u8; BUFFER_SIZE];
+    let display = build_display(Rotation::Rotate0, BufferLayout::Planar);
+    let mut display = GraphicDisplay::new(display, &mut black, &mut red);
+    display.reset(&mut NoDelay).unwrap();
+    display.interface().data.clear();
+    // the refresh fails after the window is sent
+    display.interface().fail_command = Some(0x12);
+    assert_eq!(display.update_window(window), Err(Error::Interface(())));
+    let data = &display.interface().data;
+    assert_eq!((data[0], data[data.len() - 1]), (0x91, 0x92));
+    assert_eq!(display.state().panel, PanelState::Unknown);
+
+    // buffers too short for the window are found before partial mode
+    let mut black = [0u8; 8];
+    let mut red = [0u8; 8];
+    let display = build_display(Rotation::Rotate0, BufferLayout::Planar);
+    let mut display = GraphicDisplay::new(display, &mut black, &mut red);
+    assert_eq!(
+        display.update_window(window),
+        Err(Error::BufferSize {
+            expected: 13,
+            actual: 8
+        })
+    );
+    assert!(display.interface().data.is_empty());
+}
+
+#[test]
+fn diff_upload_sends_changed_rows() {
+    let config = Builder::new()
+        .dimensions(Dimensions {
+            rows: ROWS,
+            cols: COLS,
+        })
+        .update_strategy(UpdateStrategy::DiffUpload)
+        .build()
+        .expect("invalid config");
+    let mut black = [0u8; BUFFER_SIZE];
+    let mut red = [0u8; BUFFER_SIZE];
+    let mut prev_black = [0u8; BUFFER_SIZE];
+    let mut prev_red = [0u8; BUFFER_SIZE];
+    let buffers =
+        RamBuffers::new(&mut black, &mut red).with_previous(&mut prev_black, &mut prev_red);
+    let mut display =
+        GraphicDisplay::with_store(Display::new(RecordingInterface::new(), config), buffers);
+    let update = |display: &mut GraphicDisplay<RecordingInterface, RamBuffers>| {
+        display.update().unwrap();
+        std::mem::take(&mut display.interface().data)
+    };
+
+    // the first update sends both planes in full
+    display.clear(Color::White).unwrap();
+    let data = update(&mut display);
+    assert_eq!(data.len(), 3 + 2 * BUFFER_SIZE);
+    assert_eq!((data[0], data[BUFFER_SIZE + 1]), (0x10, 0x13));
+
+    // nothing changed, only refresh
+    assert_eq!(update(&mut display), [0x12]);
+
+    // rows 5 and 6 changed, one window
+    Pixel(Point::new(3, 5), Color::Black)
+        .draw(&mut display)
+        .unwrap();
+    Pixel(Point::new(12, 6), Color::Red)
+        .draw(&mut display)
+        .unwrap();
+    let data = update(&mut display);
+    let expected = [
+        0x91, 0x90, 0x00, 0x0F, 0x00, 0x05, 0x00, 0x06, 0x01, 0x10, 0xEF, 0xFF, 0xFF, 0xFF, 0x13,
+        0xFF, 0xFF, 0xFF, 0xF7, 0x92, 0x12,
+    ];
+    assert_eq!(data, expected);
+    // the windows are shown by a full refresh, and counted as one
+    let odometer = display.odometer();
+    assert_eq!(
+        (odometer.full_refreshes, odometer.partial_refreshes),
+        (3, 0)
+    );
+    assert_eq!(odometer.since_full, 0);
+    assert_eq!(odometer.refreshes(), display.state().refreshes);
+
+    // the controller RAM is lost by a reset
+    display.reset(&mut NoDelay).unwrap();
+    display.interface().data.clear();
+    assert_eq!(update(&mut display).len(), 3 + 2 * BUFFER_SIZE);
+}
+
+#[test]
+fn fast_mono_old_and_new_frame() {
+    let config = Builder::new()
+        .dimensions(Dimensions {
+            rows: ROWS,
+            cols: COLS,
+        })
+        .lut_profile(LutProfile::FastMono)
+        .build()
+        .expect("invalid config");
+    let mut black = [0u8; BUFFER_SIZE];
+    let mut red = [0u8; BUFFER_SIZE];
+    let mut prev_black = [0u8; BUFFER_SIZE];
+    let mut prev_red = [0u8; BUFFER_SIZE];
+    let buffers =
+        RamBuffers::new(&mut black, &mut red).with_previous(&mut prev_black, &mut prev_red);
+    let mut display =
+        GraphicDisplay::with_store(Display::new(RecordingInterface::new(), config), buffers);
+    let update = |display: &mut GraphicDisplay<RecordingInterface, RamBuffers>| {
+        display.update().unwrap();
+        std::mem::take(&mut display.interface().data)
+    };
+
+    // the old frame, all black, then the new one, all white
+    display.clear(Color::White).unwrap();
+    let mut expected = vec![0x10];
+    expected.extend([0x00; BUFFER_SIZE]);
+    expected.push(0x13);
+    expected.extend([0xFF; BUFFER_SIZE]);
+    expected.push(0x12);
+    assert_eq!(update(&mut display), expected);
+
+    // the new frame becomes the old one, the Red plane isn't sent
+    Pixel(Point::new(3, 5), Color::Black)
+        .draw(&mut display)
+        .unwrap();
+    let data = update(&mut display);
+    assert_eq!(data[0], 0x10);
+    assert!(data[1..=BUFFER_SIZE].iter().all(|&b| b == 0xFF));
+    assert_eq!(data[BUFFER_SIZE + 1], 0x13);
+    let new = &data[BUFFER_SIZE + 2..2 * BUFFER_SIZE + 2];
+    assert_eq!(new[5 * 2], 0xEF);
+    assert_eq!(new.iter().filter(|&&b| b != 0xFF).count(), 1);
+    assert_eq!(data[2 * BUFFER_SIZE + 2..], [0x12]);
+}
+
+#[test]
+fn white_flash_first() {
+    let config = Builder::new()
+        .dimensions(Dimensions {
+            rows: ROWS,
+            cols: COLS,
+        })
+        .invert_red_plane(true)
+        .update_strategy(UpdateStrategy::WhiteFlashFirst)
+        .build()
+        .expect("invalid config");
+    let mut black = [0u8; BUFFER_SIZE];
+    let mut red = [0u8; BUFFER_SIZE];
+    let mut display = GraphicDisplay::new(
+        Display::new(RecordingInterface::new(), config),
+        &mut black,
+        &mut red,
+    );
+    display.clear(Color::Red).unwrap();
+    display.update().unwrap();
+    let data = std::mem::take(&mut display.interface().data);
+
+    // white, refresh, then the frame, refresh
+    let mut expected = vec![0x10];
+    expected.extend([0xFF; BUFFER_SIZE]);
+    expected.push(0x13);
+    expected.extend([0x00; BUFFER_SIZE]);
+    expected.extend([0x12, 0x10]);
+    expected.extend([0xFF; BUFFER_SIZE]);
+    expected.push(0x13);
+    expected.extend([0xFF; BUFFER_SIZE]);
+    expected.push(0x12);
+    assert_eq!(data, expected);
+    assert_eq!(display.state().refreshes, 2);
+
+    // the supply is checked before the white refresh
+    assert_eq!(display.update_guarded(|| false), Err(Error::SupplyLow));
+    let data = &display.interface().data;
+    assert_eq!(data.len(), 2 + 2 * BUFFER_SIZE);
+    assert!(!data.contains(&0x12));
+}
+
+#[test]
+fn reset_guarded_update() {
+    let mut black = [0u8; BUFFER_SIZE];
+    let mut red = [0u8; BUFFER_SIZE];
+    let mut display = GraphicDisplay::new(
+        build_display(Rotation::Rotate0, BufferLayout::Planar),
+        &mut black,
+        &mut red,
+    );
+    {
+        let mut guard = display.reset_guarded(&mut NoDelay).unwrap();
+        guard.clear(Color::Black).unwrap();
+        guard.update().unwrap();
+    }
+    // dropping the guard put the panel into deep sleep after the refresh
+    assert_eq!(display.state().refreshes, 1);
+    assert_eq!(display.panel_state(), PanelState::DeepSleep);
+    assert!(display.interface().data.ends_with(&[0x02, 0x07, 0xA5]));
+}
+
+// draw shapes and text crossing byte boundaries
+fn draw_shapes<D: DrawTarget<Color = Color>>(target: &mut D) {
+    target.clear(Color::White).ok();
+    Circle::new(Point::new(2, 3), 11)
+        .into_styled(PrimitiveStyle::with_stroke(Color::Black, 2))
+        .draw(target)
+        .ok();
+    Rectangle::new(Point::new(5, 1), Size::new(7, 9))
+        .into_styled(PrimitiveStyle::with_fill(Color::Red))
+        .draw(target)
+        .ok();
+}
+
+fn render_fixed<const ROT: u8>(layout: BufferLayout) -> (Vec<u8>, Vec<u8>) {
+    let rotation = GraphicDisplayFixed::<RecordingInterface, RamBuffers, ROT>::ROTATION;
+    let mut black = [0u8; BUFFER_SIZE];
+    let mut red = [0u8; BUFFER_SIZE];
+    let mut display = GraphicDisplay::new(build_display(rotation, layout), &mut black, &mut red);
+    draw_shapes(&mut display);
+    display.update().unwrap();
+    let runtime = display.interface().data.clone();
+
+    let mut black = [0u8; BUFFER_SIZE];
+    let mut red = [0u8; BUFFER_SIZE];
+    let display = GraphicDisplay::new(build_display(rotation, layout), &mut black, &mut red);
+    let mut display = GraphicDisplayFixed::<_, _, ROT>::new(display)
+        .ok()
+        .expect("same rotation");
+    draw_shapes(&mut display);
+    display.update().unwrap();
+    (runtime, display.interface().data.clone())
+}
+
+#[test]
+fn fixed_rotation_matches_runtime() {
+    for layout in [BufferLayout::Planar, BufferLayout::Interleaved].iter() {
+        let rendered = [
+            render_fixed::<{ Rotation::Rotate0 as u8 }>(*layout),
+            render_fixed::<{ Rotation::Rotate90 as u8 }>(*layout),
+            render_fixed::<{ Rotation::Rotate180 as u8 }>(*layout),
+            render_fixed::<{ Rotation::Rotate270 as u8 }>(*layout),
+        ];
+        for (runtime, fixed) in rendered.iter() {
+            assert_eq!(runtime, fixed);
+        }
+    }
+
+    // the config must have the same rotation
+    let mut black = [0u8; BUFFER_SIZE];
+    let mut red = [0u8; BUFFER_SIZE];
+    let display = GraphicDisplay::new(
+        build_display(Rotation::Rotate90, BufferLayout::Planar),
+        &mut black,
+        &mut red,
+    );
+    assert!(GraphicDisplayFixed::<_, _, { Rotation::Rotate0 as u8 }>::new(display).is_err());
+}
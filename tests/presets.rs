@@ -0,0 +1,191 @@
+extern crate embedded_hal as hal;
+extern crate il0373;
+
+use il0373::command::{Command, DisplayResolution};
+use il0373::config::{BuilderError, PowerProfile};
+use il0373::luts::{LutProfile, FAST_MONO};
+use il0373::{Builder, Dimensions, Display, Rotation};
+
+mod common;
+use common::{NoDelay, RecordingInterface};
+
+#[test]
+fn tricolor_2in9_init_sequence() {
+    let config = Builder::tricolor_2in9().build().expect("invalid config");
+    let interface = RecordingInterface::new();
+    let mut display = Display::new(interface, config);
+    display.reset(&mut NoDelay).unwrap();
+
+    assert_eq!(display.rows(), 296);
+    assert_eq!(display.cols(), 128);
+
+    #[rustfmt::skip]
+    assert_eq!(
+        display.interface().data,
+        [
+            0x01, 0x03, 0x00, 0x2b, 0x2b, 0x09, // PWR
+            0x06, 0x17, 0x17, 0x17,             // BTST
+            0x04,                               // PON
+            0x00, 0x8F,                         // PSR 128x296
+            0x50, 0x37,                         // CDI
+            0x30, 0x29,                         // PLL
+            0x82, 0x0A,                         // VDCS
+            0x61, 0x80, 0x01, 0x28,             // TRES 128x296
+        ]
+    );
+}
+
+#[test]
+fn inky_phat_init_sequence() {
+    let config = Builder::inky_phat().build().expect("invalid config");
+    let mut display = Display::new(RecordingInterface::new(), config);
+    display.reset(&mut NoDelay).unwrap();
+
+    assert_eq!(display.rows(), 212);
+    assert_eq!(display.cols(), 104);
+    assert_eq!(config.rotation(), Rotation::Rotate270);
+
+    #[rustfmt::skip]
+    assert_eq!(
+        display.interface().data,
+        [
+            0x01, 0x03, 0x00, 0x2b, 0x2b, 0x09, // PWR
+            0x06, 0x17, 0x17, 0x17,             // BTST
+            0x04,                               // PON
+            0x00, 0x8F,                         // PSR 128x296
+            0x50, 0xB7,                         // CDI, border driven
+            0x30, 0x29,                         // PLL
+            0x82, 0x12,                         // VDCS -1.0V
+            0x61, 0x68, 0x00, 0xD4,             // TRES 104x212
+        ]
+    );
+}
+
+#[test]
+fn config_is_copy() {
+    let config = Builder::tricolor_2in9()
+        .rotation(Rotation::Rotate90)
+        .build()
+        .expect("invalid config");
+    let copy = config;
+
+    assert_eq!(
+        config.dimensions(),
+        Dimensions {
+            rows: 296,
+            cols: 128
+        }
+    );
+    assert_eq!(copy.dimensions(), config.dimensions());
+    assert_eq!(copy.rotation(), Rotation::Rotate90);
+}
+
+#[test]
+fn low_ripple_power_profile() {
+    let config = Builder::tricolor_2in9()
+        .power_profile(PowerProfile::LowRipple)
+        .build()
+        .expect("invalid config");
+    let interface = RecordingInterface::new();
+    let mut display = Display::new(interface, config);
+    display.reset(&mut NoDelay).unwrap();
+
+    let data = &display.interface().data;
+    assert_eq!(data[6..10], [0x06, 0xC7, 0xC7, 0x07]);
+    assert_eq!(data[15..17], [0x30, 0x3A]);
+}
+
+#[test]
+fn panel_setting_resolution() {
+    let dimensions = Dimensions {
+        rows: 212,
+        cols: 104,
+    };
+    // derived from the dimensions
+    let config = Builder::new().dimensions(dimensions).build().unwrap();
+    assert!(matches!(
+        config.panel_setting(),
+        Command::PanelSetting(DisplayResolution::R128x296)
+    ));
+
+    let error = Builder::new()
+        .panel_setting(DisplayResolution::R96x252)
+        .dimensions(dimensions)
+        .build()
+        .err();
+    assert_eq!(
+        error,
+        Some(BuilderError::ResolutionMismatch {
+            resolution: DisplayResolution::R96x252,
+            dimensions,
+        })
+    );
+}
+
+#[test]
+fn lut_profile() {
+    let error = Builder::tricolor_2in9()
+        .lut_profile(LutProfile::FastMono)
+        .build()
+        .err();
+    assert_eq!(error, Some(BuilderError::MonoLutOnTricolor));
+
+    // a black and white panel with the same settings
+    let config = Builder::tricolor_2in9()
+        .tricolor(false)
+        .lut_profile(LutProfile::FastMono)
+        .build()
+        .expect("invalid config");
+    let interface = RecordingInterface::new();
+    let mut display = Display::new(interface, config);
+    display.reset(&mut NoDelay).unwrap();
+
+    let data = &display.interface().data;
+    // PSR with LUTs from registers in black and white mode
+    assert_eq!(data[11..13], [0x00, 0xBF]);
+    assert_eq!(data[13], 0x20);
+    assert_eq!(data[14..58], FAST_MONO.vcom[..]);
+    assert_eq!(data[58], 0x21);
+    assert_eq!(data[59 + 42], 0x22);
+    assert_eq!(data[102..144], FAST_MONO.bw[..]);
+    assert_eq!(data[144 + 43], 0x24);
+    // the rest of the initialization follows
+    assert_eq!(data[188 + 42], 0x50);
+}
+
+#[test]
+fn swapped_dimensions() {
+    let error = Builder::new()
+        .dimensions(Dimensions {
+            rows: 104,
+            cols: 212,
+        })
+        .build()
+        .err();
+    assert_eq!(
+        error,
+        Some(BuilderError::DimensionsLookSwapped {
+            suggested: Dimensions {
+                rows: 212,
+                cols: 104,
+            },
+        })
+    );
+
+    let dimensions = Dimensions {
+        rows: 300,
+        cols: 104,
+    };
+    let error = Builder::new().dimensions(dimensions).build().err();
+    assert_eq!(error, Some(BuilderError::InvalidDimensions { dimensions }));
+}
+
+#[test]
+fn unaligned_columns() {
+    let dimensions = Dimensions {
+        rows: 212,
+        cols: 100,
+    };
+    let error = Builder::new().dimensions(dimensions).build().err();
+    assert_eq!(error, Some(BuilderError::UnalignedColumns { dimensions }));
+}
@@ -0,0 +1,100 @@
+extern crate il0373;
+
+use il0373::luts::LutProfile;
+use il0373::scheduler::{DisplayScheduler, Now, Refresh, UpdatePolicy};
+use il0373::{Builder, Dimensions, Display, GraphicDisplay, PanelState};
+
+mod common;
+use common::{NoDelay, RecordingInterface};
+
+fn build_display(profile: LutProfile) -> Display<RecordingInterface> {
+    let config = Builder::new()
+        .dimensions(Dimensions { rows: 8, cols: 8 })
+        .lut_profile(profile)
+        .build()
+        .expect("invalid config");
+    Display::new(RecordingInterface::new(), config)
+}
+
+#[test]
+fn paces_frames() {
+    let mut black = [0u8; 8];
+    let mut red = [0u8; 8];
+    let display = GraphicDisplay::new(build_display(LutProfile::Otp), &mut black, &mut red);
+    let policy = UpdatePolicy::new().min_interval_ms(1000);
+    let mut scheduler = DisplayScheduler::new(display, policy);
+
+    // nothing drawn
+    assert_eq!(scheduler.poll(&mut NoDelay, Now::ms(0)), Ok(None));
+    scheduler.frame();
+    assert_eq!(
+        scheduler.poll(&mut NoDelay, Now::ms(10)),
+        Ok(Some(Refresh::Full))
+    );
+    assert!(!scheduler.is_pending());
+    assert_eq!(scheduler.display().panel_state(), PanelState::Awake);
+
+    // the latest frame waits for the interval
+    scheduler.frame();
+    scheduler.frame();
+    assert_eq!(scheduler.poll(&mut NoDelay, Now::ms(1009)), Ok(None));
+    assert!(scheduler.is_pending());
+    assert_eq!(
+        scheduler.poll(&mut NoDelay, Now::ms(1010)),
+        Ok(Some(Refresh::Full))
+    );
+    assert_eq!(scheduler.release().state().refreshes, 2);
+}
+
+#[test]
+fn quiet_hours() {
+    let policy = UpdatePolicy::new().quiet_hours(22, 7);
+    assert!(policy.is_quiet(23));
+    assert!(policy.is_quiet(0));
+    assert!(!policy.is_quiet(7));
+    assert!(!policy.is_quiet(12));
+    let daytime = UpdatePolicy::new().quiet_hours(9, 17);
+    assert!(daytime.is_quiet(9));
+    assert!(!daytime.is_quiet(17));
+
+    let mut black = [0u8; 8];
+    let mut red = [0u8; 8];
+    let display = GraphicDisplay::new(build_display(LutProfile::Otp), &mut black, &mut red);
+    let mut scheduler = DisplayScheduler::new(display, policy).sleep_between(true);
+    scheduler.frame();
+    assert_eq!(
+        scheduler.poll(&mut NoDelay, Now::ms(0).at_hour(23)),
+        Ok(None)
+    );
+    // without a clock the quiet hours can't apply
+    assert!(scheduler.ready(Now::ms(0)));
+    assert_eq!(
+        scheduler.poll(&mut NoDelay, Now::ms(0).at_hour(7)),
+        Ok(Some(Refresh::Full))
+    );
+    assert_eq!(scheduler.display().panel_state(), PanelState::DeepSleep);
+    // the refresh is waited for once, not again by the deep sleep
+    assert_eq!(scheduler.release().interface().busy_waits, 3);
+}
+
+#[test]
+fn full_refresh_every() {
+    let mut black = [0u8; 8];
+    let mut red = [0u8; 8];
+    let display = GraphicDisplay::new(build_display(LutProfile::FastMono), &mut black, &mut red);
+    let policy = UpdatePolicy::new().min_interval_ms(0).full_refresh_every(3);
+    let mut scheduler = DisplayScheduler::new(display, policy);
+    let mut refreshes = Vec::new();
+    for ms in 0..7 {
+        scheduler.frame();
+        refreshes.push(scheduler.poll(&mut NoDelay, Now::ms(ms)).unwrap());
+    }
+    let (full, fast) = (Some(Refresh::Full), Some(Refresh::Fast));
+    assert_eq!(refreshes, [full, fast, fast, full, fast, fast, full]);
+
+    let display = scheduler.release();
+    assert_eq!(display.config().lut_profile(), LutProfile::FastMono);
+    let odometer = display.odometer();
+    assert_eq!(odometer.full_refreshes, 3);
+    assert_eq!(odometer.fast_refreshes, 4);
+}
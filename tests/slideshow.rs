@@ -0,0 +1,105 @@
+extern crate embedded_hal as hal;
+extern crate il0373;
+
+use il0373::slideshow::{FrameSource, Slideshow, MIN_REFRESH_INTERVAL_MS};
+use il0373::timing::Clock;
+use il0373::{Builder, Dimensions, Display};
+
+mod common;
+use common::{NoDelay, RecordingInterface};
+
+struct Frames {
+    loaded: Vec<usize>,
+}
+
+impl FrameSource for Frames {
+    type Error = ();
+
+    fn len(&self) -> usize {
+        2
+    }
+
+    fn load(&mut self, index: usize, black: &mut [u8], red: &mut [u8]) -> Result<(), ()> {
+        self.loaded.push(index);
+        black[0] = index as u8;
+        red[0] = index as u8;
+        Ok(())
+    }
+}
+
+struct FakeClock(u32);
+
+impl Clock for FakeClock {
+    fn now_ms(&mut self) -> u32 {
+        self.0
+    }
+}
+
+#[test]
+fn paces_and_wraps() {
+    let config = Builder::new()
+        .dimensions(Dimensions { rows: 8, cols: 8 })
+        .build()
+        .unwrap();
+    let display = Display::new(RecordingInterface::new(), config);
+    let mut black = [0u8; 8];
+    let mut red = [0u8; 8];
+    let mut slideshow =
+        Slideshow::new(display, &mut black, &mut red, Frames { loaded: vec![] }).interval_ms(1000);
+    let mut clock = FakeClock(5);
+
+    assert_eq!(slideshow.next_frame(&mut NoDelay, &mut clock), Ok(Some(0)));
+    clock.0 += MIN_REFRESH_INTERVAL_MS - 1;
+    assert_eq!(slideshow.next_frame(&mut NoDelay, &mut clock), Ok(None));
+    clock.0 += 1;
+    assert_eq!(slideshow.next_frame(&mut NoDelay, &mut clock), Ok(Some(1)));
+    clock.0 += MIN_REFRESH_INTERVAL_MS;
+    assert_eq!(slideshow.next_frame(&mut NoDelay, &mut clock), Ok(Some(0)));
+
+    let (mut display, frames) = slideshow.release();
+    assert_eq!(frames.loaded, [0, 1, 0]);
+    // left in deep sleep, the last byte is its check code
+    assert_eq!(display.interface().data.last(), Some(&0xA5));
+    // the power on and the reset, then one wait for each refresh
+    assert_eq!(display.interface().busy_waits, 9);
+}
+
+struct Solid;
+
+impl FrameSource for Solid {
+    type Error = ();
+
+    fn len(&self) -> usize {
+        1
+    }
+
+    fn load(&mut self, _index: usize, black: &mut [u8], red: &mut [u8]) -> Result<(), ()> {
+        black.fill(0x0F);
+        red.fill(0xFF);
+        Ok(())
+    }
+}
+
+#[test]
+fn inverts_planes() {
+    let config = Builder::new()
+        .dimensions(Dimensions { rows: 1, cols: 8 })
+        .invert_black_plane(true)
+        .build()
+        .unwrap();
+    let display = Display::new(RecordingInterface::new(), config);
+    let (mut black, mut red) = ([0u8; 1], [0u8; 1]);
+    let mut slideshow = Slideshow::new(display, &mut black, &mut red, Solid);
+    let mut clock = FakeClock(0);
+    // each frame is inverted once, as it is loaded again before every refresh
+    for _ in 0..2 {
+        assert_eq!(slideshow.next_frame(&mut NoDelay, &mut clock), Ok(Some(0)));
+        let (mut display, source) = slideshow.release();
+        let data = core::mem::take(&mut display.interface().data);
+        let dtm1 = data.windows(2).position(|w| w == [0x10, 0xF0]);
+        let dtm2 = data.windows(2).position(|w| w == [0x13, 0xFF]);
+        assert!(dtm1.is_some() && dtm2.is_some(), "{:02X?}", data);
+        slideshow = Slideshow::new(display, &mut black, &mut red, source);
+        clock.0 += MIN_REFRESH_INTERVAL_MS;
+    }
+}
@@ -0,0 +1,329 @@
+//! Runs the SRAM backed drawing and update pipeline against a software model of the MCP SRAM and
+//! controller sharing the SPI bus, as wired on the Adafruit displays.
+
+#![cfg(all(feature = "sram", feature = "graphics"))]
+
+extern crate embedded_graphics;
+extern crate embedded_hal as hal;
+extern crate il0373;
+
+use std::cell::RefCell;
+use std::convert::Infallible;
+use std::fmt::Debug;
+use std::rc::Rc;
+
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::{Circle, PrimitiveStyle, Rectangle};
+use il0373::{
+    Builder, Color, Dimensions, Display, DisplayInterface, Error, FrameBufferStore, GraphicDisplay,
+    SpiSramBus, SramBuffers, SramDisplayInterface, SramGraphicDisplay, SramSize,
+};
+
+mod common;
+use common::{NoDelay, RecordingInterface};
+
+const ROWS: u16 = 24;
+const COLS: u8 = 16;
+const BUFFER_SIZE: usize = il0373::buffer_size(ROWS, COLS);
+
+const READ: u8 = 0x03;
+const WRITE: u8 = 0x02;
+const WRSR: u8 = 0x01;
+const SEQUENTIAL: u8 = 0x40;
+const PAGE: u8 = 0x80;
+const PAGE_SIZE: u32 = 32;
+
+/// The devices on the bus, driven byte by byte by the SPI and pins.
+struct Bus {
+    sram: Vec<u8>,
+    wide_address: bool,
+    // the mode register, byte mode after power up
+    mode: u8,
+    sram_selected: bool,
+    // bytes received since the sram was selected
+    transaction: Vec<u8>,
+    epd_selected: bool,
+    dc: bool,
+    // every byte the controller received, and the ones received as commands
+    epd: Vec<u8>,
+    commands: Vec<u8>,
+}
+
+impl Bus {
+    fn new(size: SramSize) -> Rc<RefCell<Bus>> {
+        Rc::new(RefCell::new(Bus {
+            // not the value of a cleared buffer, so a missed write shows
+            sram: vec![0xA5; size.bytes() as usize],
+            wide_address: size == SramSize::M1,
+            mode: 0x00,
+            sram_selected: false,
+            transaction: Vec::new(),
+            epd_selected: false,
+            dc: false,
+            epd: Vec::new(),
+            commands: Vec::new(),
+        }))
+    }
+
+    // clock a byte out on MOSI, returning the byte on MISO
+    fn clock(&mut self, mosi: u8) -> u8 {
+        let miso = if self.sram_selected {
+            self.transaction.push(mosi);
+            self.sram_clock(mosi)
+        } else {
+            0x00
+        };
+        if self.epd_selected {
+            self.epd.push(mosi);
+            if !self.dc {
+                self.commands.push(mosi);
+            }
+        }
+        miso
+    }
+
+    fn sram_clock(&mut self, mosi: u8) -> u8 {
+        let instruction = self.transaction[0];
+        let index = self.transaction.len() as u32 - 1;
+        if instruction == WRSR {
+            if index == 1 {
+                self.mode = mosi;
+            }
+            return 0x00;
+        }
+        if instruction != READ && instruction != WRITE {
+            // the reset sequence, and anything else, is ignored
+            return 0x00;
+        }
+        let address_len = if self.wide_address { 3 } else { 2 };
+        if index <= address_len {
+            return 0x00;
+        }
+        let start = self.transaction[1..=address_len as usize]
+            .iter()
+            .fold(0u32, |address, byte| (address << 8) | u32::from(*byte));
+        let offset = index - address_len - 1;
+        let capacity = self.sram.len() as u32;
+        let address = match self.mode {
+            SEQUENTIAL => (start + offset) % capacity,
+            PAGE => (start & !(PAGE_SIZE - 1)) | ((start + offset) % PAGE_SIZE),
+            // byte mode only transfers a single byte
+            _ if offset > 0 => return 0x00,
+            _ => start,
+        } as usize;
+        if instruction == WRITE {
+            self.sram[address] = mosi;
+            0x00
+        } else {
+            self.sram[address]
+        }
+    }
+}
+
+struct Spi(Rc<RefCell<Bus>>);
+
+impl hal::blocking::spi::Transfer<u8> for Spi {
+    type Error = Infallible;
+
+    fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Self::Error> {
+        let mut bus = self.0.borrow_mut();
+        for word in words.iter_mut() {
+            *word = bus.clock(*word);
+        }
+        Ok(words)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Line {
+    SramCs,
+    EpdCs,
+    Dc,
+    Reset,
+}
+
+struct Pin(Rc<RefCell<Bus>>, Line);
+
+impl hal::digital::v2::OutputPin for Pin {
+    type Error = Infallible;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.set(false);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.set(true);
+        Ok(())
+    }
+}
+
+impl Pin {
+    fn set(&mut self, high: bool) {
+        let mut bus = self.0.borrow_mut();
+        match self.1 {
+            Line::SramCs => {
+                bus.sram_selected = !high;
+                bus.transaction.clear();
+            }
+            Line::EpdCs => bus.epd_selected = !high,
+            Line::Dc => bus.dc = high,
+            Line::Reset => (),
+        }
+    }
+}
+
+/// The busy line, never busy.
+struct Idle;
+
+impl hal::digital::v2::InputPin for Idle {
+    type Error = Infallible;
+
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        Ok(false)
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        Ok(true)
+    }
+}
+
+type SimInterface = SramDisplayInterface<Spi, Pin, Pin, Idle, Pin, Pin>;
+
+fn build_display<I: DisplayInterface>(interface: I) -> Display<I> {
+    let config = Builder::new()
+        .dimensions(Dimensions {
+            rows: ROWS,
+            cols: COLS,
+        })
+        .build()
+        .expect("invalid config");
+    let mut display = Display::new(interface, config);
+    display.reset(&mut NoDelay).ok();
+    display
+}
+
+fn build_sram_display(bus: &Rc<RefCell<Bus>>, size: SramSize) -> SramGraphicDisplay<SimInterface> {
+    GraphicDisplay::new_sram(build_sim_display(bus, size))
+}
+
+fn build_sim_display(bus: &Rc<RefCell<Bus>>, size: SramSize) -> Display<SimInterface> {
+    let pin = |line| Pin(bus.clone(), line);
+    let spi_bus = SpiSramBus::new_with_size(
+        Spi(bus.clone()),
+        (pin(Line::EpdCs), pin(Line::SramCs)),
+        size,
+    );
+    let interface = SramDisplayInterface::new(spi_bus, (Idle, pin(Line::Dc), pin(Line::Reset)));
+    let display = build_display(interface);
+    bus.borrow_mut().epd.clear();
+    bus.borrow_mut().commands.clear();
+    display
+}
+
+// draw a scene touching every drawing path, and update the display
+fn draw<I, S>(display: &mut GraphicDisplay<I, S>)
+where
+    I: DisplayInterface,
+    S: FrameBufferStore<I>,
+    S::Error: Debug,
+{
+    display.clear(Color::White).unwrap();
+    Rectangle::new(Point::new(1, 2), Size::new(9, 13))
+        .into_styled(PrimitiveStyle::with_fill(Color::Black))
+        .draw(display)
+        .unwrap();
+    Circle::new(Point::new(4, 6), 8)
+        .into_styled(PrimitiveStyle::with_fill(Color::Red))
+        .draw(display)
+        .unwrap();
+    display
+        .fill_rect(
+            Rectangle::new(Point::new(3, 1), Size::new(12, 3)),
+            Color::Red,
+        )
+        .unwrap();
+    Pixel(Point::new(15, 23), BinaryColor::On)
+        .draw(&mut display.red_layer())
+        .unwrap();
+    assert!(display.update().is_ok());
+}
+
+// what the controller receives for the scene with buffers in RAM
+fn expected() -> Vec<u8> {
+    let mut black = [0u8; BUFFER_SIZE];
+    let mut red = [0u8; BUFFER_SIZE];
+    let mut display = GraphicDisplay::new(
+        build_display(RecordingInterface::new()),
+        &mut black,
+        &mut red,
+    );
+    display.interface().data.clear();
+    draw(&mut display);
+    display.interface().data.clone()
+}
+
+fn render(size: SramSize) -> Rc<RefCell<Bus>> {
+    let bus = Bus::new(size);
+    draw(&mut build_sram_display(&bus, size));
+    bus
+}
+
+#[test]
+fn sram_update_matches_ram() {
+    let expected = expected();
+    for size in [SramSize::K64, SramSize::K256, SramSize::M1].iter() {
+        let bus = render(*size);
+        let bus = bus.borrow();
+        assert_eq!(bus.epd, expected, "{:?}", size);
+        // each plane is a single command followed by its data
+        assert_eq!(bus.epd[0], 0x10);
+        assert_eq!(bus.epd[BUFFER_SIZE + 1], 0x13);
+        assert_eq!(&bus.commands[..2], &[0x10, 0x13]);
+    }
+}
+
+#[test]
+fn sram_holds_planes() {
+    let bus = render(SramSize::K64);
+    let bus = bus.borrow();
+    // the planes are at the start of the sram, followed by untouched memory
+    assert_eq!(&bus.sram[..BUFFER_SIZE], &bus.epd[1..=BUFFER_SIZE]);
+    assert_eq!(
+        &bus.sram[BUFFER_SIZE..2 * BUFFER_SIZE],
+        &bus.epd[BUFFER_SIZE + 2..=2 * BUFFER_SIZE + 1]
+    );
+    assert!(bus.sram[2 * BUFFER_SIZE..].iter().all(|b| *b == 0xA5));
+}
+
+#[test]
+fn sram_update_checks_plane() {
+    let bus = Bus::new(SramSize::K64);
+    // planes a byte short, the move would run into the next plane
+    let short = (BUFFER_SIZE - 1) as u32;
+    let display = build_sim_display(&bus, SramSize::K64);
+    let mut display = GraphicDisplay::with_store(display, SramBuffers::new(short));
+    assert_eq!(
+        display.update(),
+        Err(Error::BufferSize {
+            expected: BUFFER_SIZE,
+            actual: BUFFER_SIZE - 1
+        })
+    );
+    assert!(bus.borrow().epd.is_empty());
+}
+
+#[test]
+fn sram_storage_wide_address() {
+    let bus = Bus::new(SramSize::M1);
+    let mut display = build_sram_display(&bus, SramSize::M1);
+    let mut storage = display.storage();
+    let offset = 0x1_0000;
+    storage.write(offset, &[1, 2, 3, 4]).unwrap();
+    let mut data = [0u8; 4];
+    storage.read(offset, &mut data).unwrap();
+    assert_eq!(data, [1, 2, 3, 4]);
+    let address = 2 * BUFFER_SIZE + offset as usize;
+    assert_eq!(&bus.borrow().sram[address..address + 4], &[1, 2, 3, 4]);
+}
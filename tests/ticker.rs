@@ -0,0 +1,114 @@
+#![cfg(feature = "graphics")]
+
+extern crate embedded_graphics;
+extern crate il0373;
+
+use embedded_graphics::mono_font::ascii::FONT_6X10;
+use embedded_graphics::prelude::*;
+use il0373::layout::Region;
+use il0373::ticker::Ticker;
+use il0373::{Builder, Color, Dimensions, Display, GraphicDisplay};
+
+mod common;
+use common::RecordingInterface;
+
+const ROWS: u16 = 16;
+const COLS: u8 = 32;
+const BUFFER_SIZE: usize = il0373::buffer_size(ROWS, COLS);
+
+fn build_display() -> Display<RecordingInterface> {
+    let config = Builder::new()
+        .dimensions(Dimensions {
+            rows: ROWS,
+            cols: COLS,
+        })
+        .build()
+        .expect("invalid config");
+    Display::new(RecordingInterface::new(), config)
+}
+
+#[test]
+fn scrolls_with_partial_refreshes() {
+    let mut black = [0u8; BUFFER_SIZE];
+    let mut red = [0u8; BUFFER_SIZE];
+    let mut display = GraphicDisplay::new(build_display(), &mut black, &mut red);
+    display.clear(Color::Red).unwrap();
+    let region = Region::display(display.config()).bottom_bar(10);
+    // 30 pixels of text and a gap of 16
+    let mut ticker = Ticker::new("HELLO", region, &FONT_6X10);
+    assert_eq!(ticker.period(), 46);
+
+    assert_eq!(ticker.poll(&mut display, 100), Ok(true));
+    assert_eq!(ticker.offset(), 0);
+    let data = std::mem::take(&mut display.interface().data);
+    // partial in, the window of gates 6 to 15, both planes, refresh and partial out
+    assert_eq!(
+        data[..9],
+        [0x91, 0x90, 0x00, 0x1F, 0x00, 0x06, 0x00, 0x0F, 0x01]
+    );
+    assert_eq!(data.len(), 9 + 2 * (1 + 4 * 10) + 2);
+    assert_eq!(data[data.len() - 2..], [0x12, 0x92]);
+    // only the region is drawn
+    for Pixel(point, color) in display.pixels() {
+        if point.y < 6 {
+            assert_eq!(color, Color::Red, "{:?}", point);
+        } else {
+            assert_ne!(color, Color::Red, "{:?}", point);
+        }
+    }
+
+    // a character each interval, missed steps in one refresh
+    assert_eq!(ticker.poll(&mut display, 2099), Ok(false));
+    assert!(display.interface().data.is_empty());
+    assert_eq!(ticker.poll(&mut display, 4600), Ok(true));
+    assert_eq!(ticker.offset(), 12);
+    assert_eq!(ticker.poll(&mut display, 6099), Ok(false));
+    assert_eq!(ticker.poll(&mut display, 6100), Ok(true));
+    assert_eq!(ticker.offset(), 18);
+    // wraps after the gap
+    assert_eq!(ticker.poll(&mut display, 16_100), Ok(true));
+    assert_eq!(ticker.offset(), 2);
+    assert_eq!(display.state().refreshes, 4);
+
+    ticker.set_text("BYE");
+    assert_eq!(ticker.poll(&mut display, 16_200), Ok(true));
+    assert_eq!((ticker.offset(), ticker.period()), (0, 34));
+}
+
+#[test]
+fn text_scrolls_left() {
+    let mut black = [0u8; BUFFER_SIZE];
+    let mut red = [0u8; BUFFER_SIZE];
+    let mut display = GraphicDisplay::new(build_display(), &mut black, &mut red);
+    let region = Region::display(display.config());
+    let ticker = Ticker::new("I", region, &FONT_6X10)
+        .colors(Color::Red, Color::White)
+        .gap(2);
+    let columns = |ticker: &Ticker<&str>, display: &mut GraphicDisplay<_, _>| {
+        ticker.draw(display).unwrap();
+        let mut red: Vec<i32> = display
+            .pixels()
+            .filter(|pixel| pixel.1 == Color::Red)
+            .map(|pixel| pixel.0.x)
+            .collect();
+        red.sort();
+        red.dedup();
+        red
+    };
+    // the glyph and its repeats every 8 pixels
+    let first = columns(&ticker, &mut display);
+    let mut ticker = ticker.step(3).interval_ms(10);
+    ticker.poll(&mut display, 0).unwrap();
+    ticker.poll(&mut display, 10).unwrap();
+    let moved = columns(&ticker, &mut display);
+    let expected: Vec<i32> = first
+        .iter()
+        .map(|x| x - 3)
+        .chain(first.iter().map(|x| x + 5))
+        .filter(|x| (0..32).contains(x))
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    assert!(!first.is_empty());
+    assert_eq!(moved, expected);
+}